@@ -0,0 +1,65 @@
+//! Hot-path benchmarks: app:// serving, IPC dispatch, and response delivery scripts.
+//!
+//! Run from `core/` with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline main` / `--baseline main`.
+
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use desktop_runtime_core::config::UI;
+use desktop_runtime_core::event_loop::{json_literal, response_scripts};
+use desktop_runtime_core::ipc::{handle_command, parse_envelope, respond_json};
+use desktop_runtime_core::protocol::{app_response, serve};
+
+fn asset_serving(c: &mut Criterion) {
+    let mut group = c.benchmark_group("protocol");
+    group.bench_function("serve index.html", |b| {
+        b.iter(|| serve(&UI, black_box("/index.html")))
+    });
+    group.bench_function("app_response index.html", |b| {
+        b.iter(|| app_response(&UI, None, black_box("/")))
+    });
+    group.bench_function("app_response missing", |b| {
+        b.iter(|| app_response(&UI, None, black_box("/no/such/file.js")))
+    });
+    group.finish();
+}
+
+fn ipc_dispatch(c: &mut Criterion) {
+    let raw = r#"{"id":"bench-1","name":"Ping"}"#;
+    c.bench_function("ipc round-trip Ping", |b| {
+        b.iter(|| {
+            let envelope = parse_envelope(black_box(raw)).expect("valid envelope");
+            respond_json(envelope.id, || handle_command(&envelope.command))
+        })
+    });
+}
+
+fn response_delivery(c: &mut Criterion) {
+    let small: Vec<String> = (0..64)
+        .map(|i| format!(r#"{{"id":"{}","ok":{{"path":"/tmp/file-{}.txt"}}}}"#, i, i))
+        .collect();
+    let large = format!(
+        r#"{{"id":"big","ok":"{}"}}"#,
+        "line \\\"quoted\\\"\\n".repeat(64 * 1024)
+    );
+
+    let mut group = c.benchmark_group("delivery");
+    group.bench_function("json_literal 1 MiB", |b| {
+        b.iter(|| json_literal(black_box(&large)).len())
+    });
+    group.bench_function("response_scripts 64 small", |b| {
+        b.iter(|| response_scripts(black_box(&small), "key", 256 * 1024))
+    });
+    group.bench_function("response_scripts 4 x 1 MiB", |b| {
+        b.iter_batched(
+            || vec![large.clone(); 4],
+            |batch| response_scripts(&batch, "key", 256 * 1024),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, asset_serving, ipc_dispatch, response_delivery);
+criterion_main!(benches);
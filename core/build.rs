@@ -4,11 +4,19 @@
 //!
 //! - `DESKTOP_RUNTIME_GITHUB_REPO` – Optional. `owner/repo` for update checks. If unset, derived from
 //!   `CARGO_PKG_REPOSITORY` or defaults to `klevert-ope/desktop-runtime`.
+//! - `DESKTOP_RUNTIME_KILL_SWITCH_URL` – Optional. HTTPS URL of the kill-switch manifest, read via
+//!   `option_env!` in `core/src/killswitch.rs`.
+//! - `DESKTOP_RUNTIME_PRODUCT_NAME` – Optional. Product name (window title, tray tooltip, data dir
+//!   names). Defaults to `Desktop Runtime`.
+//! - `DESKTOP_RUNTIME_BUNDLE_ID` – Optional. Reverse-DNS app identifier (keychain service, app id
+//!   reported to the UI). Defaults to `io.github.klevert-ope.desktop-runtime`.
 //!
 //! ## Emitted
 //!
 //! - `cargo:rustc-env=GITHUB_REPO_FOR_UPDATES=<repo>` – Consumed by `core/src/ipc.rs`.
+//! - `cargo:rustc-env=APP_PRODUCT_NAME=<name>`, `APP_BUNDLE_ID=<id>` – Consumed by `core/src/config.rs`.
 //! - `cargo:rerun-if-changed=<path>` – So the crate rebuilds when UI or icons change.
+//! - `cargo:rerun-if-env-changed=<var>` – So the crate rebuilds when build-time env inputs change.
 //!
 //! ## UI build
 //!
@@ -25,6 +33,12 @@ use std::process::Command;
 /// Default GitHub repo (owner/name) when not set via env or CARGO_PKG_REPOSITORY.
 const DEFAULT_GITHUB_REPO: &str = "klevert-ope/desktop-runtime";
 
+/// Default product name when `DESKTOP_RUNTIME_PRODUCT_NAME` is unset.
+const DEFAULT_PRODUCT_NAME: &str = "Desktop Runtime";
+
+/// Default bundle identifier when `DESKTOP_RUNTIME_BUNDLE_ID` is unset.
+const DEFAULT_BUNDLE_ID: &str = "io.github.klevert-ope.desktop-runtime";
+
 /// Path to the UI app (relative to CARGO_MANIFEST_DIR).
 const UI_DIR: &str = "../ui";
 
@@ -41,6 +55,14 @@ const RERUN_IF_CHANGED: &[&str] = &[
     "../ui/src",
 ];
 
+/// Build-time env vars that are read by the crate and must trigger a rebuild when changed.
+const RERUN_IF_ENV_CHANGED: &[&str] = &[
+    "DESKTOP_RUNTIME_GITHUB_REPO",
+    "DESKTOP_RUNTIME_KILL_SWITCH_URL",
+    "DESKTOP_RUNTIME_PRODUCT_NAME",
+    "DESKTOP_RUNTIME_BUNDLE_ID",
+];
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
@@ -62,6 +84,15 @@ fn github_repo_for_updates() -> String {
     DEFAULT_GITHUB_REPO.to_string()
 }
 
+/// Returns the build env value for `var` when set and non-empty, else `default`.
+fn env_or(var: &str, default: &str) -> String {
+    std::env::var(var)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
 /// Runs `npm install` in `ui_dir`. On failure, panics with a clear message.
 fn npm_install(ui_dir: &Path) {
     let status = Command::new("npm")
@@ -115,12 +146,24 @@ fn ensure_ui_build(ui_dir: &Path) {
 fn main() {
     let repo = github_repo_for_updates();
     println!("cargo:rustc-env=GITHUB_REPO_FOR_UPDATES={}", repo);
+    println!(
+        "cargo:rustc-env=APP_PRODUCT_NAME={}",
+        env_or("DESKTOP_RUNTIME_PRODUCT_NAME", DEFAULT_PRODUCT_NAME)
+    );
+    println!(
+        "cargo:rustc-env=APP_BUNDLE_ID={}",
+        env_or("DESKTOP_RUNTIME_BUNDLE_ID", DEFAULT_BUNDLE_ID)
+    );
 
-    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR set by cargo");
+    let manifest_dir =
+        std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR set by cargo");
     let ui_dir = Path::new(&manifest_dir).join(UI_DIR);
     ensure_ui_build(&ui_dir);
 
     for path in RERUN_IF_CHANGED {
         println!("cargo:rerun-if-changed={}", path);
     }
+    for var in RERUN_IF_ENV_CHANGED {
+        println!("cargo:rerun-if-env-changed={}", var);
+    }
 }
@@ -0,0 +1,353 @@
+//! Default-application query (`GetDefaultAppFor`) and opening a file in a chosen app
+//! (`OpenWith`), for "Open in ..." actions next to the system-default `opener` path.
+//!
+//! App ids are platform specific and meant to be passed back to `OpenWith` unchanged: a desktop
+//! entry id on Linux (`org.gimp.GIMP.desktop`, launched with `gtk-launch`), a bundle id on macOS
+//! (`open -b`), and the handler's executable path on Windows. `OpenWith` only accepts the default
+//! app or a handler the platform has registered for the file's extension: desktop entries listing
+//! its MIME type, Launch Services candidates, or the extension's `OpenWithProgids`.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+/// An application that can open files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppInfo {
+    /// Value to pass as `app` to `OpenWith`.
+    pub id: String,
+    /// Display name, when the platform reports one.
+    pub name: Option<String>,
+}
+
+/// Normalizes a file extension (`".PNG"` → `"png"`). Rejects empty and non-alphanumeric input.
+pub fn normalize_extension(extension: &str) -> Result<String, String> {
+    let ext = extension
+        .trim()
+        .trim_start_matches('.')
+        .to_ascii_lowercase();
+    if ext.is_empty()
+        || ext.len() > 32
+        || !ext
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(format!("Invalid extension: {}", extension));
+    }
+    Ok(ext)
+}
+
+/// MIME type for `ext` from a shared-mime-info `globs2` file (`weight:type:*.ext[:flags]`),
+/// highest weight first.
+#[must_use]
+pub fn mime_for_extension(globs2: &str, ext: &str) -> Option<String> {
+    let pattern = format!("*.{}", ext);
+    globs2
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let weight: u32 = fields.next()?.parse().ok()?;
+            let mime = fields.next()?;
+            fields
+                .next()?
+                .eq_ignore_ascii_case(&pattern)
+                .then(|| (weight, mime.to_string()))
+        })
+        .max_by_key(|(weight, _)| *weight)
+        .map(|(_, mime)| mime)
+}
+
+/// Value of `key` in the `[Desktop Entry]` group of a desktop file.
+#[must_use]
+pub fn desktop_entry_value<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let mut in_entry = false;
+    for line in text.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry
+            && let Some((name, value)) = line.split_once('=')
+            && name.trim_end() == key
+        {
+            return Some(value.trim_start());
+        }
+    }
+    None
+}
+
+/// Whether a desktop file lists `mime` in its `MimeType=` key.
+#[must_use]
+pub fn desktop_entry_handles(text: &str, mime: &str) -> bool {
+    desktop_entry_value(text, "MimeType")
+        .is_some_and(|types| types.split(';').any(|t| t.eq_ignore_ascii_case(mime)))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ffi::OsStr;
+    use std::path::{Path, PathBuf};
+
+    use super::{AppInfo, desktop_entry_handles, desktop_entry_value, mime_for_extension, run};
+
+    /// `$XDG_DATA_HOME` then `$XDG_DATA_DIRS` (freedesktop defaults when unset).
+    fn data_dirs() -> Vec<PathBuf> {
+        let home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")));
+        let system = std::env::var("XDG_DATA_DIRS")
+            .unwrap_or_else(|_| "/usr/local/share:/usr/share".to_string());
+        home.into_iter()
+            .chain(
+                system
+                    .split(':')
+                    .filter(|d| !d.is_empty())
+                    .map(PathBuf::from),
+            )
+            .collect()
+    }
+
+    /// `Name=` from the `[Desktop Entry]` group of desktop file `id`.
+    fn desktop_name(id: &str) -> Option<String> {
+        let text = data_dirs()
+            .iter()
+            .find_map(|dir| std::fs::read_to_string(dir.join("applications").join(id)).ok())?;
+        desktop_entry_value(&text, "Name").map(str::to_string)
+    }
+
+    fn mime_type(ext: &str) -> Option<String> {
+        data_dirs().iter().find_map(|dir| {
+            mime_for_extension(&std::fs::read_to_string(dir.join("mime/globs2")).ok()?, ext)
+        })
+    }
+
+    pub fn default_app_for(ext: &str) -> Result<Option<AppInfo>, String> {
+        let Some(mime) = mime_type(ext) else {
+            return Ok(None);
+        };
+        let id =
+            run("xdg-mime", &["query", "default", &mime]).filter(|id| id.ends_with(".desktop"));
+        Ok(id.map(|id| AppInfo {
+            name: desktop_name(&id),
+            id,
+        }))
+    }
+
+    /// Desktop entry ids whose `MimeType=` lists the extension's type.
+    pub fn handlers_for(ext: &str) -> Result<Vec<String>, String> {
+        let Some(mime) = mime_type(ext) else {
+            return Ok(Vec::new());
+        };
+        let mut ids = Vec::new();
+        for dir in data_dirs() {
+            let Ok(entries) = std::fs::read_dir(dir.join("applications")) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.ends_with(".desktop")
+                    && std::fs::read_to_string(entry.path())
+                        .is_ok_and(|text| desktop_entry_handles(&text, &mime))
+                {
+                    ids.push(name);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    pub fn open_with(path: &Path, app: &str) -> Result<(), String> {
+        super::spawn("gtk-launch", &[OsStr::new(app), path.as_os_str()])
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    use super::{AppInfo, run};
+
+    pub fn default_app_for(ext: &str) -> Result<Option<AppInfo>, String> {
+        let script = format!(
+            "ObjC.import('AppKit'); ObjC.import('UniformTypeIdentifiers'); \
+             const t = $.UTType.typeWithFilenameExtension('{}'); \
+             const u = t.isNil() ? t : $.NSWorkspace.sharedWorkspace.URLForApplicationToOpenContentType(t); \
+             u.isNil() ? '' : $.NSBundle.bundleWithURL(u).bundleIdentifier.js + '|' + \
+             $.NSFileManager.defaultManager.displayNameAtPath(u.path).js",
+            ext
+        );
+        let out = run("osascript", &["-l", "JavaScript", "-e", &script]).unwrap_or_default();
+        let Some((id, name)) = out.split_once('|') else {
+            return Ok(None);
+        };
+        Ok(Some(AppInfo {
+            id: id.to_string(),
+            name: Some(name.trim_end_matches(".app").to_string()).filter(|n| !n.is_empty()),
+        }))
+    }
+
+    /// Bundle ids of every app Launch Services would offer for the extension.
+    pub fn handlers_for(ext: &str) -> Result<Vec<String>, String> {
+        let script = format!(
+            "ObjC.import('AppKit'); ObjC.import('UniformTypeIdentifiers'); \
+             const t = $.UTType.typeWithFilenameExtension('{}'); \
+             const us = t.isNil() ? [] : ObjC.unwrap($.NSWorkspace.sharedWorkspace.URLsForApplicationsToOpenContentType(t)); \
+             us.map(u => $.NSBundle.bundleWithURL(u).bundleIdentifier.js).filter(id => id).join('\\n')",
+            ext
+        );
+        let out = run("osascript", &["-l", "JavaScript", "-e", &script]).unwrap_or_default();
+        Ok(out.lines().map(str::to_string).collect())
+    }
+
+    pub fn open_with(path: &Path, app: &str) -> Result<(), String> {
+        super::spawn(
+            "/usr/bin/open",
+            &[OsStr::new("-b"), OsStr::new(app), path.as_os_str()],
+        )
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::os::windows::process::CommandExt;
+    use std::path::Path;
+
+    use super::AppInfo;
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    pub fn default_app_for(ext: &str) -> Result<Option<AppInfo>, String> {
+        // User choice first, then the machine-wide association; the open verb's executable is
+        // the id, its file description the name.
+        let script = format!(
+            "$e='.{ext}'; \
+             $p=(Get-ItemProperty \"HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\FileExts\\$e\\UserChoice\" -ErrorAction SilentlyContinue).ProgId; \
+             if (-not $p) {{ $p=(Get-ItemProperty \"Registry::HKEY_CLASSES_ROOT\\$e\" -ErrorAction SilentlyContinue).'(default)' }}; \
+             if (-not $p) {{ exit }}; \
+             $c=(Get-ItemProperty \"Registry::HKEY_CLASSES_ROOT\\$p\\shell\\open\\command\" -ErrorAction SilentlyContinue).'(default)'; \
+             if (-not $c) {{ exit }}; \
+             $x=if ($c.StartsWith('\"')) {{ $c.Split('\"')[1] }} else {{ $c.Split(' ')[0] }}; \
+             $x=[Environment]::ExpandEnvironmentVariables($x); \
+             $n=(Get-Item $x -ErrorAction SilentlyContinue).VersionInfo.FileDescription; \
+             \"$x|$n\""
+        );
+        let out = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Could not run powershell: {}", e))?;
+        let out = String::from_utf8_lossy(&out.stdout);
+        let Some((id, name)) = out.trim().split_once('|') else {
+            return Ok(None);
+        };
+        Ok(Some(AppInfo {
+            id: id.to_string(),
+            name: Some(name.to_string()).filter(|n| !n.is_empty()),
+        }))
+    }
+
+    /// Executables of the ProgIds listed under the extension's `OpenWithProgids` (per user and
+    /// machine wide).
+    pub fn handlers_for(ext: &str) -> Result<Vec<String>, String> {
+        let script = format!(
+            "$e='.{ext}'; \
+             $keys=\"HKCU:\\Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\FileExts\\$e\\OpenWithProgids\",\"Registry::HKEY_CLASSES_ROOT\\$e\\OpenWithProgids\"; \
+             foreach ($k in $keys) {{ \
+               $i=Get-Item $k -ErrorAction SilentlyContinue; if (-not $i) {{ continue }}; \
+               foreach ($p in $i.GetValueNames()) {{ \
+                 $c=(Get-ItemProperty \"Registry::HKEY_CLASSES_ROOT\\$p\\shell\\open\\command\" -ErrorAction SilentlyContinue).'(default)'; \
+                 if (-not $c) {{ continue }}; \
+                 $x=if ($c.StartsWith('\"')) {{ $c.Split('\"')[1] }} else {{ $c.Split(' ')[0] }}; \
+                 [Environment]::ExpandEnvironmentVariables($x) }} }}"
+        );
+        let out = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Could not run powershell: {}", e))?;
+        Ok(String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect())
+    }
+
+    pub fn open_with(path: &Path, app: &str) -> Result<(), String> {
+        if !Path::new(app).is_file() {
+            return Err(format!("{} is not installed", app));
+        }
+        super::spawn(app, &[path.as_os_str()])
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::path::Path;
+
+    use super::AppInfo;
+
+    pub fn default_app_for(_ext: &str) -> Result<Option<AppInfo>, String> {
+        Ok(None)
+    }
+
+    pub fn handlers_for(_ext: &str) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
+    pub fn open_with(_path: &Path, _app: &str) -> Result<(), String> {
+        let _ = super::spawn;
+        Err("OpenWith is not supported on this platform".to_string())
+    }
+}
+
+/// Starts `program args..` detached from our stdio and reaps it on a background thread.
+fn spawn(program: &str, args: &[&std::ffi::OsStr]) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Could not start {}: {}", program, e))?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Default app for files with `extension`, or `None` when no handler is registered.
+pub fn default_app_for(extension: &str) -> Result<Option<AppInfo>, String> {
+    platform::default_app_for(&normalize_extension(extension)?)
+}
+
+/// Opens `path` (already checked against grants) in `app`, which must be the default app or a
+/// registered handler for the file's extension.
+pub fn open_with(path: &Path, app: &str) -> Result<(), String> {
+    if app.trim().is_empty() {
+        return Err("app must not be empty".to_string());
+    }
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .ok_or("OpenWith needs a file with an extension")?;
+    let ext = normalize_extension(ext)?;
+    let registered = platform::default_app_for(&ext)?.is_some_and(|default| default.id == app)
+        || platform::handlers_for(&ext)?.iter().any(|id| id == app);
+    if !registered {
+        return Err(format!(
+            "{} is not a registered handler for .{} files",
+            app, ext
+        ));
+    }
+    platform::open_with(path, app)
+}
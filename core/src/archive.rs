@@ -0,0 +1,670 @@
+//! Zip and tar.gz archives: `ExtractArchive` and `CreateArchive`, and update bundles.
+//!
+//! The formats come from the `zip` crate (stored or deflated entries, no encryption) and the `tar`
+//! crate over `flate2` gzip. Extraction never writes outside `dest`: entry names become
+//! relative paths, `..` is rejected, symlinks must point inside `dest` (with `..` only leading
+//! their target, so chains of links cannot climb out), and nothing is written or hard-link copied
+//! through an existing symlink. Unix permission bits (minus setuid/setgid) and symlinks are kept
+//! so `.app` bundles survive. Entry count and extracted size are capped against archive bombs.
+//! Progress goes to the UI as `archive-progress` host events
+//! (`{ operation, archive, processedBytes, totalBytes }`), at most every 100 ms.
+
+use std::cell::Cell;
+use std::fs::{self, File, Metadata};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use serde::{Deserialize, Serialize};
+use tar::EntryType;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::config::{ARCHIVE_MAX_ENTRIES, ARCHIVE_MAX_EXTRACTED_BYTES};
+use crate::events;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Archive container format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    /// Format implied by a file name (`.zip`, `.tar.gz`, `.tgz`).
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Entries and file bytes extracted or archived.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub entries: u64,
+    pub bytes: u64,
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Relative path for an archive entry name: leading `/` and `.` parts are dropped; `None` when
+/// nothing is left or a part is `..` (or has a drive prefix on Windows).
+#[must_use]
+pub fn entry_path(name: &str) -> Option<PathBuf> {
+    let mut out = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            p if cfg!(windows) && p.contains(':') => return None,
+            p => out.push(p),
+        }
+    }
+    (!out.as_os_str().is_empty()).then_some(out)
+}
+
+/// True if a symlink at relative `entry` pointing to `target` resolves inside the archive root.
+/// `..` may only lead the target: after a named part it would climb out of whatever that name
+/// resolves to, which can be another symlink (`d/up -> ..`, then `esc -> d/up/..`).
+#[must_use]
+pub fn link_stays_inside(entry: &Path, target: &str) -> bool {
+    if target.is_empty() || target.starts_with(['/', '\\']) || target.contains(':') {
+        return false;
+    }
+    let mut depth = entry.components().count().saturating_sub(1);
+    let mut descended = false;
+    for part in target.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." if descended => return false,
+            ".." => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => descended = true,
+        }
+    }
+    true
+}
+
+struct Progress<'a> {
+    operation: &'static str,
+    archive: &'a Path,
+    total: u64,
+    last: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(operation: &'static str, archive: &'a Path, total: u64) -> Self {
+        Self {
+            operation,
+            archive,
+            total,
+            last: None,
+        }
+    }
+
+    fn report(&mut self, processed: u64, force: bool) {
+        if !force && self.last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return;
+        }
+        self.last = Some(Instant::now());
+        events::emit(
+            "archive-progress",
+            serde_json::json!({
+                "operation": self.operation,
+                "archive": self.archive.display().to_string(),
+                "processedBytes": processed.min(self.total),
+                "totalBytes": self.total,
+            }),
+        );
+    }
+}
+
+/// Counts bytes read through it into a shared cell, so the count stays readable while the
+/// reader is owned by a decoder or archive.
+struct Counting<'a, R> {
+    inner: R,
+    count: &'a Cell<u64>,
+}
+
+impl<R: Read> Read for Counting<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count.set(self.count.get() + n as u64);
+        Ok(n)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Extraction
+// ---------------------------------------------------------------------------
+
+enum EntryKind {
+    Dir,
+    File,
+    Symlink(String),
+    /// Hard link to an earlier entry (tar); extracted as a copy.
+    Hardlink(PathBuf),
+}
+
+struct Extractor<'a> {
+    dest: &'a Path,
+    /// Most file bytes to write (`ARCHIVE_MAX_EXTRACTED_BYTES` outside tests).
+    max_bytes: u64,
+    summary: Summary,
+    /// Directory modes, applied last so read-only directories can still be filled.
+    dir_modes: Vec<(PathBuf, u32)>,
+}
+
+impl Extractor<'_> {
+    /// File bytes that may still be written.
+    fn budget(&self) -> u64 {
+        self.max_bytes.saturating_sub(self.summary.bytes)
+    }
+
+    fn too_large(&self) -> io::Error {
+        invalid(format!(
+            "Archive expands to more than {} bytes",
+            self.max_bytes
+        ))
+    }
+
+    /// Creates the parents of `rel` under `dest` without following symlinks, and clears a
+    /// non-directory already at `rel`. Returns the full path.
+    fn prepare(&self, rel: &Path) -> io::Result<PathBuf> {
+        let mut current = self.dest.to_path_buf();
+        let parts: Vec<_> = rel.components().collect();
+        for part in &parts[..parts.len().saturating_sub(1)] {
+            current.push(part);
+            match fs::symlink_metadata(&current) {
+                Ok(m) if m.file_type().is_symlink() => {
+                    return Err(invalid(format!(
+                        "Entry {} passes through a symlink",
+                        rel.display()
+                    )));
+                }
+                Ok(m) if m.is_dir() => {}
+                Ok(_) => return Err(invalid(format!("Entry {} is below a file", rel.display()))),
+                Err(_) => fs::create_dir(&current)?,
+            }
+        }
+        let full = self.dest.join(rel);
+        if let Ok(m) = fs::symlink_metadata(&full)
+            && !m.is_dir()
+        {
+            fs::remove_file(&full)?;
+        }
+        Ok(full)
+    }
+
+    /// Full path and size of the already extracted regular file at `rel`, reached without
+    /// following symlinks, so a hard link copy cannot read through one.
+    fn extracted_file(&self, rel: &Path) -> Option<(PathBuf, u64)> {
+        let mut current = self.dest.to_path_buf();
+        let mut meta = None;
+        for part in rel.components() {
+            current.push(part);
+            let m = fs::symlink_metadata(&current).ok()?;
+            if m.file_type().is_symlink() {
+                return None;
+            }
+            meta = Some(m);
+        }
+        meta.filter(Metadata::is_file).map(|m| (current, m.len()))
+    }
+
+    fn entry(
+        &mut self,
+        rel: &Path,
+        kind: EntryKind,
+        mode: Option<u32>,
+        data: &mut dyn Read,
+    ) -> io::Result<()> {
+        self.summary.entries += 1;
+        if self.summary.entries > ARCHIVE_MAX_ENTRIES {
+            return Err(invalid(format!(
+                "Archive has more than {} entries",
+                ARCHIVE_MAX_ENTRIES
+            )));
+        }
+        let path = self.prepare(rel)?;
+        match kind {
+            EntryKind::Dir => {
+                if !path.is_dir() {
+                    fs::create_dir(&path)?;
+                }
+                if let Some(mode) = mode {
+                    self.dir_modes.push((path, mode));
+                }
+                return Ok(());
+            }
+            EntryKind::File => {
+                let budget = self.budget();
+                let mut file = File::create(&path)?;
+                let copied = io::copy(&mut data.take(budget.saturating_add(1)), &mut file);
+                drop(file);
+                // A failed read (such as a zip CRC mismatch) or an oversized entry leaves no file.
+                let written = match copied {
+                    Ok(n) if n <= budget => n,
+                    Ok(_) => {
+                        let _ = fs::remove_file(&path);
+                        return Err(self.too_large());
+                    }
+                    Err(e) => {
+                        let _ = fs::remove_file(&path);
+                        return Err(e);
+                    }
+                };
+                self.summary.bytes += written;
+            }
+            EntryKind::Symlink(target) => {
+                if !link_stays_inside(rel, &target) {
+                    return Err(invalid(format!(
+                        "Symlink {} points outside the archive",
+                        rel.display()
+                    )));
+                }
+                #[cfg(unix)]
+                std::os::unix::fs::symlink(&target, &path)?;
+                // Creating symlinks needs a privilege on Windows; skip rather than fail.
+                #[cfg(not(unix))]
+                log::warn!("Archive symlink {} skipped on this platform", rel.display());
+                return Ok(());
+            }
+            EntryKind::Hardlink(source) => {
+                let Some((source, len)) = self.extracted_file(&source) else {
+                    return Err(invalid(format!(
+                        "Hard link {} has no earlier file target",
+                        rel.display()
+                    )));
+                };
+                if len > self.budget() {
+                    return Err(self.too_large());
+                }
+                self.summary.bytes += fs::copy(&source, &path)?;
+            }
+        }
+        set_mode(&path, mode)
+    }
+
+    fn finish(mut self) -> io::Result<Summary> {
+        while let Some((path, mode)) = self.dir_modes.pop() {
+            set_mode(&path, Some(mode))?;
+        }
+        Ok(self.summary)
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: Option<u32>) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    match mode {
+        Some(mode) => fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o1777)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: Option<u32>) -> io::Result<()> {
+    Ok(())
+}
+
+fn read_tar(reader: impl Read, x: &mut Extractor, mut on_entry: impl FnMut()) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let link = entry
+            .link_name_bytes()
+            .map(|l| String::from_utf8_lossy(&l).into_owned())
+            .unwrap_or_default();
+        let mode = entry.header().mode().ok();
+        let kind = match entry.header().entry_type() {
+            EntryType::Directory => Some(EntryKind::Dir),
+            EntryType::Regular | EntryType::Continuous if name.ends_with('/') => {
+                Some(EntryKind::Dir)
+            }
+            EntryType::Regular | EntryType::Continuous => Some(EntryKind::File),
+            EntryType::Symlink => Some(EntryKind::Symlink(link)),
+            EntryType::Link => {
+                Some(EntryKind::Hardlink(entry_path(&link).ok_or_else(|| {
+                    invalid(format!("Bad hard link target {}", link))
+                })?))
+            }
+            // Devices, FIFOs, and unknown types are skipped.
+            _ => None,
+        };
+        match (entry_path(&name), kind) {
+            (Some(rel), Some(kind)) => x.entry(&rel, kind, mode, &mut entry)?,
+            (None, Some(_)) if !name.trim_matches(['/', '.']).is_empty() => {
+                return Err(invalid(format!("Unsafe entry name {}", name)));
+            }
+            _ => {}
+        }
+        on_entry();
+    }
+    Ok(())
+}
+
+fn read_zip(file: File, x: &mut Extractor, progress: &mut Progress) -> io::Result<()> {
+    let mut archive = ZipArchive::new(BufReader::new(file))?;
+    if archive.len() as u64 > ARCHIVE_MAX_ENTRIES {
+        return Err(invalid(format!(
+            "Archive has more than {} entries",
+            ARCHIVE_MAX_ENTRIES
+        )));
+    }
+    let mut processed = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let name = entry.name().to_string();
+        let mode = entry.unix_mode();
+        let kind = if entry.is_dir() || mode.is_some_and(|m| m & 0o170_000 == 0o040_000) {
+            EntryKind::Dir
+        } else if entry.is_symlink() {
+            let mut target = String::new();
+            (&mut entry).take(4096).read_to_string(&mut target)?;
+            EntryKind::Symlink(target)
+        } else {
+            EntryKind::File
+        };
+        match entry_path(&name) {
+            Some(rel) => x.entry(&rel, kind, mode.map(|m| m & 0o7777), &mut entry)?,
+            None if !name.trim_matches(['/', '.']).is_empty() => {
+                return Err(invalid(format!("Unsafe entry name {}", name)));
+            }
+            None => {}
+        }
+        // Reading to the end checks the entry's CRC.
+        io::copy(&mut entry, &mut io::sink())?;
+        processed += entry.compressed_size();
+        progress.report(processed, false);
+    }
+    Ok(())
+}
+
+/// Extracts `src` into the existing directory `dest`. The format comes from `format` or the
+/// file name. Blocking; run on a worker thread.
+pub fn extract(src: &Path, dest: &Path, format: Option<ArchiveFormat>) -> Result<Summary, String> {
+    extract_capped(src, dest, format, ARCHIVE_MAX_EXTRACTED_BYTES)
+}
+
+/// `extract` with another cap on the bytes written (tests use small ones).
+pub(crate) fn extract_capped(
+    src: &Path,
+    dest: &Path,
+    format: Option<ArchiveFormat>,
+    max_bytes: u64,
+) -> Result<Summary, String> {
+    let format = format
+        .or_else(|| ArchiveFormat::from_path(src))
+        .ok_or("Unknown archive format; pass format")?;
+    if !dest.is_dir() {
+        return Err(format!(
+            "Destination is not a directory: {}",
+            dest.display()
+        ));
+    }
+    let file = File::open(src).map_err(|e| format!("{}: {}", src.display(), e))?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut progress = Progress::new("extract", src, total);
+    progress.report(0, true);
+    let mut x = Extractor {
+        dest,
+        max_bytes,
+        summary: Summary::default(),
+        dir_modes: Vec::new(),
+    };
+    let result = match format {
+        ArchiveFormat::Zip => read_zip(file, &mut x, &mut progress),
+        ArchiveFormat::TarGz => {
+            let read = Cell::new(0);
+            let gz = GzDecoder::new(Counting {
+                inner: BufReader::new(file),
+                count: &read,
+            });
+            read_tar(gz, &mut x, || progress.report(read.get(), false))
+        }
+    };
+    result.map_err(|e| format!("Extract failed: {}", e))?;
+    let summary = x.finish().map_err(|e| e.to_string())?;
+    progress.report(total, true);
+    Ok(summary)
+}
+
+// ---------------------------------------------------------------------------
+// Creation
+// ---------------------------------------------------------------------------
+
+/// A filesystem entry to archive under `name` (`/`-separated, relative).
+struct Source {
+    path: PathBuf,
+    name: String,
+    meta: Metadata,
+}
+
+/// Collects `path` (and its subtree, without following symlinks) under `name`, skipping `skip`.
+fn collect(path: &Path, name: String, skip: &Path, out: &mut Vec<Source>) -> io::Result<()> {
+    if path == skip {
+        return Ok(());
+    }
+    let meta = fs::symlink_metadata(path)?;
+    let is_dir = meta.is_dir();
+    out.push(Source {
+        path: path.to_path_buf(),
+        name: name.clone(),
+        meta,
+    });
+    if out.len() as u64 > ARCHIVE_MAX_ENTRIES {
+        return Err(invalid(format!(
+            "More than {} entries",
+            ARCHIVE_MAX_ENTRIES
+        )));
+    }
+    if is_dir {
+        let mut children: Vec<_> = fs::read_dir(path)?
+            .flatten()
+            .map(|e| e.file_name())
+            .collect();
+        children.sort();
+        for child in children {
+            collect(
+                &path.join(&child),
+                format!("{}/{}", name, child.to_string_lossy()),
+                skip,
+                out,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn mtime(meta: &Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(unix)]
+fn permissions(meta: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o7777
+}
+
+#[cfg(not(unix))]
+fn permissions(meta: &Metadata) -> u32 {
+    if meta.is_dir() { 0o755 } else { 0o644 }
+}
+
+fn link_target(source: &Source) -> io::Result<String> {
+    Ok(fs::read_link(&source.path)?
+        .to_string_lossy()
+        .replace('\\', "/"))
+}
+
+fn write_tar<W: Write>(
+    out: W,
+    sources: &[Source],
+    progress: &mut Progress,
+) -> io::Result<(W, Summary)> {
+    let mut builder = tar::Builder::new(out);
+    let mut summary = Summary::default();
+    for source in sources {
+        let ft = source.meta.file_type();
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(permissions(&source.meta));
+        header.set_mtime(mtime(&source.meta));
+        header.set_size(0);
+        if ft.is_symlink() {
+            header.set_entry_type(EntryType::Symlink);
+            builder.append_link(&mut header, &source.name, link_target(source)?)?;
+        } else if ft.is_dir() {
+            header.set_entry_type(EntryType::Directory);
+            builder.append_data(&mut header, &source.name, io::empty())?;
+        } else {
+            let size = source.meta.len();
+            header.set_entry_type(EntryType::Regular);
+            header.set_size(size);
+            let copied = Cell::new(0);
+            let data = Counting {
+                inner: File::open(&source.path)?.take(size),
+                count: &copied,
+            };
+            builder.append_data(&mut header, &source.name, data)?;
+            if copied.get() != size {
+                return Err(invalid(format!(
+                    "{} changed while archiving",
+                    source.path.display()
+                )));
+            }
+            summary.bytes += size;
+            progress.report(summary.bytes, false);
+        }
+        summary.entries += 1;
+    }
+    Ok((builder.into_inner()?, summary))
+}
+
+/// Zip timestamp for a Unix time; the format starts in 1980, so earlier times become 1980-01-01.
+fn zip_time(secs: u64) -> zip::DateTime {
+    let (year, month, day) = crate::scheduler::civil_from_days(secs / 86_400);
+    let t = secs % 86_400;
+    zip::DateTime::from_date_and_time(
+        u16::try_from(year).unwrap_or(0),
+        month as u8,
+        day as u8,
+        (t / 3600) as u8,
+        ((t % 3600) / 60) as u8,
+        (t % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+fn write_zip<W: Write + Seek>(
+    out: W,
+    sources: &[Source],
+    progress: &mut Progress,
+) -> io::Result<(W, Summary)> {
+    let mut zip = ZipWriter::new(out);
+    let mut summary = Summary::default();
+    for source in sources {
+        let ft = source.meta.file_type();
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .unix_permissions(permissions(&source.meta))
+            .last_modified_time(zip_time(mtime(&source.meta)));
+        if ft.is_dir() {
+            zip.add_directory(source.name.as_str(), options)?;
+        } else if ft.is_symlink() {
+            zip.add_symlink(source.name.as_str(), link_target(source)?, options)?;
+        } else {
+            let large = source.meta.len() >= u64::from(u32::MAX);
+            zip.start_file(source.name.as_str(), options.large_file(large))?;
+            summary.bytes += io::copy(&mut File::open(&source.path)?, &mut zip)?;
+            progress.report(summary.bytes, false);
+        }
+        summary.entries += 1;
+    }
+    Ok((zip.finish()?, summary))
+}
+
+/// Archives `sources` (files or directories, each stored under its own name) into `dest`. The
+/// format comes from `format` or the file name. A partial `dest` is removed on failure.
+/// Blocking; run on a worker thread.
+pub fn create(
+    sources: &[PathBuf],
+    dest: &Path,
+    format: Option<ArchiveFormat>,
+) -> Result<Summary, String> {
+    let format = format
+        .or_else(|| ArchiveFormat::from_path(dest))
+        .ok_or("Unknown archive format; pass format")?;
+    if sources.is_empty() {
+        return Err("No sources to archive".to_string());
+    }
+    let mut entries = Vec::new();
+    for source in sources {
+        let name = source
+            .file_name()
+            .ok_or_else(|| format!("Cannot archive {}", source.display()))?
+            .to_string_lossy()
+            .into_owned();
+        collect(source, name, dest, &mut entries)
+            .map_err(|e| format!("{}: {}", source.display(), e))?;
+    }
+    let total = entries
+        .iter()
+        .filter(|s| s.meta.is_file())
+        .map(|s| s.meta.len())
+        .sum();
+    let mut progress = Progress::new("create", dest, total);
+    progress.report(0, true);
+    let mut write = || -> io::Result<Summary> {
+        let out = BufWriter::new(File::create(dest)?);
+        match format {
+            ArchiveFormat::Zip => {
+                let (out, summary) = write_zip(out, &entries, &mut progress)?;
+                out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+                Ok(summary)
+            }
+            ArchiveFormat::TarGz => {
+                let gz = GzEncoder::new(out, Compression::default());
+                let (gz, summary) = write_tar(gz, &entries, &mut progress)?;
+                gz.finish()?
+                    .into_inner()
+                    .map_err(|e| e.into_error())?
+                    .sync_all()?;
+                Ok(summary)
+            }
+        }
+    };
+    match write() {
+        Ok(summary) => {
+            progress.report(total, true);
+            Ok(summary)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(dest);
+            Err(format!("Create failed: {}", e))
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Append-only audit log of privileged commands.
+//!
+//! One JSON object per line in `<user data>/audit.log`: timestamp (ms since epoch), command name,
+//! arguments, and outcome. Argument fields listed in the `auditRedact` config key (e.g.
+//! `["url", "path"]`) are replaced with `"[redacted]"`. Once the file exceeds `MAX_LOG_BYTES` it is
+//! rotated to `audit.log.1`, shifting older files to `audit.log.2` and so on. The newest
+//! `ROTATED_GENERATIONS` rotated files are kept (about 10 MB of history); older ones are deleted.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::paths::user_data_dir;
+use crate::storage;
+
+/// Config key: array of argument names to redact. Host-only: the audited UI does not choose what
+/// is recorded about it.
+pub const REDACT_CONFIG_KEY: &str = "auditRedact";
+
+const LOG_FILENAME: &str = "audit.log";
+
+/// Rotated files kept next to the live log (`audit.log.1` newest).
+const ROTATED_GENERATIONS: usize = 9;
+
+/// Rotate when the log grows past this size.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Default number of entries returned by `GetAuditLog`.
+pub const DEFAULT_LIMIT: usize = 100;
+
+/// Serializes writers so lines are never interleaved.
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+fn log_path() -> PathBuf {
+    user_data_dir().join(LOG_FILENAME)
+}
+
+fn now_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+/// Moves `log` to `log.1`, shifting existing generations up by one and dropping the oldest.
+pub(crate) fn rotate(log: &Path) {
+    let generation = |n: usize| {
+        let mut name = log.as_os_str().to_owned();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    };
+    let _ = fs::remove_file(generation(ROTATED_GENERATIONS));
+    for n in (1..ROTATED_GENERATIONS).rev() {
+        let _ = fs::rename(generation(n), generation(n + 1));
+    }
+    let _ = fs::rename(log, generation(1));
+}
+
+/// Replaces redacted fields of a tagged command object (`{ name, ...args }`) with a marker.
+fn redact(args: &mut serde_json::Value, fields: &[String]) {
+    if let Some(obj) = args.as_object_mut() {
+        for f in fields {
+            if let Some(v) = obj.get_mut(f) {
+                *v = serde_json::json!("[redacted]");
+            }
+        }
+    }
+}
+
+/// Appends one entry for `command` (serialized as `{ name, ...args }`) and its outcome.
+/// I/O errors are logged and otherwise ignored; auditing never fails the command.
+pub fn record<T: serde::Serialize>(command: &T, result: &Result<serde_json::Value, String>) {
+    let mut args = serde_json::to_value(command).unwrap_or(serde_json::Value::Null);
+    let name = args
+        .as_object_mut()
+        .and_then(|o| o.remove("name"))
+        .unwrap_or(serde_json::Value::Null);
+    let fields: Vec<String> = storage::get_value(REDACT_CONFIG_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    redact(&mut args, &fields);
+    let entry = serde_json::json!({
+        "ts": now_ms() as u64,
+        "command": name,
+        "args": args,
+        "ok": result.is_ok(),
+        "error": result.as_ref().err(),
+    });
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = log_path();
+    if fs::metadata(&path).is_ok_and(|m| m.len() > MAX_LOG_BYTES) {
+        rotate(&path);
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", entry));
+    if let Err(e) = written {
+        log::warn!("Audit log write failed: {}", e);
+    }
+}
+
+/// Returns up to `limit` most recent entries (oldest first) from the current log file.
+#[must_use]
+pub fn recent(limit: usize) -> Vec<serde_json::Value> {
+    let Ok(content) = fs::read_to_string(log_path()) else {
+        return Vec::new();
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(limit)..]
+        .iter()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
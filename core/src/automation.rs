@@ -0,0 +1,155 @@
+//! Automation control port for end-to-end tests.
+//!
+//! With `DESKTOP_RUNTIME_AUTOMATION_PORT=<port>` and `DESKTOP_RUNTIME_AUTOMATION_TOKEN=<secret>`,
+//! a listener on `127.0.0.1:<port>` accepts newline-delimited JSON requests, each answered by one
+//! JSON line:
+//! - `{ "token": "...", "type": "ipc", "envelope": { "id": "1", "name": "Ping" } }` returns the
+//!   `IpcResponse`, exactly as the UI would receive it.
+//! - `{ "token": "...", "type": "eval", "script": "document.title" }` evaluates in the WebView and
+//!   returns `{ "result": <JSON-serialized value> }`.
+//!
+//! The port is not opened unless both variables are set; requests with a wrong token get an error.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use tao::event_loop::EventLoopProxy;
+
+use crate::config::{ENV_AUTOMATION_PORT, ENV_AUTOMATION_TOKEN};
+use crate::event_loop::UserEvent;
+use crate::ipc::{
+    IPC_TIMEOUT_MS, IpcEnvelope, IpcResponse, handle_command, is_window_command, respond,
+};
+
+/// Work the control port hands to the event loop thread.
+pub enum AutomationRequest {
+    /// Evaluate script in the WebView; the callback result (JSON string) goes to `reply`.
+    Eval {
+        script: String,
+        reply: mpsc::Sender<String>,
+    },
+    /// Run a window command on the loop thread.
+    WindowCommand {
+        envelope: IpcEnvelope,
+        reply: mpsc::Sender<IpcResponse>,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request {
+    Ipc { envelope: IpcEnvelope },
+    Eval { script: String },
+}
+
+#[derive(Deserialize)]
+struct Incoming {
+    token: String,
+    #[serde(flatten)]
+    request: Request,
+}
+
+fn error_line(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Handles one request line and returns the reply line.
+fn handle_line(line: &str, token: &str, proxy: &EventLoopProxy<UserEvent>) -> String {
+    let incoming: Incoming = match serde_json::from_str(line) {
+        Ok(i) => i,
+        Err(e) => return error_line(&format!("Invalid request: {}", e)),
+    };
+    if incoming.token != token {
+        return error_line("Invalid token");
+    }
+    let timeout = Duration::from_millis(IPC_TIMEOUT_MS);
+    match incoming.request {
+        Request::Ipc { envelope } if is_window_command(&envelope.command) => {
+            let (reply, rx) = mpsc::channel();
+            let id = envelope.id.clone();
+            let _ = proxy.send_event(UserEvent::Automation(AutomationRequest::WindowCommand {
+                envelope,
+                reply,
+            }));
+            let resp = rx
+                .recv_timeout(timeout)
+                .unwrap_or_else(|_| IpcResponse::err(id, "Event loop did not respond".to_string()));
+            serde_json::to_string(&resp).unwrap_or_else(|e| error_line(&e.to_string()))
+        }
+        Request::Ipc { envelope } => {
+            let resp = respond(envelope.id, || handle_command(&envelope.command));
+            serde_json::to_string(&resp).unwrap_or_else(|e| error_line(&e.to_string()))
+        }
+        Request::Eval { script } => {
+            let (reply, rx) = mpsc::channel();
+            let _ = proxy.send_event(UserEvent::Automation(AutomationRequest::Eval {
+                script,
+                reply,
+            }));
+            match rx.recv_timeout(timeout) {
+                Ok(result) => {
+                    let value = serde_json::from_str::<serde_json::Value>(&result)
+                        .unwrap_or(serde_json::Value::String(result));
+                    serde_json::json!({ "result": value }).to_string()
+                }
+                Err(_) => error_line("Script evaluation timed out"),
+            }
+        }
+    }
+}
+
+fn serve_connection(stream: TcpStream, token: &str, proxy: &EventLoopProxy<UserEvent>) {
+    let Ok(mut writer) = stream.try_clone() else {
+        return;
+    };
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { return };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = handle_line(&line, token, proxy);
+        if writeln!(writer, "{}", reply).is_err() {
+            return;
+        }
+    }
+}
+
+/// Opens the control port if both env vars are set. Each connection gets its own thread.
+pub fn start(proxy: EventLoopProxy<UserEvent>) {
+    let Ok(port) = std::env::var(ENV_AUTOMATION_PORT) else {
+        return;
+    };
+    let Some(port) = port.parse::<u16>().ok() else {
+        log::warn!("Automation: invalid port '{}'", port);
+        return;
+    };
+    let token = match std::env::var(ENV_AUTOMATION_TOKEN) {
+        Ok(t) if !t.is_empty() => t,
+        _ => {
+            log::warn!(
+                "Automation: {} is required; control port not opened",
+                ENV_AUTOMATION_TOKEN
+            );
+            return;
+        }
+    };
+    let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, port)) {
+        Ok(l) => l,
+        Err(e) => {
+            log::warn!("Automation: cannot bind 127.0.0.1:{}: {}", port, e);
+            return;
+        }
+    };
+    log::warn!("Automation control port open on 127.0.0.1:{}", port);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let token = token.clone();
+            let proxy = proxy.clone();
+            thread::spawn(move || serve_connection(stream, &token, &proxy));
+        }
+    });
+}
@@ -0,0 +1,503 @@
+//! Bluetooth LE for the UI (wry has no Web Bluetooth): scanning, connections, and GATT
+//! characteristic read / write / notify.
+//!
+//! Behind the `bluetooth` cargo feature; without it every command returns an error. Built on
+//! `btleplug` (BlueZ, WinRT, CoreBluetooth), driven by a tokio runtime on its own thread so its
+//! event streams keep running between commands. Host events: `bluetooth-device`
+//! (`{ address, name?, rssi?, services? }`, fields present when known) while scanning,
+//! `bluetooth-notification` (`{ address, service, characteristic, value }`, base64) for subscribed
+//! characteristics, and `bluetooth-disconnected` (`{ address }`).
+
+/// Base UUID that 16- and 32-bit Bluetooth SIG ids expand into.
+const BASE_UUID_SUFFIX: &str = "-0000-1000-8000-00805f9b34fb";
+
+/// Lowercase 128-bit UUID for `uuid`; 16/32-bit short ids (`"180d"`, `"0x2a37"`) are expanded.
+#[must_use]
+pub fn normalize_uuid(uuid: &str) -> Option<String> {
+    let raw = uuid.trim().to_ascii_lowercase();
+    let short = raw.strip_prefix("0x").unwrap_or(&raw);
+    if matches!(short.len(), 4 | 8) && short.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Some(format!("{:0>8}{}", short, BASE_UUID_SUFFIX));
+    }
+    let groups: Vec<&str> = raw.split('-').collect();
+    let lengths = groups.iter().map(|g| g.len()).collect::<Vec<_>>();
+    (lengths == [8, 4, 4, 4, 12]
+        && groups
+            .iter()
+            .all(|g| g.chars().all(|c| c.is_ascii_hexdigit())))
+    .then_some(raw)
+}
+
+/// Validates a device address and returns it uppercased: a MAC (`AA:BB:CC:DD:EE:FF`), or on macOS,
+/// which hides MACs, the CoreBluetooth peripheral UUID.
+pub fn normalize_address(address: &str) -> Result<String, String> {
+    let upper = address.trim().to_ascii_uppercase();
+    let parts: Vec<&str> = upper.split(':').collect();
+    let mac = parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    if mac || (upper.len() == 36 && normalize_uuid(&upper).is_some()) {
+        Ok(upper)
+    } else {
+        Err(format!("Invalid Bluetooth address: {}", address))
+    }
+}
+
+fn uuid_arg(uuid: &str) -> Result<String, String> {
+    normalize_uuid(uuid).ok_or_else(|| format!("Invalid UUID: {}", uuid))
+}
+
+#[cfg(feature = "bluetooth")]
+mod platform {
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::future::Future;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+
+    use base64::Engine;
+    use btleplug::api::{
+        BDAddr, Central, CentralEvent, Characteristic, Manager as _, Peripheral as _, ScanFilter,
+        WriteType,
+    };
+    use btleplug::platform::{Adapter, Manager, Peripheral};
+    use futures_util::StreamExt;
+    use tokio::runtime::Handle;
+
+    use crate::events;
+
+    /// Runtime thread that drives btleplug's background tasks (event streams, BlueZ signals).
+    static RUNTIME: Mutex<Option<Handle>> = Mutex::new(None);
+    static ADAPTER: Mutex<Option<Adapter>> = Mutex::new(None);
+    static SCANNING: AtomicBool = AtomicBool::new(false);
+    /// Subscribed (address, service, characteristic).
+    static SUBSCRIBED: Mutex<BTreeSet<(String, String, String)>> = Mutex::new(BTreeSet::new());
+    /// Devices with a running notification stream task.
+    static NOTIFYING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+    fn runtime() -> Result<Handle, String> {
+        let mut guard = RUNTIME.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(handle) = guard.as_ref() {
+            return Ok(handle.clone());
+        }
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let handle = rt.handle().clone();
+        thread::Builder::new()
+            .name("bluetooth".to_string())
+            .spawn(move || rt.block_on(std::future::pending::<()>()))
+            .map_err(|e| e.to_string())?;
+        *guard = Some(handle.clone());
+        Ok(handle)
+    }
+
+    /// Runs `future` to completion from a worker thread; spawned tasks stay on the runtime thread.
+    fn run<T>(future: impl Future<Output = Result<T, String>>) -> Result<T, String> {
+        runtime()?.block_on(future)
+    }
+
+    fn failed(e: btleplug::Error) -> String {
+        e.to_string()
+    }
+
+    /// The address the UI uses for `peripheral`: its MAC, or on macOS (which hides MACs) the
+    /// CoreBluetooth peripheral UUID, uppercased.
+    fn key(peripheral: &Peripheral) -> String {
+        let address = peripheral.address();
+        if address == BDAddr::default() {
+            peripheral.id().to_string().to_ascii_uppercase()
+        } else {
+            address.to_string()
+        }
+    }
+
+    /// The first adapter, opened once; its event stream feeds the host events.
+    async fn adapter() -> Result<Adapter, String> {
+        if let Some(adapter) = ADAPTER.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(adapter);
+        }
+        let manager = Manager::new().await.map_err(failed)?;
+        let adapter = manager
+            .adapters()
+            .await
+            .map_err(failed)?
+            .into_iter()
+            .next()
+            .ok_or("No Bluetooth adapter found")?;
+        let mut events = adapter.events().await.map_err(failed)?;
+        let watched = adapter.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                on_event(&watched, event).await;
+            }
+            log::warn!("Bluetooth event stream closed; bluetooth events stopped");
+        });
+        *ADAPTER.lock().unwrap_or_else(|e| e.into_inner()) = Some(adapter.clone());
+        Ok(adapter)
+    }
+
+    async fn on_event(adapter: &Adapter, event: CentralEvent) {
+        let (id, discovered) = match event {
+            CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) => (id, true),
+            CentralEvent::DeviceDisconnected(id) => (id, false),
+            _ => return,
+        };
+        if discovered && !SCANNING.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(peripheral) = adapter.peripheral(&id).await else {
+            return;
+        };
+        let address = key(&peripheral);
+        if !discovered {
+            events::emit(
+                "bluetooth-disconnected",
+                serde_json::json!({ "address": address }),
+            );
+            return;
+        }
+        let mut payload = serde_json::json!({ "address": address });
+        if let Ok(Some(props)) = peripheral.properties().await {
+            if let Some(name) = props.local_name {
+                payload["name"] = serde_json::json!(name);
+            }
+            if let Some(rssi) = props.rssi {
+                payload["rssi"] = serde_json::json!(rssi);
+            }
+            if !props.services.is_empty() {
+                let services: Vec<String> = props.services.iter().map(|u| u.to_string()).collect();
+                payload["services"] = serde_json::json!(services);
+            }
+        }
+        events::emit("bluetooth-device", payload);
+    }
+
+    async fn peripheral(address: &str) -> Result<Peripheral, String> {
+        adapter()
+            .await?
+            .peripherals()
+            .await
+            .map_err(failed)?
+            .into_iter()
+            .find(|p| key(p) == address)
+            .ok_or_else(|| format!("Unknown device {}; scan for it first", address))
+    }
+
+    fn characteristic(
+        peripheral: &Peripheral,
+        address: &str,
+        service: &str,
+        characteristic: &str,
+    ) -> Result<Characteristic, String> {
+        peripheral
+            .characteristics()
+            .into_iter()
+            .find(|c| c.uuid.to_string() == characteristic && c.service_uuid.to_string() == service)
+            .ok_or_else(|| {
+                format!(
+                    "Characteristic {} of service {} not found on {}",
+                    characteristic, service, address
+                )
+            })
+    }
+
+    /// Starts the task forwarding `peripheral`'s notifications for subscribed characteristics.
+    async fn forward_notifications(peripheral: &Peripheral, address: &str) -> Result<(), String> {
+        if !NOTIFYING
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(address.to_string())
+        {
+            return Ok(());
+        }
+        let mut stream = match peripheral.notifications().await {
+            Ok(stream) => stream,
+            Err(e) => {
+                NOTIFYING
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(address);
+                return Err(failed(e));
+            }
+        };
+        let services: BTreeMap<String, String> = peripheral
+            .characteristics()
+            .into_iter()
+            .map(|c| (c.uuid.to_string(), c.service_uuid.to_string()))
+            .collect();
+        let address = address.to_string();
+        tokio::spawn(async move {
+            while let Some(notification) = stream.next().await {
+                let characteristic = notification.uuid.to_string();
+                let Some(service) = services.get(&characteristic) else {
+                    continue;
+                };
+                let entry = (address.clone(), service.clone(), characteristic);
+                if SUBSCRIBED
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .contains(&entry)
+                {
+                    events::emit(
+                        "bluetooth-notification",
+                        serde_json::json!({
+                            "address": entry.0,
+                            "service": entry.1,
+                            "characteristic": entry.2,
+                            "value": base64::engine::general_purpose::STANDARD
+                                .encode(notification.value),
+                        }),
+                    );
+                }
+            }
+            NOTIFYING
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&address);
+        });
+        Ok(())
+    }
+
+    pub fn start_scan(services: &[String]) -> Result<(), String> {
+        let services = services
+            .iter()
+            .map(|s| uuid::Uuid::parse_str(s).map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        run(async {
+            let adapter = adapter().await?;
+            SCANNING.store(true, Ordering::Relaxed);
+            adapter
+                .start_scan(ScanFilter { services })
+                .await
+                .map_err(|e| {
+                    SCANNING.store(false, Ordering::Relaxed);
+                    failed(e)
+                })
+        })
+    }
+
+    pub fn stop_scan() -> Result<bool, String> {
+        if !SCANNING.swap(false, Ordering::Relaxed) {
+            return Ok(false);
+        }
+        run(async {
+            adapter().await?.stop_scan().await.map_err(failed)?;
+            Ok(true)
+        })
+    }
+
+    pub fn connect(address: &str) -> Result<Vec<String>, String> {
+        run(async {
+            let peripheral = peripheral(address).await?;
+            peripheral
+                .connect()
+                .await
+                .map_err(|e| format!("Could not connect to {}: {}", address, e))?;
+            peripheral.discover_services().await.map_err(failed)?;
+            let uuids: BTreeSet<String> = peripheral
+                .services()
+                .iter()
+                .map(|s| s.uuid.to_string())
+                .collect();
+            Ok(uuids.into_iter().collect())
+        })
+    }
+
+    pub fn disconnect(address: &str) -> Result<(), String> {
+        SUBSCRIBED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(subscribed, _, _)| subscribed != address);
+        run(async {
+            peripheral(address)
+                .await?
+                .disconnect()
+                .await
+                .map_err(failed)
+        })
+    }
+
+    pub fn read(address: &str, service: &str, characteristic: &str) -> Result<Vec<u8>, String> {
+        run(async {
+            let peripheral = peripheral(address).await?;
+            let target = self::characteristic(&peripheral, address, service, characteristic)?;
+            peripheral.read(&target).await.map_err(failed)
+        })
+    }
+
+    pub fn write(
+        address: &str,
+        service: &str,
+        characteristic: &str,
+        value: &[u8],
+        with_response: bool,
+    ) -> Result<(), String> {
+        let kind = if with_response {
+            WriteType::WithResponse
+        } else {
+            WriteType::WithoutResponse
+        };
+        run(async {
+            let peripheral = peripheral(address).await?;
+            let target = self::characteristic(&peripheral, address, service, characteristic)?;
+            peripheral.write(&target, value, kind).await.map_err(failed)
+        })
+    }
+
+    pub fn subscribe(address: &str, service: &str, characteristic: &str) -> Result<(), String> {
+        run(async {
+            let peripheral = peripheral(address).await?;
+            let target = self::characteristic(&peripheral, address, service, characteristic)?;
+            forward_notifications(&peripheral, address).await?;
+            peripheral.subscribe(&target).await.map_err(failed)?;
+            SUBSCRIBED
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert((
+                    address.to_string(),
+                    service.to_string(),
+                    characteristic.to_string(),
+                ));
+            Ok(())
+        })
+    }
+
+    pub fn unsubscribe(address: &str, service: &str, characteristic: &str) -> Result<bool, String> {
+        let entry = (
+            address.to_string(),
+            service.to_string(),
+            characteristic.to_string(),
+        );
+        if !SUBSCRIBED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&entry)
+        {
+            return Ok(false);
+        }
+        run(async {
+            let peripheral = peripheral(address).await?;
+            let target = self::characteristic(&peripheral, address, service, characteristic)?;
+            peripheral.unsubscribe(&target).await.map_err(failed)?;
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(not(feature = "bluetooth"))]
+mod platform {
+    const UNSUPPORTED: &str = "Bluetooth requires the `bluetooth` feature";
+
+    pub fn start_scan(_services: &[String]) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn stop_scan() -> Result<bool, String> {
+        Ok(false)
+    }
+
+    pub fn connect(_address: &str) -> Result<Vec<String>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn disconnect(_address: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn read(_address: &str, _service: &str, _characteristic: &str) -> Result<Vec<u8>, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn write(
+        _address: &str,
+        _service: &str,
+        _characteristic: &str,
+        _value: &[u8],
+        _with_response: bool,
+    ) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn subscribe(_address: &str, _service: &str, _characteristic: &str) -> Result<(), String> {
+        Err(UNSUPPORTED.to_string())
+    }
+
+    pub fn unsubscribe(
+        _address: &str,
+        _service: &str,
+        _characteristic: &str,
+    ) -> Result<bool, String> {
+        Err(UNSUPPORTED.to_string())
+    }
+}
+
+/// Starts LE discovery, optionally limited to devices advertising one of `services`.
+pub fn start_scan(services: &[String]) -> Result<(), String> {
+    let services = services
+        .iter()
+        .map(|s| uuid_arg(s))
+        .collect::<Result<Vec<_>, _>>()?;
+    platform::start_scan(&services)
+}
+
+/// Stops discovery. Returns whether a scan was running.
+pub fn stop_scan() -> Result<bool, String> {
+    platform::stop_scan()
+}
+
+/// Connects to a discovered device and returns its GATT service UUIDs.
+pub fn connect(address: &str) -> Result<Vec<String>, String> {
+    platform::connect(&normalize_address(address)?)
+}
+
+/// Disconnects a device; its subscriptions end.
+pub fn disconnect(address: &str) -> Result<(), String> {
+    platform::disconnect(&normalize_address(address)?)
+}
+
+/// Reads a characteristic value.
+pub fn read(address: &str, service: &str, characteristic: &str) -> Result<Vec<u8>, String> {
+    platform::read(
+        &normalize_address(address)?,
+        &uuid_arg(service)?,
+        &uuid_arg(characteristic)?,
+    )
+}
+
+/// Writes a characteristic value, with or without a response from the device.
+pub fn write(
+    address: &str,
+    service: &str,
+    characteristic: &str,
+    value: &[u8],
+    with_response: bool,
+) -> Result<(), String> {
+    platform::write(
+        &normalize_address(address)?,
+        &uuid_arg(service)?,
+        &uuid_arg(characteristic)?,
+        value,
+        with_response,
+    )
+}
+
+/// Starts `bluetooth-notification` events for a characteristic.
+pub fn subscribe(address: &str, service: &str, characteristic: &str) -> Result<(), String> {
+    platform::subscribe(
+        &normalize_address(address)?,
+        &uuid_arg(service)?,
+        &uuid_arg(characteristic)?,
+    )
+}
+
+/// Stops notifications. Returns whether the characteristic was subscribed.
+pub fn unsubscribe(address: &str, service: &str, characteristic: &str) -> Result<bool, String> {
+    platform::unsubscribe(
+        &normalize_address(address)?,
+        &uuid_arg(service)?,
+        &uuid_arg(characteristic)?,
+    )
+}
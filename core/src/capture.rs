@@ -0,0 +1,157 @@
+//! Window capture for `CaptureWindow` (bug reports, "share screenshot").
+//!
+//! WebKitGTK snapshots the visible WebView area asynchronously on the event loop thread; the
+//! result is converted from cairo's premultiplied ARGB and encoded as PNG. On Windows and macOS,
+//! `xcap` captures the whole native window (frame included), found by process id and title; macOS
+//! asks for the Screen Recording permission on first use.
+
+use std::path::PathBuf;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::grants::{self, Access};
+
+/// Encoded image format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureFormat {
+    #[default]
+    Png,
+}
+
+/// Where the encoded image goes: a granted file, or base64 in the response.
+enum Target {
+    File(PathBuf),
+    Inline,
+}
+
+/// Captures the WebView and passes `{ path | data, format, width, height }` to `done`, later, on
+/// the event loop thread. `path` must be granted for writing (e.g. from `SaveFileDialog`).
+pub fn capture(
+    window: &tao::window::Window,
+    webview: &wry::WebView,
+    path: Option<&str>,
+    format: CaptureFormat,
+    done: impl FnOnce(Result<serde_json::Value, String>) + 'static,
+) {
+    let target = match path {
+        Some(p) => match grants::check(std::path::Path::new(p), Access::Write) {
+            Ok(path) => Target::File(path),
+            Err(e) => return done(Err(e)),
+        },
+        None => Target::Inline,
+    };
+    #[cfg(target_os = "linux")]
+    {
+        use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+        use wry::WebViewExtUnix;
+        let _ = window;
+        webview.webview().snapshot(
+            SnapshotRegion::Visible,
+            SnapshotOptions::NONE,
+            None::<&webkit2gtk::gio::Cancellable>,
+            move |result| {
+                done(
+                    result
+                        .map_err(|e| format!("Snapshot failed: {}", e))
+                        .and_then(surface_to_rgba)
+                        .and_then(|image| deliver(&image, format, target)),
+                );
+            },
+        );
+    }
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        let _ = webview;
+        done(capture_native(&window.title()).and_then(|image| deliver(&image, format, target)));
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+    {
+        let _ = (window, webview, format, target, deliver);
+        done(Err(
+            "CaptureWindow is not supported on this platform".to_string()
+        ));
+    }
+}
+
+/// Captures this process's top-level window titled `title`.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn capture_native(title: &str) -> Result<image::RgbaImage, String> {
+    let pid = std::process::id();
+    let windows = xcap::Window::all().map_err(|e| format!("Could not list windows: {}", e))?;
+    let window = windows
+        .into_iter()
+        .find(|w| w.pid().ok() == Some(pid) && w.title().is_ok_and(|t| t == title))
+        .ok_or_else(|| "Window not found for capture".to_string())?;
+    window
+        .capture_image()
+        .map_err(|e| format!("Capture failed: {}", e))
+}
+
+/// Converts a cairo ARGB32 surface (premultiplied, native-endian words) to straight RGBA.
+#[cfg(target_os = "linux")]
+fn surface_to_rgba(surface: cairo::Surface) -> Result<image::RgbaImage, String> {
+    let mut surface = cairo::ImageSurface::try_from(surface)
+        .map_err(|_| "Snapshot is not an image surface".to_string())?;
+    let (width, height) = (
+        surface.width().max(0) as u32,
+        surface.height().max(0) as u32,
+    );
+    let stride = surface.stride().max(0) as usize;
+    let data = surface
+        .data()
+        .map_err(|e| format!("Snapshot data unavailable: {}", e))?;
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for row in data.chunks(stride).take(height as usize) {
+        for px in row[..width as usize * 4].chunks_exact(4) {
+            let argb = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+            let a = argb >> 24;
+            let straight = |c: u32| {
+                ((c & 0xff) * 255)
+                    .checked_div(a)
+                    .map_or(0, |v| v.min(255) as u8)
+            };
+            rgba.extend_from_slice(&[
+                straight(argb >> 16),
+                straight(argb >> 8),
+                straight(argb),
+                a as u8,
+            ]);
+        }
+    }
+    image::RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| "Snapshot size mismatch".to_string())
+}
+
+/// Encodes `image` and writes it to the target.
+fn deliver(
+    image: &image::RgbaImage,
+    format: CaptureFormat,
+    target: Target,
+) -> Result<serde_json::Value, String> {
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    match format {
+        CaptureFormat::Png => image
+            .write_to(&mut bytes, image::ImageFormat::Png)
+            .map_err(|e| format!("Could not encode PNG: {}", e))?,
+    }
+    let bytes = bytes.into_inner();
+    let mut out = serde_json::json!({
+        "format": format,
+        "width": image.width(),
+        "height": image.height(),
+    });
+    match target {
+        Target::File(path) => {
+            std::fs::write(&path, &bytes)
+                .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+            out["path"] = serde_json::json!(path.display().to_string());
+        }
+        Target::Inline => {
+            out["data"] =
+                serde_json::json!(base64::engine::general_purpose::STANDARD.encode(&bytes));
+        }
+    }
+    Ok(out)
+}
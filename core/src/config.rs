@@ -5,13 +5,59 @@
 
 use include_dir::include_dir;
 
-/// Max pending IPC responses before dropping new ones (backpressure).
-/// Also bounds IPC queue memory: at most this many response strings are queued at once.
+/// Product name (build env `DESKTOP_RUNTIME_PRODUCT_NAME`): window title, tray tooltip, and
+/// data dir names (see `paths`).
+pub const PRODUCT_NAME: &str = env!("APP_PRODUCT_NAME");
+
+/// Reverse-DNS app identifier (build env `DESKTOP_RUNTIME_BUNDLE_ID`).
+pub const BUNDLE_ID: &str = env!("APP_BUNDLE_ID");
+
+/// Default max pending IPC responses before backpressure applies (`ipcQueueCapacity` config
+/// key). Also bounds IPC queue memory: at most this many response strings are queued at once.
 pub const MAX_PENDING_IPC: usize = 256;
 
+/// Largest accepted `ipcQueueCapacity`.
+pub const MAX_IPC_QUEUE_CAPACITY: usize = 16 * 1024;
+
 /// Number of worker threads for blocking IPC commands (e.g. file dialog, update check).
 pub const IPC_WORKER_POOL_SIZE: usize = 4;
 
+/// Max response payload bytes per delivery script; larger drains are split across scripts.
+pub const IPC_SCRIPT_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Longest text accepted by `Speak`, in characters.
+pub const SPEECH_MAX_TEXT_CHARS: usize = 10_000;
+
+/// Max serial ports open at once (`SerialOpen`).
+pub const SERIAL_MAX_OPEN_PORTS: usize = 8;
+
+/// `SerialWrite` fails after this long without progress (e.g. hardware flow control holding the line).
+pub const SERIAL_WRITE_TIMEOUT_MS: u64 = 2000;
+
+/// Max entries in an archive read or written by `ExtractArchive` / `CreateArchive`.
+pub const ARCHIVE_MAX_ENTRIES: u64 = 100_000;
+
+/// Max bytes `ExtractArchive` writes, against archive bombs.
+pub const ARCHIVE_MAX_EXTRACTED_BYTES: u64 = 8 * 1024 * 1024 * 1024;
+
+/// `CheckForUpdates` answers from the cached result for this long after a successful check.
+pub const UPDATE_CHECK_MIN_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Max panels (child webviews) open in the main window at once (`OpenPanel`).
+pub const MAX_PANELS: usize = 4;
+
+/// Max concurrent `ScheduleTask` tasks.
+pub const SCHEDULED_TASK_LIMIT: usize = 64;
+
+/// Shortest allowed `ScheduleTask` interval, in milliseconds.
+pub const SCHEDULED_TASK_MIN_INTERVAL_MS: u64 = 1000;
+
+/// Seconds a completed request id is remembered so a UI retry is answered from cache.
+pub const IPC_DEDUP_WINDOW_SECS: u64 = 120;
+
+/// Max completed request ids remembered for retry deduplication.
+pub const IPC_DEDUP_CAPACITY: usize = 512;
+
 /// Initial window size (logical).
 pub const WINDOW_WIDTH: f64 = 800.0;
 
@@ -24,11 +70,63 @@ pub const WINDOW_MIN_WIDTH: f64 = 400.0;
 /// Minimum window height (logical).
 pub const WINDOW_MIN_HEIGHT: f64 = 300.0;
 
-/// Seconds to wait before showing the window if the first page load never fires.
-pub const SHOW_WINDOW_FALLBACK_SECS: u64 = 3;
+/// Restored window bounds must leave this much of the title bar on a display (physical pixels).
+pub const WINDOW_MIN_VISIBLE_PX: u32 = 64;
+
+/// Most automatic reloads after a WebView content process crash within
+/// `RENDERER_RESTART_WINDOW_SECS`; the next crash shows the load error page.
+pub const MAX_RENDERER_RESTARTS: usize = 3;
+
+/// Window over which `MAX_RENDERER_RESTARTS` is counted.
+pub const RENDERER_RESTART_WINDOW_SECS: u64 = 60;
+
+/// Most host events held for the UI before it sends `UiReady`; older ones are dropped first.
+pub const HOST_EVENT_BUFFER_LEN: usize = 256;
+
+/// CLI flag: start with the window hidden (tray only). Overrides the `startMode` config key.
+pub const ARG_HIDDEN: &str = "--hidden";
+
+/// CLI flag: start with the window minimized. Overrides the `startMode` config key.
+pub const ARG_MINIMIZED: &str = "--minimized";
 
 /// Env var: set to `"1"` to enable WebView DevTools.
 pub const ENV_DEVTOOLS: &str = "DESKTOP_RUNTIME_DEVTOOLS";
 
+/// Env var: set to `"1"` to require HMAC-signed IPC envelopes (per-session key via init script).
+pub const ENV_SIGNED_IPC: &str = "DESKTOP_RUNTIME_SIGNED_IPC";
+
+/// Env var: set to `"1"` in debug builds to serve `ui/dist` from disk and reload the WebView when
+/// it changes (pair with `npm run dev:watch`). Ignored in release builds.
+pub const ENV_DEV_RELOAD: &str = "DESKTOP_RUNTIME_DEV_RELOAD";
+
+/// Env var: localhost TCP port for the automation control port (see `automation`).
+pub const ENV_AUTOMATION_PORT: &str = "DESKTOP_RUNTIME_AUTOMATION_PORT";
+
+/// Env var: shared secret every automation request must carry. Required to open the port.
+pub const ENV_AUTOMATION_TOKEN: &str = "DESKTOP_RUNTIME_AUTOMATION_TOKEN";
+
+/// Interval between `ui/dist` change scans in dev reload mode.
+pub const DEV_RELOAD_POLL_MS: u64 = 500;
+
+/// Size limit (serialized key + value bytes) for a config namespace set via `WriteConfig`.
+/// A namespace is the part of the key before the first `.` (`ui.sidebar` → `ui`); keys without
+/// a dot share the `default` namespace.
+pub const CONFIG_NAMESPACE_QUOTA_BYTES: usize = 256 * 1024;
+
+/// Per-namespace overrides of `CONFIG_NAMESPACE_QUOTA_BYTES`.
+pub const CONFIG_NAMESPACE_QUOTAS: &[(&str, usize)] = &[("cache", 1024 * 1024)];
+
+/// Interval between `config.json` change checks (edits by other instances or by hand).
+pub const CONFIG_WATCH_POLL_MS: u64 = 1000;
+
+/// Interval between cursor position checks while `WatchCursorLeave` is on.
+pub const CURSOR_POLL_MS: u64 = 100;
+
+/// Tracked temp files (see `paths::temp_file`) older than this are deleted at startup and exit.
+pub const TEMP_FILE_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+
 /// Embedded UI directory (must match `ui/dist` at build time).
 pub static UI: include_dir::Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../ui/dist");
+
+/// Embedded translation bundles (`ui/locales/<tag>.json`, flat key → string).
+pub static LOCALES: include_dir::Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../ui/locales");
@@ -0,0 +1,83 @@
+//! Global cursor position (`GetCursorPosition`) and the opt-in `cursor-left-window` event.
+//!
+//! Pointer events stop at the window's edge, so a popover-style window cannot tell where the
+//! pointer went. After `WatchCursorLeave { on: true }` the event loop polls the global cursor
+//! position every `CURSOR_POLL_MS` and emits `cursor-left-window` (`{ x, y }`, physical screen
+//! pixels) each time it moves from inside the main window's outer bounds to outside. Off by
+//! default, so the loop does not wake for it. Wayland reports no global position (always 0,0),
+//! so there the event does not fire.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tao::window::Window;
+
+use crate::config::CURSOR_POLL_MS;
+use crate::window::PixelRect;
+
+struct Watch {
+    /// Whether the cursor was inside at the last poll; unknown until the first one.
+    inside: Option<bool>,
+    next: Instant,
+}
+
+static WATCH: Mutex<Option<Watch>> = Mutex::new(None);
+
+/// Outer bounds of `window` in physical screen pixels.
+fn window_rect(window: &Window) -> Option<PixelRect> {
+    let pos = window.outer_position().ok()?;
+    let size = window.outer_size();
+    Some(PixelRect {
+        x: pos.x,
+        y: pos.y,
+        width: size.width,
+        height: size.height,
+    })
+}
+
+/// True when the cursor at physical screen position (`x`, `y`) is over `rect`.
+#[must_use]
+pub fn is_inside(rect: PixelRect, x: f64, y: f64) -> bool {
+    rect.contains(x.floor() as i32, y.floor() as i32)
+}
+
+/// Global cursor position and whether it is over `window`.
+pub fn position(window: &Window) -> Result<serde_json::Value, String> {
+    let pos = window
+        .cursor_position()
+        .map_err(|e| format!("Could not get cursor position: {}", e))?;
+    let inside = window_rect(window).is_some_and(|rect| is_inside(rect, pos.x, pos.y));
+    Ok(serde_json::json!({ "x": pos.x, "y": pos.y, "insideWindow": inside }))
+}
+
+/// Starts or stops the `cursor-left-window` polling.
+pub fn set_watching(on: bool) {
+    let mut watch = WATCH.lock().unwrap_or_else(|e| e.into_inner());
+    *watch = on.then(|| Watch {
+        inside: None,
+        next: Instant::now(),
+    });
+}
+
+/// Polls the cursor if due and emits `cursor-left-window` when it left `window`. Returns the
+/// next poll time while watching, for the event loop's wake-up deadline.
+pub fn poll(window: &Window) -> Option<Instant> {
+    let mut guard = WATCH.lock().unwrap_or_else(|e| e.into_inner());
+    let watch = guard.as_mut()?;
+    let now = Instant::now();
+    if now < watch.next {
+        return Some(watch.next);
+    }
+    watch.next = now + Duration::from_millis(CURSOR_POLL_MS);
+    if let (Ok(pos), Some(rect)) = (window.cursor_position(), window_rect(window)) {
+        let inside = is_inside(rect, pos.x, pos.y);
+        if watch.inside == Some(true) && !inside {
+            crate::events::emit(
+                "cursor-left-window",
+                serde_json::json!({ "x": pos.x, "y": pos.y }),
+            );
+        }
+        watch.inside = Some(inside);
+    }
+    Some(watch.next)
+}
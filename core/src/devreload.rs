@@ -0,0 +1,74 @@
+//! Live reload for frontend iteration (debug builds only).
+//!
+//! With `DESKTOP_RUNTIME_DEV_RELOAD=1`, app:// serves `ui/dist` from disk instead of the embedded
+//! copy, and a background thread polls the directory; when its newest modification time changes
+//! (e.g. `vite build --watch` finished a rebuild) it sends `UserEvent::Reload` to the main loop.
+
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use tao::event_loop::EventLoopProxy;
+
+use crate::config::{DEV_RELOAD_POLL_MS, ENV_DEV_RELOAD};
+use crate::event_loop::UserEvent;
+
+/// True when dev reload is requested and this is a debug build.
+#[must_use]
+pub fn enabled() -> bool {
+    cfg!(debug_assertions) && std::env::var(ENV_DEV_RELOAD).as_deref() == Ok("1")
+}
+
+/// On-disk `ui/dist` of the source tree this binary was built from.
+#[must_use]
+pub fn dist_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("ui")
+        .join("dist")
+}
+
+/// Newest modification time of any file under `dir` (recursive). `None` if unreadable or empty.
+fn newest_mtime(dir: &Path) -> Option<SystemTime> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            if meta.is_dir() {
+                newest_mtime(&entry.path())
+            } else {
+                meta.modified().ok()
+            }
+        })
+        .max()
+}
+
+/// Spawns the watcher thread. Reloads once the tree has been stable for one poll interval, so a
+/// rebuild that writes many files triggers a single reload.
+pub fn watch(proxy: EventLoopProxy<UserEvent>) {
+    let dir = dist_dir();
+    log::info!("Dev reload: serving and watching {}", dir.display());
+    thread::spawn(move || {
+        let interval = Duration::from_millis(DEV_RELOAD_POLL_MS);
+        let mut loaded = newest_mtime(&dir);
+        let mut pending: Option<SystemTime> = None;
+        loop {
+            thread::sleep(interval);
+            let current = newest_mtime(&dir);
+            if current == loaded {
+                pending = None;
+                continue;
+            }
+            if pending.is_some() && pending == current {
+                loaded = current;
+                pending = None;
+                if proxy.send_event(UserEvent::Reload).is_err() {
+                    return;
+                }
+            } else {
+                pending = current;
+            }
+        }
+    });
+}
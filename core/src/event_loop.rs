@@ -3,105 +3,353 @@
 //! Owns `UserEvent`, `run_event_loop`, and the JSON escape helper used only
 //! when dispatching IPC responses back to the WebView.
 //! IPC responses are batched: producers push to a queue and send `IpcFlush`;
-//! the main loop drains the queue and delivers all in one `evaluate_script`.
+//! the main loop drains the queue and delivers each window's responses to its WebView in as few
+//! `evaluate_script` calls as possible.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
+use tao::dpi::LogicalSize;
+
+use crate::automation::AutomationRequest;
+use crate::config::{
+    HOST_EVENT_BUFFER_LEN, IPC_SCRIPT_CHUNK_BYTES, PRODUCT_NAME, TEMP_FILE_MAX_AGE_SECS,
+    WINDOW_MIN_HEIGHT, WINDOW_MIN_WIDTH,
+};
+use crate::events::HostEventBuffer;
+use crate::grants;
+use crate::i18n;
+use crate::ipc::{
+    Command, IpcEnvelope, IpcLimits, IpcReceiver, IpcSender, IpcSlot, handle_window_command,
+    is_deferred_window_command, respond, respond_json, start_deferred_window_command,
+};
+use crate::metrics;
+use crate::paths;
+use crate::popover::Popover;
+use crate::priority::VisibilityFollower;
+use crate::protocol::{APP_ORIGIN, INDEX_PATH, LOAD_ERROR_PATH};
+use crate::renderer::RestartBudget;
+use crate::scheduler;
+use crate::session;
+use crate::settings::Settings;
 use crate::storage;
+use crate::watchdog::Watchdog;
+use crate::window::{Children, MODAL_WINDOW, PixelRect, ViewContext};
 
 /// User-defined events sent from background threads or IPC into the main loop.
 #[allow(dead_code)]
 pub enum UserEvent {
     /// Wake to drain the IPC response queue and deliver a batch to the WebView.
     IpcFlush,
-    /// Request to show the window (after first load or fallback timeout).
+    /// Request to show the window (tray "Show", `ShowWindow`, `UiReady`, or the readiness timeout).
     ShowWindow,
-    /// Hide window (e.g. minimize to tray).
+    /// Hide window (e.g. minimize to tray, or the UI's `HideWindow`).
     HideWindow,
+    /// Left click on the tray icon in popover mode, with the icon's bounds (physical pixels).
+    TrayClick(PixelRect),
     /// Exit the application.
     Quit,
+    /// Run a window command on the loop thread and queue its response in the reserved slot.
+    WindowCommand(IpcEnvelope, IpcSlot),
+    /// Reload the WebView (dev reload after `ui/dist` changed, or the watchdog).
+    Reload,
+    /// `uiReadyTimeoutMs` passed after the page loaded without a `UiReady`.
+    UiReadyTimeout,
+    /// The main document failed to load (error status, or no page load before the timeout).
+    LoadFailed(String),
+    /// The main WebView's content process died, with the reason (see `renderer`).
+    RendererGone(String),
+    /// Memory pressure: clear the WebView's caches (see `memory`).
+    TrimMemory,
+    /// Request from the automation control port.
+    Automation(AutomationRequest),
+    /// Push a named event to the UI (see `events::emit`).
+    HostEvent {
+        name: String,
+        payload: serde_json::Value,
+    },
+    /// Consent dialog answer for a held camera/microphone request (see `permissions`).
+    MediaPermission { request: u64, allow: bool },
+}
+
+struct Timer {
+    at: Instant,
+    /// Insertion order, so timers with equal deadlines run first-in first-out.
+    seq: u64,
+    action: Box<dyn FnOnce()>,
+}
+
+impl PartialEq for Timer {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Timer {}
+
+impl PartialOrd for Timer {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Timer {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.at, self.seq).cmp(&(other.at, other.seq))
+    }
+}
+
+/// One-shot callbacks run on the loop thread at a deadline. The loop waits with
+/// `ControlFlow::WaitUntil` for the earliest one instead of each delay holding a sleeping thread.
+#[derive(Default)]
+pub struct Timers {
+    heap: BinaryHeap<Reverse<Timer>>,
+    next_seq: u64,
 }
 
-/// Escapes a JSON string for safe embedding inside a JS string (backslash, quote, newline, carriage return).
-/// Avoids allocation when the string contains none of these characters.
+impl Timers {
+    /// Runs `action` on the loop thread once `at` has passed.
+    pub fn add(&mut self, at: Instant, action: impl FnOnce() + 'static) {
+        self.next_seq += 1;
+        self.heap.push(Reverse(Timer {
+            at,
+            seq: self.next_seq,
+            action: Box::new(action),
+        }));
+    }
+
+    /// Runs every timer due at `now`, earliest first. Returns the next deadline, if any.
+    pub fn run_due(&mut self, now: Instant) -> Option<Instant> {
+        while self.heap.peek().is_some_and(|Reverse(t)| t.at <= now) {
+            if let Some(Reverse(timer)) = self.heap.pop() {
+                (timer.action)();
+            }
+        }
+        self.heap.peek().map(|Reverse(t)| t.at)
+    }
+}
+
+/// Makes serialized JSON safe to embed as a JS expression. JSON is already valid JS except that
+/// older engines reject raw U+2028/U+2029 in string literals, so only those are escaped.
+/// Avoids allocation when neither is present.
 #[must_use]
-pub fn escape_json_for_js(s: &str) -> std::borrow::Cow<'_, str> {
-    if !s.contains(['\\', '"', '\n', '\r']) {
-        return std::borrow::Cow::Borrowed(s);
+pub fn json_literal(json: &str) -> std::borrow::Cow<'_, str> {
+    if !json.contains(['\u{2028}', '\u{2029}']) {
+        return std::borrow::Cow::Borrowed(json);
     }
-    let mut out = String::with_capacity(s.len() * 2);
-    for c in s.chars() {
-        match c {
-            '\\' => out.push_str("\\\\"),
-            '"' => out.push_str("\\\""),
-            '\n' => out.push_str("\\n"),
-            '\r' => out.push_str("\\r"),
-            other => out.push(other),
+    std::borrow::Cow::Owned(
+        json.replace('\u{2028}', "\\u2028")
+            .replace('\u{2029}', "\\u2029"),
+    )
+}
+
+/// Builds delivery scripts for a batch of serialized responses. Responses are passed to
+/// `window.__resolveIpcBatch` as array literals (no `JSON.parse`, no string escaping), split so
+/// each script stays under `max_bytes` of payload; a single larger response gets its own script.
+#[must_use]
+pub fn response_scripts(batch: &[String], key: &str, max_bytes: usize) -> Vec<String> {
+    let open = format!(
+        r#"if (window.__resolveIpcBatch) {{ window.__resolveIpcBatch("{}", ["#,
+        key
+    );
+    let close = "]); }";
+    let mut scripts = Vec::new();
+    let mut script = String::new();
+    for response_json in batch {
+        let literal = json_literal(response_json);
+        if !script.is_empty() && script.len() - open.len() + literal.len() > max_bytes {
+            script.push_str(close);
+            scripts.push(std::mem::take(&mut script));
+        }
+        if script.is_empty() {
+            script.push_str(&open);
+        } else {
+            script.push(',');
         }
+        script.push_str(&literal);
     }
-    std::borrow::Cow::Owned(out)
+    if !script.is_empty() {
+        script.push_str(close);
+        scripts.push(script);
+    }
+    scripts
 }
 
-/// Drains the IPC queue and runs one script to deliver all responses. Returns true if any were delivered.
-/// Recovers from mutex poison so the queue can be drained and memory released.
+/// Drains the IPC queue (up to `batch_size` responses; the rest wait for the next iteration) and
+/// delivers them in size-bounded scripts, each to the window that sent the request; responses for
+/// windows that have closed are dropped. Returns true if any were drained.
 fn drain_ipc_queue_and_deliver(
-    queue: &Mutex<Vec<String>>,
-    pending_ipc: &AtomicUsize,
+    queue: &IpcReceiver,
     webview: &wry::WebView,
+    children: &Children,
+    batch_size: usize,
 ) -> bool {
-    let batch: Vec<String> = {
-        let mut q = queue.lock().unwrap_or_else(|e| {
-            log::error!("IPC queue mutex was poisoned, recovering");
-            e.into_inner()
-        });
-        std::mem::take(&mut *q)
-    };
+    let batch = queue.drain(batch_size);
     let n = batch.len();
     if n == 0 {
         return false;
     }
-    let to_sub = n.min(pending_ipc.load(Ordering::Relaxed));
-    pending_ipc.fetch_sub(to_sub, Ordering::Relaxed);
 
-    let mut script = String::from("if (window.__resolveIpc) { ");
-    for response_json in batch {
-        let escaped = escape_json_for_js(&response_json);
-        script.push_str(&format!(
-            r#"try {{ var r = JSON.parse("{}"); window.__resolveIpc(r.id, r); }} catch(e) {{}}"#,
-            escaped
-        ));
+    let mut main = Vec::with_capacity(n);
+    let mut others: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for response in batch {
+        match response.window {
+            None => main.push(response.json),
+            Some(window) => others.entry(window).or_default().push(response.json),
+        }
     }
-    script.push_str(" }");
-    if let Err(e) = webview.evaluate_script(&script) {
-        log::warn!("IPC evaluate_script failed: {}", e);
+    deliver_responses(webview, &main);
+    for (window, responses) in others {
+        match children.webview(&window) {
+            Some(target) => deliver_responses(target, &responses),
+            None => log::debug!(
+                "IPC: dropping {} response(s) for closed window {}",
+                responses.len(),
+                window
+            ),
+        }
     }
     true
 }
 
+fn deliver_responses(webview: &wry::WebView, batch: &[String]) {
+    let key = crate::window::delivery_key();
+    for script in response_scripts(batch, key, IPC_SCRIPT_CHUNK_BYTES) {
+        if let Err(e) = webview.evaluate_script(&script) {
+            log::warn!("IPC evaluate_script failed: {}", e);
+        }
+    }
+}
+
+/// Queues one IPC response in its reserved slot for the next drain. Called on the loop thread,
+/// so no wake-up is needed: `MainEventsCleared` drains the queue.
+fn enqueue_response(queue: &IpcSender, slot: IpcSlot, window: Option<String>, json: String) {
+    queue.send(slot, window, json);
+}
+
+/// Position and size of `window` when it is neither maximized nor fullscreen.
+fn normal_bounds(window: &tao::window::Window) -> Option<(i32, i32, u32, u32)> {
+    if window.is_maximized() || window.fullscreen().is_some() {
+        return None;
+    }
+    let pos = window.outer_position().ok()?;
+    let size = window.inner_size();
+    Some((pos.x, pos.y, size.width, size.height))
+}
+
+/// Saves the window's bounds with its maximized and fullscreen state. While maximized or
+/// fullscreen, the saved bounds are `normal`, the last ones seen outside those states.
+fn save_bounds(window: &tao::window::Window, normal: Option<(i32, i32, u32, u32)>) {
+    let current = window.outer_position().ok().map(|pos| {
+        let size = window.inner_size();
+        (pos.x, pos.y, size.width, size.height)
+    });
+    let Some((x, y, width, height)) = normal_bounds(window).or(normal).or(current) else {
+        return;
+    };
+    storage::save_window_bounds(storage::WindowBounds {
+        x,
+        y,
+        width,
+        height,
+        maximized: window.is_maximized(),
+        fullscreen: window.fullscreen().is_some(),
+        scale_factor: Some(window.scale_factor()),
+    });
+}
+
+/// Delivers one host event to `window.__onHostEvent` in the WebView.
+pub(crate) fn deliver_host_event(webview: &wry::WebView, name: &str, payload: &serde_json::Value) {
+    let event = serde_json::json!({ "event": name, "data": payload });
+    let Ok(json) = serde_json::to_string(&event) else {
+        return;
+    };
+    let script = format!(
+        r#"if (window.__onHostEvent) {{ try {{ var e = {}; window.__onHostEvent("{}", e.event, e.data); }} catch(e) {{}} }}"#,
+        json_literal(&json),
+        crate::window::delivery_key()
+    );
+    if let Err(e) = webview.evaluate_script(&script) {
+        log::warn!("Host event evaluate_script failed: {}", e);
+    }
+}
+
 /// Runs the tao event loop until exit.
 ///
-/// Keeps `web_context`, `window`, and `_tray_icon` alive for the lifetime of `webview`.
+/// Keeps `views` (the shared web context), `window`, and `_tray_icon` alive for the lifetime of
+/// `webview`. Panels and modals built from `views` live here too.
+/// `splash` (if any) is dropped when the main window is first shown. No tray icon is created
+/// unless `tray` is set; with `popover` the main window is a tray popover (see `popover`). With
+/// `show_when_ready` the main window is shown on the first `UiReady`, `UiReadyTimeout`, or
+/// `LoadFailed` (which also loads the error page at `LOAD_ERROR_PATH`).
 /// Uses `ControlFlow::Poll` after draining IPC so the loop re-runs immediately
-/// when there is pending work; otherwise `Wait` to avoid busy-waiting.
+/// when there is pending work; otherwise `Wait` (or `WaitUntil` the next of `timers`, path grant
+/// expiry, scheduled task, cursor poll, or `watchdog` ping) to avoid busy-waiting.
+#[allow(clippy::too_many_arguments)]
 pub fn run_event_loop(
     event_loop: tao::event_loop::EventLoop<UserEvent>,
     webview: wry::WebView,
     window: tao::window::Window,
-    _web_context: wry::WebContext,
+    mut splash: Option<crate::window::Splash>,
+    mut views: ViewContext,
     event_proxy: tao::event_loop::EventLoopProxy<UserEvent>,
-    pending_ipc: Arc<AtomicUsize>,
-    ipc_queue: Arc<Mutex<Vec<String>>>,
+    ipc_sender: IpcSender,
+    ipc_queue: Rc<IpcReceiver>,
+    ipc_limits: IpcLimits,
+    mut timers: Timers,
+    tray: bool,
+    popover: bool,
+    show_when_ready: bool,
+    mut watchdog: Option<Watchdog>,
 ) {
     let mut tray_icon_holder: Option<tray_icon::TrayIcon> = None;
     let show_proxy = event_proxy.clone();
     let quit_proxy = event_proxy.clone();
+    let mut children = Children::default();
+    let mut tray_popover = popover.then(Popover::default);
+    // Host events wait here until the main page sends `UiReady` (see `events`).
+    let mut host_events = HostEventBuffer::new(HOST_EVENT_BUFFER_LEN);
+    // Cleared by the first reveal, so later `UiReady`s (reloads) leave the window alone.
+    let mut awaiting_ui = show_when_ready;
+    let mut renderer_restarts = RestartBudget::default();
+    // Last seen main window visibility, for `backgroundEfficiency`.
+    let mut efficiency = VisibilityFollower::new(window.is_visible());
+    // Last bounds outside maximized/fullscreen, saved in their place on close.
+    let mut normal = normal_bounds(&window);
 
-    event_loop.run(move |event, _event_loop, control_flow| {
+    event_loop.run(move |event, target, control_flow| {
         *control_flow = tao::event_loop::ControlFlow::Wait;
 
+        if let tao::event::Event::NewEvents(_) = event {
+            metrics::record_wakeup();
+        }
+
+        // Run due timers, revoke expired path grants, fire due scheduled tasks, poll the cursor when
+        // watched, ping the page, and wake again at the next deadline.
+        let timer_deadline = timers.run_due(Instant::now());
+        let grant_deadline = grants::sweep_expired();
+        let task_deadline = scheduler::fire_due();
+        let cursor_deadline = crate::cursor::poll(&window);
+        let watchdog_deadline = watchdog.as_mut().map(|w| w.poll(&window, &webview));
+        if let Some(deadline) = [
+            timer_deadline,
+            grant_deadline,
+            task_deadline,
+            cursor_deadline,
+            watchdog_deadline,
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+        {
+            *control_flow = tao::event_loop::ControlFlow::WaitUntil(deadline);
+        }
+
         // Create tray icon on first run (required on macOS: event loop must be running).
-        if tray_icon_holder.is_none()
+        if tray
+            && tray_icon_holder.is_none()
             && let Some(icon) = crate::window::tray_icon()
         {
             let proxy = event_proxy.clone();
@@ -111,22 +359,39 @@ pub fn run_event_loop(
             let quit_id = tray_icon::menu::MenuId::new("quit");
             menu.append(&tray_icon::menu::MenuItem::with_id(
                 show_id.clone(),
-                "Show",
+                i18n::t("tray.show"),
                 true,
                 None,
             ))
             .ok();
             menu.append(&tray_icon::menu::MenuItem::with_id(
                 quit_id.clone(),
-                "Quit",
+                i18n::t("tray.quit"),
                 true,
                 None,
             ))
             .ok();
             let sp = show_proxy.clone();
-            tray_icon::TrayIconEvent::set_event_handler(Some(move |_| {
-                let _ = sp.send_event(UserEvent::ShowWindow);
-            }));
+            tray_icon::TrayIconEvent::set_event_handler(Some(
+                move |event: tray_icon::TrayIconEvent| {
+                    if !popover {
+                        let _ = sp.send_event(UserEvent::ShowWindow);
+                    } else if let tray_icon::TrayIconEvent::Click {
+                        rect,
+                        button: tray_icon::MouseButton::Left,
+                        button_state: tray_icon::MouseButtonState::Up,
+                        ..
+                    } = event
+                    {
+                        let _ = sp.send_event(UserEvent::TrayClick(PixelRect {
+                            x: rect.position.x as i32,
+                            y: rect.position.y as i32,
+                            width: rect.size.width,
+                            height: rect.size.height,
+                        }));
+                    }
+                },
+            ));
             tray_icon::menu::MenuEvent::set_event_handler(Some(
                 move |event: tray_icon::menu::MenuEvent| {
                     if event.id == show_id {
@@ -138,7 +403,8 @@ pub fn run_event_loop(
             ));
             if let Ok(tray) = tray_icon::TrayIconBuilder::new()
                 .with_menu(Box::new(menu))
-                .with_tooltip("Desktop Runtime")
+                .with_menu_on_left_click(!popover)
+                .with_tooltip(PRODUCT_NAME)
                 .with_icon(icon)
                 .build()
             {
@@ -149,16 +415,266 @@ pub fn run_event_loop(
         if let tao::event::Event::UserEvent(ev) = event {
             match ev {
                 UserEvent::ShowWindow => {
-                    window.set_visible(true);
+                    drop(splash.take());
+                    match &mut tray_popover {
+                        Some(tray_popover) => tray_popover.show_at_cursor(&window),
+                        None => window.set_visible(true),
+                    }
                 }
-                UserEvent::HideWindow => {
-                    window.set_visible(false);
+                UserEvent::TrayClick(anchor) => {
+                    if let Some(tray_popover) = &mut tray_popover {
+                        tray_popover.toggle(&window, anchor);
+                    }
                 }
+                UserEvent::HideWindow => match &mut tray_popover {
+                    Some(tray_popover) => tray_popover.hide(&window),
+                    None => window.set_visible(false),
+                },
                 UserEvent::Quit => {
                     *control_flow = tao::event_loop::ControlFlow::Exit;
                 }
+                UserEvent::WindowCommand(
+                    IpcEnvelope {
+                        id,
+                        window_id,
+                        command: Command::OpenModal { url, width, height },
+                    },
+                    slot,
+                ) => {
+                    let (queue, proxy) = (ipc_sender.clone(), event_proxy.clone());
+                    let done = Box::new(move |result| {
+                        if let Some(json) = respond_json(id, || result) {
+                            enqueue_response(&queue, slot, window_id, json);
+                            let _ = proxy.send_event(UserEvent::IpcFlush);
+                        }
+                    });
+                    children
+                        .modals
+                        .open(target, &window, &mut views, &url, width, height, done);
+                }
+                UserEvent::WindowCommand(
+                    IpcEnvelope {
+                        id,
+                        window_id,
+                        command: Command::ResolveModal { result },
+                    },
+                    slot,
+                ) => {
+                    let resolved = || {
+                        if window_id.as_deref() != Some(MODAL_WINDOW) {
+                            return Err(
+                                "ResolveModal must be sent from the modal window".to_string()
+                            );
+                        }
+                        children.modals.finish(&window, Some(result));
+                        Ok(serde_json::json!({ "resolved": true }))
+                    };
+                    if let Some(json) = respond_json(id, resolved) {
+                        enqueue_response(&ipc_sender, slot, window_id.clone(), json);
+                    }
+                }
+                UserEvent::WindowCommand(
+                    IpcEnvelope {
+                        id,
+                        window_id,
+                        command: Command::UiReady,
+                    },
+                    slot,
+                ) => {
+                    let replay = || {
+                        if window_id.is_some() {
+                            return Err(
+                                "UiReady must be sent from the main window's page".to_string()
+                            );
+                        }
+                        if std::mem::take(&mut awaiting_ui) {
+                            let _ = event_proxy.send_event(UserEvent::ShowWindow);
+                        }
+                        let held = host_events.ready();
+                        for (name, payload) in &held {
+                            deliver_host_event(&webview, name, payload);
+                            for child in children.webviews() {
+                                deliver_host_event(child, name, payload);
+                            }
+                        }
+                        Ok(serde_json::json!({ "replayed": held.len() }))
+                    };
+                    if let Some(json) = respond_json(id, replay) {
+                        enqueue_response(&ipc_sender, slot, window_id.clone(), json);
+                    }
+                }
+                UserEvent::WindowCommand(
+                    IpcEnvelope {
+                        id,
+                        window_id,
+                        command: command @ (Command::ShowWindow | Command::HideWindow),
+                    },
+                    slot,
+                ) => {
+                    let visible = matches!(command, Command::ShowWindow);
+                    let _ = event_proxy.send_event(if visible {
+                        UserEvent::ShowWindow
+                    } else {
+                        UserEvent::HideWindow
+                    });
+                    if let Some(json) =
+                        respond_json(id, || Ok(serde_json::json!({ "visible": visible })))
+                    {
+                        enqueue_response(&ipc_sender, slot, window_id, json);
+                    }
+                }
+                UserEvent::WindowCommand(envelope, slot)
+                    if is_deferred_window_command(&envelope.command) =>
+                {
+                    let IpcEnvelope {
+                        id,
+                        window_id,
+                        command,
+                    } = envelope;
+                    let (queue, proxy) = (ipc_sender.clone(), event_proxy.clone());
+                    start_deferred_window_command(&window, &webview, command, move |result| {
+                        if let Some(json) = respond_json(id, || result) {
+                            enqueue_response(&queue, slot, window_id, json);
+                            let _ = proxy.send_event(UserEvent::IpcFlush);
+                        }
+                    });
+                }
+                UserEvent::WindowCommand(
+                    IpcEnvelope {
+                        id,
+                        window_id,
+                        command,
+                    },
+                    slot,
+                ) => {
+                    let handled = || {
+                        handle_window_command(
+                            &window,
+                            &webview,
+                            &mut views,
+                            &mut children,
+                            &command,
+                        )
+                    };
+                    if let Some(json) = respond_json(id, handled) {
+                        enqueue_response(&ipc_sender, slot, window_id, json);
+                    }
+                }
+                UserEvent::Reload => {
+                    host_events.reset();
+                    if let Some(watchdog) = &mut watchdog {
+                        watchdog.reset();
+                    }
+                    if let Err(e) = webview.reload() {
+                        log::warn!("Reload failed: {}", e);
+                    }
+                }
+                UserEvent::UiReadyTimeout => {
+                    if std::mem::take(&mut awaiting_ui) {
+                        log::warn!("UI did not send UiReady in time; showing the window");
+                        let _ = event_proxy.send_event(UserEvent::ShowWindow);
+                    }
+                }
+                UserEvent::LoadFailed(reason) => {
+                    log::error!("Main page failed to load: {}", reason);
+                    let url = format!("{}{}", APP_ORIGIN, LOAD_ERROR_PATH);
+                    if let Err(e) = webview.load_url(&url) {
+                        log::warn!("Could not load the error page: {}", e);
+                    }
+                    if std::mem::take(&mut awaiting_ui) {
+                        let _ = event_proxy.send_event(UserEvent::ShowWindow);
+                    }
+                }
+                UserEvent::RendererGone(reason) => {
+                    log::error!("WebView content process gone ({})", reason);
+                    host_events.reset();
+                    if let Some(watchdog) = &mut watchdog {
+                        watchdog.reset();
+                    }
+                    if renderer_restarts.try_restart(Instant::now()) {
+                        if let Err(e) = webview.load_url(&format!("{}/{}", APP_ORIGIN, INDEX_PATH))
+                        {
+                            log::warn!("Could not reload after the content process died: {}", e);
+                        }
+                        let restarts = renderer_restarts.count();
+                        crate::events::emit(
+                            "renderer-restarted",
+                            serde_json::json!({ "reason": reason, "restarts": restarts }),
+                        );
+                    } else {
+                        let _ = event_proxy.send_event(UserEvent::LoadFailed(format!(
+                            "content process gone ({}) too often",
+                            reason
+                        )));
+                    }
+                }
+                UserEvent::TrimMemory => {
+                    crate::memory::trim_webview(&webview);
+                }
+                UserEvent::Automation(AutomationRequest::Eval { script, reply }) => {
+                    let error_reply = reply.clone();
+                    if let Err(e) = webview.evaluate_script_with_callback(&script, move |result| {
+                        let _ = reply.send(result);
+                    }) {
+                        let _ = error_reply
+                            .send(serde_json::json!({ "error": e.to_string() }).to_string());
+                    }
+                }
+                UserEvent::Automation(AutomationRequest::WindowCommand {
+                    envelope:
+                        IpcEnvelope {
+                            id,
+                            command: Command::OpenModal { url, width, height },
+                            ..
+                        },
+                    reply,
+                }) => {
+                    let done = Box::new(move |result| {
+                        let _ = reply.send(respond(id, || result));
+                    });
+                    children
+                        .modals
+                        .open(target, &window, &mut views, &url, width, height, done);
+                }
+                UserEvent::Automation(AutomationRequest::WindowCommand { envelope, reply })
+                    if is_deferred_window_command(&envelope.command) =>
+                {
+                    let IpcEnvelope { id, command, .. } = envelope;
+                    start_deferred_window_command(&window, &webview, command, move |result| {
+                        let _ = reply.send(respond(id, || result));
+                    });
+                }
+                UserEvent::Automation(AutomationRequest::WindowCommand { envelope, reply }) => {
+                    let resp = respond(envelope.id, || {
+                        handle_window_command(
+                            &window,
+                            &webview,
+                            &mut views,
+                            &mut children,
+                            &envelope.command,
+                        )
+                    });
+                    let _ = reply.send(resp);
+                }
+                UserEvent::HostEvent { name, payload } => {
+                    let Some((name, payload)) = host_events.hold(name, payload) else {
+                        return;
+                    };
+                    deliver_host_event(&webview, &name, &payload);
+                    for child in children.webviews() {
+                        deliver_host_event(child, &name, &payload);
+                    }
+                }
+                UserEvent::MediaPermission { request, allow } => {
+                    crate::permissions::resolve(request, allow);
+                }
                 UserEvent::IpcFlush => {
-                    let had_work = drain_ipc_queue_and_deliver(&ipc_queue, &pending_ipc, &webview);
+                    let had_work = drain_ipc_queue_and_deliver(
+                        &ipc_queue,
+                        &webview,
+                        &children,
+                        ipc_limits.batch_size,
+                    );
                     if had_work {
                         *control_flow = tao::event_loop::ControlFlow::Poll;
                     }
@@ -167,25 +683,88 @@ pub fn run_event_loop(
             return;
         }
 
+        // Modal windows: closing dismisses; the parent handing focus back goes to the modal.
+        if let tao::event::Event::WindowEvent {
+            window_id,
+            ref event,
+            ..
+        } = event
+        {
+            if children.modals.is_modal(window_id) {
+                if matches!(event, tao::event::WindowEvent::CloseRequested) {
+                    children.modals.finish(&window, None);
+                }
+                return;
+            }
+            if matches!(event, tao::event::WindowEvent::Focused(true)) {
+                children.modals.refocus();
+            }
+            // The popover hides when focus goes elsewhere, except to its own modal.
+            if matches!(event, tao::event::WindowEvent::Focused(false))
+                && children.modals.webview().is_none()
+                && let Some(tray_popover) = &mut tray_popover
+            {
+                tray_popover.hide(&window);
+            }
+        }
+
+        // Moved to a display with another scale: the min size is logical, so re-apply it at the
+        // new scale, and tell the UI (it may swap raster assets).
+        if let tao::event::Event::WindowEvent {
+            event: tao::event::WindowEvent::ScaleFactorChanged { scale_factor, .. },
+            ..
+        } = event
+        {
+            window.set_min_inner_size(Some(LogicalSize::new(WINDOW_MIN_WIDTH, WINDOW_MIN_HEIGHT)));
+            crate::events::emit(
+                "scale-factor-changed",
+                serde_json::json!({ "scaleFactor": scale_factor }),
+            );
+        }
+
+        if let tao::event::Event::WindowEvent {
+            event: tao::event::WindowEvent::Moved(_) | tao::event::WindowEvent::Resized(_),
+            ..
+        } = event
+            && let Some(bounds) = normal_bounds(&window)
+        {
+            normal = Some(bounds);
+        }
+
         if let tao::event::Event::WindowEvent {
             event: tao::event::WindowEvent::CloseRequested,
             ..
         } = event
         {
-            if let Ok(pos) = window.outer_position() {
-                let size = window.inner_size();
-                storage::save_window_bounds(pos.x, pos.y, size.width, size.height);
+            save_bounds(&window, normal);
+            if let Some(tray_popover) = &mut tray_popover {
+                tray_popover.hide(&window);
+                return;
+            }
+            if tray_icon_holder.is_some() && Settings::load().close_to_tray {
+                window.set_visible(false);
+                return;
             }
             *control_flow = tao::event_loop::ControlFlow::Exit;
             return;
         }
 
         if let tao::event::Event::MainEventsCleared = event {
-            if drain_ipc_queue_and_deliver(&ipc_queue, &pending_ipc, &webview) {
+            if let Some(on) = efficiency.observe(window.is_visible(), || {
+                Settings::load().background_efficiency
+            }) {
+                crate::priority::set_in_background(on);
+            }
+            if drain_ipc_queue_and_deliver(&ipc_queue, &webview, &children, ipc_limits.batch_size) {
                 *control_flow = tao::event_loop::ControlFlow::Poll;
             }
             return;
         }
+        if let tao::event::Event::LoopDestroyed = event {
+            session::mark_clean_exit();
+            paths::cleanup_temp_files(Duration::from_secs(TEMP_FILE_MAX_AGE_SECS));
+            return;
+        }
         if let tao::event::Event::RedrawEventsCleared = event {}
     });
 }
@@ -0,0 +1,140 @@
+//! Host-to-UI events (push notifications not tied to an IPC request).
+//!
+//! Any thread can call `emit`; the event is forwarded to the main loop as
+//! `UserEvent::HostEvent` and delivered to `window.__onHostEvent` in every window's WebView
+//! (main, panels, modal). `EmitToWindow` sends one to a single window instead.
+//! The sink is installed once at startup; events emitted before that are dropped.
+//!
+//! Until the main page sends `UiReady`, the loop holds events in a `HostEventBuffer` (at most
+//! `HOST_EVENT_BUFFER_LEN`, oldest dropped first) and replays them in order on `UiReady`, so
+//! events from startup (deep links, opened files, tray clicks) reach a UI that loads late. A
+//! reload holds them again until the next `UiReady`.
+
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+use tao::event_loop::EventLoopProxy;
+
+use crate::event_loop::UserEvent;
+
+static SINK: OnceLock<Mutex<EventLoopProxy<UserEvent>>> = OnceLock::new();
+
+/// Installs the event loop proxy used by `emit`. Later calls are ignored.
+pub fn init(proxy: EventLoopProxy<UserEvent>) {
+    let _ = SINK.set(Mutex::new(proxy));
+}
+
+/// Sends a named host event with a JSON payload to the UI. Names are kebab-case (e.g. `modules-changed`).
+pub fn emit(name: &str, payload: serde_json::Value) {
+    let Some(sink) = SINK.get() else {
+        log::debug!("Host event '{}' emitted before init; dropped", name);
+        return;
+    };
+    let proxy = sink.lock().unwrap_or_else(|e| e.into_inner());
+    let _ = proxy.send_event(UserEvent::HostEvent {
+        name: name.to_string(),
+        payload,
+    });
+}
+
+/// Host events waiting for the main page's `UiReady`.
+#[derive(Debug)]
+pub struct HostEventBuffer {
+    ready: bool,
+    capacity: usize,
+    held: VecDeque<(String, serde_json::Value)>,
+}
+
+impl HostEventBuffer {
+    /// An empty, not-ready buffer holding at most `capacity` events.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            ready: false,
+            capacity,
+            held: VecDeque::new(),
+        }
+    }
+
+    /// Whether the UI has signalled readiness since the last page load.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Holds an event until `ready`; returns it back when the UI is already ready.
+    pub fn hold(
+        &mut self,
+        name: String,
+        payload: serde_json::Value,
+    ) -> Option<(String, serde_json::Value)> {
+        if self.ready {
+            return Some((name, payload));
+        }
+        if self.capacity == 0 {
+            return None;
+        }
+        if self.held.len() == self.capacity
+            && let Some((dropped, _)) = self.held.pop_front()
+        {
+            log::warn!(
+                "Host event buffer full before UiReady; dropping '{}'",
+                dropped
+            );
+        }
+        self.held.push_back((name, payload));
+        None
+    }
+
+    /// Marks the UI ready and returns the held events, oldest first.
+    pub fn ready(&mut self) -> Vec<(String, serde_json::Value)> {
+        self.ready = true;
+        self.held.drain(..).collect()
+    }
+
+    /// The page is reloading: hold events again until the next `UiReady`.
+    pub fn reset(&mut self) {
+        self.ready = false;
+    }
+}
+
+/// Checks an event name sent by the UI (`EmitToWindow`): 1–64 ASCII letters, digits, `-`, `_`,
+/// `.` or `:`.
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("Invalid event name: {}", name))
+    }
+}
+
+/// Quit requests so far, so tests can see one arrive without an event loop.
+#[cfg(test)]
+pub static QUIT_REQUESTS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+/// Asks the main loop to exit, as the tray Quit item does. Ignored before `init`.
+pub fn request_quit() {
+    #[cfg(test)]
+    QUIT_REQUESTS.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    if let Some(sink) = SINK.get() {
+        let _ = sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .send_event(UserEvent::Quit);
+    }
+}
+
+/// Asks the main loop to clear the WebView's caches (memory pressure). Ignored before `init`.
+pub fn request_memory_trim() {
+    if let Some(sink) = SINK.get() {
+        let _ = sink
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .send_event(UserEvent::TrimMemory);
+    }
+}
@@ -0,0 +1,65 @@
+//! Installed system font enumeration for `ListSystemFonts`.
+//!
+//! Uses `fontdb` to scan the platform font directories (and fontconfig config on Linux), so no
+//! font database is bundled. Families are de-duplicated by their first (English) name and sorted.
+
+use std::collections::BTreeMap;
+
+/// One face of a family: style (`normal`, `italic`, `oblique`), weight (100–900), PostScript name.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FontStyle {
+    style: &'static str,
+    weight: u16,
+    postscript_name: String,
+    monospaced: bool,
+}
+
+fn style_name(style: fontdb::Style) -> &'static str {
+    match style {
+        fontdb::Style::Normal => "normal",
+        fontdb::Style::Italic => "italic",
+        fontdb::Style::Oblique => "oblique",
+    }
+}
+
+/// Lists installed font families. With `include_styles`, each family carries its faces sorted
+/// by weight then style; otherwise only names are returned.
+#[must_use]
+pub fn list_system_fonts(include_styles: bool) -> serde_json::Value {
+    let mut db = fontdb::Database::new();
+    db.load_system_fonts();
+    families_json(&db, include_styles)
+}
+
+/// The `ListSystemFonts` response for the faces in `db`.
+#[must_use]
+pub fn families_json(db: &fontdb::Database, include_styles: bool) -> serde_json::Value {
+    let mut families: BTreeMap<String, Vec<FontStyle>> = BTreeMap::new();
+    for face in db.faces() {
+        let Some((family, _)) = face.families.first() else {
+            continue;
+        };
+        let styles = families.entry(family.clone()).or_default();
+        if include_styles {
+            styles.push(FontStyle {
+                style: style_name(face.style),
+                weight: face.weight.0,
+                postscript_name: face.post_script_name.clone(),
+                monospaced: face.monospaced,
+            });
+        }
+    }
+    if !include_styles {
+        return serde_json::json!({ "families": families.into_keys().collect::<Vec<_>>() });
+    }
+    let fonts: Vec<serde_json::Value> = families
+        .into_iter()
+        .map(|(family, mut styles)| {
+            styles.sort_by(|a, b| a.weight.cmp(&b.weight).then(a.style.cmp(b.style)));
+            styles.dedup_by(|a, b| a.postscript_name == b.postscript_name);
+            serde_json::json!({ "family": family, "styles": styles })
+        })
+        .collect();
+    serde_json::json!({ "families": fonts })
+}
@@ -0,0 +1,167 @@
+//! Scoped capability grants for user-chosen paths.
+//!
+//! Paths picked in native dialogs are granted to the UI for the session, or until an expiry
+//! (`grantTtlSecs` config key). Commands that touch the filesystem call `check` before use.
+//! Expired grants are swept by the event loop and reported as `grant-revoked` host events.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::events;
+use crate::storage;
+
+/// Config key: default grant lifetime in seconds. Missing or `0` means session-long. Host-only:
+/// the UI cannot set the lifetime of its own grants.
+pub const TTL_CONFIG_KEY: &str = "grantTtlSecs";
+
+/// Kind of access a grant allows. `Write` implies `Read`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone)]
+struct Grant {
+    /// Canonical path (file or directory).
+    path: PathBuf,
+    /// Directory grants cover their whole subtree.
+    is_dir: bool,
+    access: Access,
+    /// `None` = until exit.
+    expires: Option<Instant>,
+}
+
+impl Grant {
+    fn covers(&self, path: &Path, access: Access) -> bool {
+        let access_ok = self.access == Access::Write || access == Access::Read;
+        let path_ok = if self.is_dir {
+            path.starts_with(&self.path)
+        } else {
+            path == self.path
+        };
+        access_ok && path_ok
+    }
+}
+
+static GRANTS: Mutex<Vec<Grant>> = Mutex::new(Vec::new());
+
+fn lock() -> std::sync::MutexGuard<'static, Vec<Grant>> {
+    GRANTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Canonicalizes `path`; for a not-yet-existing file (save dialog), canonicalizes its parent.
+fn canonical(path: &Path) -> Option<PathBuf> {
+    if let Ok(p) = std::fs::canonicalize(path) {
+        return Some(p);
+    }
+    let parent = std::fs::canonicalize(path.parent()?).ok()?;
+    Some(parent.join(path.file_name()?))
+}
+
+/// Default lifetime from config (`None` = session).
+#[must_use]
+pub fn default_ttl() -> Option<Duration> {
+    storage::get_value(TTL_CONFIG_KEY)
+        .and_then(|v| v.as_u64())
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+}
+
+/// Grants `access` to `path` for `ttl` (or the session). Re-granting a path replaces the old grant.
+pub fn grant(path: &Path, access: Access, ttl: Option<Duration>) {
+    let Some(path) = canonical(path) else {
+        log::warn!("Grant skipped: cannot resolve {}", path.display());
+        return;
+    };
+    let is_dir = path.is_dir();
+    let mut grants = lock();
+    grants.retain(|g| g.path != path);
+    grants.push(Grant {
+        path,
+        is_dir,
+        access,
+        expires: ttl.map(|t| Instant::now() + t),
+    });
+}
+
+/// Checks that `path` is covered by a live grant for `access`. Returns the canonical path to use.
+pub fn check(path: &Path, access: Access) -> Result<PathBuf, String> {
+    let resolved = canonical(path).ok_or_else(|| "Path not accessible".to_string())?;
+    let now = Instant::now();
+    let allowed = lock()
+        .iter()
+        .any(|g| g.expires.is_none_or(|e| e > now) && g.covers(&resolved, access));
+    if allowed {
+        Ok(resolved)
+    } else {
+        Err("Path not granted".to_string())
+    }
+}
+
+/// Revokes the grant for exactly `path`. Returns false if there was none.
+pub fn revoke(path: &Path) -> bool {
+    let Some(path) = canonical(path) else {
+        return false;
+    };
+    let removed = {
+        let mut grants = lock();
+        let before = grants.len();
+        grants.retain(|g| g.path != path);
+        grants.len() != before
+    };
+    if removed {
+        events::emit(
+            "grant-revoked",
+            serde_json::json!({ "path": path.display().to_string(), "reason": "revoked" }),
+        );
+    }
+    removed
+}
+
+/// Lists live grants with remaining seconds (`null` = session).
+#[must_use]
+pub fn list() -> serde_json::Value {
+    let now = Instant::now();
+    let grants: Vec<serde_json::Value> = lock()
+        .iter()
+        .filter(|g| g.expires.is_none_or(|e| e > now))
+        .map(|g| {
+            serde_json::json!({
+                "path": g.path.display().to_string(),
+                "access": g.access,
+                "isDir": g.is_dir,
+                "expiresInSecs": g.expires.map(|e| e.saturating_duration_since(now).as_secs()),
+            })
+        })
+        .collect();
+    serde_json::Value::Array(grants)
+}
+
+/// Removes expired grants, emitting `grant-revoked` for each. Returns the next expiry deadline so
+/// the event loop can `WaitUntil` it.
+pub fn sweep_expired() -> Option<Instant> {
+    let now = Instant::now();
+    let (expired, next) = {
+        let mut grants = lock();
+        let mut expired = Vec::new();
+        grants.retain(|g| match g.expires {
+            Some(e) if e <= now => {
+                expired.push(g.path.display().to_string());
+                false
+            }
+            _ => true,
+        });
+        (expired, grants.iter().filter_map(|g| g.expires).min())
+    };
+    for path in expired {
+        events::emit(
+            "grant-revoked",
+            serde_json::json!({ "path": path, "reason": "expired" }),
+        );
+    }
+    next
+}
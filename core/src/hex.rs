@@ -0,0 +1,7 @@
+//! Lowercase hex encoding for digests.
+
+/// `bytes` as lowercase hex, two characters per byte.
+#[must_use]
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
@@ -0,0 +1,75 @@
+//! Host-side translations from the embedded `ui/locales/*.json` bundles.
+//!
+//! Bundles are flat `{ "key": "text" }` maps. The chosen bundle is merged over `en.json`, so a
+//! partial translation falls back per key. Native strings (tray menu, dialog titles) use `t` and
+//! the UI can fetch the same messages via `GetTranslations`.
+
+use crate::config::LOCALES;
+use crate::locale;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+/// Bundle used for missing keys and when no preferred locale has a bundle.
+pub const FALLBACK_LOCALE: &str = "en";
+
+/// Messages for the startup locale; native strings are not re-localized on config change.
+static CURRENT: OnceLock<(String, BTreeMap<String, String>)> = OnceLock::new();
+
+fn load(tag: &str) -> Option<BTreeMap<String, String>> {
+    let file = LOCALES.get_file(format!("{}.json", tag))?;
+    match serde_json::from_slice(file.contents()) {
+        Ok(map) => Some(map),
+        Err(e) => {
+            log::warn!("i18n: invalid bundle {}.json: {}", tag, e);
+            None
+        }
+    }
+}
+
+/// Picks the first candidate with a bundle (case-insensitive match on the file stem).
+#[must_use]
+pub fn select_locale(candidates: &[String]) -> String {
+    candidates
+        .iter()
+        .find_map(|c| {
+            LOCALES.files().find_map(|f| {
+                let stem = f.path().file_stem()?.to_str()?;
+                (f.path().extension()? == "json" && stem.eq_ignore_ascii_case(c))
+                    .then(|| stem.to_string())
+            })
+        })
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+/// Resolves `requested` (or the preferred locales when `None`) to a bundle tag and its messages,
+/// merged over the fallback bundle.
+#[must_use]
+pub fn resolve(requested: Option<&str>) -> (String, BTreeMap<String, String>) {
+    let tags = match requested {
+        Some(tag) => vec![tag.to_string()],
+        None => locale::preferred_locales(),
+    };
+    let tag = select_locale(&locale::candidate_tags(&tags));
+    let mut messages = load(FALLBACK_LOCALE).unwrap_or_default();
+    if tag != FALLBACK_LOCALE {
+        messages.extend(load(&tag).unwrap_or_default());
+    }
+    (tag, messages)
+}
+
+/// Translated string for `key` in the startup locale; the key itself when no bundle has it.
+#[must_use]
+pub fn t(key: &str) -> String {
+    let (_, messages) = CURRENT.get_or_init(|| resolve(None));
+    messages
+        .get(key)
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// `GetTranslations` response: `{ locale, messages }`.
+#[must_use]
+pub fn translations(requested: Option<&str>) -> serde_json::Value {
+    let (tag, messages) = resolve(requested);
+    serde_json::json!({ "locale": tag, "messages": messages })
+}
@@ -0,0 +1,96 @@
+//! User idle time and idle/active host events.
+//!
+//! System idle time comes from the Mutter IdleMonitor or freedesktop ScreenSaver D-Bus API on
+//! Linux and `IOHIDSystem`'s `HIDIdleTime` on macOS. Windows is not supported yet (`null`).
+//! When the `idleThresholdSecs` config key is set, `watch` emits `user-idle` `{ idleSecs }` once the
+//! user has been idle that long and `user-active` when input resumes.
+
+use std::thread;
+use std::time::Duration;
+
+use crate::events;
+use crate::settings::Settings;
+
+/// Config key: idle seconds before `user-idle` is emitted. Missing or `0` disables idle events.
+pub const THRESHOLD_CONFIG_KEY: &str = "idleThresholdSecs";
+
+/// Poll interval for idle events.
+const POLL: Duration = Duration::from_secs(5);
+
+#[cfg(target_os = "linux")]
+fn read_idle() -> Option<Duration> {
+    use dbus::blocking::Connection;
+
+    let conn = Connection::new_session().ok()?;
+    let timeout = Duration::from_millis(500);
+    let mutter = conn
+        .with_proxy(
+            "org.gnome.Mutter.IdleMonitor",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            timeout,
+        )
+        .method_call::<(u64,), _, _, _>("org.gnome.Mutter.IdleMonitor", "GetIdletime", ());
+    if let Ok((ms,)) = mutter {
+        return Some(Duration::from_millis(ms));
+    }
+    conn.with_proxy(
+        "org.freedesktop.ScreenSaver",
+        "/org/freedesktop/ScreenSaver",
+        timeout,
+    )
+    .method_call::<(u32,), _, _, _>("org.freedesktop.ScreenSaver", "GetSessionIdleTime", ())
+    .ok()
+    .map(|(ms,)| Duration::from_millis(u64::from(ms)))
+}
+
+#[cfg(target_os = "macos")]
+fn read_idle() -> Option<Duration> {
+    let out = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let line = text.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let nanos: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(Duration::from_nanos(nanos))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn read_idle() -> Option<Duration> {
+    None
+}
+
+/// Time since the last user input, if the platform reports it.
+#[must_use]
+pub fn idle_time() -> Option<Duration> {
+    read_idle()
+}
+
+/// Starts the idle watcher thread. The threshold is re-read every poll, so changes apply live.
+pub fn watch() {
+    thread::spawn(|| {
+        let mut idle = false;
+        loop {
+            thread::sleep(POLL);
+            let secs = Settings::load().idle_threshold_secs;
+            let threshold = (secs > 0).then(|| Duration::from_secs(secs));
+            let Some(threshold) = threshold else {
+                idle = false;
+                continue;
+            };
+            let Some(current) = idle_time() else {
+                continue;
+            };
+            if !idle && current >= threshold {
+                idle = true;
+                events::emit(
+                    "user-idle",
+                    serde_json::json!({ "idleSecs": current.as_secs() }),
+                );
+            } else if idle && current < threshold {
+                idle = false;
+                events::emit("user-active", serde_json::json!({}));
+            }
+        }
+    });
+}
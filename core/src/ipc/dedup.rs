@@ -0,0 +1,95 @@
+//! Duplicate envelope detection.
+//!
+//! The UI may resend a request with the same `id` after a timeout. A retry that arrives while the
+//! original is still running is dropped (the original's response resolves it); a retry after
+//! completion is answered from the cached response instead of running the command again.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::{IPC_DEDUP_CAPACITY, IPC_DEDUP_WINDOW_SECS};
+
+/// Responses larger than this are not cached; a late retry gets a `DUPLICATE_REQUEST` error.
+const MAX_CACHED_RESPONSE_BYTES: usize = 64 * 1024;
+
+/// Outcome of `check` for an incoming envelope id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Seen {
+    /// First sighting; the caller runs the command.
+    New,
+    /// Same id still running; drop the duplicate.
+    InFlight,
+    /// Same id completed recently; re-send the cached response (`None` if it was too large).
+    Completed(Option<String>),
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: BTreeSet<String>,
+    /// Completed ids, oldest first, with completion time.
+    order: VecDeque<(String, Instant)>,
+    completed: BTreeMap<String, Option<String>>,
+}
+
+impl State {
+    fn expire(&mut self, now: Instant) {
+        let window = Duration::from_secs(IPC_DEDUP_WINDOW_SECS);
+        while let Some((id, at)) = self.order.front() {
+            if self.order.len() <= IPC_DEDUP_CAPACITY && now.duration_since(*at) < window {
+                break;
+            }
+            self.completed.remove(id);
+            self.order.pop_front();
+        }
+    }
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// Classifies `id` and, when new, marks it in flight.
+pub fn check(id: &str) -> Seen {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.get_or_insert_with(State::default);
+    state.expire(Instant::now());
+    if state.in_flight.contains(id) {
+        return Seen::InFlight;
+    }
+    if let Some(cached) = state.completed.get(id) {
+        return Seen::Completed(cached.clone());
+    }
+    state.in_flight.insert(id.to_string());
+    Seen::New
+}
+
+/// Records the serialized response for `id` so later retries can be answered from cache.
+/// `json` is only called when `id` was admitted by `check`.
+pub(super) fn complete(id: &str, json: impl FnOnce() -> Option<String>) {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let state = guard.get_or_insert_with(State::default);
+    if !state.in_flight.remove(id) {
+        return;
+    }
+    let json = json().filter(|j| j.len() <= MAX_CACHED_RESPONSE_BYTES);
+    let now = Instant::now();
+    state.completed.insert(id.to_string(), json);
+    state.order.push_back((id.to_string(), now));
+    state.expire(now);
+}
+
+/// Forgets an in-flight `id` that was dropped without running, so a retry is treated as new.
+pub fn abandon(id: &str) {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(state) = guard.as_mut() {
+        state.in_flight.remove(id);
+    }
+}
+
+/// Drops the cached responses (memory pressure); later retries of those ids run again.
+pub fn trim() {
+    let mut guard = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(state) = guard.as_mut() {
+        state.completed.clear();
+        state.order.clear();
+    }
+}
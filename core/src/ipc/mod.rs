@@ -3,19 +3,78 @@
 //! The UI sends `{ id, name, ...args }`; the host returns `{ id, ok? | err? }`. Invalid messages
 //! are ignored (no panic). Timeout is enforced in the UI (see `IPC_TIMEOUT_MS`).
 
+mod dedup;
+mod pool;
+mod queue;
+mod runtime;
+mod sender;
+mod signing;
+mod system;
+mod trace;
 mod updates;
+mod window;
 
+pub use dedup::{
+    Seen, abandon as abandon_request, check as check_duplicate, trim as trim_duplicate_cache,
+};
+pub use pool::{ConcurrencyLimit, Lane, WorkerPool};
+pub use queue::{
+    DropPolicy, IPC_BATCH_SIZE_KEY, IPC_DROP_POLICY_KEY, IPC_QUEUE_CAPACITY_KEY, IpcLimits,
+    IpcReceiver, IpcSender, IpcSlot, IpcStats, QueuedResponse, channel as ipc_channel,
+};
+pub use runtime::AsyncRuntime;
+pub use sender::SessionToken;
+pub use signing::SessionKey;
+pub use trace::{clear as clear_trace, enable as enable_trace};
+pub use window::{
+    ALL_WORKSPACES_KEY, ALWAYS_ON_TOP_KEY, CursorKind, PlacementPreset, handle_window_command,
+    is_deferred_window_command, start_deferred_window_command,
+};
+
+use crate::apps;
+use crate::archive::{self, ArchiveFormat};
+use crate::audit;
+use crate::bluetooth;
+use crate::capture::CaptureFormat;
+use crate::fonts;
+use crate::grants::{self, Access};
+use crate::i18n;
+use crate::idle;
+use crate::keyboard;
+use crate::killswitch::{self, RuntimeModule};
+use crate::locale;
+use crate::media::PlaybackStatus;
+use crate::metrics;
+use crate::permissions;
+use crate::power;
+use crate::profiler;
+use crate::scheduler::{self, Schedule};
+use crate::serial::{self, Parity};
+use crate::session;
+use crate::settings::{self, Settings, SettingsPatch};
+use crate::sound::{self, SystemSound};
+use crate::speech;
+use crate::startup;
 use crate::storage;
+use crate::usb;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 
 // ---------------------------------------------------------------------------
 // Constants
 // ---------------------------------------------------------------------------
 
-/// Timeout in ms for an IPC round-trip. Enforced in the UI (e.g. bridge.js); keep in sync with the frontend.
-#[allow(dead_code)]
+/// Timeout in ms for an IPC round-trip. Enforced in the UI by `window.native.invoke` (default; injected
+/// by the init script) and bridge.js; keep in sync with the frontend.
 pub const IPC_TIMEOUT_MS: u64 = 30_000;
 
+/// Parse error codes (see `parse_envelope`). Only reported to the UI in debug builds or with
+/// DevTools on; release builds drop malformed messages silently.
+pub const UNKNOWN_COMMAND: &str = "unknown_command";
+pub const MISSING_FIELD: &str = "missing_field";
+pub const INVALID_TYPE: &str = "invalid_type";
+pub const INVALID_MESSAGE: &str = "invalid_message";
+
 /// Allowed URL schemes for OpenUrl. Prevents file:// and other non-http(s) opens from the UI.
 const ALLOWED_URL_SCHEMES: [&str; 2] = ["https://", "http://"];
 
@@ -24,9 +83,15 @@ const ALLOWED_URL_SCHEMES: [&str; 2] = ["https://", "http://"];
 // ---------------------------------------------------------------------------
 
 /// Incoming message: `id` (correlation) + flattened command (`name` + args).
+///
+/// `window_id` is the window that sent it (`windowId`; `None` for the main window) and receives
+/// the response. The host sets it from the WebView the message came from, replacing whatever the
+/// page put there.
 #[derive(Debug, Clone, Deserialize)]
 pub struct IpcEnvelope {
     pub id: String,
+    #[serde(default, rename = "windowId")]
+    pub window_id: Option<String>,
     #[serde(flatten)]
     pub command: Command,
 }
@@ -43,10 +108,41 @@ pub struct FileFilter {
 #[serde(tag = "name")]
 pub enum Command {
     ReadConfig,
-    WriteConfig { data: ConfigPayload },
+    WriteConfig {
+        data: ConfigPayload,
+    },
+    DeleteConfig {
+        key: String,
+    },
+    GetStorageUsage,
+    GetSettings,
+    UpdateSettings {
+        settings: SettingsPatch,
+    },
+    PutBlob {
+        data: String,
+    },
+    GetBlob {
+        blob_id: String,
+    },
+    DeleteBlob {
+        blob_id: String,
+    },
+    ExportSettings {
+        path: String,
+    },
+    ImportSettings {
+        path: String,
+    },
+    ClearConfig {
+        #[serde(default)]
+        prefix: Option<String>,
+    },
     Ping,
     OpenFileDialog,
-    OpenFileDialogWithFilters { filters: Vec<FileFilter> },
+    OpenFileDialogWithFilters {
+        filters: Vec<FileFilter>,
+    },
     SaveFileDialog {
         #[serde(default)]
         default_name: Option<String>,
@@ -56,13 +152,290 @@ pub enum Command {
     OpenFolderDialog,
     GetVersion,
     CheckForUpdates,
-    DownloadUpdate { url: String },
-    InstallUpdate { path: String },
-    OpenUrl { url: String },
+    DownloadUpdate {
+        url: String,
+    },
+    InstallUpdate {
+        path: String,
+        #[serde(default)]
+        relaunch: Option<bool>,
+    },
+    GetUpdateState,
+    RestartApp {
+        #[serde(default)]
+        args: Option<Vec<String>>,
+    },
+    SetEfficiencyMode {
+        on: bool,
+    },
+    GetEfficiencyMode,
+    QuitApp,
+    OpenUrl {
+        url: String,
+    },
+    GetDefaultAppFor {
+        extension: String,
+    },
+    OpenWith {
+        path: String,
+        app: String,
+    },
     GetSystemInfo,
+    GetDisabledModules,
+    ListGrants,
+    RevokeGrant {
+        path: String,
+    },
+    GetDisplays,
+    SetAlwaysOnTop {
+        on: bool,
+    },
+    SetWindowPlacement {
+        preset: PlacementPreset,
+        #[serde(default)]
+        monitor: Option<usize>,
+    },
+    SetVisibleOnAllWorkspaces {
+        on: bool,
+    },
+    SetWindowOpacity {
+        value: f64,
+    },
+    SetIgnoreCursorEvents {
+        on: bool,
+    },
+    SetCursor {
+        kind: CursorKind,
+    },
+    SetCursorVisible {
+        visible: bool,
+    },
+    SetCursorGrab {
+        on: bool,
+    },
+    GetCursorPosition,
+    WatchCursorLeave {
+        on: bool,
+    },
+    SetWindowTitle {
+        title: String,
+    },
+    SetWindowIcon {
+        #[serde(default)]
+        app_asset_path: Option<String>,
+    },
+    StartRuntimeProfile {
+        seconds: u64,
+    },
+    SetBadgeCount {
+        count: u32,
+    },
+    GetAuditLog {
+        #[serde(default)]
+        limit: Option<usize>,
+    },
+    RequestUserAttention {
+        #[serde(default)]
+        critical: bool,
+    },
+    ClearWebData {
+        #[serde(default)]
+        cookies: bool,
+        #[serde(default)]
+        cache: bool,
+        #[serde(default)]
+        local_storage: bool,
+    },
+    SetSpellcheck {
+        enabled: bool,
+        #[serde(default)]
+        languages: Option<Vec<String>>,
+    },
+    CaptureWindow {
+        #[serde(default)]
+        path: Option<String>,
+        #[serde(default)]
+        format: CaptureFormat,
+    },
+    OpenPanel {
+        panel: String,
+        path: String,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    SetPanelBounds {
+        panel: String,
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    },
+    ClosePanel {
+        panel: String,
+    },
+    ListPanels,
+    OpenModal {
+        url: String,
+        width: f64,
+        height: f64,
+    },
+    ResolveModal {
+        #[serde(default)]
+        result: serde_json::Value,
+    },
+    ListWindows,
+    UiReady,
+    ShowWindow,
+    HideWindow,
+    EmitToWindow {
+        window_id: String,
+        event: String,
+        #[serde(default)]
+        payload: serde_json::Value,
+    },
+    Speak {
+        text: String,
+        #[serde(default)]
+        voice: Option<String>,
+        #[serde(default)]
+        rate: Option<f32>,
+    },
+    StopSpeaking,
+    ListVoices,
+    GetMediaPermissions,
+    PlaySystemSound {
+        kind: SystemSound,
+    },
+    SetMediaMetadata {
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        artist: Option<String>,
+        #[serde(default)]
+        album: Option<String>,
+        #[serde(default)]
+        artwork_url: Option<String>,
+        #[serde(default)]
+        duration_ms: Option<u64>,
+        #[serde(default)]
+        position_ms: Option<u64>,
+        #[serde(default)]
+        playback: PlaybackStatus,
+    },
+    PlayAudioFile {
+        path: String,
+    },
+    ListSerialPorts,
+    SerialOpen {
+        path: String,
+        baud_rate: u32,
+        #[serde(default)]
+        data_bits: Option<u8>,
+        #[serde(default)]
+        parity: Parity,
+        #[serde(default)]
+        stop_bits: Option<u8>,
+        #[serde(default)]
+        flow_control: bool,
+    },
+    SerialWrite {
+        port_id: String,
+        data: String,
+    },
+    SerialClose {
+        port_id: String,
+    },
+    ListUsbDevices,
+    ExtractArchive {
+        src: String,
+        dest: String,
+        #[serde(default)]
+        format: Option<ArchiveFormat>,
+    },
+    CreateArchive {
+        sources: Vec<String>,
+        dest: String,
+        #[serde(default)]
+        format: Option<ArchiveFormat>,
+    },
+    BluetoothStartScan {
+        #[serde(default)]
+        services: Vec<String>,
+    },
+    BluetoothStopScan,
+    BluetoothConnect {
+        address: String,
+    },
+    BluetoothDisconnect {
+        address: String,
+    },
+    BluetoothRead {
+        address: String,
+        service: String,
+        characteristic: String,
+    },
+    BluetoothWrite {
+        address: String,
+        service: String,
+        characteristic: String,
+        value: String,
+        #[serde(default)]
+        without_response: bool,
+    },
+    BluetoothSubscribe {
+        address: String,
+        service: String,
+        characteristic: String,
+    },
+    BluetoothUnsubscribe {
+        address: String,
+        service: String,
+        characteristic: String,
+    },
+    GetIpcTrace,
+    GetRuntimeMetrics,
+    GetStartupTimings,
+    ScheduleTask {
+        task_id: String,
+        #[serde(default)]
+        interval_ms: Option<u64>,
+        #[serde(default)]
+        cron: Option<String>,
+    },
+    CancelTask {
+        task_id: String,
+    },
+    GetDiskSpace {
+        path: String,
+    },
+    GetPowerStatus,
+    GetIdleTime,
+    GetLocale,
+    GetTranslations {
+        #[serde(default)]
+        locale: Option<String>,
+    },
+    GetKeyboardLayout,
+    ListSystemFonts {
+        #[serde(default)]
+        include_styles: bool,
+    },
+    WriteSecureConfig {
+        key: String,
+        value: serde_json::Value,
+    },
+    ReadSecureConfig {
+        key: String,
+    },
+    SaveSession {
+        state: serde_json::Value,
+    },
+    GetSession,
+    ClearSession,
 }
 
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigPayload {
     pub key: String,
@@ -73,7 +446,18 @@ pub struct ConfigPayload {
 // Response
 // ---------------------------------------------------------------------------
 
-/// Outgoing response correlated by `id`. Exactly one of `ok` or `err` is set.
+/// Error code set on responses for commands whose handler panicked.
+pub const INTERNAL_ERROR: &str = "internal_error";
+
+/// Error code (and `err` text) for a request rejected because the queue capacity
+/// (`ipcQueueCapacity`) is reached. The command did not run; the UI may retry later.
+pub const BUSY: &str = "busy";
+
+/// Error code for a retried `id` whose original response is no longer cached.
+pub const DUPLICATE_REQUEST: &str = "duplicate_request";
+
+/// Outgoing response correlated by `id`. Exactly one of `ok` or `err` is set; `code` optionally
+/// classifies an error (e.g. `INTERNAL_ERROR`).
 #[derive(Debug, Clone, Serialize)]
 pub struct IpcResponse {
     pub id: String,
@@ -81,6 +465,8 @@ pub struct IpcResponse {
     pub ok: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub err: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
 }
 
 impl IpcResponse {
@@ -90,6 +476,7 @@ impl IpcResponse {
             id,
             ok: Some(data),
             err: None,
+            code: None,
         }
     }
 
@@ -99,6 +486,78 @@ impl IpcResponse {
             id,
             ok: None,
             err: Some(message),
+            code: None,
+        }
+    }
+
+    #[must_use]
+    pub fn internal_error(id: String, message: String) -> Self {
+        Self {
+            code: Some(INTERNAL_ERROR),
+            ..Self::err(id, message)
+        }
+    }
+
+    #[must_use]
+    pub fn busy(id: String) -> Self {
+        Self {
+            code: Some(BUSY),
+            ..Self::err(id, BUSY.to_string())
+        }
+    }
+
+    #[must_use]
+    pub fn duplicate(id: String) -> Self {
+        Self {
+            code: Some(DUPLICATE_REQUEST),
+            ..Self::err(id, "Duplicate request id".to_string())
+        }
+    }
+}
+
+/// Runs `handler` and builds the response for `id`. A panic in the handler is caught and turned
+/// into an `INTERNAL_ERROR` response, so one broken command cannot take down the IPC thread.
+/// The response is cached for retries of `id` admitted by `check_duplicate`.
+pub fn respond(
+    id: String,
+    handler: impl FnOnce() -> Result<serde_json::Value, String>,
+) -> IpcResponse {
+    let resp = run_handler(id, handler);
+    dedup::complete(&resp.id, || serde_json::to_string(&resp).ok());
+    resp
+}
+
+/// Like `respond`, but returns the serialized response ready for delivery. Serializes once and
+/// shares the string with the retry cache.
+pub fn respond_json(
+    id: String,
+    handler: impl FnOnce() -> Result<serde_json::Value, String>,
+) -> Option<String> {
+    complete_json(run_handler(id, handler))
+}
+
+/// Serializes a finished response and shares the string with the retry cache.
+fn complete_json(resp: IpcResponse) -> Option<String> {
+    let json = serde_json::to_string(&resp).ok();
+    dedup::complete(&resp.id, || json.clone());
+    json
+}
+
+fn run_handler(
+    id: String,
+    handler: impl FnOnce() -> Result<serde_json::Value, String>,
+) -> IpcResponse {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(handler)) {
+        Ok(Ok(data)) => IpcResponse::ok(id, data),
+        Ok(Err(e)) => IpcResponse::err(id, e),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            log::error!("IPC handler panicked (id={}): {}", id, message);
+            IpcResponse::internal_error(id, format!("Internal error: {}", message))
         }
     }
 }
@@ -120,46 +579,388 @@ pub fn is_blocking_command(command: &Command) -> bool {
             | Command::DownloadUpdate { .. }
             | Command::InstallUpdate { .. }
             | Command::OpenUrl { .. }
+            | Command::GetDefaultAppFor { .. }
+            | Command::OpenWith { .. }
+            | Command::StartRuntimeProfile { .. }
+            | Command::GetDiskSpace { .. }
+            | Command::GetPowerStatus
+            | Command::GetIdleTime
+            | Command::SetEfficiencyMode { .. }
+            | Command::RestartApp { .. }
+            | Command::GetKeyboardLayout
+            | Command::ListSystemFonts { .. }
+            | Command::WriteSecureConfig { .. }
+            | Command::ReadSecureConfig { .. }
+            | Command::GetAuditLog { .. }
+            | Command::SaveSession { .. }
+            | Command::GetSession
+            | Command::ClearSession
+            | Command::ExportSettings { .. }
+            | Command::PutBlob { .. }
+            | Command::GetBlob { .. }
+            | Command::DeleteBlob { .. }
+            | Command::ImportSettings { .. }
+            | Command::Speak { .. }
+            | Command::StopSpeaking
+            | Command::ListVoices
+            | Command::PlaySystemSound { .. }
+            | Command::PlayAudioFile { .. }
+            | Command::ListSerialPorts
+            | Command::SerialOpen { .. }
+            | Command::SerialWrite { .. }
+            | Command::SerialClose { .. }
+            | Command::ListUsbDevices
+            | Command::ExtractArchive { .. }
+            | Command::CreateArchive { .. }
+            | Command::BluetoothStartScan { .. }
+            | Command::BluetoothStopScan
+            | Command::BluetoothConnect { .. }
+            | Command::BluetoothDisconnect { .. }
+            | Command::BluetoothRead { .. }
+            | Command::BluetoothWrite { .. }
+            | Command::BluetoothSubscribe { .. }
+            | Command::BluetoothUnsubscribe { .. }
+    )
+}
+
+/// Worker pool lane for a blocking command. Network transfers, installers, profiling, and font
+/// enumeration run in the background lane so dialogs and quick queries are not queued behind them.
+#[must_use]
+pub fn command_lane(command: &Command) -> Lane {
+    match command {
+        Command::CheckForUpdates
+        | Command::DownloadUpdate { .. }
+        | Command::InstallUpdate { .. }
+        | Command::StartRuntimeProfile { .. }
+        | Command::ListSystemFonts { .. }
+        | Command::ExtractArchive { .. }
+        | Command::CreateArchive { .. } => Lane::Background,
+        _ => Lane::Interactive,
+    }
+}
+
+/// Concurrency cap for a blocking command. Only one native dialog is open at a time; later dialog
+/// requests wait for it to close.
+#[must_use]
+pub fn concurrency_limit(command: &Command) -> Option<ConcurrencyLimit> {
+    match command {
+        Command::OpenFileDialog
+        | Command::OpenFileDialogWithFilters { .. }
+        | Command::SaveFileDialog { .. }
+        | Command::OpenFolderDialog => Some(ConcurrencyLimit {
+            group: "dialog",
+            max: 1,
+        }),
+        Command::DownloadUpdate { .. } => Some(ConcurrencyLimit {
+            group: "update-download",
+            max: 1,
+        }),
+        _ => None,
+    }
+}
+
+/// True for commands with an async handler (`handle_command_async`). These run on the
+/// `AsyncRuntime` instead of holding a worker thread while they wait: profiling on a timer,
+/// update checks and downloads on the async HTTP client.
+#[must_use]
+pub fn is_async_command(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::StartRuntimeProfile { .. }
+            | Command::CheckForUpdates
+            | Command::DownloadUpdate { .. }
     )
 }
 
-/// Parses a raw IPC message. Invalid JSON or missing required fields return `None` (ignored safely).
+/// True for commands that need the window or WebView. These are routed to the event loop thread.
 #[must_use]
-pub fn parse_message(raw: &str) -> Option<IpcEnvelope> {
-    serde_json::from_str(raw).ok()
+pub fn is_window_command(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::GetDisplays
+            | Command::SetAlwaysOnTop { .. }
+            | Command::SetWindowPlacement { .. }
+            | Command::SetVisibleOnAllWorkspaces { .. }
+            | Command::SetWindowOpacity { .. }
+            | Command::SetIgnoreCursorEvents { .. }
+            | Command::SetCursor { .. }
+            | Command::SetCursorVisible { .. }
+            | Command::SetCursorGrab { .. }
+            | Command::GetCursorPosition
+            | Command::WatchCursorLeave { .. }
+            | Command::SetWindowTitle { .. }
+            | Command::SetWindowIcon { .. }
+            | Command::SetBadgeCount { .. }
+            | Command::RequestUserAttention { .. }
+            | Command::ClearWebData { .. }
+            | Command::SetSpellcheck { .. }
+            | Command::CaptureWindow { .. }
+            | Command::SetMediaMetadata { .. }
+            | Command::OpenPanel { .. }
+            | Command::SetPanelBounds { .. }
+            | Command::ClosePanel { .. }
+            | Command::ListPanels
+            | Command::OpenModal { .. }
+            | Command::ResolveModal { .. }
+            | Command::ListWindows
+            | Command::UiReady
+            | Command::ShowWindow
+            | Command::HideWindow
+            | Command::EmitToWindow { .. }
+    )
+}
+
+/// True for privileged commands recorded in the audit log (network, installers, external opens, profiling,
+/// serial ports, relaunch, archives, settings export/import, window capture).
+#[must_use]
+pub fn is_audited(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::CheckForUpdates
+            | Command::DownloadUpdate { .. }
+            | Command::InstallUpdate { .. }
+            | Command::OpenUrl { .. }
+            | Command::OpenWith { .. }
+            | Command::StartRuntimeProfile { .. }
+            | Command::SerialOpen { .. }
+            | Command::RestartApp { .. }
+            | Command::ExtractArchive { .. }
+            | Command::CreateArchive { .. }
+            | Command::ExportSettings { .. }
+            | Command::ImportSettings { .. }
+            | Command::CaptureWindow { .. }
+    )
+}
+
+/// Runtime module a command belongs to, for kill-switch checks. `None` for always-available commands.
+#[must_use]
+pub fn module_for(command: &Command) -> Option<RuntimeModule> {
+    match command {
+        Command::OpenFileDialog
+        | Command::OpenFileDialogWithFilters { .. }
+        | Command::SaveFileDialog { .. }
+        | Command::OpenFolderDialog => Some(RuntimeModule::Dialogs),
+        Command::CheckForUpdates
+        | Command::DownloadUpdate { .. }
+        | Command::InstallUpdate { .. }
+        | Command::GetUpdateState => Some(RuntimeModule::Updater),
+        Command::OpenUrl { .. } => Some(RuntimeModule::OpenUrl),
+        Command::OpenWith { .. }
+        | Command::RestartApp { .. }
+        | Command::ExtractArchive { .. }
+        | Command::CreateArchive { .. }
+        | Command::SerialOpen { .. }
+        | Command::SerialWrite { .. } => Some(RuntimeModule::Shell),
+        _ => None,
+    }
+}
+
+/// Why an IPC message could not be parsed, with the envelope `id` when one could be read.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub id: Option<String>,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Error response for the UI, if the message carried an `id` to correlate it with.
+    #[must_use]
+    pub fn to_response(&self) -> Option<IpcResponse> {
+        let id = self.id.clone()?;
+        Some(IpcResponse {
+            code: Some(self.code),
+            ..IpcResponse::err(id, self.message.clone())
+        })
+    }
+}
+
+/// Parses a raw IPC message, classifying failures as `UNKNOWN_COMMAND`, `MISSING_FIELD`,
+/// `INVALID_TYPE`, or `INVALID_MESSAGE`. Well-formed messages are parsed once; the diagnostic
+/// path only runs on failure.
+pub fn parse_envelope(raw: &str) -> Result<IpcEnvelope, ParseError> {
+    let err = match serde_json::from_str(raw) {
+        Ok(envelope) => return Ok(envelope),
+        Err(e) => e,
+    };
+    let id = serde_json::from_str::<serde_json::Value>(raw)
+        .ok()
+        .and_then(|v| v.get("id").and_then(|id| id.as_str()).map(String::from));
+    let full = err.to_string();
+    // serde lists every command after "expected one of"; keep just the offending name.
+    let message = full
+        .split(", expected one of")
+        .next()
+        .unwrap_or(&full)
+        .to_string();
+    let code = if err.is_syntax() || err.is_eof() {
+        INVALID_MESSAGE
+    } else if message.starts_with("unknown variant") {
+        UNKNOWN_COMMAND
+    } else if message.starts_with("missing field") {
+        MISSING_FIELD
+    } else if message.starts_with("invalid type") || message.starts_with("invalid value") {
+        INVALID_TYPE
+    } else {
+        INVALID_MESSAGE
+    };
+    Err(ParseError { id, code, message })
+}
+
+/// Grants `access` to a dialog-picked path (default TTL from config) and returns `{ path }`.
+fn granted_path(path: Option<std::path::PathBuf>, access: Access) -> serde_json::Value {
+    if let Some(ref p) = path {
+        grants::grant(p, access, grants::default_ttl());
+    }
+    serde_json::json!({
+        "path": path.map(|p| p.display().to_string())
+    })
 }
 
 /// Handles one command synchronously. Returns a JSON-serializable value on success or an error string.
+/// Privileged commands (`is_audited`) are recorded in the audit log with their outcome; all
+/// commands are traced for the developer console when DevTools are on.
 pub fn handle_command(command: &Command) -> Result<serde_json::Value, String> {
+    let started = std::time::Instant::now();
+    let result = check_enabled(command).and_then(|()| dispatch(command));
+    if is_audited(command) {
+        audit::record(command, &result);
+    }
+    trace::record(command, &result, started.elapsed());
+    result
+}
+
+/// Async counterpart of `handle_command` for `is_async_command` commands, with the same kill
+/// switch, audit, and trace handling. Other commands fall back to the synchronous handler.
+pub async fn handle_command_async(command: Command) -> Result<serde_json::Value, String> {
+    let started = std::time::Instant::now();
+    let result = match check_enabled(&command) {
+        Ok(()) => dispatch_async(&command).await,
+        Err(e) => Err(e),
+    };
+    if is_audited(&command) {
+        audit::record(&command, &result);
+    }
+    trace::record(&command, &result, started.elapsed());
+    result
+}
+
+async fn dispatch_async(command: &Command) -> Result<serde_json::Value, String> {
+    match command {
+        Command::StartRuntimeProfile { seconds } => {
+            let path = profiler::capture_async(*seconds).await?;
+            Ok(profile_result(&path))
+        }
+        Command::CheckForUpdates => updates::check_for_updates().await,
+        Command::DownloadUpdate { url } => updates::download_update(url).await,
+        _ => dispatch(command),
+    }
+}
+
+/// Runs `future` to completion on a throwaway current-thread runtime.
+fn block_on<T>(future: impl std::future::Future<Output = Result<T, String>>) -> Result<T, String> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| e.to_string())?
+        .block_on(future)
+}
+
+fn profile_result(path: &std::path::Path) -> serde_json::Value {
+    serde_json::json!({
+        "path": path.display().to_string(),
+        "webviewTrace": null
+    })
+}
+
+/// Fails when the command's module is turned off by the kill switch.
+fn check_enabled(command: &Command) -> Result<(), String> {
+    match module_for(command) {
+        Some(module) if killswitch::is_disabled(module) => {
+            Err(format!("Module disabled: {}", module.as_str()))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn dispatch(command: &Command) -> Result<serde_json::Value, String> {
     match command {
         Command::ReadConfig => Ok(serde_json::json!({ "config": storage::get_full_config() })),
         Command::WriteConfig { data } => {
-            storage::set_value(data.key.clone(), data.value.clone());
+            storage::set_value_within_quota(data.key.clone(), data.value.clone())?;
+            if data.key == killswitch::CONFIG_KEY {
+                killswitch::reload_local();
+            }
             Ok(serde_json::json!({ "written": true }))
         }
+        Command::GetStorageUsage => Ok(storage::storage_usage()),
+        Command::GetSettings => serde_json::to_value(Settings::load()).map_err(|e| e.to_string()),
+        Command::UpdateSettings { settings: patch } => {
+            serde_json::to_value(settings::apply(patch)?).map_err(|e| e.to_string())
+        }
+        Command::PutBlob { data } => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| format!("Invalid base64: {}", e))?;
+            Ok(serde_json::json!({ "id": storage::put_blob(&bytes)?, "size": bytes.len() }))
+        }
+        Command::GetBlob { blob_id } => {
+            let data = storage::get_blob(blob_id)?
+                .map(|b| base64::engine::general_purpose::STANDARD.encode(b));
+            Ok(serde_json::json!({ "data": data }))
+        }
+        Command::DeleteBlob { blob_id } => {
+            Ok(serde_json::json!({ "deleted": storage::delete_blob(blob_id)? }))
+        }
+        Command::ExportSettings { path } => {
+            let path = grants::check(std::path::Path::new(path), Access::Write)?;
+            let count = storage::export_settings(&path)?;
+            Ok(serde_json::json!({ "path": path.display().to_string(), "keys": count }))
+        }
+        Command::ImportSettings { path } => {
+            let path = grants::check(std::path::Path::new(path), Access::Read)?;
+            let imported = storage::import_settings(&path)?;
+            if imported.iter().any(|k| k == killswitch::CONFIG_KEY) {
+                killswitch::reload_local();
+            }
+            Ok(serde_json::json!({ "imported": imported }))
+        }
+        Command::DeleteConfig { key } => {
+            storage::check_ui_deletable(key)?;
+            let deleted = storage::delete_value(key)?;
+            if key == killswitch::CONFIG_KEY {
+                killswitch::reload_local();
+            }
+            Ok(serde_json::json!({ "deleted": deleted }))
+        }
+        Command::ClearConfig { prefix } => {
+            let removed = storage::clear_values(prefix.as_deref())?;
+            if removed.iter().any(|k| k == killswitch::CONFIG_KEY) {
+                killswitch::reload_local();
+            }
+            Ok(serde_json::json!({ "removed": removed }))
+        }
         Command::Ping => Ok(serde_json::json!({ "pong": true })),
         Command::OpenFileDialog => {
-            let path = rfd::FileDialog::new().pick_file();
-            Ok(serde_json::json!({
-                "path": path.map(|p| p.display().to_string())
-            }))
+            let path = rfd::FileDialog::new()
+                .set_title(i18n::t("dialog.openFile"))
+                .pick_file();
+            Ok(granted_path(path, Access::Read))
         }
         Command::OpenFileDialogWithFilters { filters } => {
-            let mut dlg = rfd::FileDialog::new();
+            let mut dlg = rfd::FileDialog::new().set_title(i18n::t("dialog.openFile"));
             for f in filters {
                 let exts: Vec<&str> = f.extensions.iter().map(String::as_str).collect();
                 dlg = dlg.add_filter(&f.name, &exts);
             }
             let path = dlg.pick_file();
-            Ok(serde_json::json!({
-                "path": path.map(|p| p.display().to_string())
-            }))
+            Ok(granted_path(path, Access::Read))
         }
         Command::SaveFileDialog {
             default_name,
             filters,
         } => {
-            let mut dlg = rfd::FileDialog::new();
+            let mut dlg = rfd::FileDialog::new().set_title(i18n::t("dialog.saveFile"));
             if let Some(name) = default_name {
                 dlg = dlg.set_file_name(name);
             }
@@ -170,23 +971,38 @@ pub fn handle_command(command: &Command) -> Result<serde_json::Value, String> {
                 }
             }
             let path = dlg.save_file();
-            Ok(serde_json::json!({
-                "path": path.map(|p| p.display().to_string())
-            }))
+            Ok(granted_path(path, Access::Write))
         }
         Command::OpenFolderDialog => {
-            let path = rfd::FileDialog::new().pick_folder();
-            Ok(serde_json::json!({
-                "path": path.map(|p| p.display().to_string())
-            }))
+            let path = rfd::FileDialog::new()
+                .set_title(i18n::t("dialog.openFolder"))
+                .pick_folder();
+            Ok(granted_path(path, Access::Read))
         }
         Command::GetVersion => Ok(serde_json::json!({
             "version": env!("CARGO_PKG_VERSION"),
+            "productName": crate::config::PRODUCT_NAME,
+            "bundleId": crate::config::BUNDLE_ID,
             "releasesUrl": format!("https://github.com/{}/releases", updates::GITHUB_REPO)
         })),
-        Command::CheckForUpdates => updates::check_for_updates(),
-        Command::DownloadUpdate { url } => updates::download_update(url),
-        Command::InstallUpdate { path } => updates::install_update(path),
+        // Async-only handlers, reached synchronously from the automation port and tests.
+        Command::CheckForUpdates | Command::DownloadUpdate { .. } => {
+            block_on(dispatch_async(command))
+        }
+        Command::InstallUpdate { path, relaunch } => updates::install_update(path, *relaunch),
+        Command::GetUpdateState => {
+            serde_json::to_value(updates::state()).map_err(|e| e.to_string())
+        }
+        Command::RestartApp { args } => {
+            crate::lifecycle::restart(args.as_deref())?;
+            Ok(serde_json::json!({ "restarting": true }))
+        }
+        Command::QuitApp => {
+            crate::lifecycle::quit_soon();
+            Ok(serde_json::json!({ "quitting": true }))
+        }
+        Command::SetEfficiencyMode { on } => crate::priority::set(*on),
+        Command::GetEfficiencyMode => Ok(serde_json::json!({ "on": crate::priority::is_on() })),
         Command::OpenUrl { url } => {
             if !ALLOWED_URL_SCHEMES.iter().any(|s| url.starts_with(s)) {
                 return Err("URL must be http:// or https://".to_string());
@@ -194,18 +1010,210 @@ pub fn handle_command(command: &Command) -> Result<serde_json::Value, String> {
             opener::open(url).map_err(|e| e.to_string())?;
             Ok(serde_json::json!({ "opened": true }))
         }
-        Command::GetSystemInfo => {
-            let info = serde_json::json!({
-                "os": std::env::consts::OS,
-                "arch": std::env::consts::ARCH,
-                "family": std::env::consts::FAMILY,
-                "hostname": std::env::var("COMPUTERNAME")
-                    .ok()
-                    .or_else(|| std::env::var("HOSTNAME").ok())
-                    .unwrap_or_else(|| "unknown".to_string()),
-                "appVersion": env!("CARGO_PKG_VERSION"),
-            });
-            Ok(serde_json::json!({ "info": info }))
+        Command::GetDefaultAppFor { extension } => {
+            Ok(serde_json::json!({ "app": apps::default_app_for(extension)? }))
+        }
+        Command::OpenWith { path, app } => {
+            let path = grants::check(std::path::Path::new(path), Access::Read)?;
+            apps::open_with(&path, app)?;
+            Ok(serde_json::json!({ "opened": true }))
+        }
+        Command::GetSystemInfo => Ok(serde_json::json!({ "info": system::system_info() })),
+        Command::GetDiskSpace { path } => system::disk_space(path),
+        Command::GetLocale => Ok(locale::locale_info()),
+        Command::GetTranslations { locale } => Ok(i18n::translations(locale.as_deref())),
+        Command::WriteSecureConfig { key, value } => {
+            storage::set_secure(key, value)?;
+            Ok(serde_json::json!({ "written": true }))
+        }
+        Command::ReadSecureConfig { key } => {
+            Ok(serde_json::json!({ "value": storage::get_secure(key)? }))
+        }
+        Command::SaveSession { state } => session::save(state),
+        Command::GetSession => Ok(session::load()),
+        Command::ClearSession => session::clear(),
+        Command::ListSystemFonts { include_styles } => {
+            Ok(fonts::list_system_fonts(*include_styles))
+        }
+        Command::GetKeyboardLayout => {
+            Ok(serde_json::json!({ "layout": keyboard::current_layout() }))
+        }
+        Command::GetIdleTime => Ok(serde_json::json!({
+            "idleSecs": idle::idle_time().map(|d| d.as_secs())
+        })),
+        Command::GetPowerStatus => serde_json::to_value(power::status()).map_err(|e| e.to_string()),
+        Command::GetDisabledModules => Ok(serde_json::json!({
+            "disabled": killswitch::disabled_modules()
+        })),
+        Command::ListGrants => Ok(serde_json::json!({ "grants": grants::list() })),
+        Command::RevokeGrant { path } => Ok(serde_json::json!({
+            "revoked": grants::revoke(std::path::Path::new(path))
+        })),
+        Command::StartRuntimeProfile { seconds } => {
+            Ok(profile_result(&profiler::capture(*seconds)?))
+        }
+        Command::GetIpcTrace => trace::snapshot(),
+        Command::GetRuntimeMetrics => Ok(metrics::snapshot()),
+        Command::GetStartupTimings => Ok(startup::timings()),
+        Command::ScheduleTask {
+            task_id,
+            interval_ms,
+            cron,
+        } => {
+            let next =
+                scheduler::schedule(task_id, Schedule::from_args(*interval_ms, cron.as_deref())?)?;
+            Ok(serde_json::json!({ "taskId": task_id, "nextFireAt": next }))
+        }
+        Command::Speak { text, voice, rate } => {
+            speech::speak(text, voice.as_deref(), *rate)?;
+            Ok(serde_json::json!({ "speaking": true }))
+        }
+        Command::PlaySystemSound { kind } => {
+            sound::play_system(*kind)?;
+            Ok(serde_json::json!({ "playing": true }))
+        }
+        Command::PlayAudioFile { path } => {
+            let path = grants::check(std::path::Path::new(path), Access::Read)?;
+            sound::play_file(&path)?;
+            Ok(serde_json::json!({ "playing": true }))
+        }
+        Command::ListSerialPorts => Ok(serde_json::json!({ "ports": serial::list()? })),
+        Command::SerialOpen {
+            path,
+            baud_rate,
+            data_bits,
+            parity,
+            stop_bits,
+            flow_control,
+        } => {
+            let line = serial::LineSettings {
+                baud_rate: *baud_rate,
+                data_bits: data_bits.unwrap_or(8),
+                parity: *parity,
+                stop_bits: stop_bits.unwrap_or(1),
+                flow_control: *flow_control,
+            };
+            Ok(serde_json::json!({ "portId": serial::open(path, line)? }))
+        }
+        Command::SerialWrite { port_id, data } => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| format!("Invalid base64 data: {}", e))?;
+            Ok(serde_json::json!({ "written": serial::write(port_id, &bytes)? }))
+        }
+        Command::SerialClose { port_id } => {
+            Ok(serde_json::json!({ "closed": serial::close(port_id) }))
+        }
+        Command::ExtractArchive { src, dest, format } => {
+            let src = grants::check(std::path::Path::new(src), Access::Read)?;
+            let dest = grants::check(std::path::Path::new(dest), Access::Write)?;
+            serde_json::to_value(archive::extract(&src, &dest, *format)?).map_err(|e| e.to_string())
+        }
+        Command::CreateArchive {
+            sources,
+            dest,
+            format,
+        } => {
+            let sources = sources
+                .iter()
+                .map(|s| grants::check(std::path::Path::new(s), Access::Read))
+                .collect::<Result<Vec<_>, _>>()?;
+            let dest = grants::check(std::path::Path::new(dest), Access::Write)?;
+            serde_json::to_value(archive::create(&sources, &dest, *format)?)
+                .map_err(|e| e.to_string())
+        }
+        Command::ListUsbDevices => Ok(serde_json::json!({ "devices": usb::list()? })),
+        Command::BluetoothStartScan { services } => {
+            bluetooth::start_scan(services)?;
+            Ok(serde_json::json!({ "scanning": true }))
+        }
+        Command::BluetoothStopScan => Ok(serde_json::json!({ "stopped": bluetooth::stop_scan()? })),
+        Command::BluetoothConnect { address } => Ok(serde_json::json!({
+            "connected": true,
+            "services": bluetooth::connect(address)?
+        })),
+        Command::BluetoothDisconnect { address } => {
+            bluetooth::disconnect(address)?;
+            Ok(serde_json::json!({ "disconnected": true }))
+        }
+        Command::BluetoothRead {
+            address,
+            service,
+            characteristic,
+        } => {
+            let value = bluetooth::read(address, service, characteristic)?;
+            Ok(
+                serde_json::json!({ "value": base64::engine::general_purpose::STANDARD.encode(value) }),
+            )
+        }
+        Command::BluetoothWrite {
+            address,
+            service,
+            characteristic,
+            value,
+            without_response,
+        } => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(value)
+                .map_err(|e| format!("Invalid base64 value: {}", e))?;
+            bluetooth::write(address, service, characteristic, &bytes, !*without_response)?;
+            Ok(serde_json::json!({ "written": bytes.len() }))
+        }
+        Command::BluetoothSubscribe {
+            address,
+            service,
+            characteristic,
+        } => {
+            bluetooth::subscribe(address, service, characteristic)?;
+            Ok(serde_json::json!({ "subscribed": true }))
+        }
+        Command::BluetoothUnsubscribe {
+            address,
+            service,
+            characteristic,
+        } => Ok(serde_json::json!({
+            "unsubscribed": bluetooth::unsubscribe(address, service, characteristic)?
+        })),
+        Command::GetMediaPermissions => Ok(permissions::list()),
+        Command::StopSpeaking => Ok(serde_json::json!({ "stopped": speech::stop()? })),
+        Command::ListVoices => Ok(serde_json::json!({ "voices": speech::voices()? })),
+        Command::CancelTask { task_id } => {
+            Ok(serde_json::json!({ "cancelled": scheduler::cancel(task_id) }))
+        }
+        Command::GetAuditLog { limit } => Ok(serde_json::json!({
+            "entries": audit::recent(limit.unwrap_or(audit::DEFAULT_LIMIT))
+        })),
+        Command::GetDisplays
+        | Command::SetAlwaysOnTop { .. }
+        | Command::SetWindowPlacement { .. }
+        | Command::SetVisibleOnAllWorkspaces { .. }
+        | Command::SetWindowOpacity { .. }
+        | Command::SetIgnoreCursorEvents { .. }
+        | Command::SetCursor { .. }
+        | Command::SetCursorVisible { .. }
+        | Command::SetCursorGrab { .. }
+        | Command::GetCursorPosition
+        | Command::WatchCursorLeave { .. }
+        | Command::SetWindowTitle { .. }
+        | Command::SetWindowIcon { .. }
+        | Command::SetBadgeCount { .. }
+        | Command::RequestUserAttention { .. }
+        | Command::ClearWebData { .. }
+        | Command::SetSpellcheck { .. }
+        | Command::CaptureWindow { .. }
+        | Command::SetMediaMetadata { .. }
+        | Command::OpenPanel { .. }
+        | Command::SetPanelBounds { .. }
+        | Command::ClosePanel { .. }
+        | Command::ListPanels
+        | Command::OpenModal { .. }
+        | Command::ResolveModal { .. }
+        | Command::ListWindows
+        | Command::UiReady
+        | Command::ShowWindow
+        | Command::HideWindow
+        | Command::EmitToWindow { .. } => {
+            Err("Window command must run on the event loop".to_string())
         }
     }
 }
@@ -0,0 +1,161 @@
+//! Prioritized worker pool for blocking commands.
+//!
+//! Jobs wait in per-lane queues and are handed to the rayon pool only when a thread is free, so
+//! an interactive job queued behind downloads still starts next. Background jobs never hold every
+//! thread, and jobs sharing a concurrency group (e.g. native dialogs) are capped.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Scheduling lane. Interactive jobs are always dequeued before background ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    Interactive,
+    Background,
+}
+
+/// Named cap on how many jobs of one kind may run at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcurrencyLimit {
+    pub group: &'static str,
+    pub max: usize,
+}
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Queued {
+    limit: Option<ConcurrencyLimit>,
+    job: Job,
+}
+
+#[derive(Default)]
+struct State {
+    interactive: VecDeque<Queued>,
+    background: VecDeque<Queued>,
+    running: usize,
+    running_background: usize,
+    running_by_group: BTreeMap<&'static str, usize>,
+}
+
+impl State {
+    fn group_has_room(&self, limit: Option<ConcurrencyLimit>) -> bool {
+        limit.is_none_or(|l| self.running_by_group.get(l.group).copied().unwrap_or(0) < l.max)
+    }
+
+    /// Removes the next runnable job: first eligible interactive job, then background if allowed.
+    fn take_next(&mut self, background_max: usize) -> Option<(Lane, Queued)> {
+        if let Some(i) = self
+            .interactive
+            .iter()
+            .position(|q| self.group_has_room(q.limit))
+        {
+            return self.interactive.remove(i).map(|q| (Lane::Interactive, q));
+        }
+        if self.running_background >= background_max {
+            return None;
+        }
+        let i = self
+            .background
+            .iter()
+            .position(|q| self.group_has_room(q.limit))?;
+        self.background.remove(i).map(|q| (Lane::Background, q))
+    }
+}
+
+struct Inner {
+    pool: rayon::ThreadPool,
+    threads: usize,
+    background_max: usize,
+    state: Mutex<State>,
+}
+
+/// Cloneable handle to the pool.
+#[derive(Clone)]
+pub struct WorkerPool {
+    inner: Arc<Inner>,
+}
+
+impl WorkerPool {
+    /// Builds a pool of `threads` workers; background jobs may occupy at most `threads - 1`.
+    pub fn new(threads: usize) -> Result<Self, String> {
+        let threads = threads.max(1);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            inner: Arc::new(Inner {
+                pool,
+                threads,
+                background_max: threads.saturating_sub(1).max(1),
+                state: Mutex::new(State::default()),
+            }),
+        })
+    }
+
+    /// Queues `job` in `lane`, subject to `limit`.
+    pub fn spawn(
+        &self,
+        lane: Lane,
+        limit: Option<ConcurrencyLimit>,
+        job: impl FnOnce() + Send + 'static,
+    ) {
+        {
+            let mut state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+            let queued = Queued {
+                limit,
+                job: Box::new(job),
+            };
+            match lane {
+                Lane::Interactive => state.interactive.push_back(queued),
+                Lane::Background => state.background.push_back(queued),
+            }
+        }
+        dispatch(&self.inner);
+    }
+
+    /// Jobs waiting for a free thread, as `(interactive, background)`.
+    #[must_use]
+    pub fn queued(&self) -> (usize, usize) {
+        let state = self.inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        (state.interactive.len(), state.background.len())
+    }
+}
+
+/// Starts queued jobs while threads are free.
+fn dispatch(inner: &Arc<Inner>) {
+    let mut state = inner.state.lock().unwrap_or_else(|e| e.into_inner());
+    while state.running < inner.threads {
+        let Some((lane, queued)) = state.take_next(inner.background_max) else {
+            break;
+        };
+        state.running += 1;
+        if lane == Lane::Background {
+            state.running_background += 1;
+        }
+        if let Some(l) = queued.limit {
+            *state.running_by_group.entry(l.group).or_default() += 1;
+        }
+        let done = Arc::clone(inner);
+        inner.pool.spawn(move || {
+            (queued.job)();
+            finish(&done, lane, queued.limit);
+        });
+    }
+}
+
+fn finish(inner: &Arc<Inner>, lane: Lane, limit: Option<ConcurrencyLimit>) {
+    {
+        let mut state = inner.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.running -= 1;
+        if lane == Lane::Background {
+            state.running_background -= 1;
+        }
+        if let Some(l) = limit
+            && let Some(n) = state.running_by_group.get_mut(l.group)
+        {
+            *n -= 1;
+        }
+    }
+    dispatch(inner);
+}
@@ -0,0 +1,222 @@
+//! IPC response queue: a bounded channel from every thread that produces responses to the event
+//! loop, which drains it and delivers the responses to their WebViews.
+//!
+//! Backpressure is slot based. A request reserves an `IpcSlot` before it runs
+//! (`IpcSender::try_reserve` fails once `capacity` are held); the slot travels with the response
+//! through the channel and is released when dropped: after delivery, when the response is
+//! discarded, or when the command produced none. The pending count is the number of live slots,
+//! so it cannot drift from the queue. `send` reports when the loop needs an `IpcFlush` wake-up:
+//! only for the first response after a drain.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::MAX_PENDING_IPC;
+use crate::metrics;
+
+/// Config key: most IPC responses pending at once (running or queued) before `ipcDropPolicy`
+/// applies. Read at startup.
+pub const IPC_QUEUE_CAPACITY_KEY: &str = "ipcQueueCapacity";
+
+/// Config key: `"reject"`, `"drop-new"`, or `"drop-oldest"` (see `DropPolicy`). Read at startup.
+pub const IPC_DROP_POLICY_KEY: &str = "ipcDropPolicy";
+
+/// Config key: most responses delivered per loop iteration; `0` delivers the whole queue. Read
+/// at startup.
+pub const IPC_BATCH_SIZE_KEY: &str = "ipcBatchSize";
+
+/// What happens to a request that arrives while `capacity` responses are pending.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DropPolicy {
+    /// Answer it with a `busy` error without running it.
+    Reject,
+    /// Ignore it; its promise times out in the UI.
+    DropNew,
+    /// Run it and discard the oldest undelivered response to make room (rejects as busy when
+    /// every pending response is still running).
+    DropOldest,
+}
+
+/// IPC backpressure and delivery limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IpcLimits {
+    pub capacity: usize,
+    pub drop_policy: DropPolicy,
+    /// `0`: no limit.
+    pub batch_size: usize,
+}
+
+impl IpcLimits {
+    pub const DEFAULT: Self = Self {
+        capacity: MAX_PENDING_IPC,
+        drop_policy: DropPolicy::Reject,
+        batch_size: 0,
+    };
+}
+
+struct Shared {
+    capacity: usize,
+    pending: AtomicUsize,
+    queued: AtomicUsize,
+    /// Set by the first `send` after a drain; cleared by the drain.
+    flush_requested: AtomicBool,
+}
+
+/// A reserved place for one response; released on drop.
+pub struct IpcSlot {
+    shared: Arc<Shared>,
+}
+
+impl Drop for IpcSlot {
+    fn drop(&mut self) {
+        self.shared.pending.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// One serialized IPC response and the window that sent the request (`None`: main window).
+pub struct QueuedResponse {
+    pub window: Option<String>,
+    pub json: String,
+    _slot: IpcSlot,
+}
+
+/// Producer side; cloned into every thread that answers requests.
+#[derive(Clone)]
+pub struct IpcSender {
+    tx: SyncSender<QueuedResponse>,
+    shared: Arc<Shared>,
+}
+
+/// Consumer side, owned by the loop thread (the IPC handler also uses it for `drop-oldest`).
+pub struct IpcReceiver {
+    rx: Receiver<QueuedResponse>,
+    shared: Arc<Shared>,
+}
+
+/// Counters for metrics and the IPC trace.
+#[derive(Clone)]
+pub struct IpcStats {
+    shared: Arc<Shared>,
+}
+
+/// Creates the response channel for `capacity` pending responses. The channel holds twice that,
+/// leaving room for `busy` answers, which are sent past the capacity.
+#[must_use]
+pub fn channel(capacity: usize) -> (IpcSender, IpcReceiver) {
+    let (tx, rx) = mpsc::sync_channel(capacity.saturating_mul(2));
+    let shared = Arc::new(Shared {
+        capacity,
+        pending: AtomicUsize::new(0),
+        queued: AtomicUsize::new(0),
+        flush_requested: AtomicBool::new(false),
+    });
+    (
+        IpcSender {
+            tx,
+            shared: Arc::clone(&shared),
+        },
+        IpcReceiver { rx, shared },
+    )
+}
+
+impl IpcSender {
+    /// Reserves a slot unless `capacity` are already held.
+    #[must_use]
+    pub fn try_reserve(&self) -> Option<IpcSlot> {
+        self.shared
+            .pending
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                (n < self.shared.capacity).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| IpcSlot {
+                shared: Arc::clone(&self.shared),
+            })
+    }
+
+    /// Reserves a slot past the capacity, for answers that must not be refused (`busy`).
+    #[must_use]
+    pub fn reserve(&self) -> IpcSlot {
+        self.shared.pending.fetch_add(1, Ordering::Relaxed);
+        IpcSlot {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+
+    /// Queues a response. Returns true when the loop must be woken (`IpcFlush`) to drain it. A
+    /// response that does not fit in the channel is dropped and counted.
+    pub fn send(&self, slot: IpcSlot, window: Option<String>, json: String) -> bool {
+        self.shared.queued.fetch_add(1, Ordering::Relaxed);
+        match self.tx.try_send(QueuedResponse {
+            window,
+            json,
+            _slot: slot,
+        }) {
+            Ok(()) => !self.shared.flush_requested.swap(true, Ordering::AcqRel),
+            Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => {
+                self.shared.queued.fetch_sub(1, Ordering::Relaxed);
+                log::warn!("IPC backpressure: response channel full, dropping a response");
+                metrics::record_dropped();
+                false
+            }
+        }
+    }
+
+    /// Counters for metrics and the IPC trace.
+    #[must_use]
+    pub fn stats(&self) -> IpcStats {
+        IpcStats {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl IpcReceiver {
+    /// Takes queued responses for one delivery: all of them, or the oldest `batch_size`.
+    pub fn drain(&self, batch_size: usize) -> Vec<QueuedResponse> {
+        // Cleared first: a response sent during the drain either gets taken here or wakes the loop.
+        self.shared.flush_requested.store(false, Ordering::Release);
+        let limit = if batch_size == 0 {
+            usize::MAX
+        } else {
+            batch_size
+        };
+        let batch: Vec<QueuedResponse> = self.rx.try_iter().take(limit).collect();
+        self.shared.queued.fetch_sub(batch.len(), Ordering::Relaxed);
+        batch
+    }
+
+    /// Discards the oldest queued response to make room under `DropPolicy::DropOldest`. False
+    /// when nothing is queued.
+    pub fn drop_oldest(&self) -> bool {
+        let Ok(dropped) = self.rx.try_recv() else {
+            return false;
+        };
+        self.shared.queued.fetch_sub(1, Ordering::Relaxed);
+        log::warn!(
+            "IPC backpressure: dropping the oldest queued response ({} bytes)",
+            dropped.json.len()
+        );
+        metrics::record_dropped();
+        true
+    }
+}
+
+impl IpcStats {
+    /// Responses reserved and not yet delivered or discarded (running or queued).
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.shared.pending.load(Ordering::Relaxed)
+    }
+
+    /// Responses waiting in the channel.
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.shared.queued.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,73 @@
+//! Async command path: a current-thread tokio runtime for handlers that mostly wait.
+//!
+//! Commands listed in `is_async_command` are sent to one owned thread running a current-thread
+//! runtime with a `LocalSet`, so handler futures need not be `Send` and waiting (timers, HTTP)
+//! holds no worker. The runtime does not run on the event loop thread: tao blocks inside its own
+//! loop and would only give tokio's timer driver a turn on wakeups.
+
+use std::thread;
+
+use tokio::sync::mpsc;
+
+use super::{IpcEnvelope, IpcResponse, complete_json, handle_command_async};
+
+/// Receives the serialized response (`None` if serialization failed).
+type Done = Box<dyn FnOnce(Option<String>) + Send>;
+
+/// Handle to the async command thread. Cloning shares the same runtime.
+#[derive(Clone)]
+pub struct AsyncRuntime {
+    tx: mpsc::UnboundedSender<(IpcEnvelope, Done)>,
+}
+
+impl AsyncRuntime {
+    /// Starts the runtime thread.
+    pub fn start() -> Result<Self, String> {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .enable_time()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let (tx, mut rx) = mpsc::unbounded_channel::<(IpcEnvelope, Done)>();
+        thread::Builder::new()
+            .name("ipc-async".to_string())
+            .spawn(move || {
+                let local = tokio::task::LocalSet::new();
+                local.block_on(&rt, async move {
+                    while let Some((envelope, done)) = rx.recv().await {
+                        tokio::task::spawn_local(run(envelope, done));
+                    }
+                });
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Self { tx })
+    }
+
+    /// Runs `envelope` on the runtime and passes its serialized response to `done`.
+    pub fn spawn(&self, envelope: IpcEnvelope, done: impl FnOnce(Option<String>) + Send + 'static) {
+        if let Err(mpsc::error::SendError((envelope, done))) =
+            self.tx.send((envelope, Box::new(done)))
+        {
+            log::error!("Async IPC runtime stopped (id={})", envelope.id);
+            done(complete_json(IpcResponse::internal_error(
+                envelope.id,
+                "Internal error: async runtime stopped".to_string(),
+            )));
+        }
+    }
+}
+
+/// Runs one command in its own task so a panic becomes an `INTERNAL_ERROR` response.
+async fn run(envelope: IpcEnvelope, done: Done) {
+    let IpcEnvelope { id, command, .. } = envelope;
+    let task = tokio::task::spawn_local(handle_command_async(command));
+    let resp = match task.await {
+        Ok(Ok(data)) => IpcResponse::ok(id, data),
+        Ok(Err(e)) => IpcResponse::err(id, e),
+        Err(e) => {
+            log::error!("Async IPC handler panicked (id={}): {}", id, e);
+            IpcResponse::internal_error(id, format!("Internal error: {}", e))
+        }
+    };
+    done(complete_json(resp));
+}
@@ -0,0 +1,37 @@
+//! Sender verification: per-session token that must accompany every envelope.
+//!
+//! The token is generated at startup and captured in the init script's `window.native.send`
+//! closure, which adds it to each outgoing message. Messages posted to `window.ipc` by other
+//! means (injected or navigated-in content) do not carry it and are rejected before parsing.
+
+use serde::Deserialize;
+
+/// Per-session random token (UUID v4, 122 random bits).
+#[derive(Clone)]
+pub struct SessionToken(String);
+
+/// Outer message shape: only `token` is read here; other fields are ignored.
+#[derive(Deserialize)]
+struct Tagged {
+    token: String,
+}
+
+impl SessionToken {
+    /// Generates a fresh token from OS randomness.
+    #[must_use]
+    pub fn generate() -> Self {
+        Self(uuid::Uuid::new_v4().simple().to_string())
+    }
+
+    /// The token value, for embedding in the init script.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// True if `raw` is a JSON object whose `token` equals this session's token.
+    #[must_use]
+    pub fn verify(&self, raw: &str) -> bool {
+        serde_json::from_str::<Tagged>(raw).is_ok_and(|t| t.token == self.0)
+    }
+}
@@ -0,0 +1,74 @@
+//! Signed IPC mode: HMAC-SHA256 over every envelope with a per-session key.
+//!
+//! Enabled with `DESKTOP_RUNTIME_SIGNED_IPC=1`. The key is generated at startup and delivered only
+//! through the init script, which keeps it in the bridge closure (see `window::init_script`), so
+//! messages posted to `window.ipc` directly (e.g. by injected script) fail verification.
+//! Wire format: `{ "signed": "<envelope json>", "sig": "<hex hmac>" }`.
+
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Per-session signing key (32 bytes).
+#[derive(Clone)]
+pub struct SessionKey([u8; 32]);
+
+/// Wrapper posted by the UI in signed mode.
+#[derive(Deserialize)]
+struct SignedMessage {
+    signed: String,
+    sig: String,
+}
+
+impl SessionKey {
+    /// Generates a random key from two v4 UUIDs (OS randomness via `getrandom`).
+    #[must_use]
+    pub fn generate() -> Self {
+        let mut key = [0u8; 32];
+        key[..16].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+        key[16..].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+        Self(key)
+    }
+
+    /// Hex encoding of the key, for the init script.
+    #[must_use]
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    /// Hex HMAC of `payload`, as the UI computes it.
+    #[cfg(test)]
+    #[must_use]
+    pub fn sign(&self, payload: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(&self.0).expect("HMAC accepts any key length");
+        mac.update(payload.as_bytes());
+        to_hex(&mac.finalize().into_bytes())
+    }
+
+    /// Verifies a signed wrapper and returns the inner envelope JSON. Constant-time comparison.
+    #[must_use]
+    pub fn verify(&self, raw: &str) -> Option<String> {
+        let msg: SignedMessage = serde_json::from_str(raw).ok()?;
+        let sig = from_hex(&msg.sig)?;
+        let mut mac = HmacSha256::new_from_slice(&self.0).ok()?;
+        mac.update(msg.signed.as_bytes());
+        mac.verify_slice(&sig).ok()?;
+        Some(msg.signed)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
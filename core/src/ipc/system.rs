@@ -0,0 +1,61 @@
+//! System information for `GetSystemInfo` (bug reports, support diagnostics).
+
+use std::path::Path;
+
+use sysinfo::{CpuRefreshKind, Disks, System};
+
+use crate::grants::{self, Access};
+use crate::paths::user_data_dir;
+
+/// Host, OS, memory, CPU, and uptime details. Fields the platform cannot report are `null`.
+pub(super) fn system_info() -> serde_json::Value {
+    let mut sys = System::new();
+    sys.refresh_memory();
+    sys.refresh_cpu_list(CpuRefreshKind::nothing());
+    let cpu_model = sys
+        .cpus()
+        .first()
+        .map(|c| c.brand().trim().to_string())
+        .filter(|b| !b.is_empty());
+    serde_json::json!({
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "family": std::env::consts::FAMILY,
+        "hostname": System::host_name().unwrap_or_else(|| "unknown".to_string()),
+        "osName": System::name(),
+        "osVersion": System::os_version(),
+        "osLongVersion": System::long_os_version(),
+        "kernelVersion": System::kernel_version(),
+        "totalMemoryBytes": sys.total_memory(),
+        "availableMemoryBytes": sys.available_memory(),
+        "cpuModel": cpu_model,
+        "cpuLogicalCores": sys.cpus().len(),
+        "cpuPhysicalCores": System::physical_core_count(),
+        "uptimeSecs": System::uptime(),
+        "appVersion": env!("CARGO_PKG_VERSION"),
+    })
+}
+
+/// Total and free bytes of the volume containing `path`. The path must be inside the app data
+/// directory or covered by a read grant.
+pub(super) fn disk_space(path: &str) -> Result<serde_json::Value, String> {
+    let requested = Path::new(path);
+    let data_dir = std::fs::canonicalize(user_data_dir()).ok();
+    let resolved = match std::fs::canonicalize(requested) {
+        Ok(p) if data_dir.as_ref().is_some_and(|d| p.starts_with(d)) => p,
+        _ => grants::check(requested, Access::Read)?,
+    };
+    let disks = Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|d| resolved.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())
+        .ok_or("No volume found for path")?;
+    Ok(serde_json::json!({
+        "path": resolved.display().to_string(),
+        "mountPoint": disk.mount_point().display().to_string(),
+        "totalBytes": disk.total_space(),
+        "freeBytes": disk.available_space(),
+    }))
+}
@@ -1,21 +1,23 @@
 //! Unit tests for IPC parsing, commands, and semver.
 
 use super::*;
-use updates::semver_compare;
+use updates::{
+    is_pinned_download_url, rollout_bucket, rollout_percent, select_release, semver_compare,
+};
 
 #[test]
 fn parse_message_valid_ping() {
     let raw = r#"{"id":"abc-123","name":"Ping"}"#;
-    let env = parse_message(raw).expect("valid");
+    let env = parse_envelope(raw).expect("valid");
     assert_eq!(env.id, "abc-123");
     assert!(matches!(env.command, Command::Ping));
 }
 
 #[test]
 fn parse_message_invalid_returns_none() {
-    assert!(parse_message("").is_none());
-    assert!(parse_message("{}").is_none());
-    assert!(parse_message("not json").is_none());
+    assert!(parse_envelope("").is_err());
+    assert!(parse_envelope("{}").is_err());
+    assert!(parse_envelope("not json").is_err());
 }
 
 #[test]
@@ -51,6 +53,36 @@ fn is_blocking_command_identifies_blocking_commands() {
     assert!(!super::is_blocking_command(&Command::GetSystemInfo));
 }
 
+#[test]
+fn is_window_command_identifies_window_commands() {
+    assert!(is_window_command(&Command::GetDisplays));
+    assert!(is_window_command(&Command::SetAlwaysOnTop { on: true }));
+    let clear = parse_envelope(r#"{"id":"c","name":"ClearWebData","cookies":true}"#).unwrap();
+    assert!(matches!(
+        clear.command,
+        Command::ClearWebData {
+            cookies: true,
+            cache: false,
+            local_storage: false
+        }
+    ));
+    assert!(is_window_command(&clear.command));
+    assert!(!is_deferred_window_command(&clear.command));
+    let capture = parse_envelope(r#"{"id":"d","name":"CaptureWindow"}"#).unwrap();
+    assert!(matches!(
+        capture.command,
+        Command::CaptureWindow {
+            path: None,
+            format: crate::capture::CaptureFormat::Png
+        }
+    ));
+    assert!(is_window_command(&capture.command) && is_deferred_window_command(&capture.command));
+    assert!(parse_envelope(r#"{"id":"e","name":"CaptureWindow","format":"bmp"}"#).is_err());
+    assert!(!is_window_command(&Command::Ping));
+    assert!(!is_window_command(&Command::OpenFileDialog));
+    assert!(handle_command(&Command::GetDisplays).is_err());
+}
+
 #[test]
 fn open_url_rejects_non_http() {
     let cmd = Command::OpenUrl {
@@ -62,3 +94,2307 @@ fn open_url_rejects_non_http() {
     };
     assert!(handle_command(&cmd).is_err());
 }
+
+#[test]
+fn module_for_maps_commands_to_kill_switch_modules() {
+    assert_eq!(
+        module_for(&Command::OpenFolderDialog),
+        Some(RuntimeModule::Dialogs)
+    );
+    assert_eq!(
+        module_for(&Command::CheckForUpdates),
+        Some(RuntimeModule::Updater)
+    );
+    assert_eq!(
+        module_for(&Command::InstallUpdate {
+            path: "x".to_string(),
+            relaunch: None,
+        }),
+        Some(RuntimeModule::Updater)
+    );
+    assert_eq!(
+        module_for(&Command::RestartApp { args: None }),
+        Some(RuntimeModule::Shell)
+    );
+    assert_eq!(
+        module_for(&Command::OpenWith {
+            path: "a.txt".to_string(),
+            app: "b".to_string(),
+        }),
+        Some(RuntimeModule::Shell)
+    );
+    assert_eq!(module_for(&Command::Ping), None);
+    assert_eq!(module_for(&Command::GetDisabledModules), None);
+}
+
+#[test]
+fn grants_check_denies_ungranted_path() {
+    let dir = std::env::temp_dir();
+    assert!(grants::check(&dir.join("desktop-runtime-ungranted.txt"), Access::Read).is_err());
+    let cmd = Command::RevokeGrant {
+        path: dir
+            .join("desktop-runtime-ungranted.txt")
+            .display()
+            .to_string(),
+    };
+    assert_eq!(handle_command(&cmd).unwrap()["revoked"], false);
+}
+
+#[test]
+fn signed_envelope_roundtrip_and_tamper() {
+    let key = SessionKey::generate();
+    let payload = r#"{"id":"1","name":"Ping"}"#;
+    let wrapped = serde_json::json!({ "signed": payload, "sig": key.sign(payload) }).to_string();
+    assert_eq!(key.verify(&wrapped).as_deref(), Some(payload));
+
+    let tampered = serde_json::json!({ "signed": r#"{"id":"1","name":"GetVersion"}"#, "sig": key.sign(payload) })
+        .to_string();
+    assert!(key.verify(&tampered).is_none());
+    assert!(key.verify(payload).is_none());
+    assert!(SessionKey::generate().verify(&wrapped).is_none());
+}
+
+#[test]
+fn session_token_verification() {
+    let token = SessionToken::generate();
+    let ok = format!(r#"{{"id":"1","name":"Ping","token":"{}"}}"#, token.as_str());
+    assert!(token.verify(&ok));
+    assert!(parse_envelope(&ok).is_ok());
+    assert!(!token.verify(r#"{"id":"1","name":"Ping"}"#));
+    assert!(!token.verify(r#"{"id":"1","name":"Ping","token":"guess"}"#));
+    assert!(!SessionToken::generate().verify(&ok));
+}
+
+#[test]
+fn is_audited_covers_privileged_commands() {
+    assert!(is_audited(&Command::CheckForUpdates));
+    assert!(is_audited(&Command::OpenUrl {
+        url: "https://example.com".to_string()
+    }));
+    assert!(is_audited(&Command::InstallUpdate {
+        path: "x".to_string(),
+        relaunch: None,
+    }));
+    assert!(is_audited(&Command::ExportSettings {
+        path: "x".to_string()
+    }));
+    assert!(is_audited(&Command::CaptureWindow {
+        path: None,
+        format: Default::default(),
+    }));
+    assert!(!is_audited(&Command::Ping));
+    assert!(!is_audited(&Command::GetAuditLog { limit: None }));
+    assert!(is_blocking_command(&Command::GetAuditLog { limit: None }));
+}
+
+#[test]
+fn audit_rotation_keeps_numbered_generations() {
+    let dir = std::env::temp_dir().join(format!("desktop-runtime-audit-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("audit.log");
+    for i in 0..12 {
+        std::fs::write(&log, i.to_string()).unwrap();
+        crate::audit::rotate(&log);
+    }
+    assert!(!log.exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("audit.log.1")).unwrap(),
+        "11"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("audit.log.9")).unwrap(),
+        "3"
+    );
+    assert!(!dir.join("audit.log.10").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn download_url_must_be_pinned() {
+    let offered = vec!["https://objects.example.com/app.msi".to_string()];
+    assert!(is_pinned_download_url(
+        "https://objects.example.com/app.msi",
+        &offered
+    ));
+    assert!(!is_pinned_download_url(
+        "https://evil.example.com/app.msi",
+        &offered
+    ));
+    let release = format!(
+        "https://github.com/{}/releases/download/v1.0.0/app.deb",
+        updates::GITHUB_REPO
+    );
+    assert!(is_pinned_download_url(&release, &[]));
+    let escaped = format!(
+        "https://github.com/{}/releases/download/../../../x/y",
+        updates::GITHUB_REPO
+    );
+    assert!(!is_pinned_download_url(&escaped, &[]));
+}
+
+#[test]
+fn install_update_rejects_files_not_downloaded() {
+    let path = std::env::temp_dir().join(format!("not-an-update-{}.bin", uuid::Uuid::new_v4()));
+    std::fs::write(&path, b"payload").unwrap();
+    let err = handle_command(&Command::InstallUpdate {
+        path: path.display().to_string(),
+        relaunch: None,
+    })
+    .unwrap_err();
+    assert!(err.contains("not downloaded"), "{err}");
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn spki_extracted_from_certificate_der() {
+    // Certificate { TBSCertificate { [0] version, serial, sigAlg, issuer, validity, subject, spki } }
+    let spki = [0x30, 0x03, 0x02, 0x01, 0x07];
+    let mut tbs = vec![0xa0, 0x03, 0x02, 0x01, 0x02, 0x02, 0x01, 0x01];
+    tbs.extend_from_slice(&[0x30, 0x00, 0x30, 0x00, 0x30, 0x00, 0x30, 0x00]);
+    tbs.extend_from_slice(&spki);
+    let mut tbs_seq = vec![0x30, tbs.len() as u8];
+    tbs_seq.extend_from_slice(&tbs);
+    let mut cert = vec![0x30, tbs_seq.len() as u8];
+    cert.extend_from_slice(&tbs_seq);
+    assert_eq!(crate::net::spki_der(&cert), Some(&spki[..]));
+    assert_eq!(crate::net::spki_der(&cert[..cert.len() - 1]), None);
+    assert_eq!(crate::net::spki_pin(&spki).len(), 44);
+}
+
+#[test]
+fn ipc_trace_requires_devtools() {
+    assert!(handle_command(&Command::GetIpcTrace).is_err());
+}
+
+#[test]
+fn respond_turns_panic_into_internal_error() {
+    let resp = respond("7".to_string(), || panic!("boom"));
+    assert_eq!(resp.id, "7");
+    assert_eq!(resp.code, Some(INTERNAL_ERROR));
+    assert!(resp.err.as_deref().is_some_and(|e| e.contains("boom")));
+    let resp = respond("8".to_string(), || Err("nope".to_string()));
+    assert_eq!(resp.code, None);
+}
+
+#[test]
+fn runtime_metrics_report_all_fields() {
+    let m = handle_command(&Command::GetRuntimeMetrics).unwrap();
+    for key in [
+        "rssBytes",
+        "cpuTimeMs",
+        "ipcQueueDepth",
+        "pendingIpc",
+        "droppedResponses",
+        "eventLoopWakeupsPerMinute",
+    ] {
+        assert!(m.get(key).is_some(), "missing {key}");
+    }
+}
+
+#[test]
+fn system_info_includes_host_details() {
+    let info = &handle_command(&Command::GetSystemInfo).unwrap()["info"];
+    assert_eq!(info["os"], std::env::consts::OS);
+    assert!(info["hostname"].is_string());
+    assert!(info["totalMemoryBytes"].as_u64().is_some());
+    assert!(info["cpuLogicalCores"].as_u64().is_some());
+}
+
+#[test]
+fn disk_space_requires_allowlisted_path() {
+    let outside = std::env::temp_dir().join(format!("disk-space-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&outside).unwrap();
+    let err = handle_command(&Command::GetDiskSpace {
+        path: outside.display().to_string(),
+    });
+    assert!(err.is_err());
+    let _ = std::fs::remove_dir_all(&outside);
+}
+
+#[test]
+fn power_status_parses_platform_sources() {
+    use crate::power::{PowerStatus, parse_pmset, parse_power_supply, parse_win32_battery};
+    let root = std::env::temp_dir().join(format!("power-supply-{}", uuid::Uuid::new_v4()));
+    let supply = |name: &str, files: &[(&str, &str)]| {
+        let dir = root.join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, text) in files {
+            std::fs::write(dir.join(file), format!("{}\n", text)).unwrap();
+        }
+    };
+    supply("AC", &[("type", "Mains"), ("online", "0")]);
+    supply(
+        "BAT0",
+        &[
+            ("type", "Battery"),
+            ("capacity", "42"),
+            ("status", "Discharging"),
+        ],
+    );
+    let on_battery = PowerStatus {
+        on_battery: Some(true),
+        percentage: Some(42),
+        charging: Some(false),
+    };
+    assert_eq!(parse_power_supply(&root), on_battery);
+    supply("AC", &[("online", "1")]);
+    supply("BAT0", &[("status", "Charging")]);
+    let charging = PowerStatus {
+        on_battery: Some(false),
+        percentage: Some(42),
+        charging: Some(true),
+    };
+    assert_eq!(parse_power_supply(&root), charging);
+    let _ = std::fs::remove_dir_all(&root);
+    assert_eq!(parse_power_supply(&root), PowerStatus::default());
+
+    let pmset = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t42%; discharging; 3:10 remaining present: true\n";
+    assert_eq!(parse_pmset(pmset), on_battery);
+    let pmset = "Now drawing from 'AC Power'\n -InternalBattery-0 (id=1234)\t42%; charging; 0:50 remaining present: true\n";
+    assert_eq!(parse_pmset(pmset), charging);
+
+    let win = br#"{"EstimatedChargeRemaining":42,"BatteryStatus":1}"#;
+    assert_eq!(parse_win32_battery(win), on_battery);
+    let win = br#"{"EstimatedChargeRemaining":42,"BatteryStatus":6}"#;
+    assert_eq!(parse_win32_battery(win), charging);
+    assert_eq!(parse_win32_battery(b"").on_battery, Some(false));
+
+    let v = serde_json::to_value(&charging).unwrap();
+    assert_eq!(
+        v,
+        serde_json::json!({ "onBattery": false, "percentage": 42, "charging": true })
+    );
+}
+
+#[test]
+fn system_fonts_group_and_sort_faces() {
+    use fontdb::{Database, FaceInfo, ID, Language, Source, Stretch, Style, Weight};
+    let mut db = Database::new();
+    let mut face = |family: &str, ps: &str, style: Style, weight: u16, monospaced: bool| {
+        db.push_face_info(FaceInfo {
+            id: ID::dummy(),
+            source: Source::Binary(std::sync::Arc::new(Vec::<u8>::new())),
+            index: 0,
+            families: vec![
+                (family.to_string(), Language::English_UnitedStates),
+                (format!("{} (localized)", family), Language::German_Germany),
+            ],
+            post_script_name: ps.to_string(),
+            style,
+            weight: Weight(weight),
+            stretch: Stretch::Normal,
+            monospaced,
+        });
+    };
+    face("Inter", "Inter-Bold", Style::Normal, 700, false);
+    face("Inter", "Inter-Italic", Style::Italic, 400, false);
+    face("Inter", "Inter-Regular", Style::Normal, 400, false);
+    face("Inter", "Inter-Regular", Style::Normal, 400, false);
+    face("Fira Mono", "FiraMono", Style::Normal, 400, true);
+
+    let names = crate::fonts::families_json(&db, false);
+    assert_eq!(
+        names,
+        serde_json::json!({ "families": ["Fira Mono", "Inter"] })
+    );
+    let full = crate::fonts::families_json(&db, true);
+    let inter: Vec<(&str, u64)> = full["families"][1]["styles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            (
+                s["postscriptName"].as_str().unwrap(),
+                s["weight"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        inter,
+        vec![
+            ("Inter-Italic", 400),
+            ("Inter-Regular", 400),
+            ("Inter-Bold", 700)
+        ]
+    );
+    assert_eq!(full["families"][0]["styles"][0]["monospaced"], true);
+    let env = parse_envelope(r#"{"id":"1","name":"ListSystemFonts"}"#).expect("valid");
+    assert!(matches!(
+        env.command,
+        Command::ListSystemFonts {
+            include_styles: false
+        }
+    ));
+}
+
+#[test]
+fn session_state_roundtrip() {
+    let state = serde_json::json!({ "documents": ["a.txt"], "scroll": 120 });
+    handle_command(&Command::SaveSession {
+        state: state.clone(),
+    })
+    .unwrap();
+    let v = handle_command(&Command::GetSession).unwrap();
+    assert_eq!(v["state"], state);
+    assert!(v["savedAt"].is_u64());
+    assert!(v["crashed"].is_boolean());
+    assert!(super::is_blocking_command(&Command::SaveSession { state }));
+    assert!(super::is_blocking_command(&Command::GetSession));
+    assert!(super::is_blocking_command(&Command::ClearSession));
+}
+
+#[test]
+fn tracked_temp_files_are_cleaned_up() {
+    let path = crate::paths::temp_file("test", "bin").unwrap();
+    assert!(path.exists());
+    crate::paths::cleanup_temp_files(std::time::Duration::from_secs(3600));
+    assert!(path.exists());
+    crate::paths::cleanup_temp_files(std::time::Duration::ZERO);
+    assert!(!path.exists());
+}
+
+#[test]
+fn secure_values_are_bound_to_their_key() {
+    use crate::storage::{open_value, seal_value};
+    let secret = [7u8; 32];
+    let value = serde_json::json!({ "token": "abc" });
+    let sealed = seal_value(&secret, "apiToken", &value).unwrap();
+    assert!(!sealed.contains("abc"));
+    assert_eq!(open_value(&secret, "apiToken", &sealed).unwrap(), value);
+    assert!(open_value(&secret, "otherKey", &sealed).is_err());
+    assert!(open_value(&[8u8; 32], "apiToken", &sealed).is_err());
+}
+
+#[test]
+fn config_changed_keys_cover_add_change_remove() {
+    use std::collections::BTreeMap;
+    let old: BTreeMap<String, serde_json::Value> = [
+        ("a".into(), serde_json::json!(1)),
+        ("b".into(), serde_json::json!(2)),
+    ]
+    .into();
+    let new: BTreeMap<String, serde_json::Value> = [
+        ("a".into(), serde_json::json!(1)),
+        ("b".into(), serde_json::json!(3)),
+        ("c".into(), serde_json::json!(0)),
+    ]
+    .into();
+    assert_eq!(crate::storage::changed_keys(&old, &new), vec!["b", "c"]);
+    assert_eq!(crate::storage::changed_keys(&new, &old), vec!["b", "c"]);
+    assert!(crate::storage::changed_keys(&old, &old).is_empty());
+}
+
+#[test]
+fn delete_and_clear_config_keys() {
+    let prefix = format!("test-{}.", uuid::Uuid::new_v4());
+    for name in ["a", "b"] {
+        handle_command(&Command::WriteConfig {
+            data: ConfigPayload {
+                key: format!("{prefix}{name}"),
+                value: serde_json::json!(1),
+            },
+        })
+        .unwrap();
+    }
+    let v = handle_command(&Command::DeleteConfig {
+        key: format!("{prefix}a"),
+    })
+    .unwrap();
+    assert_eq!(v["deleted"], true);
+    let v = handle_command(&Command::DeleteConfig {
+        key: format!("{prefix}a"),
+    })
+    .unwrap();
+    assert_eq!(v["deleted"], false);
+    let v = handle_command(&Command::ClearConfig {
+        prefix: Some(prefix.clone()),
+    })
+    .unwrap();
+    assert_eq!(v["removed"], serde_json::json!([format!("{prefix}b")]));
+    assert!(crate::storage::get_value(&format!("{prefix}b")).is_none());
+}
+
+#[test]
+fn config_namespace_quota_rejects_oversized_writes() {
+    assert_eq!(crate::storage::namespace_of("ui.sidebar.width"), "ui");
+    assert_eq!(crate::storage::namespace_of("theme"), "default");
+    assert_eq!(crate::storage::namespace_of(".hidden"), "default");
+    let key = format!("quota-{}.blob", uuid::Uuid::new_v4());
+    let quota = crate::storage::namespace_quota(crate::storage::namespace_of(&key));
+    let err = handle_command(&Command::WriteConfig {
+        data: ConfigPayload {
+            key: key.clone(),
+            value: serde_json::json!("x".repeat(quota)),
+        },
+    });
+    assert!(err.unwrap_err().contains("quota"));
+    assert!(crate::storage::get_value(&key).is_none());
+}
+
+#[test]
+fn settings_import_validates_bundle() {
+    let path = std::env::temp_dir().join(format!("settings-{}.json", uuid::Uuid::new_v4()));
+    let arg = path.display().to_string();
+    std::fs::write(
+        &path,
+        r#"{"format":"desktop-runtime-settings","version":1,"settings":{"window":{}}}"#,
+    )
+    .unwrap();
+    assert!(
+        handle_command(&Command::ImportSettings { path: arg.clone() })
+            .unwrap_err()
+            .contains("not granted")
+    );
+    grants::grant(&path, Access::Read, None);
+    assert!(
+        handle_command(&Command::ImportSettings { path: arg.clone() })
+            .unwrap_err()
+            .contains("window")
+    );
+    std::fs::write(&path, r#"{"format":"other","version":1,"settings":{}}"#).unwrap();
+    assert!(handle_command(&Command::ImportSettings { path: arg.clone() }).is_err());
+    assert!(super::is_blocking_command(&Command::ImportSettings {
+        path: arg.clone()
+    }));
+    assert!(super::is_blocking_command(&Command::ExportSettings {
+        path: arg
+    }));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn host_only_config_keys_are_refused_to_the_ui() {
+    let key = crate::grants::TTL_CONFIG_KEY.to_string();
+    let err = handle_command(&Command::WriteConfig {
+        data: ConfigPayload {
+            key: key.clone(),
+            value: serde_json::json!(1),
+        },
+    });
+    assert!(err.unwrap_err().contains("reserved"));
+    assert!(
+        handle_command(&Command::DeleteConfig { key: key.clone() })
+            .unwrap_err()
+            .contains("reserved")
+    );
+    assert!(crate::storage::is_host_only(
+        crate::audit::REDACT_CONFIG_KEY
+    ));
+    assert!(crate::storage::is_host_only(crate::net::PROXY_CONFIG_KEY));
+    assert!(crate::storage::is_host_only(
+        crate::net::CA_BUNDLE_CONFIG_KEY
+    ));
+    assert!(crate::storage::is_host_only(crate::net::PINS_CONFIG_KEY));
+
+    let path = std::env::temp_dir().join(format!("settings-{}.json", uuid::Uuid::new_v4()));
+    let bundle = serde_json::json!({ "format": "desktop-runtime-settings", "version": 1, "settings": { key.clone(): 1 } });
+    std::fs::write(&path, bundle.to_string()).unwrap();
+    grants::grant(&path, Access::Read, None);
+    let err = handle_command(&Command::ImportSettings {
+        path: path.display().to_string(),
+    });
+    assert!(err.unwrap_err().contains(&key));
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn concurrent_config_writes_keep_every_key() {
+    let prefix = format!("concurrent-{}.", uuid::Uuid::new_v4());
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let key = format!("{prefix}{i}");
+            std::thread::spawn(move || crate::storage::set_value(key, serde_json::json!(i)))
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    for i in 0..8 {
+        assert_eq!(
+            crate::storage::get_value(&format!("{prefix}{i}")),
+            Some(serde_json::json!(i))
+        );
+    }
+    crate::storage::clear_values(Some(&prefix)).unwrap();
+}
+
+#[test]
+fn blob_store_roundtrip() {
+    let bytes = uuid::Uuid::new_v4().as_bytes().repeat(64);
+    let id = crate::storage::put_blob(&bytes).unwrap();
+    assert_eq!(id.len(), 64);
+    assert_eq!(crate::storage::put_blob(&bytes).unwrap(), id);
+    assert_eq!(crate::storage::get_blob(&id).unwrap(), Some(bytes));
+    assert!(crate::storage::delete_blob(&id).unwrap());
+    assert_eq!(crate::storage::get_blob(&id).unwrap(), None);
+    assert!(crate::storage::get_blob("../config.json").is_err());
+    // Command arguments cannot be named `id`: the envelope's correlation id would swallow them.
+    let env = parse_envelope(r#"{"id":"req-1","name":"GetBlob","blob_id":"abc"}"#).unwrap();
+    assert_eq!(env.id, "req-1");
+    assert!(matches!(env.command, Command::GetBlob { ref blob_id } if blob_id == "abc"));
+    assert!(super::is_blocking_command(&env.command));
+}
+
+#[test]
+fn settings_apply_defaults_for_missing_or_mistyped_values() {
+    use crate::settings::{DEFAULTS, Settings, UpdateChannel};
+    let mut config = crate::storage::AppConfig::default();
+    assert_eq!(Settings::from_config(&config), DEFAULTS);
+    config.data.insert("zoom".into(), serde_json::json!(9.0));
+    config
+        .data
+        .insert("closeToTray".into(), serde_json::json!("yes"));
+    config
+        .data
+        .insert("updateChannel".into(), serde_json::json!("beta"));
+    config
+        .data
+        .insert("lowBatteryPercent".into(), serde_json::json!(250));
+    let s = Settings::from_config(&config);
+    assert_eq!(s.zoom, DEFAULTS.zoom);
+    assert!(!s.close_to_tray);
+    assert_eq!(s.update_channel, UpdateChannel::Beta);
+    assert_eq!(s.low_battery_percent, 100);
+    config
+        .data
+        .insert("userAgentSuffix".into(), serde_json::json!(true));
+    config
+        .data
+        .insert("backgroundThrottling".into(), serde_json::json!("disabled"));
+    config
+        .data
+        .insert("autoplay".into(), serde_json::json!(false));
+    let s = Settings::from_config(&config);
+    assert_eq!(
+        s.user_agent_suffix,
+        Some(crate::window::default_user_agent_suffix())
+    );
+    assert_eq!(
+        s.background_throttling,
+        Some(crate::settings::BackgroundThrottling::Disabled)
+    );
+    assert!(!s.autoplay);
+    assert!(!s.incognito);
+    assert_eq!(s.spellcheck, None);
+    config
+        .data
+        .insert("spellcheck".into(), serde_json::json!(true));
+    config.data.insert(
+        "spellcheckLanguages".into(),
+        serde_json::json!(["en-US", "de"]),
+    );
+    assert_eq!(
+        Settings::from_config(&config).spellcheck_languages,
+        ["en-US", "de"]
+    );
+    config
+        .data
+        .insert("spellcheckLanguages".into(), serde_json::json!(["en US"]));
+    let s = Settings::from_config(&config);
+    assert_eq!(
+        (s.spellcheck, s.spellcheck_languages.len()),
+        (Some(true), 0)
+    );
+    let patch: crate::settings::SettingsPatch = serde_json::from_str(r#"{"zoom":0.1}"#).unwrap();
+    assert!(crate::settings::apply(&patch).is_err());
+}
+
+#[test]
+fn parse_errors_are_classified_and_correlated() {
+    let e = parse_envelope(r#"{"id":"a","name":"NoSuchCommand"}"#).unwrap_err();
+    assert_eq!((e.id.as_deref(), e.code), (Some("a"), UNKNOWN_COMMAND));
+    assert!(!e.message.contains("expected one of"));
+    let e = parse_envelope(r#"{"id":"b","name":"SetAlwaysOnTop"}"#).unwrap_err();
+    assert_eq!(e.code, MISSING_FIELD);
+    let e = parse_envelope(r#"{"id":"c","name":"SetAlwaysOnTop","on":"yes"}"#).unwrap_err();
+    assert_eq!(e.code, INVALID_TYPE);
+    assert_eq!(e.to_response().unwrap().code, Some(INVALID_TYPE));
+    let e = parse_envelope("{not json").unwrap_err();
+    assert_eq!((e.id, e.code), (None, INVALID_MESSAGE));
+}
+
+#[test]
+fn retried_request_ids_are_coalesced() {
+    let id = "dedup-test-1".to_string();
+    assert_eq!(check_duplicate(&id), Seen::New);
+    assert_eq!(check_duplicate(&id), Seen::InFlight);
+    let resp = respond(id.clone(), || Ok(serde_json::json!(42)));
+    let cached = serde_json::to_string(&resp).unwrap();
+    assert_eq!(check_duplicate(&id), Seen::Completed(Some(cached)));
+
+    let dropped = "dedup-test-2";
+    assert_eq!(check_duplicate(dropped), Seen::New);
+    abandon_request(dropped);
+    assert_eq!(check_duplicate(dropped), Seen::New);
+}
+
+#[test]
+fn worker_pool_prefers_interactive_and_caps_dialogs() {
+    use std::sync::mpsc;
+    let timeout = std::time::Duration::from_secs(5);
+    assert_eq!(
+        command_lane(&Command::DownloadUpdate { url: String::new() }),
+        Lane::Background
+    );
+    assert_eq!(command_lane(&Command::OpenFolderDialog), Lane::Interactive);
+
+    // Single thread: while it is busy, a later interactive job overtakes a queued background job.
+    let pool = WorkerPool::new(1).unwrap();
+    let (order_tx, order_rx) = mpsc::channel();
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let tx = order_tx.clone();
+    pool.spawn(Lane::Interactive, None, move || {
+        let _ = release_rx.recv();
+        tx.send("blocker").unwrap();
+    });
+    let tx = order_tx.clone();
+    pool.spawn(Lane::Background, None, move || tx.send("download").unwrap());
+    let tx = order_tx.clone();
+    pool.spawn(Lane::Interactive, None, move || tx.send("query").unwrap());
+    assert_eq!(pool.queued(), (1, 1));
+    release_tx.send(()).unwrap();
+    let order: Vec<_> = (0..3)
+        .map(|_| order_rx.recv_timeout(timeout).unwrap())
+        .collect();
+    assert_eq!(order, ["blocker", "query", "download"]);
+
+    // Two threads: a second dialog waits for the first even though a thread is free.
+    let pool = WorkerPool::new(2).unwrap();
+    let dialog = concurrency_limit(&Command::OpenFolderDialog);
+    let (release_tx, release_rx) = mpsc::channel::<()>();
+    let tx = order_tx.clone();
+    pool.spawn(Lane::Interactive, dialog, move || {
+        let _ = release_rx.recv();
+        tx.send("dialog-1").unwrap();
+    });
+    let tx = order_tx.clone();
+    pool.spawn(Lane::Interactive, dialog, move || {
+        tx.send("dialog-2").unwrap()
+    });
+    let tx = order_tx;
+    pool.spawn(Lane::Interactive, None, move || tx.send("query").unwrap());
+    assert_eq!(order_rx.recv_timeout(timeout).unwrap(), "query");
+    release_tx.send(()).unwrap();
+    assert_eq!(order_rx.recv_timeout(timeout).unwrap(), "dialog-1");
+    assert_eq!(order_rx.recv_timeout(timeout).unwrap(), "dialog-2");
+}
+
+#[test]
+fn busy_response_shape() {
+    let json = serde_json::to_value(IpcResponse::busy("q1".into())).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "id": "q1", "err": "busy", "code": "busy" })
+    );
+}
+
+#[test]
+fn startup_timings_record_phases() {
+    crate::startup::init();
+    drop(crate::startup::phase("test-phase"));
+    let env = parse_envelope(r#"{"id":"t","name":"GetStartupTimings"}"#).unwrap();
+    let timings = handle_command(&env.command).unwrap();
+    let phases = timings["phases"].as_array().unwrap();
+    assert!(
+        phases
+            .iter()
+            .any(|p| p["name"] == "test-phase" && p["durationMs"].as_f64().unwrap() >= 0.0)
+    );
+    assert!(crate::startup::summary().contains("test-phase"));
+}
+
+#[test]
+fn async_runtime_runs_commands_and_reports_errors() {
+    let rt = AsyncRuntime::start().unwrap();
+    let (tx, rx) = std::sync::mpsc::channel();
+    let timeout = std::time::Duration::from_secs(5);
+    for raw in [
+        r#"{"id":"async-1","name":"Ping"}"#,
+        r#"{"id":"async-2","name":"StartRuntimeProfile","seconds":0}"#,
+        r#"{"id":"async-3","name":"DownloadUpdate","url":"http://example.com/app.AppImage"}"#,
+    ] {
+        let tx = tx.clone();
+        rt.spawn(parse_envelope(raw).unwrap(), move |json| {
+            tx.send(json).unwrap()
+        });
+    }
+    let mut replies: Vec<serde_json::Value> = (0..3)
+        .map(|_| serde_json::from_str(&rx.recv_timeout(timeout).unwrap().unwrap()).unwrap())
+        .collect();
+    replies.sort_by_key(|r| r["id"].as_str().unwrap().to_string());
+    assert_eq!(replies[0]["ok"]["pong"], true);
+    if !cfg!(all(feature = "profiling", unix)) {
+        assert!(
+            replies[1]["err"]
+                .as_str()
+                .unwrap()
+                .contains("not available")
+        );
+    }
+    assert!(
+        replies[2]["err"]
+            .as_str()
+            .unwrap()
+            .contains("must be https")
+    );
+    assert!(is_async_command(&Command::StartRuntimeProfile {
+        seconds: 1
+    }));
+    assert!(is_async_command(&Command::CheckForUpdates));
+}
+
+#[test]
+fn cron_schedules_and_task_commands() {
+    use crate::scheduler::Cron;
+    // Friday 2026-10-16 09:30 UTC.
+    let friday_0930 = 1_792_143_000;
+    let every_15 = Cron::parse("*/15 * * * *").unwrap();
+    assert_eq!(
+        every_15.next_after(friday_0930),
+        Some(friday_0930 + 15 * 60)
+    );
+    // Weekdays at 09:00: next is Monday 2026-10-19.
+    let weekdays_9 = Cron::parse("0 9 * * 1-5").unwrap();
+    assert_eq!(weekdays_9.next_after(friday_0930), Some(1_792_400_400));
+    // Leap day only.
+    assert_eq!(
+        Cron::parse("0 12 29 2 *")
+            .unwrap()
+            .next_after(1_700_000_000),
+        Some(1_709_208_000)
+    );
+    assert!(Cron::parse("60 * * * *").is_err());
+    assert!(Cron::parse("* * *").is_err());
+
+    let run = |raw: &str| handle_command(&parse_envelope(raw).unwrap().command);
+    assert!(run(r#"{"id":"1","name":"ScheduleTask","task_id":"sync"}"#).is_err());
+    assert!(run(r#"{"id":"2","name":"ScheduleTask","task_id":"sync","interval_ms":10}"#).is_err());
+    let ok =
+        run(r#"{"id":"3","name":"ScheduleTask","task_id":"sync","interval_ms":60000}"#).unwrap();
+    assert_eq!(ok["taskId"], "sync");
+    assert!(ok["nextFireAt"].as_u64().unwrap() > 0);
+    assert_eq!(
+        run(r#"{"id":"4","name":"CancelTask","task_id":"sync"}"#).unwrap()["cancelled"],
+        true
+    );
+    assert_eq!(
+        run(r#"{"id":"5","name":"CancelTask","task_id":"sync"}"#).unwrap()["cancelled"],
+        false
+    );
+}
+
+#[test]
+fn speech_validates_and_maps_rate() {
+    use crate::speech::engine_rate;
+    // speech-dispatcher scale (-100..=100, normal 0) and a 0.5..=10 scale with normal 1.
+    assert_eq!(
+        (
+            engine_rate(1.0, -100.0, 0.0, 100.0),
+            engine_rate(2.0, -100.0, 0.0, 100.0),
+            engine_rate(0.5, -100.0, 0.0, 100.0),
+            engine_rate(4.0, -100.0, 0.0, 100.0)
+        ),
+        (0.0, 50.0, -50.0, 100.0)
+    );
+    assert_eq!(engine_rate(0.25, 0.5, 1.0, 10.0), 0.5);
+    assert_eq!(engine_rate(4.0, 0.5, 1.0, 10.0), 10.0);
+    let err = handle_command(&Command::Speak {
+        text: "  ".to_string(),
+        voice: None,
+        rate: None,
+    });
+    assert!(err.is_err());
+    let err = handle_command(&Command::Speak {
+        text: "hello".to_string(),
+        voice: None,
+        rate: Some(9.0),
+    });
+    assert!(err.unwrap_err().contains("Rate"));
+}
+
+#[test]
+fn audio_file_requires_grant_and_media_commands_parse() {
+    use crate::media::action_for;
+    use souvlaki::{MediaControlEvent, MediaPosition, SeekDirection};
+    use std::time::Duration;
+    let env = parse_envelope(r#"{"id":"s","name":"PlaySystemSound","kind":"warning"}"#).unwrap();
+    assert!(matches!(
+        env.command,
+        Command::PlaySystemSound {
+            kind: crate::sound::SystemSound::Warning
+        }
+    ));
+    assert!(parse_envelope(r#"{"id":"t","name":"PlaySystemSound","kind":"beep"}"#).is_err());
+    let env = parse_envelope(
+        r#"{"id":"m","name":"SetMediaMetadata","title":"Song","playback":"paused"}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        env.command,
+        Command::SetMediaMetadata {
+            title: Some(_),
+            artist: None,
+            playback: crate::media::PlaybackStatus::Paused,
+            ..
+        }
+    ));
+    assert!(is_window_command(&env.command));
+    assert_eq!(
+        action_for(MediaControlEvent::Toggle),
+        Some(("playPause", serde_json::json!({})))
+    );
+    assert_eq!(
+        action_for(MediaControlEvent::SeekBy(
+            SeekDirection::Backward,
+            Duration::from_millis(1500)
+        )),
+        Some(("seek", serde_json::json!({ "offsetMs": -1500 })))
+    );
+    assert_eq!(
+        action_for(MediaControlEvent::SetPosition(MediaPosition(
+            Duration::from_secs(42)
+        ))),
+        Some(("setPosition", serde_json::json!({ "positionMs": 42_000 })))
+    );
+    assert_eq!(action_for(MediaControlEvent::Quit), None);
+    let path = std::env::temp_dir().join("desktop-runtime-ungranted.wav");
+    let err = handle_command(&Command::PlayAudioFile {
+        path: path.display().to_string(),
+    });
+    assert!(err.is_err());
+}
+
+#[test]
+fn media_permission_origins() {
+    use crate::permissions::{Decision, decide, origin_of};
+    assert_eq!(
+        origin_of("app://localhost/index.html?x=1"),
+        "app://localhost"
+    );
+    assert_eq!(
+        origin_of("https://example.com:8443#top"),
+        "https://example.com:8443"
+    );
+    assert_eq!(decide("app://never-stored.invalid", &[]), Decision::Ask);
+    assert!(handle_command(&Command::GetMediaPermissions).unwrap()["permissions"].is_object());
+    let key = crate::permissions::MEDIA_PERMISSIONS_KEY.to_string();
+    let grant = serde_json::json!({ "app://localhost": { "camera": true } });
+    assert!(
+        handle_command(&Command::WriteConfig {
+            data: ConfigPayload {
+                key: key.clone(),
+                value: grant
+            }
+        })
+        .is_err()
+    );
+    assert!(crate::storage::check_ui_deletable(&key).is_ok());
+}
+
+#[test]
+#[cfg(unix)]
+fn serial_paths_and_line_settings() {
+    use crate::serial::is_serial_path;
+    assert!(is_serial_path("/dev/ttyUSB0"));
+    assert!(is_serial_path("/dev/cu.usbmodem1101"));
+    assert!(is_serial_path("/dev/serial/by-id/usb-Arduino_Uno-if00"));
+    assert!(!is_serial_path("/dev/sda"));
+    assert!(!is_serial_path("/dev/serial/../sda"));
+    assert!(!is_serial_path("ttyUSB0"));
+    assert!(!is_serial_path("/dev/tty"));
+    assert!(!is_serial_path("/dev/tty1"));
+    assert!(is_serial_path("/dev/ttyS0"));
+    let env = parse_envelope(
+        r#"{"id":"p","name":"SerialOpen","path":"/dev/ttyACM0","baud_rate":115200}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        env.command,
+        Command::SerialOpen {
+            baud_rate: 115_200,
+            data_bits: None,
+            parity: crate::serial::Parity::None,
+            flow_control: false,
+            ..
+        }
+    ));
+    let err = handle_command(&Command::SerialOpen {
+        path: "/dev/ttyACM0".to_string(),
+        baud_rate: 9600,
+        data_bits: Some(9),
+        parity: crate::serial::Parity::Even,
+        stop_bits: None,
+        flow_control: false,
+    });
+    assert!(err.unwrap_err().contains("data_bits"));
+    let err = handle_command(&Command::SerialOpen {
+        path: "/dev/ttyUSB999".to_string(),
+        baud_rate: 9600,
+        data_bits: None,
+        parity: crate::serial::Parity::None,
+        stop_bits: None,
+        flow_control: false,
+    });
+    assert!(err.unwrap_err().contains("Not an available serial port"));
+    assert!(
+        !handle_command(&Command::SerialClose {
+            port_id: "nope".to_string()
+        })
+        .unwrap()["closed"]
+            .as_bool()
+            .unwrap()
+    );
+}
+
+#[test]
+fn usb_ids_and_list_command() {
+    use crate::usb::parse_hex_id;
+    assert_eq!(parse_hex_id("046d"), Some(0x046d));
+    assert_eq!(parse_hex_id("0x05ac  (Apple Inc.)"), Some(0x05ac));
+    assert_eq!(parse_hex_id("C52B\\6&1A2B"), Some(0xc52b));
+    assert_eq!(parse_hex_id("(none)"), None);
+    let env = parse_envelope(r#"{"id":"u","name":"ListUsbDevices"}"#).unwrap();
+    assert!(matches!(env.command, Command::ListUsbDevices));
+    assert!(is_blocking_command(&env.command));
+    let key = crate::usb::USB_ACCESS_KEY.to_string();
+    assert!(
+        handle_command(&Command::WriteConfig {
+            data: ConfigPayload {
+                key: key.clone(),
+                value: serde_json::json!(true)
+            }
+        })
+        .is_err()
+    );
+    assert!(crate::storage::check_ui_deletable(&key).is_ok());
+}
+
+#[test]
+fn bluetooth_ids_and_commands() {
+    use crate::bluetooth::{normalize_address, normalize_uuid};
+    assert_eq!(
+        normalize_uuid("180D").as_deref(),
+        Some("0000180d-0000-1000-8000-00805f9b34fb")
+    );
+    assert_eq!(
+        normalize_uuid("0x2a37").as_deref(),
+        Some("00002a37-0000-1000-8000-00805f9b34fb")
+    );
+    assert_eq!(
+        normalize_uuid("6E400001-B5A3-F393-E0A9-E50E24DCCA9E").as_deref(),
+        Some("6e400001-b5a3-f393-e0a9-e50e24dcca9e")
+    );
+    assert_eq!(normalize_uuid("heart-rate"), None);
+    assert_eq!(
+        normalize_address("aa:bb:cc:dd:ee:ff").unwrap(),
+        "AA:BB:CC:DD:EE:FF"
+    );
+    assert!(normalize_address("aa:bb:cc").is_err());
+    assert_eq!(
+        normalize_address("6e400001-b5a3-f393-e0a9-e50e24dcca9e").unwrap(),
+        "6E400001-B5A3-F393-E0A9-E50E24DCCA9E"
+    );
+    let env = parse_envelope(
+        r#"{"id":"b","name":"BluetoothWrite","address":"AA:BB:CC:DD:EE:FF","service":"180d","characteristic":"2a39","value":"AQ=="}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        env.command,
+        Command::BluetoothWrite {
+            without_response: false,
+            ..
+        }
+    ));
+    let err = handle_command(&Command::BluetoothConnect {
+        address: "not-an-address".to_string(),
+    });
+    assert!(err.unwrap_err().contains("Invalid Bluetooth address"));
+}
+
+#[test]
+fn open_with_extensions_and_grants() {
+    use crate::apps::{desktop_entry_handles, mime_for_extension, normalize_extension};
+    assert_eq!(normalize_extension(".PSD").unwrap(), "psd");
+    assert!(normalize_extension("").is_err());
+    assert!(normalize_extension("png/../x").is_err());
+    let globs =
+        "# comment\n50:image/png:*.png\n60:image/x-apple-png:*.png\n50:text/plain:*.txt:cs\n";
+    assert_eq!(
+        mime_for_extension(globs, "png").as_deref(),
+        Some("image/x-apple-png")
+    );
+    assert_eq!(
+        mime_for_extension(globs, "txt").as_deref(),
+        Some("text/plain")
+    );
+    assert_eq!(mime_for_extension(globs, "psd"), None);
+    let entry = "[Desktop Entry]\nName=GIMP\nMimeType=image/png;image/x-psd;\n[Desktop Action new]\nMimeType=text/plain;\n";
+    assert!(desktop_entry_handles(entry, "image/x-psd"));
+    assert!(!desktop_entry_handles(entry, "text/plain"));
+    let path = std::env::temp_dir().join("desktop-runtime-ungranted.psd");
+    let err = handle_command(&Command::OpenWith {
+        path: path.display().to_string(),
+        app: "org.gimp.GIMP.desktop".to_string(),
+    });
+    assert!(err.is_err());
+    let path = std::env::temp_dir().join("desktop-runtime-open-with.drnohandler");
+    std::fs::write(&path, b"x").unwrap();
+    grants::grant(&path, Access::Read, None);
+    let err = handle_command(&Command::OpenWith {
+        path: path.display().to_string(),
+        app: "org.gimp.GIMP.desktop".to_string(),
+    })
+    .unwrap_err();
+    assert!(err.contains("not a registered handler"), "{}", err);
+    let _ = std::fs::remove_file(&path);
+    assert!(is_audited(&Command::OpenWith {
+        path: String::new(),
+        app: String::new()
+    }));
+    assert!(is_audited(&Command::RestartApp { args: None }));
+    assert!(is_blocking_command(&Command::RestartApp { args: None }));
+}
+
+/// One ustar header block; `size` is the raw 12-byte size field.
+fn tar_header(name: &str, flag: u8, size: &[u8], link: &str) -> [u8; 512] {
+    let mut h = [0u8; 512];
+    h[..name.len()].copy_from_slice(name.as_bytes());
+    h[100..107].copy_from_slice(b"0000644");
+    h[124..124 + size.len()].copy_from_slice(size);
+    h[156] = flag;
+    h[157..157 + link.len()].copy_from_slice(link.as_bytes());
+    h[257..263].copy_from_slice(b"ustar\0");
+    h[148..156].fill(b' ');
+    let sum: u32 = h.iter().map(|b| u32::from(*b)).sum();
+    h[148..156].copy_from_slice(format!("{:06o}\0 ", sum).as_bytes());
+    h
+}
+
+fn gzip(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+    gz.write_all(data).unwrap();
+    gz.finish().unwrap()
+}
+
+/// Builds a tar.gz from `(name, type flag, data, link target)` entries, for hostile fixtures.
+fn tar_gz(entries: &[(&str, u8, &[u8], &str)]) -> Vec<u8> {
+    let mut tar = Vec::new();
+    for (name, flag, data, link) in entries {
+        tar.extend_from_slice(&tar_header(
+            name,
+            *flag,
+            format!("{:011o}", data.len()).as_bytes(),
+            link,
+        ));
+        tar.extend_from_slice(data);
+        tar.resize(tar.len().next_multiple_of(512), 0);
+    }
+    tar.extend_from_slice(&[0u8; 1024]);
+    gzip(&tar)
+}
+
+/// Fresh scratch directory with an empty `out` extraction target.
+fn archive_scratch() -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("archive-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(dir.join("out")).unwrap();
+    dir
+}
+
+#[test]
+fn archive_hardlinks_count_against_the_size_cap() {
+    let dir = archive_scratch();
+    let src = dir.join("bomb.tar.gz");
+    let names: Vec<String> = (0..10).map(|i| format!("link{i}")).collect();
+    let big = [7u8; 1000];
+    let mut entries = vec![("big", b'0', &big[..], "")];
+    entries.extend(names.iter().map(|n| (n.as_str(), b'1', &[][..], "big")));
+    std::fs::write(&src, tar_gz(&entries)).unwrap();
+    let err = crate::archive::extract_capped(&src, &dir.join("out"), None, 5000).unwrap_err();
+    assert!(err.contains("expands to more than 5000 bytes"));
+    std::fs::create_dir(dir.join("fits")).unwrap();
+    let summary = crate::archive::extract_capped(&src, &dir.join("fits"), None, 11_000).unwrap();
+    assert_eq!(summary.bytes, 11_000);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn archive_symlink_chains_cannot_escape() {
+    let dir = archive_scratch();
+    let src = dir.join("chain.tar.gz");
+    let chain = [
+        ("d1/", b'5', &[][..], ""),
+        ("d1/d2/", b'5', &[][..], ""),
+        ("d1/d2/up", b'2', &[][..], "../.."),
+        ("d1/esc", b'2', &[][..], "d2/up/.."),
+    ];
+    std::fs::write(&src, tar_gz(&chain)).unwrap();
+    let err = crate::archive::extract(&src, &dir.join("out"), None).unwrap_err();
+    assert!(err.contains("d1/esc points outside"), "{err}");
+
+    // A hard link may not copy through a symlink, even one that stays inside.
+    let through = [
+        ("here", b'2', &[][..], "."),
+        ("f", b'0', &b"data"[..], ""),
+        ("copy", b'1', &[][..], "here/f"),
+    ];
+    std::fs::write(&src, tar_gz(&through)).unwrap();
+    let err = crate::archive::extract(&src, &dir.join("out"), None).unwrap_err();
+    assert!(err.contains("Hard link copy"), "{err}");
+
+    #[cfg(unix)]
+    {
+        let tree = dir.join("tree");
+        std::fs::create_dir_all(tree.join("d1/d2")).unwrap();
+        std::os::unix::fs::symlink("../..", tree.join("d1/d2/up")).unwrap();
+        std::os::unix::fs::symlink("d2/up/..", tree.join("d1/esc")).unwrap();
+        let zip = dir.join("chain.zip");
+        crate::archive::create(&[tree.join("d1")], &zip, None).unwrap();
+        std::fs::create_dir(dir.join("zip-out")).unwrap();
+        let err = crate::archive::extract(&zip, &dir.join("zip-out"), None).unwrap_err();
+        assert!(err.contains("points outside"), "{err}");
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn archive_headers_with_impossible_sizes_fail_cleanly() {
+    let dir = archive_scratch();
+    let out = dir.join("out");
+
+    // Base-256 size near u64::MAX.
+    let mut size = [0xffu8; 12];
+    size[0] = 0x80;
+    let mut tar = tar_header("huge", b'0', &size, "").to_vec();
+    tar.extend_from_slice(&[0u8; 1024]);
+    let src = dir.join("huge.tar.gz");
+    std::fs::write(&src, gzip(&tar)).unwrap();
+    let err = crate::archive::extract(&src, &out, None).unwrap_err();
+    assert!(err.contains("size overflow"), "{err}");
+
+    std::fs::write(dir.join("a.txt"), b"hello").unwrap();
+    let zip = dir.join("a.zip");
+    crate::archive::create(&[dir.join("a.txt")], &zip, None).unwrap();
+    let bytes = std::fs::read(&zip).unwrap();
+    let end = bytes.len() - 22;
+
+    // Truncated before the end of the central directory.
+    let mut truncated = bytes[..end - 10].to_vec();
+    truncated.extend_from_slice(&bytes[end..]);
+    std::fs::write(&zip, &truncated).unwrap();
+    let err = crate::archive::extract(&zip, &out, None).unwrap_err();
+    assert!(err.contains("invalid Zip archive"), "{err}");
+
+    std::fs::write(&zip, &bytes).unwrap();
+    assert_eq!(crate::archive::extract(&zip, &out, None).unwrap().bytes, 5);
+
+    // A stored entry whose data no longer matches its CRC is not left behind.
+    let mut stored = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    stored.start_file("b.txt", options).unwrap();
+    std::io::Write::write_all(&mut stored, b"hello").unwrap();
+    let mut bytes = stored.finish().unwrap().into_inner();
+    let at = bytes.windows(5).position(|w| w == b"hello").unwrap();
+    bytes[at] = b'j';
+    std::fs::write(&zip, &bytes).unwrap();
+    let err = crate::archive::extract(&zip, &out, None).unwrap_err();
+    assert!(err.contains("checksum"), "{err}");
+    assert!(!out.join("b.txt").exists());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn archive_entry_paths_and_grants() {
+    use crate::archive::{ArchiveFormat, entry_path, link_stays_inside};
+    use std::path::Path;
+    assert_eq!(
+        entry_path("./My.app/Contents/Info.plist").unwrap(),
+        Path::new("My.app/Contents/Info.plist")
+    );
+    assert_eq!(entry_path("/etc/passwd").unwrap(), Path::new("etc/passwd"));
+    assert!(entry_path("a/../../b").is_none());
+    assert!(entry_path("./").is_none());
+    assert!(link_stays_inside(
+        Path::new("My.app/Contents/Current"),
+        "../Versions/A"
+    ));
+    assert!(!link_stays_inside(Path::new("link"), "../outside"));
+    assert!(!link_stays_inside(Path::new("a/link"), "/etc"));
+    assert!(link_stays_inside(Path::new("d1/d2/up"), "../.."));
+    assert!(!link_stays_inside(Path::new("d1/esc"), "d2/up/.."));
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("App.app.tar.gz")),
+        Some(ArchiveFormat::TarGz)
+    );
+    assert_eq!(
+        ArchiveFormat::from_path(Path::new("notes.ZIP")),
+        Some(ArchiveFormat::Zip)
+    );
+    let env = parse_envelope(r#"{"id":"a","name":"CreateArchive","sources":["/tmp/x"],"dest":"/tmp/x.zip","format":"tar.gz"}"#)
+        .unwrap();
+    assert!(matches!(
+        env.command,
+        Command::CreateArchive {
+            format: Some(ArchiveFormat::TarGz),
+            ..
+        }
+    ));
+    assert_eq!(command_lane(&env.command), Lane::Background);
+    let dir = std::env::temp_dir();
+    let err = handle_command(&Command::ExtractArchive {
+        src: dir
+            .join("desktop-runtime-ungranted.zip")
+            .display()
+            .to_string(),
+        dest: dir.display().to_string(),
+        format: None,
+    });
+    assert!(err.is_err());
+}
+
+#[test]
+fn app_bundle_path_from_executable() {
+    use crate::paths::app_bundle_path;
+    use std::path::Path;
+    assert_eq!(
+        app_bundle_path(Path::new("/Applications/My App.app/Contents/MacOS/my-app")).as_deref(),
+        Some(Path::new("/Applications/My App.app"))
+    );
+    assert_eq!(app_bundle_path(Path::new("/usr/local/bin/my-app")), None);
+    assert_eq!(app_bundle_path(Path::new("/tmp/odd.app")), None);
+    assert_eq!(app_bundle_path(Path::new("/tmp/odd.app/my-app")), None);
+}
+
+#[test]
+fn windows_install_scope_and_msi_arguments() {
+    use std::path::{Path, PathBuf};
+    use updates::{InstallScope, msi_arguments};
+    let machine = [
+        PathBuf::from(r"C:\Program Files"),
+        PathBuf::from(r"C:\Program Files (x86)"),
+    ];
+    let user = Path::new(r"C:\Users\me\AppData\Local");
+    assert_eq!(
+        InstallScope::from_location(
+            Path::new(r"C:\PROGRAM FILES\My App\app.exe"),
+            &machine,
+            Some(user)
+        ),
+        Some(InstallScope::PerMachine)
+    );
+    assert_eq!(
+        InstallScope::from_location(
+            Path::new(r"C:\Users\me\AppData\Local\Programs\app.exe"),
+            &machine,
+            Some(user)
+        ),
+        Some(InstallScope::PerUser)
+    );
+    assert_eq!(
+        InstallScope::from_location(Path::new(r"D:\apps\app.exe"), &machine, Some(user)),
+        None
+    );
+    assert_eq!(
+        msi_arguments(r"C:\Temp\update 1.msi", InstallScope::PerMachine),
+        r#"/i "C:\Temp\update 1.msi" /passive /norestart ALLUSERS=1"#
+    );
+    assert!(
+        msi_arguments("u.msi", InstallScope::PerUser).ends_with("ALLUSERS=2 MSIINSTALLPERUSER=1")
+    );
+}
+
+#[test]
+fn staged_rollout_percent_and_buckets() {
+    assert_eq!(rollout_percent("Fixes.\n<!-- rollout: 25% -->\n"), Some(25));
+    assert_eq!(rollout_percent("Rollout: 10"), Some(10));
+    assert_eq!(rollout_percent("rollout: 250%"), Some(100));
+    assert_eq!(rollout_percent("Changelog: rollout soon"), None);
+    assert_eq!(rollout_bucket("id", "1.2.0"), rollout_bucket("id", "1.2.0"));
+    assert!(rollout_bucket("id", "1.2.0") < 100);
+    let in_10 = (0..1000)
+        .filter(|i| rollout_bucket(&i.to_string(), "1.2.0") < 10)
+        .count();
+    assert!((50..150).contains(&in_10), "{}", in_10);
+}
+
+#[test]
+fn release_selection_skips_drafts_and_prereleases() {
+    let releases = serde_json::json!([
+        { "tag_name": "v2.0.0", "draft": true, "prerelease": false },
+        { "tag_name": "v1.3.0-rc.1", "draft": false, "prerelease": true },
+        { "tag_name": "v1.2.0", "draft": false, "prerelease": false }
+    ]);
+    assert_eq!(
+        select_release(&releases, false).unwrap()["tag_name"],
+        "v1.2.0"
+    );
+    assert_eq!(
+        select_release(&releases, true).unwrap()["tag_name"],
+        "v1.3.0-rc.1"
+    );
+    assert!(select_release(&serde_json::json!([]), true).is_none());
+    let mut config = crate::storage::AppConfig::default();
+    assert!(!crate::settings::Settings::from_config(&config).allows_prerelease());
+    config
+        .data
+        .insert("updateChannel".into(), serde_json::json!("beta"));
+    assert!(crate::settings::Settings::from_config(&config).allows_prerelease());
+}
+
+#[test]
+fn update_state_serializes_for_host_events() {
+    use updates::UpdateState;
+    assert_eq!(
+        serde_json::to_value(UpdateState::Idle).unwrap(),
+        serde_json::json!({ "state": "idle" })
+    );
+    assert_eq!(
+        serde_json::to_value(UpdateState::Downloading { percent: Some(42) }).unwrap(),
+        serde_json::json!({ "state": "downloading", "percent": 42 })
+    );
+    assert_eq!(
+        serde_json::to_value(UpdateState::Error {
+            message: "offline".into()
+        })
+        .unwrap(),
+        serde_json::json!({ "state": "error", "message": "offline" })
+    );
+    let env = parse_envelope(r#"{"id":"u","name":"GetUpdateState"}"#).unwrap();
+    assert_eq!(module_for(&env.command), Some(RuntimeModule::Updater));
+    assert!(handle_command(&env.command).unwrap()["state"].is_string());
+}
+
+#[test]
+fn restart_and_relaunch_arguments() {
+    use crate::lifecycle::{ps_quote, windows_command_line};
+    use std::ffi::{OsStr, OsString};
+    let env = parse_envelope(
+        r#"{"id":"r","name":"InstallUpdate","path":"/tmp/u.AppImage","relaunch":false}"#,
+    )
+    .unwrap();
+    assert!(matches!(
+        env.command,
+        Command::InstallUpdate {
+            relaunch: Some(false),
+            ..
+        }
+    ));
+    let env = parse_envelope(r#"{"id":"r","name":"RestartApp"}"#).unwrap();
+    assert!(matches!(env.command, Command::RestartApp { args: None }));
+    let args: Vec<OsString> = vec![
+        "--flag".into(),
+        "C:\\My Files\\a.txt".into(),
+        String::new().into(),
+    ];
+    assert_eq!(
+        windows_command_line(&args),
+        OsString::from(r#"--flag "C:\My Files\a.txt" """#)
+    );
+    let tricky: Vec<OsString> = vec![
+        "C:\\dir x\\".into(),
+        "next".into(),
+        r#"a\"b"#.into(),
+        r"a\\b".into(),
+    ];
+    assert_eq!(
+        windows_command_line(&tricky),
+        OsString::from(r#""C:\dir x\\" next "a\\\"b" a\\b"#)
+    );
+    assert_eq!(ps_quote(OsStr::new("it's")), "'it''s'");
+    assert_eq!(
+        ps_quote(OsStr::new("\u{2018}; calc; \u{2019}")),
+        "'\u{2018}\u{2018}; calc; \u{2019}\u{2019}'"
+    );
+}
+
+#[test]
+fn system_proxy_settings_parse() {
+    use crate::net::{parse_scutil_proxy, parse_windows_proxy_server, with_proxy_credentials};
+    let scutil = "<dictionary> {\n  HTTPEnable : 1\n  HTTPPort : 3128\n  HTTPProxy : proxy.corp\n  HTTPSEnable : 0\n}";
+    assert_eq!(
+        parse_scutil_proxy(scutil).as_deref(),
+        Some("http://proxy.corp:3128")
+    );
+    assert_eq!(
+        parse_scutil_proxy("<dictionary> {\n  HTTPEnable : 0\n}"),
+        None
+    );
+    assert_eq!(
+        parse_windows_proxy_server("proxy:8080").as_deref(),
+        Some("http://proxy:8080")
+    );
+    assert_eq!(
+        parse_windows_proxy_server("http=a:80;https=b:443;ftp=c:21").as_deref(),
+        Some("http://b:443")
+    );
+    assert_eq!(parse_windows_proxy_server(""), None);
+    assert_eq!(
+        with_proxy_credentials("http://proxy:8080", "me:p@ss"),
+        "http://me:p@ss@proxy:8080"
+    );
+    assert_eq!(
+        with_proxy_credentials("http://u:p@proxy:8080", "me:x"),
+        "http://u:p@proxy:8080"
+    );
+}
+
+#[test]
+fn cached_update_check_freshness() {
+    let cache: updates::CachedCheck = serde_json::from_value(serde_json::json!({
+        "checkedAt": 1_000_000,
+        "allowPrerelease": false,
+        "latest": "9.9.9",
+        "url": "https://example.com/release",
+        "assetUrl": null,
+        "prerelease": false,
+        "rolloutPercent": null,
+        "offered": [],
+        "digests": {}
+    }))
+    .unwrap();
+    let interval_ms = crate::config::UPDATE_CHECK_MIN_INTERVAL_SECS * 1000;
+    assert!(cache.is_fresh(1_000_000));
+    assert!(cache.is_fresh(1_000_000 + interval_ms - 1));
+    assert!(!cache.is_fresh(1_000_000 + interval_ms));
+    assert_eq!(
+        serde_json::to_value(&cache).unwrap()["checkedAt"],
+        1_000_000
+    );
+
+    // The cache lives outside config.json; a page writing the old key cannot plant assets.
+    let mut planted = serde_json::to_value(&cache).unwrap();
+    planted["checkedAt"] = serde_json::json!(u64::MAX);
+    planted["offered"] = serde_json::json!(["https://evil.example/installer.exe"]);
+    handle_command(&Command::WriteConfig {
+        data: ConfigPayload {
+            key: "updateCheckCache".to_string(),
+            value: planted,
+        },
+    })
+    .unwrap();
+    assert!(
+        updates::load_check_cache()
+            .is_none_or(|c| c.offered.iter().all(|u| !u.contains("evil.example")))
+    );
+    crate::storage::delete_value("updateCheckCache").unwrap();
+}
+
+#[test]
+fn semver_compare_prereleases_and_build_metadata() {
+    assert_eq!(semver_compare("1.2.0-beta.1", "1.2.0"), -1);
+    assert_eq!(semver_compare("1.2.0", "1.2.0-rc.1"), 1);
+    assert_eq!(semver_compare("1.2.0-beta.2", "1.2.0-beta.10"), -1);
+    assert_eq!(semver_compare("1.2.0-beta.1", "1.2.0-rc.1"), -1);
+    assert_eq!(semver_compare("1.2.0-alpha", "1.2.0-alpha.1"), -1);
+    assert_eq!(semver_compare("1.2.0-rc.1", "1.1.9"), 1);
+    assert_eq!(semver_compare("1.2.0+build.5", "1.2.0+build.7"), 0);
+    assert_eq!(semver_compare("v1.2", "1.2.0"), 0);
+    assert_eq!(semver_compare("1.10.0", "1.9.0"), 1);
+    assert_eq!(semver_compare("2", "1.9.9-rc.1"), 1);
+    assert!(updates::parse_version("1.2.3.4").is_none());
+    assert!(updates::parse_version("latest").is_none());
+}
+
+#[test]
+fn panel_commands_are_window_commands_with_checked_input() {
+    use crate::panel::{PanelBounds, panel_url, validate_name};
+    let open = parse_envelope(
+        r#"{"id":"p","name":"OpenPanel","panel":"docs","path":"docs/index.html","x":600,"y":0,"width":400,"height":800}"#,
+    )
+    .unwrap();
+    assert!(
+        matches!(open.command, Command::OpenPanel { ref panel, width, .. } if panel == "docs" && width == 400.0)
+    );
+    assert!(is_window_command(&open.command));
+    assert!(is_window_command(&Command::ListPanels));
+
+    assert_eq!(
+        panel_url("docs/index.html").unwrap(),
+        "app://localhost/docs/index.html"
+    );
+    assert_eq!(
+        panel_url("/preview.html?file=a:b").unwrap(),
+        "app://localhost/preview.html?file=a:b"
+    );
+    assert!(panel_url("https://example.com/").is_err());
+    assert!(panel_url("//example.com/x").is_err());
+    assert!(panel_url("../secret").is_err());
+    assert!(panel_url("a\\b").is_err());
+
+    assert!(validate_name("preview-1").is_ok());
+    assert!(validate_name("").is_err());
+    assert!(validate_name("a b").is_err());
+
+    let bounds = PanelBounds {
+        x: 0.0,
+        y: 0.0,
+        width: 300.0,
+        height: 200.0,
+    };
+    assert!(bounds.validate().is_ok());
+    assert!(
+        PanelBounds {
+            width: 0.0,
+            ..bounds
+        }
+        .validate()
+        .is_err()
+    );
+    assert!(
+        PanelBounds {
+            x: f64::NAN,
+            ..bounds
+        }
+        .validate()
+        .is_err()
+    );
+}
+
+#[test]
+fn open_modal_urls_and_responses() {
+    use crate::modal::{modal_response, modal_url};
+    let open = parse_envelope(
+        r#"{"id":"m","name":"OpenModal","url":"confirm.html","width":420,"height":240}"#,
+    )
+    .unwrap();
+    assert!(matches!(open.command, Command::OpenModal { ref url, .. } if url == "confirm.html"));
+    assert!(is_window_command(&open.command));
+
+    assert_eq!(
+        modal_url("confirm.html?step=2").unwrap(),
+        "app://localhost/confirm.html?step=2"
+    );
+    assert_eq!(
+        modal_url("app://localhost/confirm.html").unwrap(),
+        "app://localhost/confirm.html"
+    );
+    assert!(modal_url("https://example.com/").is_err());
+
+    let posted = modal_response(Some(serde_json::json!({ "confirmed": true })));
+    assert_eq!(
+        posted,
+        serde_json::json!({ "result": { "confirmed": true }, "dismissed": false })
+    );
+    assert_eq!(
+        modal_response(None),
+        serde_json::json!({ "result": null, "dismissed": true })
+    );
+}
+
+#[test]
+fn window_ids_in_envelopes_and_emit_to_window() {
+    let plain = parse_envelope(r#"{"id":"a","name":"Ping"}"#).unwrap();
+    assert_eq!(plain.window_id, None);
+    let from_panel = parse_envelope(r#"{"id":"b","windowId":"docs","name":"Ping"}"#).unwrap();
+    assert_eq!(from_panel.window_id.as_deref(), Some("docs"));
+
+    let emit = parse_envelope(
+        r#"{"id":"c","name":"EmitToWindow","window_id":"modal","event":"selection-changed","payload":{"n":2}}"#,
+    )
+    .unwrap();
+    assert!(is_window_command(&emit.command));
+    assert!(matches!(
+        emit.command,
+        Command::EmitToWindow { ref window_id, ref payload, .. } if window_id == "modal" && payload["n"] == 2
+    ));
+    let bare =
+        parse_envelope(r#"{"id":"d","name":"EmitToWindow","window_id":"main","event":"ping"}"#)
+            .unwrap();
+    assert!(matches!(bare.command, Command::EmitToWindow { ref payload, .. } if payload.is_null()));
+
+    assert!(crate::events::validate_name("docs:scroll-to").is_ok());
+    assert!(crate::events::validate_name("").is_err());
+    assert!(crate::events::validate_name("a\"b").is_err());
+    assert!(crate::panel::validate_name(crate::window::MAIN_WINDOW).is_err());
+    assert!(crate::panel::validate_name(crate::window::MODAL_WINDOW).is_err());
+}
+
+#[test]
+fn window_placement_presets() {
+    use crate::window::PixelRect;
+    use window::placement_rect;
+    let parsed =
+        parse_envelope(r#"{"id":"w","name":"SetWindowPlacement","preset":"left-half"}"#).unwrap();
+    assert!(matches!(
+        parsed.command,
+        Command::SetWindowPlacement {
+            preset: PlacementPreset::LeftHalf,
+            monitor: None
+        }
+    ));
+    assert!(is_window_command(&parsed.command));
+
+    let monitor = PixelRect {
+        x: 1920,
+        y: 0,
+        width: 2561,
+        height: 1440,
+    };
+    assert_eq!(
+        placement_rect(PlacementPreset::LeftHalf, monitor, (800, 600)),
+        PixelRect {
+            x: 1920,
+            y: 0,
+            width: 1280,
+            height: 1440
+        }
+    );
+    assert_eq!(
+        placement_rect(PlacementPreset::RightHalf, monitor, (800, 600)),
+        PixelRect {
+            x: 3200,
+            y: 0,
+            width: 1281,
+            height: 1440
+        }
+    );
+    assert_eq!(
+        placement_rect(PlacementPreset::Center, monitor, (801, 600)),
+        PixelRect {
+            x: 2800,
+            y: 420,
+            width: 801,
+            height: 600
+        }
+    );
+    assert_eq!(
+        placement_rect(PlacementPreset::Center, monitor, (4000, 3000)),
+        monitor
+    );
+    assert_eq!(
+        placement_rect(PlacementPreset::Maximize, monitor, (800, 600)),
+        monitor
+    );
+}
+
+#[test]
+fn overlay_commands_only_run_on_the_loop() {
+    use crate::window::opacity_alpha;
+    // On a worker thread they fail instead of touching the window.
+    for command in [
+        Command::SetWindowOpacity { value: 0.5 },
+        Command::SetIgnoreCursorEvents { on: true },
+    ] {
+        assert!(is_window_command(&command));
+        let err = handle_command(&command).unwrap_err();
+        assert!(err.contains("must run on the event loop"), "{err}");
+    }
+
+    assert_eq!(opacity_alpha(0.0), Ok(0));
+    assert_eq!(opacity_alpha(0.5), Ok(128));
+    assert_eq!(opacity_alpha(1.0), Ok(255));
+    assert!(opacity_alpha(1.01).is_err());
+    assert!(opacity_alpha(-0.1).is_err());
+    assert!(opacity_alpha(f64::NAN).is_err());
+}
+
+#[test]
+fn window_bounds_keep_maximized_and_fullscreen_flags() {
+    use crate::storage::WindowBounds;
+    let legacy: WindowBounds =
+        serde_json::from_str(r#"{"x":10,"y":20,"width":800,"height":600}"#).unwrap();
+    assert_eq!(
+        legacy,
+        WindowBounds {
+            x: 10,
+            y: 20,
+            width: 800,
+            height: 600,
+            ..WindowBounds::default()
+        }
+    );
+    let maximized = WindowBounds {
+        maximized: true,
+        ..legacy.clone()
+    };
+    let json = serde_json::to_value(&maximized).unwrap();
+    assert_eq!(json["maximized"], true);
+    assert_eq!(json["fullscreen"], false);
+    assert_eq!(
+        serde_json::from_value::<WindowBounds>(json).unwrap(),
+        maximized
+    );
+}
+
+#[test]
+fn restored_bounds_fit_connected_monitors() {
+    use crate::storage::WindowBounds;
+    use crate::window::{PixelRect, fit_to_monitors};
+    let primary = PixelRect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    let bounds = WindowBounds {
+        x: 2200,
+        y: 100,
+        width: 800,
+        height: 600,
+        maximized: true,
+        ..Default::default()
+    };
+
+    // Saved on a second display that is now unplugged: centered on the remaining one.
+    let fitted = fit_to_monitors(bounds.clone(), &[primary]);
+    assert_eq!(
+        fitted,
+        WindowBounds {
+            x: 560,
+            y: 240,
+            width: 800,
+            height: 600,
+            maximized: true,
+            ..Default::default()
+        }
+    );
+    // Still connected: kept as saved.
+    let second = PixelRect {
+        x: 1920,
+        y: 0,
+        width: 2560,
+        height: 1440,
+    };
+    assert_eq!(fit_to_monitors(bounds.clone(), &[primary, second]), bounds);
+    // Title bar above the top edge is not reachable.
+    let above = WindowBounds {
+        y: -200,
+        ..bounds.clone()
+    };
+    assert_eq!(fit_to_monitors(above, &[primary, second]).y, 420);
+    // Resolution dropped below the saved size: shrunk into the display.
+    let large = WindowBounds {
+        x: 100,
+        y: 50,
+        width: 2400,
+        height: 1300,
+        ..Default::default()
+    };
+    assert_eq!(
+        fit_to_monitors(large, &[primary]),
+        WindowBounds {
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            ..Default::default()
+        }
+    );
+    assert_eq!(fit_to_monitors(bounds.clone(), &[]), bounds);
+}
+
+#[test]
+fn restored_bounds_convert_between_display_scales() {
+    use crate::storage::WindowBounds;
+    use crate::window::{PixelRect, rescale_bounds};
+    let saved = WindowBounds {
+        x: 10,
+        y: 20,
+        width: 1600,
+        height: 1200,
+        scale_factor: Some(2.0),
+        ..Default::default()
+    };
+    assert_eq!(
+        rescale_bounds(saved.clone(), 1.0),
+        WindowBounds {
+            x: 10,
+            y: 20,
+            width: 800,
+            height: 600,
+            scale_factor: Some(1.0),
+            ..Default::default()
+        }
+    );
+    assert_eq!(rescale_bounds(saved.clone(), 2.0), saved);
+    // Bounds saved before the scale was recorded are kept as is.
+    let legacy = WindowBounds {
+        scale_factor: None,
+        ..saved.clone()
+    };
+    assert_eq!(rescale_bounds(legacy, 1.5).width, 1600);
+
+    let json = serde_json::to_value(&saved).unwrap();
+    assert_eq!(json["scaleFactor"], 2.0);
+    assert_eq!(serde_json::from_value::<WindowBounds>(json).unwrap(), saved);
+
+    let monitor = PixelRect {
+        x: -1920,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    assert!(monitor.contains(-1920, 0));
+    assert!(!monitor.contains(0, 0));
+}
+
+#[test]
+fn window_title_and_icon_commands() {
+    let parsed =
+        parse_envelope(r#"{"id":"t","name":"SetWindowTitle","title":"notes.md — App"}"#).unwrap();
+    assert!(is_window_command(&parsed.command));
+    let parsed =
+        parse_envelope(r#"{"id":"i","name":"SetWindowIcon","app_asset_path":"icons/doc.png"}"#)
+            .unwrap();
+    assert!(
+        matches!(parsed.command, Command::SetWindowIcon { app_asset_path: Some(ref p) } if p == "icons/doc.png")
+    );
+    assert!(is_window_command(&parsed.command));
+    let parsed = parse_envelope(r#"{"id":"r","name":"SetWindowIcon"}"#).unwrap();
+    assert!(matches!(
+        parsed.command,
+        Command::SetWindowIcon {
+            app_asset_path: None
+        }
+    ));
+
+    assert!(crate::window::validate_title("notes.md — App").is_ok());
+    assert!(crate::window::validate_title("a\nb").is_err());
+    assert!(crate::window::validate_title(&"x".repeat(257)).is_err());
+    assert!(
+        crate::window::icon_from_bytes(include_bytes!("../../../packaging/icons/react.png"))
+            .is_ok()
+    );
+    assert!(crate::window::icon_from_bytes(b"not an image").is_err());
+}
+
+#[test]
+fn css_cursor_names_map_to_native_cursors() {
+    // Every CSS name shows the native cursor of the same name; only `pointer` is called `Hand`.
+    let names = [
+        "default",
+        "pointer",
+        "crosshair",
+        "text",
+        "move",
+        "wait",
+        "progress",
+        "help",
+        "not-allowed",
+        "grab",
+        "grabbing",
+        "all-scroll",
+        "zoom-in",
+        "zoom-out",
+        "ew-resize",
+        "ns-resize",
+        "nesw-resize",
+        "nwse-resize",
+        "col-resize",
+        "row-resize",
+    ];
+    for name in names {
+        let env = parse_envelope(&format!(
+            r#"{{"id":"c","name":"SetCursor","kind":"{name}"}}"#
+        ))
+        .unwrap_or_else(|e| panic!("{name}: {}", e.message));
+        let Command::SetCursor { kind } = env.command else {
+            panic!("{name} did not parse as SetCursor");
+        };
+        let native = format!("{:?}", tao::window::CursorIcon::from(kind));
+        let expected: String = match name {
+            "pointer" => "Hand".to_string(),
+            _ => name
+                .split('-')
+                .map(|part| part[..1].to_uppercase() + &part[1..])
+                .collect(),
+        };
+        assert_eq!(native, expected, "{name}");
+    }
+    // tao's own names are not accepted.
+    assert!(parse_envelope(r#"{"id":"c","name":"SetCursor","kind":"hand"}"#).is_err());
+    for command in [
+        Command::SetCursor {
+            kind: CursorKind::Grab,
+        },
+        Command::SetCursorVisible { visible: false },
+        Command::SetCursorGrab { on: true },
+    ] {
+        let err = handle_command(&command).unwrap_err();
+        assert!(err.contains("must run on the event loop"), "{err}");
+    }
+}
+
+#[test]
+fn cursor_position_commands() {
+    use crate::window::PixelRect;
+    for json in [
+        r#"{"id":"p","name":"GetCursorPosition"}"#,
+        r#"{"id":"w","name":"WatchCursorLeave","on":true}"#,
+    ] {
+        assert!(is_window_command(&parse_envelope(json).unwrap().command));
+    }
+    let rect = PixelRect {
+        x: 100,
+        y: 100,
+        width: 300,
+        height: 200,
+    };
+    assert!(crate::cursor::is_inside(rect, 100.0, 100.0));
+    assert!(crate::cursor::is_inside(rect, 399.5, 299.9));
+    assert!(!crate::cursor::is_inside(rect, 400.0, 150.0));
+    assert!(!crate::cursor::is_inside(rect, 99.5, 150.0));
+}
+
+#[test]
+fn tray_popover_position() {
+    use crate::popover::popover_position;
+    use crate::window::PixelRect;
+    let monitor = PixelRect {
+        x: 0,
+        y: 0,
+        width: 1920,
+        height: 1080,
+    };
+    // Windows taskbar at the bottom: above the icon, kept on screen at the right edge.
+    let icon = PixelRect {
+        x: 1800,
+        y: 1050,
+        width: 24,
+        height: 24,
+    };
+    assert_eq!(popover_position(icon, (360, 480), monitor), (1560, 566));
+    // macOS menu bar at the top: below the icon, centered on it.
+    let icon = PixelRect {
+        x: 1000,
+        y: 0,
+        width: 22,
+        height: 24,
+    };
+    assert_eq!(popover_position(icon, (360, 480), monitor), (831, 28));
+
+    let config = crate::storage::AppConfig {
+        data: [(
+            crate::popover::TRAY_POPOVER_KEY.to_string(),
+            serde_json::json!(true),
+        )]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    };
+    assert!(crate::settings::Settings::from_config(&config).tray_popover);
+    assert!(!crate::settings::Settings::from_config(&Default::default()).tray_popover);
+}
+
+#[test]
+fn event_loop_timers_run_in_deadline_order() {
+    use crate::event_loop::Timers;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
+    let start = Instant::now();
+    let fired = Rc::new(RefCell::new(Vec::new()));
+    let mut timers = Timers::default();
+    for (name, ms) in [("late", 30), ("early", 10), ("also-early", 10)] {
+        let fired = Rc::clone(&fired);
+        timers.add(start + Duration::from_millis(ms), move || {
+            fired.borrow_mut().push(name)
+        });
+    }
+    assert_eq!(
+        timers.run_due(start),
+        Some(start + Duration::from_millis(10))
+    );
+    assert!(fired.borrow().is_empty());
+    assert_eq!(
+        timers.run_due(start + Duration::from_millis(20)),
+        Some(start + Duration::from_millis(30))
+    );
+    assert_eq!(*fired.borrow(), ["early", "also-early"]);
+    assert_eq!(timers.run_due(start + Duration::from_millis(30)), None);
+    assert_eq!(*fired.borrow(), ["early", "also-early", "late"]);
+}
+
+#[test]
+fn ipc_queue_limits_from_config() {
+    use crate::settings::Settings;
+    let limits = |pairs: &[(&str, serde_json::Value)]| {
+        let config = crate::storage::AppConfig {
+            data: pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        Settings::from_config(&config).ipc_limits
+    };
+    assert_eq!(limits(&[]), IpcLimits::DEFAULT);
+    assert_eq!(
+        limits(&[
+            ("ipcQueueCapacity", serde_json::json!(64)),
+            ("ipcDropPolicy", serde_json::json!("drop-oldest")),
+            ("ipcBatchSize", serde_json::json!(16)),
+        ]),
+        IpcLimits {
+            capacity: 64,
+            drop_policy: DropPolicy::DropOldest,
+            batch_size: 16
+        }
+    );
+    assert_eq!(
+        limits(&[("ipcQueueCapacity", serde_json::json!(0))]).capacity,
+        1
+    );
+    assert_eq!(
+        limits(&[("ipcDropPolicy", serde_json::json!("newest"))]).drop_policy,
+        DropPolicy::Reject
+    );
+}
+
+#[test]
+fn ipc_queue_slots_track_pending_responses() {
+    let (sender, receiver) = ipc_channel(2);
+    let stats = sender.stats();
+    let first = sender.try_reserve().unwrap();
+    let second = sender.try_reserve().unwrap();
+    assert!(sender.try_reserve().is_none());
+    // A command without a response releases its slot by dropping it.
+    drop(second);
+    assert_eq!(stats.pending(), 1);
+
+    // Only the first response after a drain asks for a wake-up.
+    assert!(sender.send(first, None, "a".to_string()));
+    assert!(!sender.send(
+        sender.try_reserve().unwrap(),
+        Some("docs".to_string()),
+        "b".to_string()
+    ));
+    assert_eq!((stats.pending(), stats.queued()), (2, 2));
+    // Busy answers go past the capacity.
+    assert!(!sender.send(sender.reserve(), None, "busy".to_string()));
+
+    assert!(receiver.drop_oldest());
+    let batch = receiver.drain(1);
+    assert_eq!(batch[0].window.as_deref(), Some("docs"));
+    assert_eq!(batch[0].json, "b");
+    drop(batch);
+    assert_eq!((stats.pending(), stats.queued()), (1, 1));
+    assert!(sender.send(sender.try_reserve().unwrap(), None, "c".to_string()));
+    assert_eq!(receiver.drain(0).len(), 2);
+    assert_eq!(stats.pending(), 0);
+    assert!(!receiver.drop_oldest());
+}
+
+#[test]
+fn host_events_are_held_until_ui_ready() {
+    let command: Command = serde_json::from_str(r#"{"name":"UiReady"}"#).unwrap();
+    assert!(is_window_command(&command));
+
+    let mut buffer = crate::events::HostEventBuffer::new(2);
+    for name in ["open-file", "deep-link", "tray-click"] {
+        assert!(
+            buffer
+                .hold(name.to_string(), serde_json::json!({}))
+                .is_none()
+        );
+    }
+    // Oldest dropped when full; the rest replayed in order.
+    let held: Vec<String> = buffer.ready().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(held, ["deep-link", "tray-click"]);
+    assert!(
+        buffer
+            .hold("later".to_string(), serde_json::json!(1))
+            .is_some()
+    );
+
+    buffer.reset();
+    assert!(!buffer.is_ready());
+    assert!(
+        buffer
+            .hold("reloading".to_string(), serde_json::json!(null))
+            .is_none()
+    );
+    assert_eq!(buffer.ready().len(), 1);
+}
+
+#[test]
+fn renderer_restarts_are_budgeted() {
+    use crate::config::{MAX_RENDERER_RESTARTS, RENDERER_RESTART_WINDOW_SECS};
+    let mut budget = crate::renderer::RestartBudget::default();
+    let start = std::time::Instant::now();
+    for _ in 0..MAX_RENDERER_RESTARTS {
+        assert!(budget.try_restart(start));
+    }
+    assert!(!budget.try_restart(start + std::time::Duration::from_secs(1)));
+    // Crashes age out of the window.
+    assert!(
+        budget.try_restart(start + std::time::Duration::from_secs(RENDERER_RESTART_WINDOW_SECS))
+    );
+    assert_eq!(budget.count(), 1);
+}
+
+#[test]
+fn watchdog_flags_missed_pings_once() {
+    use crate::watchdog::{PingState, Verdict, WatchdogAction};
+    let mut pings = PingState::default();
+    let answer = pings.send();
+    answer();
+    assert_eq!(pings.check(2), None);
+
+    let _lost = pings.send();
+    let late = pings.send();
+    assert_eq!(pings.check(2), Some(Verdict::Unresponsive));
+    let _ = pings.send();
+    assert_eq!(pings.check(2), None);
+    // Any answer clears the streak.
+    late();
+    assert_eq!(pings.check(2), Some(Verdict::Responsive));
+
+    let config = crate::settings::Settings::from_config(&Default::default()).watchdog;
+    assert_eq!(config.action, WatchdogAction::Notify);
+    assert_eq!(
+        serde_json::from_value::<WatchdogAction>(serde_json::json!("ask")).unwrap(),
+        WatchdogAction::Ask
+    );
+}
+
+#[test]
+fn memory_usage_sums_the_process_tree() {
+    use crate::memory::tree_memory;
+    let rows = [
+        (10, Some(1), 100),
+        (11, Some(10), 20),
+        (12, Some(11), 3),
+        (13, Some(1), 1000),
+        (14, None, 5),
+    ];
+    assert_eq!(tree_memory(10, &rows), 123);
+    assert_eq!(tree_memory(99, &rows), 0);
+
+    let settings = crate::settings::Settings::from_config(&Default::default());
+    assert_eq!(
+        (settings.memory_limit_mb, settings.memory_trim_webview),
+        (0, false)
+    );
+}
+
+#[test]
+fn background_efficiency_follows_window_visibility() {
+    use crate::priority::VisibilityFollower;
+    let mut follow = VisibilityFollower::new(true);
+    assert_eq!(follow.observe(true, || true), None);
+    assert_eq!(follow.observe(false, || true), Some(true));
+    assert_eq!(follow.observe(false, || true), None);
+    assert_eq!(follow.observe(true, || true), Some(false));
+    // Off in config: changes are still tracked, but nothing switches.
+    assert_eq!(follow.observe(false, || false), None);
+    assert_eq!(follow.observe(true, || true), Some(false));
+    // The setting is only read when the visibility changes.
+    assert_eq!(
+        follow.observe(true, || panic!("read without a change")),
+        None
+    );
+}
+
+#[test]
+fn efficiency_mode_commands() {
+    let env = parse_envelope(r#"{"id":"e","name":"SetEfficiencyMode","on":true}"#).unwrap();
+    assert!(matches!(
+        env.command,
+        Command::SetEfficiencyMode { on: true }
+    ));
+    assert!(is_blocking_command(&env.command));
+    let state = handle_command(&Command::GetEfficiencyMode).unwrap();
+    assert_eq!(state["on"], serde_json::json!(crate::priority::is_on()));
+    assert!(!crate::settings::Settings::from_config(&Default::default()).background_efficiency);
+}
+
+#[test]
+fn restart_app_takes_replacement_args() {
+    use crate::lifecycle::{relaunch_command, validate_restart_args};
+    let env =
+        parse_envelope(r#"{"id":"r","name":"RestartApp","args":["--hidden","--profile=work"]}"#)
+            .unwrap();
+    let Command::RestartApp { args: Some(args) } = env.command else {
+        panic!("expected args");
+    };
+    assert!(validate_restart_args(&args).is_ok());
+    assert!(validate_restart_args(&["a\0b".to_string()]).is_err());
+    assert!(validate_restart_args(&vec![String::new(); 65]).is_err());
+
+    if std::env::var_os("APPIMAGE").is_none() {
+        let command = relaunch_command(Some(&args)).unwrap();
+        assert_eq!(
+            command[1..],
+            [
+                std::ffi::OsString::from("--hidden"),
+                std::ffi::OsString::from("--profile=work")
+            ]
+        );
+        assert_eq!(relaunch_command(Some(&[])).unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn quit_app_asks_the_loop_to_quit() {
+    use crate::events::QUIT_REQUESTS;
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+    let before = QUIT_REQUESTS.load(Ordering::Relaxed);
+    assert_eq!(
+        handle_command(&Command::QuitApp).unwrap(),
+        serde_json::json!({ "quitting": true })
+    );
+    // The request follows after a delay that lets the response reach the UI.
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while QUIT_REQUESTS.load(Ordering::Relaxed) == before {
+        assert!(Instant::now() < deadline, "QuitApp never requested a quit");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    for command in [Command::ShowWindow, Command::HideWindow] {
+        let err = handle_command(&command).unwrap_err();
+        assert!(err.contains("must run on the event loop"), "{err}");
+    }
+}
+
+#[test]
+fn translations_resolve_with_fallback() {
+    use crate::i18n::{FALLBACK_LOCALE, resolve, select_locale};
+    use crate::locale::candidate_tags;
+    let tags = candidate_tags(&["fr_CA".to_string()]);
+    assert_eq!(select_locale(&tags), "fr");
+    assert_eq!(select_locale(&["xx".to_string()]), FALLBACK_LOCALE);
+    let (tag, messages) = resolve(Some("fr-CA"));
+    assert_eq!(tag, "fr");
+    assert_eq!(messages["tray.quit"], "Quitter");
+    assert_eq!(resolve(Some("xx")).1["tray.quit"], "Quit");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn keyboard_layout_parsing() {
+    use crate::keyboard::{parse_gsettings_sources, parse_setxkbmap};
+    assert_eq!(
+        parse_gsettings_sources("[('ibus', 'anthy'), ('xkb', 'de+nodeadkeys'), ('xkb', 'us')]")
+            .as_deref(),
+        Some("de+nodeadkeys")
+    );
+    assert_eq!(parse_gsettings_sources("@a(ss) []"), None);
+    let query = "rules:      evdev\nmodel:      pc105\nlayout:     fr,us\nvariant:    azerty,\n";
+    assert_eq!(parse_setxkbmap(query).as_deref(), Some("fr+azerty"));
+    assert_eq!(parse_setxkbmap("layout:     us\n").as_deref(), Some("us"));
+}
+
+#[test]
+fn response_scripts_chunk_by_size() {
+    use crate::event_loop::{json_literal, response_scripts};
+    assert_eq!(json_literal(r#"{"a":"x\u2028"}"#), r#"{"a":"x\u2028"}"#);
+    assert_eq!(json_literal("\"\u{2028}\u{2029}\""), r#""\u2028\u2029""#);
+
+    let batch: Vec<String> = (0..4)
+        .map(|i| format!(r#"{{"id":"{}","ok":"{}"}}"#, i, "x".repeat(40)))
+        .collect();
+    let all = response_scripts(&batch, "k", 1 << 20);
+    assert_eq!(all.len(), 1);
+    assert!(all[0].contains(&format!(
+        "[{},{},{},{}]",
+        batch[0], batch[1], batch[2], batch[3]
+    )));
+    let split = response_scripts(&batch, "k", 2 * batch[0].len() + 1);
+    assert_eq!(split.len(), 2);
+    // An oversized response is still delivered, alone.
+    assert_eq!(response_scripts(&batch[..2], "k", 10).len(), 2);
+    assert!(response_scripts(&[], "k", 10).is_empty());
+}
@@ -0,0 +1,77 @@
+//! IPC trace buffer backing the developer console (`window.__devIpc`).
+//!
+//! Enabled only when DevTools are on. Keeps the last `CAPACITY` commands with arguments, outcome,
+//! and host-side latency; `GetIpcTrace` returns them with the current response queue depth.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use super::{Command, IpcStats};
+
+/// Entries kept in the ring buffer.
+const CAPACITY: usize = 200;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static BUFFER: Mutex<VecDeque<serde_json::Value>> = Mutex::new(VecDeque::new());
+static QUEUE_DEPTH: OnceLock<IpcStats> = OnceLock::new();
+
+/// Turns tracing on; `queue` gives the pending IPC response count reported by `GetIpcTrace`.
+pub fn enable(queue: IpcStats) {
+    let _ = QUEUE_DEPTH.set(queue);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// True when tracing is on (DevTools enabled).
+#[must_use]
+pub(super) fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Appends one entry, evicting the oldest past `CAPACITY`.
+pub(super) fn record(
+    command: &Command,
+    result: &Result<serde_json::Value, String>,
+    elapsed: Duration,
+) {
+    if !enabled() || matches!(command, Command::GetIpcTrace) {
+        return;
+    }
+    let at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let entry = serde_json::json!({
+        "at": at,
+        "command": command,
+        "ok": result.is_ok(),
+        "error": result.as_ref().err(),
+        "durationMs": elapsed.as_secs_f64() * 1000.0,
+    });
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    if buffer.len() == CAPACITY {
+        buffer.pop_front();
+    }
+    buffer.push_back(entry);
+}
+
+/// Recent entries (oldest first) and the current response queue depth.
+pub(super) fn snapshot() -> Result<serde_json::Value, String> {
+    if !enabled() {
+        return Err("IPC trace requires DevTools".to_string());
+    }
+    let entries: Vec<serde_json::Value> = BUFFER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .cloned()
+        .collect();
+    let queue_depth = QUEUE_DEPTH.get().map_or(0, IpcStats::pending);
+    Ok(serde_json::json!({ "entries": entries, "queueDepth": queue_depth }))
+}
+
+/// Drops the recorded entries (memory pressure).
+pub fn clear() {
+    BUFFER.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
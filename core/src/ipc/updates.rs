@@ -3,13 +3,23 @@
 //! Isolated from generic command handling so protocol and network concerns
 //! stay in one place.
 
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::UPDATE_CHECK_MIN_INTERVAL_SECS;
+use crate::hex;
 
 /// GitHub repo (owner/name) for update checks. Set at build via `DESKTOP_RUNTIME_GITHUB_REPO` or derived from CARGO_PKG_REPOSITORY.
-pub(super) const GITHUB_REPO: &str =
-    env!("GITHUB_REPO_FOR_UPDATES", "Set GITHUB_REPO_FOR_UPDATES via build.rs");
+pub(super) const GITHUB_REPO: &str = env!(
+    "GITHUB_REPO_FOR_UPDATES",
+    "Set GITHUB_REPO_FOR_UPDATES via build.rs"
+);
 
 /// Preferred asset extensions per platform (first match wins).
 #[cfg(target_os = "windows")]
@@ -24,6 +34,148 @@ const ASSET_EXTENSIONS: &[&str] = &[".AppImage", ".appimage", ".deb"];
 #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
 const ASSET_EXTENSIONS: &[&str] = &[];
 
+/// Config key: `true` treats every staged rollout as reaching this machine.
+const FORCE_ROLLOUT_KEY: &str = "updateForceRollout";
+
+/// File under the user data dir holding the random id that places this install in a rollout bucket.
+const INSTALL_ID_FILE: &str = "install-id";
+
+/// Updater progress, reported by `GetUpdateState` and the `update-state` host event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum UpdateState {
+    Idle,
+    Checking,
+    Available {
+        version: String,
+    },
+    /// `percent` is `None` when the server sends no length.
+    Downloading {
+        percent: Option<u8>,
+    },
+    Ready {
+        path: String,
+    },
+    Installing,
+    Error {
+        message: String,
+    },
+}
+
+static STATE: Mutex<UpdateState> = Mutex::new(UpdateState::Idle);
+
+/// Current updater state.
+pub(super) fn state() -> UpdateState {
+    STATE.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Records `next` and emits `update-state` when it differs from the current state.
+fn set_state(next: UpdateState) {
+    let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+    if *state == next {
+        return;
+    }
+    *state = next.clone();
+    drop(state);
+    if let Ok(payload) = serde_json::to_value(&next) {
+        crate::events::emit("update-state", payload);
+    }
+}
+
+/// Sets the error state for a failed step and passes the result through.
+fn track<T>(result: Result<T, String>) -> Result<T, String> {
+    if let Err(e) = &result {
+        set_state(UpdateState::Error { message: e.clone() });
+    }
+    result
+}
+
+/// Asset URLs from the last successful `check_for_updates`. `download_update` only fetches these
+/// or URLs under the repo's release download path.
+static OFFERED_ASSETS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// SHA-256 (hex) per asset URL, from the release's `digest` field when GitHub provides it.
+static ASSET_DIGESTS: Mutex<BTreeMap<String, String>> = Mutex::new(BTreeMap::new());
+
+/// Installers written by `download_update`: canonical path → SHA-256 (hex) of the bytes written.
+/// `install_update` only launches files registered here whose contents still match.
+static DOWNLOADS: Mutex<BTreeMap<PathBuf, String>> = Mutex::new(BTreeMap::new());
+
+/// Held for the duration of a download, so only one runs at a time.
+static DOWNLOAD_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+const USER_AGENT: &str = "Desktop-Runtime-Update-Check";
+
+/// Returns true if `url` is an asset offered by the last update check, or a release download of `GITHUB_REPO`.
+#[must_use]
+pub(super) fn is_pinned_download_url(url: &str, offered: &[String]) -> bool {
+    if offered.iter().any(|o| o == url) {
+        return true;
+    }
+    let prefix = format!("https://github.com/{}/releases/download/", GITHUB_REPO);
+    // Reject traversal out of the release path (e.g. `.../download/../../other/repo`).
+    url.strip_prefix(&prefix).is_some_and(|rest| {
+        !rest.is_empty()
+            && !rest
+                .split(['/', '?', '#'])
+                .any(|seg| seg == ".." || seg == ".")
+    })
+}
+
+/// Rollout percentage declared in a release body by a `rollout: <n>%` line (the `%` is
+/// optional; an HTML comment keeps it out of the rendered notes). `None` means a full rollout.
+#[must_use]
+pub(super) fn rollout_percent(body: &str) -> Option<u8> {
+    body.lines().find_map(|line| {
+        let line = line
+            .trim()
+            .trim_start_matches("<!--")
+            .trim_end_matches("-->")
+            .trim();
+        let (key, value) = line.split_once(':')?;
+        if !key.trim().eq_ignore_ascii_case("rollout") {
+            return None;
+        }
+        let pct: u8 = value.trim().trim_end_matches('%').trim().parse().ok()?;
+        Some(pct.min(100))
+    })
+}
+
+/// Bucket 0–99 for `install_id` and `version`. Stable for a release, so raising its percentage
+/// only adds machines; independent between releases, so the same machines are not always first.
+#[must_use]
+pub(super) fn rollout_bucket(install_id: &str, version: &str) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", install_id, version).as_bytes());
+    let mut head = [0u8; 8];
+    head.copy_from_slice(&digest[..8]);
+    u8::try_from(u64::from_be_bytes(head) % 100).unwrap_or(0)
+}
+
+/// Random per-install id, created on first use. Kept outside `config.json` so `DeleteConfig`
+/// does not move the machine to another bucket.
+fn install_id() -> String {
+    let path = crate::paths::user_data_dir().join(INSTALL_ID_FILE);
+    if let Ok(id) = fs::read_to_string(&path)
+        && !id.trim().is_empty()
+    {
+        return id.trim().to_string();
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Err(e) = fs::write(&path, &id) {
+        log::warn!("Could not write {}: {}", path.display(), e);
+    }
+    id
+}
+
+/// Whether this machine is inside the release's staged rollout.
+fn in_rollout(percent: Option<u8>, version: &str) -> bool {
+    let Some(pct) = percent.filter(|&p| p < 100) else {
+        return true;
+    };
+    crate::storage::get_value(FORCE_ROLLOUT_KEY).and_then(|v| v.as_bool()) == Some(true)
+        || rollout_bucket(&install_id(), version) < pct
+}
+
 fn pick_asset_url(assets: &serde_json::Value) -> Option<String> {
     let arr = assets.as_array()?;
     for ext in ASSET_EXTENSIONS {
@@ -39,89 +191,759 @@ fn pick_asset_url(assets: &serde_json::Value) -> Option<String> {
         .map(String::from)
 }
 
-/// Fetches latest release info from GitHub and returns a JSON-serializable value.
-pub(super) fn check_for_updates() -> Result<serde_json::Value, String> {
-    let current = env!("CARGO_PKG_VERSION");
-    let api_url = format!("https://api.github.com/repos/{}/releases/latest", GITHUB_REPO);
+/// Newest entry of a `/releases` listing (newest first) that is published and, unless
+/// `allow_prerelease`, not a prerelease.
+#[must_use]
+pub(super) fn select_release(
+    releases: &serde_json::Value,
+    allow_prerelease: bool,
+) -> Option<&serde_json::Value> {
+    releases.as_array()?.iter().find(|r| {
+        r["draft"].as_bool() != Some(true)
+            && (allow_prerelease || r["prerelease"].as_bool() != Some(true))
+    })
+}
 
-    let resp = ureq::get(&api_url)
-        .set("Accept", "application/vnd.github.v3+json")
-        .set("User-Agent", "Desktop-Runtime-Update-Check")
-        .call()
-        .map_err(|e| e.to_string())?;
+async fn github_api(path: &str) -> Result<serde_json::Value, String> {
+    let api_url = format!("https://api.github.com/repos/{}/{}", GITHUB_REPO, path);
+    crate::net::client()?
+        .get(&api_url)
+        .header("Accept", "application/vnd.github.v3+json")
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())
+}
 
-    let body: serde_json::Value = resp.into_json().map_err(|e| e.to_string())?;
-    let tag_name = body["tag_name"].as_str().ok_or("No tag_name in response")?;
-    let latest = tag_name.trim_start_matches('v');
-    let html_url = body["html_url"]
-        .as_str()
-        .ok_or("No html_url in response")?
-        .to_string();
-    let asset_url = body.get("assets").and_then(pick_asset_url);
+/// The release to offer. Stable installs use `/releases/latest` (which never returns
+/// prereleases) and fall back to the listing when it fails, e.g. while every release is
+/// still a prerelease; prerelease-enabled installs always use the listing.
+async fn fetch_release(allow_prerelease: bool) -> Result<serde_json::Value, String> {
+    if !allow_prerelease {
+        match github_api("releases/latest").await {
+            Ok(release) => return Ok(release),
+            Err(e) => log::debug!("releases/latest failed ({}); listing releases", e),
+        }
+    }
+    let releases = github_api("releases?per_page=30").await?;
+    select_release(&releases, allow_prerelease)
+        .cloned()
+        .ok_or_else(|| "No published release".to_string())
+}
 
-    let is_newer = semver_compare(latest, current) > 0;
+/// File under the user data dir holding the last successful update check (`CachedCheck`). Not a
+/// config key: the offered assets and digests it restores decide what `DownloadUpdate` fetches
+/// and `InstallUpdate` runs, so the UI must not be able to write it.
+const CHECK_CACHE_FILE: &str = "update-check.json";
 
-    Ok(serde_json::json!({
-        "current": current,
-        "latest": latest,
-        "url": html_url,
-        "assetUrl": asset_url,
-        "isNewer": is_newer
-    }))
+/// Outcome of a release lookup, kept in `CHECK_CACHE_FILE` so `CheckForUpdates` can answer
+/// offline and without hitting GitHub on every call.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct CachedCheck {
+    /// Unix time in milliseconds.
+    pub checked_at: u64,
+    /// Whether prereleases were considered; a cache from the other mode is ignored.
+    pub allow_prerelease: bool,
+    pub latest: String,
+    pub url: String,
+    pub asset_url: Option<String>,
+    pub prerelease: bool,
+    pub rollout_percent: Option<u8>,
+    pub offered: Vec<String>,
+    pub digests: BTreeMap<String, String>,
+}
+
+impl CachedCheck {
+    /// Makes this release's assets downloadable by `download_update`.
+    fn apply(&self) {
+        *OFFERED_ASSETS.lock().unwrap_or_else(|e| e.into_inner()) = self.offered.clone();
+        *ASSET_DIGESTS.lock().unwrap_or_else(|e| e.into_inner()) = self.digests.clone();
+    }
+
+    /// `CheckForUpdates` response. `isNewer` is recomputed, so a cache from before the app
+    /// updated does not offer the installed version again.
+    fn info(&self, cached: bool, stale: bool) -> serde_json::Value {
+        let current = env!("CARGO_PKG_VERSION");
+        let is_newer = semver_compare(&self.latest, current) > 0
+            && in_rollout(self.rollout_percent, &self.latest);
+        serde_json::json!({
+            "current": current,
+            "latest": self.latest,
+            "url": self.url,
+            "assetUrl": self.asset_url,
+            "isNewer": is_newer,
+            "prerelease": self.prerelease,
+            "rolloutPercent": self.rollout_percent,
+            "checkedAt": self.checked_at,
+            "cached": cached,
+            "stale": stale
+        })
+    }
+
+    /// True while `now_ms` is within `UPDATE_CHECK_MIN_INTERVAL_SECS` of the check.
+    #[must_use]
+    pub fn is_fresh(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.checked_at) < UPDATE_CHECK_MIN_INTERVAL_SECS * 1000
+    }
+}
+
+/// The stored check, if any.
+pub(super) fn load_check_cache() -> Option<CachedCheck> {
+    let text = fs::read_to_string(crate::paths::user_data_dir().join(CHECK_CACHE_FILE)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Replaces the stored check (temp file + rename).
+fn store_check_cache(check: &CachedCheck) {
+    let path = crate::paths::user_data_dir().join(CHECK_CACHE_FILE);
+    let tmp = path.with_extension("json.tmp");
+    let result = serde_json::to_vec_pretty(check)
+        .map_err(std::io::Error::other)
+        .and_then(|json| fs::write(&tmp, json))
+        .and_then(|()| fs::rename(&tmp, &path));
+    if let Err(e) = result {
+        log::warn!("Could not write {}: {}", path.display(), e);
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// Checks GitHub for a newer release. Within `UPDATE_CHECK_MIN_INTERVAL_SECS` of the last
+/// successful check the cached result is returned (`cached: true`); when the request fails and
+/// a cached result exists, it is returned with `stale: true` and the `error`. Prereleases are
+/// considered when the `allowPrerelease` setting is on or the update channel is beta.
+pub(super) async fn check_for_updates() -> Result<serde_json::Value, String> {
+    set_state(UpdateState::Checking);
+    let allow_prerelease = crate::settings::Settings::load().allows_prerelease();
+    let cache = load_check_cache().filter(|c| c.allow_prerelease == allow_prerelease);
+    let info = match cache {
+        Some(cache) if cache.is_fresh(now_ms()) => {
+            cache.apply();
+            cache.info(true, false)
+        }
+        cache => match fetch_check(allow_prerelease).await {
+            Ok(check) => {
+                check.apply();
+                store_check_cache(&check);
+                check.info(false, false)
+            }
+            Err(e) => {
+                let Some(cache) = cache else {
+                    return track(Err(e));
+                };
+                log::warn!("Update check failed, using cached result: {}", e);
+                cache.apply();
+                let mut info = cache.info(true, true);
+                info["error"] = serde_json::json!(e);
+                info
+            }
+        },
+    };
+    set_state(match (info["isNewer"].as_bool(), info["latest"].as_str()) {
+        (Some(true), Some(version)) => UpdateState::Available {
+            version: version.to_string(),
+        },
+        _ => UpdateState::Idle,
+    });
+    Ok(info)
+}
+
+async fn fetch_check(allow_prerelease: bool) -> Result<CachedCheck, String> {
+    let body = fetch_release(allow_prerelease).await?;
+    let tag_name = body["tag_name"].as_str().ok_or("No tag_name in response")?;
+    let html_url = body["html_url"].as_str().ok_or("No html_url in response")?;
+    let assets = body["assets"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    Ok(CachedCheck {
+        checked_at: now_ms(),
+        allow_prerelease,
+        latest: tag_name.trim_start_matches('v').to_string(),
+        url: html_url.to_string(),
+        asset_url: body.get("assets").and_then(pick_asset_url),
+        prerelease: body["prerelease"].as_bool().unwrap_or(false),
+        rollout_percent: body["body"].as_str().and_then(rollout_percent),
+        offered: assets
+            .iter()
+            .filter_map(|a| a["browser_download_url"].as_str().map(String::from))
+            .collect(),
+        digests: assets
+            .iter()
+            .filter_map(|a| {
+                let url = a["browser_download_url"].as_str()?;
+                let hex = a["digest"].as_str()?.strip_prefix("sha256:")?;
+                Some((url.to_string(), hex.to_ascii_lowercase()))
+            })
+            .collect(),
+    })
 }
 
 /// Downloads an update from the given URL to a temp file. Returns the local path.
-pub(super) fn download_update(url: &str) -> Result<serde_json::Value, String> {
+/// The URL must be pinned (see `is_pinned_download_url`) so the UI cannot point the host at arbitrary binaries.
+/// One download runs at a time; later requests wait for it.
+pub(super) async fn download_update(url: &str) -> Result<serde_json::Value, String> {
+    let _one = DOWNLOAD_LOCK.lock().await;
+    let result = track(download(url).await)?;
+    if let Some(path) = result["path"].as_str() {
+        set_state(UpdateState::Ready {
+            path: path.to_string(),
+        });
+    }
+    Ok(result)
+}
+
+async fn download(url: &str) -> Result<serde_json::Value, String> {
     if !url.starts_with("https://") {
         return Err("Download URL must be https://".to_string());
     }
-    let resp = ureq::get(url)
-        .set("User-Agent", "Desktop-Runtime-Update-Check")
-        .call()
+    let pinned = {
+        let offered = OFFERED_ASSETS.lock().unwrap_or_else(|e| e.into_inner());
+        is_pinned_download_url(url, &offered)
+    };
+    if !pinned {
+        return Err("Download URL is not a known release asset".to_string());
+    }
+    let mut resp = crate::net::client()?
+        .get(url)
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
         .map_err(|e| e.to_string())?;
 
-    let mut reader = resp.into_reader();
+    let total = resp.content_length();
+    set_state(UpdateState::Downloading {
+        percent: total.map(|_| 0),
+    });
     let mut bytes = Vec::new();
-    std::io::Read::read_to_end(&mut reader, &mut bytes).map_err(|e| e.to_string())?;
+    while let Some(chunk) = resp.chunk().await.map_err(|e| e.to_string())? {
+        bytes.extend_from_slice(&chunk);
+        if let Some(total) = total.filter(|&t| t > 0) {
+            let pct = (bytes.len() as u64).saturating_mul(100) / total;
+            set_state(UpdateState::Downloading {
+                percent: Some(u8::try_from(pct.min(100)).unwrap_or(100)),
+            });
+        }
+    }
+
+    let sha256 = hex::encode(&Sha256::digest(&bytes));
+    let expected = ASSET_DIGESTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(url)
+        .cloned();
+    if let Some(expected) = expected
+        && expected != sha256
+    {
+        return Err("Downloaded file does not match the release digest".to_string());
+    }
 
     let ext = Path::new(url)
         .extension()
         .and_then(|e| e.to_str())
         .unwrap_or("bin");
-    let file_name = format!("desktop-runtime-update.{}", ext);
-    let temp_dir = std::env::temp_dir();
-    let dest = temp_dir.join(&file_name);
+    let dest = crate::paths::temp_file("update", ext).map_err(|e| e.to_string())?;
 
     let mut file = fs::File::create(&dest).map_err(|e| e.to_string())?;
     file.write_all(&bytes).map_err(|e| e.to_string())?;
+    let canonical = dest.canonicalize().map_err(|e| e.to_string())?;
+    DOWNLOADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(canonical, sha256.clone());
 
     Ok(serde_json::json!({
-        "path": dest.display().to_string()
+        "path": dest.display().to_string(),
+        "sha256": sha256
     }))
 }
 
-/// Launches the installer at the given path using the system default handler.
-pub(super) fn install_update(path: &str) -> Result<serde_json::Value, String> {
-    let p = Path::new(path);
-    if !p.exists() {
-        return Err("Installer file not found".to_string());
+/// Resolves `path` to a file registered by `download_update` whose SHA-256 is unchanged.
+fn verified_download(path: &str) -> Result<PathBuf, String> {
+    let canonical = Path::new(path)
+        .canonicalize()
+        .map_err(|_| "Installer file not found".to_string())?;
+    let expected = DOWNLOADS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&canonical)
+        .cloned()
+        .ok_or("Installer was not downloaded by DownloadUpdate")?;
+    let bytes = fs::read(&canonical).map_err(|e| e.to_string())?;
+    if hex::encode(&Sha256::digest(&bytes)) != expected {
+        return Err("Installer changed since download".to_string());
+    }
+    Ok(canonical)
+}
+
+/// Relaunches from `command` after exit and quits, when `relaunch` is set.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn finish_replace(
+    target: &Path,
+    command: &[std::ffi::OsString],
+    relaunch: bool,
+) -> Result<serde_json::Value, String> {
+    if relaunch {
+        crate::lifecycle::run_after_exit(command)?;
+        crate::lifecycle::quit_soon();
+    }
+    Ok(serde_json::json!({
+        "launched": true,
+        "installed": true,
+        "relaunching": relaunch,
+        "path": target.display().to_string()
+    }))
+}
+
+/// Replaces the running AppImage (`$APPIMAGE`) with the verified download, then relaunches it
+/// if asked. The new file is copied next to the old one first so the final rename is atomic;
+/// the running process keeps its mounted image.
+#[cfg(target_os = "linux")]
+fn replace_appimage(
+    download: &Path,
+    current: &Path,
+    relaunch: bool,
+) -> Result<serde_json::Value, String> {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = current.parent().ok_or("Invalid AppImage path")?;
+    let name = current
+        .file_name()
+        .ok_or("Invalid AppImage path")?
+        .to_string_lossy();
+    let staged = dir.join(format!(".{}.update-{}", name, std::process::id()));
+    let result = (|| {
+        fs::copy(download, &staged)?;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+        fs::File::open(&staged)?.sync_all()?;
+        fs::rename(&staged, current)
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&staged);
+        return Err(format!("Could not replace {}: {}", current.display(), e));
+    }
+    finish_replace(current, &[current.into()], relaunch)
+}
+
+/// macOS bundle updates (`.app.tar.gz`, `.dmg`): the new `.app` is staged next to the bundle
+/// being replaced, so the swap is two renames on one volume, then quarantine is cleared and the
+/// bundle can be reopened once this process has exited.
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::{Command, Stdio};
+
+    use crate::archive::{self, ArchiveFormat};
+    use crate::config::{BUNDLE_ID, PRODUCT_NAME};
+
+    fn run(program: &str, args: &[&OsStr]) -> Result<(), String> {
+        let out = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Could not run {}: {}", program, e))?;
+        if out.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} failed: {}",
+                program,
+                String::from_utf8_lossy(&out.stderr).trim()
+            ))
+        }
+    }
+
+    fn find_app(dir: &Path) -> Result<PathBuf, String> {
+        fs::read_dir(dir)
+            .map_err(|e| e.to_string())?
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| p.extension().is_some_and(|e| e == "app") && p.is_dir())
+            .ok_or_else(|| "Update contains no .app bundle".to_string())
+    }
+
+    fn bundle_id(app: &Path) -> Option<String> {
+        let plist = app.join("Contents/Info.plist");
+        let out = Command::new("/usr/libexec/PlistBuddy")
+            .args([
+                OsStr::new("-c"),
+                OsStr::new("Print :CFBundleIdentifier"),
+                plist.as_os_str(),
+            ])
+            .output()
+            .ok()?;
+        out.status
+            .success()
+            .then(|| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    }
+
+    /// The running bundle, or `/Applications/<product>.app` when not started from one.
+    fn target_bundle() -> PathBuf {
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| crate::paths::app_bundle_path(&exe))
+            .unwrap_or_else(|| PathBuf::from("/Applications").join(format!("{}.app", PRODUCT_NAME)))
+    }
+
+    /// Unpacks the `.app` from `download` into `staging`.
+    fn unpack(download: &Path, staging: &Path, dmg: bool) -> Result<PathBuf, String> {
+        if !dmg {
+            archive::extract(download, staging, Some(ArchiveFormat::TarGz))?;
+            return find_app(staging);
+        }
+        let mount = staging.join("mount");
+        fs::create_dir(&mount).map_err(|e| e.to_string())?;
+        let attach = [
+            OsStr::new("attach"),
+            OsStr::new("-nobrowse"),
+            OsStr::new("-readonly"),
+            OsStr::new("-noautoopen"),
+            OsStr::new("-mountpoint"),
+            mount.as_os_str(),
+            download.as_os_str(),
+        ];
+        run("hdiutil", &attach)?;
+        let copied = find_app(&mount).and_then(|app| {
+            let dest = staging.join(app.file_name().unwrap_or_default());
+            run("ditto", &[app.as_os_str(), dest.as_os_str()]).map(|()| dest)
+        });
+        let _ = run(
+            "hdiutil",
+            &[
+                OsStr::new("detach"),
+                OsStr::new("-force"),
+                mount.as_os_str(),
+            ],
+        );
+        copied
+    }
+
+    /// Moves `new` into place at `target`, keeping the old bundle until the move succeeded.
+    fn swap(new: &Path, target: &Path) -> Result<(), String> {
+        let old = target.with_extension(format!("app.old-{}", std::process::id()));
+        let had_old = target.exists();
+        if had_old {
+            fs::rename(target, &old)
+                .map_err(|e| format!("Could not move {} aside: {}", target.display(), e))?;
+        }
+        if let Err(e) = fs::rename(new, target) {
+            if had_old {
+                let _ = fs::rename(&old, target);
+            }
+            return Err(format!("Could not install {}: {}", target.display(), e));
+        }
+        if had_old && let Err(e) = fs::remove_dir_all(&old) {
+            log::warn!("Could not remove old bundle {}: {}", old.display(), e);
+        }
+        Ok(())
+    }
+
+    /// Installs a verified `.app.tar.gz` or `.dmg` download over the app bundle and, if asked,
+    /// relaunches it.
+    pub fn install_bundle(
+        download: &Path,
+        dmg: bool,
+        relaunch: bool,
+    ) -> Result<serde_json::Value, String> {
+        let target = target_bundle();
+        let parent = target.parent().ok_or("Invalid bundle path")?;
+        let staging = parent.join(format!(".{}.update-{}", PRODUCT_NAME, std::process::id()));
+        let _ = fs::remove_dir_all(&staging);
+        fs::create_dir_all(&staging)
+            .map_err(|e| format!("Could not write to {}: {}", parent.display(), e))?;
+        let result = unpack(download, &staging, dmg).and_then(|app| {
+            if bundle_id(&app).as_deref() != Some(BUNDLE_ID) {
+                return Err(format!("Update bundle is not {}", BUNDLE_ID));
+            }
+            let _ = run(
+                "xattr",
+                &[
+                    OsStr::new("-dr"),
+                    OsStr::new("com.apple.quarantine"),
+                    app.as_os_str(),
+                ],
+            );
+            swap(&app, &target)
+        });
+        let _ = fs::remove_dir_all(&staging);
+        result?;
+        super::finish_replace(
+            &target,
+            &["/usr/bin/open".into(), target.clone().into()],
+            relaunch,
+        )
+    }
+}
+
+/// How the app is installed on Windows: decides the MSI properties and whether the installer
+/// must run elevated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(super) enum InstallScope {
+    PerUser,
+    PerMachine,
+}
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+impl InstallScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::PerUser => "perUser",
+            Self::PerMachine => "perMachine",
+        }
+    }
+
+    /// Scope implied by where `exe` lives: under one of `machine_roots` (Program Files) it is
+    /// per-machine, under `user_root` (`%LOCALAPPDATA%`) per-user, otherwise unknown.
+    pub(super) fn from_location(
+        exe: &Path,
+        machine_roots: &[PathBuf],
+        user_root: Option<&Path>,
+    ) -> Option<Self> {
+        // Windows paths compare case-insensitively.
+        let exe = exe.to_string_lossy().to_lowercase();
+        let under = |root: &Path| {
+            let root = root.to_string_lossy().to_lowercase();
+            exe.strip_prefix(root.trim_end_matches(['\\', '/']))
+                .is_some_and(|rest| rest.starts_with(['\\', '/']))
+        };
+        if machine_roots.iter().any(|root| under(root)) {
+            Some(Self::PerMachine)
+        } else if user_root.is_some_and(under) {
+            Some(Self::PerUser)
+        } else {
+            None
+        }
+    }
+}
+
+/// `msiexec` arguments that install `msi` into `scope` with a progress-only UI. The path is
+/// quoted for a Windows command line.
+#[must_use]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+pub(super) fn msi_arguments(msi: &str, scope: InstallScope) -> String {
+    let properties = match scope {
+        InstallScope::PerUser => "ALLUSERS=2 MSIINSTALLPERUSER=1",
+        InstallScope::PerMachine => "ALLUSERS=1",
+    };
+    format!("/i \"{}\" /passive /norestart {}", msi, properties)
+}
+
+/// Windows installers run through `Start-Process` so per-machine installs can ask for UAC
+/// elevation; a declined prompt is reported instead of failing silently.
+#[cfg(target_os = "windows")]
+mod windows {
+    use std::ffi::OsStr;
+    use std::os::windows::process::CommandExt;
+    use std::path::{Path, PathBuf};
+
+    use super::{InstallScope, msi_arguments};
+    use crate::config::PRODUCT_NAME;
+    use crate::lifecycle::{self, ps_quote};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    fn powershell(script: &str) -> Result<String, String> {
+        let out = std::process::Command::new("powershell")
+            .args(["-NoProfile", "-NonInteractive", "-Command", script])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .map_err(|e| format!("Could not run powershell: {}", e))?;
+        if out.status.success() {
+            Ok(String::from_utf8_lossy(&out.stdout).trim().to_string())
+        } else {
+            Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+        }
+    }
+
+    /// Install location first; otherwise an uninstall entry for the product under HKLM means a
+    /// per-machine install.
+    fn detect_scope() -> InstallScope {
+        let machine_roots: Vec<PathBuf> = ["ProgramFiles", "ProgramFiles(x86)", "ProgramW6432"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .map(PathBuf::from)
+            .collect();
+        let user_root = std::env::var_os("LOCALAPPDATA").map(PathBuf::from);
+        if let Ok(exe) = std::env::current_exe()
+            && let Some(scope) =
+                InstallScope::from_location(&exe, &machine_roots, user_root.as_deref())
+        {
+            return scope;
+        }
+        let script = format!(
+            "@(Get-ItemProperty 'HKLM:\\Software\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\*', \
+             'HKLM:\\Software\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\*' \
+             -ErrorAction SilentlyContinue | Where-Object {{ $_.DisplayName -eq {} }}).Count",
+            ps_quote(OsStr::new(PRODUCT_NAME))
+        );
+        match powershell(&script).ok().and_then(|n| n.parse::<u32>().ok()) {
+            Some(n) if n > 0 => InstallScope::PerMachine,
+            _ => InstallScope::PerUser,
+        }
+    }
+
+    /// Starts the `.msi` or `.exe` installer at `path` for the detected scope. With `relaunch`,
+    /// a detached PowerShell runs the installer to completion and then starts the app again
+    /// while this process quits; a declined UAC prompt then only skips the install.
+    pub fn install(path: &Path, relaunch: bool) -> Result<serde_json::Value, String> {
+        let scope = detect_scope();
+        let elevated = scope == InstallScope::PerMachine;
+        let is_msi = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("msi"));
+        let path = path.display().to_string();
+        let mut script = if is_msi {
+            format!(
+                "Start-Process -FilePath msiexec -ArgumentList {}",
+                ps_quote(OsStr::new(&msi_arguments(&path, scope)))
+            )
+        } else {
+            format!("Start-Process -FilePath {}", ps_quote(OsStr::new(&path)))
+        };
+        if elevated {
+            script.push_str(" -Verb RunAs");
+        }
+        if relaunch {
+            let after = lifecycle::after_exit_script(&lifecycle::relaunch_command(None)?)?;
+            lifecycle::run_powershell_detached(&format!(
+                "try {{ {} -Wait }} catch {{}}; {}",
+                script, after
+            ))?;
+            lifecycle::quit_soon();
+            return Ok(serde_json::json!({
+                "launched": true,
+                "scope": scope.as_str(),
+                "elevated": elevated,
+                "relaunching": true
+            }));
+        }
+        powershell(&script).map_err(|e| {
+            if elevated {
+                format!(
+                    "Installer needs administrator rights and could not be started: {}",
+                    e
+                )
+            } else {
+                format!("Could not start installer: {}", e)
+            }
+        })?;
+        Ok(serde_json::json!({
+            "launched": true,
+            "scope": scope.as_str(),
+            "elevated": elevated,
+            "relaunching": false
+        }))
+    }
+}
+
+/// Installs the update at the given path. Only files produced by `download_update` (and
+/// unchanged since) are accepted. macOS `.app.tar.gz` and `.dmg` downloads replace the app
+/// bundle, as does an AppImage download when running from an AppImage (`$APPIMAGE`); Windows
+/// installers run for the app's install scope (elevated for per-machine installs); everything
+/// else is launched with the system default handler.
+///
+/// `relaunch` (default: on for in-place replacement, off for installers) restarts the app
+/// after a replacement, runs a Windows installer to completion and then restarts the app, or,
+/// for other installers, quits so the installer can replace the app's files.
+pub(super) fn install_update(
+    path: &str,
+    relaunch: Option<bool>,
+) -> Result<serde_json::Value, String> {
+    set_state(UpdateState::Installing);
+    track(install(path, relaunch))
+}
+
+fn install(path: &str, relaunch: Option<bool>) -> Result<serde_json::Value, String> {
+    let path = verified_download(path)?;
+    #[cfg(target_os = "macos")]
+    {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if ext == "gz" || ext == "dmg" {
+            return macos::install_bundle(&path, ext == "dmg", relaunch.unwrap_or(true));
+        }
+    }
+    #[cfg(target_os = "windows")]
+    {
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("msi") || e.eq_ignore_ascii_case("exe"))
+        {
+            return windows::install(&path, relaunch.unwrap_or(false));
+        }
     }
     #[cfg(target_os = "linux")]
     {
-        if path.ends_with(".AppImage") || path.ends_with(".appimage") {
+        let is_appimage = path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("appimage"));
+        if is_appimage && let Some(current) = std::env::var_os("APPIMAGE").map(PathBuf::from) {
+            return replace_appimage(&path, &current, relaunch.unwrap_or(true));
+        }
+        if is_appimage {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(path).map_err(|e| e.to_string())?.permissions();
+            let mut perms = fs::metadata(&path)
+                .map_err(|e| e.to_string())?
+                .permissions();
             perms.set_mode(0o755);
-            fs::set_permissions(path, perms).map_err(|e| e.to_string())?;
+            fs::set_permissions(&path, perms).map_err(|e| e.to_string())?;
         }
     }
-    opener::open(path).map_err(|e| e.to_string())?;
-    Ok(serde_json::json!({ "launched": true }))
+    opener::open(&path).map_err(|e| e.to_string())?;
+    let quitting = relaunch.unwrap_or(false);
+    if quitting {
+        crate::lifecycle::quit_soon();
+    }
+    Ok(serde_json::json!({ "launched": true, "relaunching": false, "quitting": quitting }))
+}
+
+/// Parses a release version leniently: a leading `v` is dropped and a missing minor or patch
+/// number counts as 0 (`v1.2-beta.1` → `1.2.0-beta.1`).
+#[must_use]
+pub fn parse_version(text: &str) -> Option<semver::Version> {
+    let text = text.trim().trim_start_matches(['v', 'V']);
+    if let Ok(v) = semver::Version::parse(text) {
+        return Some(v);
+    }
+    let split = text.find(['-', '+']).unwrap_or(text.len());
+    let (core, suffix) = text.split_at(split);
+    let mut parts: Vec<&str> = core.split('.').collect();
+    if parts.is_empty() || parts.len() > 3 {
+        return None;
+    }
+    parts.resize(3, "0");
+    semver::Version::parse(&format!("{}{}", parts.join("."), suffix)).ok()
 }
 
-/// Compares two semver-like strings. Returns 1 if a > b, -1 if a < b, 0 if equal. Non-numeric segments treated as 0.
+/// Compares two versions by semver precedence: 1 if a > b, -1 if a < b, 0 if equal.
+/// Prereleases sort before their release (`1.2.0-beta.1 < 1.2.0-rc.1 < 1.2.0`) and build
+/// metadata is ignored. Unparseable versions compare numerically segment by segment, with
+/// non-numeric segments as 0.
 #[must_use]
 pub fn semver_compare(a: &str, b: &str) -> i32 {
+    if let (Some(va), Some(vb)) = (parse_version(a), parse_version(b)) {
+        return match va.cmp_precedence(&vb) {
+            std::cmp::Ordering::Greater => 1,
+            std::cmp::Ordering::Less => -1,
+            std::cmp::Ordering::Equal => 0,
+        };
+    }
     let mut ai = a.split('.').map(|p| p.parse::<u64>().unwrap_or(0));
     let mut bi = b.split('.').map(|p| p.parse::<u64>().unwrap_or(0));
     loop {
@@ -0,0 +1,482 @@
+//! Window commands: run on the event loop thread, which owns the `tao` window and the WebView.
+//!
+//! `main` routes commands for which `is_window_command` is true to the event loop as
+//! `UserEvent::WindowCommand`; the loop calls `handle_window_command` and queues the response.
+//! Panel and window-targeting commands also get the loop's child windows (`Children`: panels and
+//! the modal). `OpenModal` needs the loop's window target and `ResolveModal` the sending window,
+//! so the loop runs those itself (see `crate::modal`), as it does `UiReady`, and `ShowWindow` /
+//! `HideWindow`, which go through the same path as the tray (splash, popover placement).
+
+use serde::{Deserialize, Serialize};
+use tao::dpi::{PhysicalPosition, PhysicalSize};
+use tao::monitor::MonitorHandle;
+use tao::window::{CursorIcon, UserAttentionType, Window};
+use wry::WebView;
+
+use super::Command;
+use crate::panel::PanelBounds;
+use crate::settings;
+use crate::window::{Children, MAIN_WINDOW, PixelRect, ViewContext};
+
+/// Config key: keep the window above others (persisted by `SetAlwaysOnTop`).
+pub const ALWAYS_ON_TOP_KEY: &str = "alwaysOnTop";
+
+/// Config key: show the window on every workspace (persisted by `SetVisibleOnAllWorkspaces`).
+pub const ALL_WORKSPACES_KEY: &str = "visibleOnAllWorkspaces";
+
+/// Shows `count` on the dock (macOS), taskbar overlay (Windows), or launcher (Linux); 0 clears it.
+fn set_badge(window: &Window, count: u32) {
+    #[cfg(target_os = "macos")]
+    {
+        use tao::platform::macos::WindowExtMacOS;
+        window.set_badge_label((count > 0).then(|| count.to_string()));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use tao::platform::windows::WindowExtWindows;
+        let icon = if count > 0 {
+            crate::window::overlay_icon()
+        } else {
+            None
+        };
+        window.set_overlay_icon(icon.as_ref());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use tao::platform::unix::WindowExtUnix;
+        window.set_badge_count(
+            (count > 0).then_some(i64::from(count)),
+            Some(format!("{}.desktop", crate::paths::app_slug())),
+        );
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    let _ = (window, count);
+}
+
+/// Cursor shape for `SetCursor`, named like the CSS `cursor` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CursorKind {
+    Default,
+    Pointer,
+    Crosshair,
+    Text,
+    Move,
+    Wait,
+    Progress,
+    Help,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+}
+
+impl From<CursorKind> for CursorIcon {
+    fn from(kind: CursorKind) -> Self {
+        match kind {
+            CursorKind::Default => Self::Default,
+            CursorKind::Pointer => Self::Hand,
+            CursorKind::Crosshair => Self::Crosshair,
+            CursorKind::Text => Self::Text,
+            CursorKind::Move => Self::Move,
+            CursorKind::Wait => Self::Wait,
+            CursorKind::Progress => Self::Progress,
+            CursorKind::Help => Self::Help,
+            CursorKind::NotAllowed => Self::NotAllowed,
+            CursorKind::Grab => Self::Grab,
+            CursorKind::Grabbing => Self::Grabbing,
+            CursorKind::AllScroll => Self::AllScroll,
+            CursorKind::ZoomIn => Self::ZoomIn,
+            CursorKind::ZoomOut => Self::ZoomOut,
+            CursorKind::EwResize => Self::EwResize,
+            CursorKind::NsResize => Self::NsResize,
+            CursorKind::NeswResize => Self::NeswResize,
+            CursorKind::NwseResize => Self::NwseResize,
+            CursorKind::ColResize => Self::ColResize,
+            CursorKind::RowResize => Self::RowResize,
+        }
+    }
+}
+
+/// Preset for `SetWindowPlacement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PlacementPreset {
+    LeftHalf,
+    RightHalf,
+    /// Current size (shrunk to fit), centered.
+    Center,
+    Maximize,
+}
+
+/// Outer window bounds for `preset` on a monitor with bounds `monitor`, given the current outer
+/// size. `Maximize` covers the whole monitor (the window is then maximized there).
+pub(super) fn placement_rect(
+    preset: PlacementPreset,
+    monitor: PixelRect,
+    outer: (u32, u32),
+) -> PixelRect {
+    let half = monitor.width / 2;
+    match preset {
+        PlacementPreset::LeftHalf => PixelRect {
+            width: half,
+            ..monitor
+        },
+        PlacementPreset::RightHalf => PixelRect {
+            x: monitor.x + half as i32,
+            width: monitor.width - half,
+            ..monitor
+        },
+        PlacementPreset::Center => {
+            let width = outer.0.min(monitor.width);
+            let height = outer.1.min(monitor.height);
+            PixelRect {
+                x: monitor.x + ((monitor.width - width) / 2) as i32,
+                y: monitor.y + ((monitor.height - height) / 2) as i32,
+                width,
+                height,
+            }
+        }
+        PlacementPreset::Maximize => monitor,
+    }
+}
+
+/// Applies `preset` on monitor `index` (as listed by `GetDisplays`), or the window's current
+/// monitor. Returns the resulting outer bounds.
+fn set_placement(
+    window: &Window,
+    preset: PlacementPreset,
+    index: Option<usize>,
+) -> Result<PixelRect, String> {
+    let monitor = match index {
+        Some(i) => window
+            .available_monitors()
+            .nth(i)
+            .ok_or_else(|| format!("No display {}", i))?,
+        None => window
+            .current_monitor()
+            .or_else(|| window.primary_monitor())
+            .ok_or("No display found")?,
+    };
+    let bounds = PixelRect::from(&monitor);
+    let outer = window.outer_size();
+    let rect = placement_rect(preset, bounds, (outer.width, outer.height));
+    window.set_maximized(false);
+    window.set_outer_position(PhysicalPosition::new(rect.x, rect.y));
+    if preset == PlacementPreset::Maximize {
+        window.set_maximized(true);
+        return Ok(rect);
+    }
+    // `set_inner_size` excludes decorations; take them off the target outer size.
+    let inner = window.inner_size();
+    let frame = (
+        outer.width.saturating_sub(inner.width),
+        outer.height.saturating_sub(inner.height),
+    );
+    window.set_inner_size(PhysicalSize::new(
+        rect.width.saturating_sub(frame.0).max(1),
+        rect.height.saturating_sub(frame.1).max(1),
+    ));
+    Ok(rect)
+}
+
+/// Serializes one monitor (physical bounds, scale factor, primary flag).
+fn monitor_json(monitor: &MonitorHandle, primary: Option<&MonitorHandle>) -> serde_json::Value {
+    let pos = monitor.position();
+    let size = monitor.size();
+    serde_json::json!({
+        "name": monitor.name(),
+        "x": pos.x,
+        "y": pos.y,
+        "width": size.width,
+        "height": size.height,
+        "scaleFactor": monitor.scale_factor(),
+        "primary": primary == Some(monitor),
+    })
+}
+
+/// Handles one window command against `window`, `webview`, and the child windows (built from
+/// `context`). Non-window commands return an error.
+pub fn handle_window_command(
+    window: &Window,
+    webview: &WebView,
+    context: &mut ViewContext,
+    children: &mut Children,
+    command: &Command,
+) -> Result<serde_json::Value, String> {
+    let started = std::time::Instant::now();
+    let result = super::check_enabled(command)
+        .and_then(|()| run(window, webview, context, children, command));
+    if super::is_audited(command) {
+        crate::audit::record(command, &result);
+    }
+    super::trace::record(command, &result, started.elapsed());
+    result
+}
+
+/// True for window commands that finish in a later callback (`CaptureWindow`). The event loop
+/// runs these with `start_deferred_window_command` instead of `handle_window_command`.
+#[must_use]
+pub fn is_deferred_window_command(command: &Command) -> bool {
+    matches!(command, Command::CaptureWindow { .. })
+}
+
+/// Starts a deferred window command; `done` receives the result on the event loop thread.
+pub fn start_deferred_window_command(
+    window: &Window,
+    webview: &WebView,
+    command: Command,
+    done: impl FnOnce(Result<serde_json::Value, String>) + 'static,
+) {
+    let started = std::time::Instant::now();
+    let finish = move |command: Command, result: Result<serde_json::Value, String>| {
+        if super::is_audited(&command) {
+            crate::audit::record(&command, &result);
+        }
+        super::trace::record(&command, &result, started.elapsed());
+        done(result);
+    };
+    if let Err(e) = super::check_enabled(&command) {
+        return finish(command, Err(e));
+    }
+    match command {
+        Command::CaptureWindow { ref path, format } => {
+            let path = path.clone();
+            crate::capture::capture(window, webview, path.as_deref(), format, move |result| {
+                finish(command, result)
+            });
+        }
+        _ => finish(command, Err("Not a deferred window command".to_string())),
+    }
+}
+
+fn run(
+    window: &Window,
+    webview: &WebView,
+    context: &mut ViewContext,
+    children: &mut Children,
+    command: &Command,
+) -> Result<serde_json::Value, String> {
+    match command {
+        Command::GetDisplays => {
+            let primary = window.primary_monitor();
+            let displays: Vec<serde_json::Value> = window
+                .available_monitors()
+                .map(|m| monitor_json(&m, primary.as_ref()))
+                .collect();
+            Ok(serde_json::json!({ "displays": displays }))
+        }
+        Command::SetWindowPlacement { preset, monitor } => {
+            let rect = set_placement(window, *preset, *monitor)?;
+            Ok(serde_json::json!({
+                "preset": preset,
+                "x": rect.x,
+                "y": rect.y,
+                "width": rect.width,
+                "height": rect.height,
+            }))
+        }
+        Command::SetAlwaysOnTop { on } => {
+            window.set_always_on_top(*on);
+            settings::set_always_on_top(*on);
+            Ok(serde_json::json!({ "alwaysOnTop": window.is_always_on_top() }))
+        }
+        Command::SetVisibleOnAllWorkspaces { on } => {
+            // No-op on Windows; supported on macOS and Linux (X11).
+            window.set_visible_on_all_workspaces(*on);
+            settings::set_visible_on_all_workspaces(*on);
+            Ok(serde_json::json!({ "visibleOnAllWorkspaces": on }))
+        }
+        Command::SetWindowOpacity { value } => {
+            crate::window::set_opacity(window, *value)?;
+            Ok(serde_json::json!({ "opacity": value }))
+        }
+        Command::SetWindowTitle { title } => {
+            let title = crate::window::validate_title(title)?;
+            window.set_title(title);
+            Ok(serde_json::json!({ "title": title }))
+        }
+        Command::SetWindowIcon { app_asset_path } => {
+            // No path restores the app icon. macOS windows have no icon of their own (the Dock
+            // shows the bundle's), so this is a no-op there.
+            let icon = match app_asset_path {
+                Some(path) => Some(crate::window::icon_from_bytes(&context.asset(path)?)?),
+                None => crate::window::window_icon(),
+            };
+            window.set_window_icon(icon);
+            Ok(serde_json::json!({ "appAssetPath": app_asset_path }))
+        }
+        Command::SetIgnoreCursorEvents { on } => {
+            // Clicks go to whatever is below the window until turned off again (from a shortcut,
+            // the tray, or another window); the window itself can no longer be clicked.
+            window
+                .set_ignore_cursor_events(*on)
+                .map_err(|e| format!("Could not change click-through: {}", e))?;
+            Ok(serde_json::json!({ "ignoreCursorEvents": on }))
+        }
+        Command::SetCursor { kind } => {
+            // The WebView sets its own cursor from CSS as the pointer moves; pages using this
+            // leave `cursor` unset (`auto`) over the area where the native cursor should show.
+            window.set_cursor_icon((*kind).into());
+            Ok(serde_json::json!({ "kind": kind }))
+        }
+        Command::SetCursorVisible { visible } => {
+            window.set_cursor_visible(*visible);
+            Ok(serde_json::json!({ "visible": visible }))
+        }
+        Command::SetCursorGrab { on } => {
+            // Confines the cursor to the window (locks it in place on macOS) until released or
+            // the window loses focus on some platforms.
+            window
+                .set_cursor_grab(*on)
+                .map_err(|e| format!("Could not change cursor grab: {}", e))?;
+            Ok(serde_json::json!({ "grab": on }))
+        }
+        Command::GetCursorPosition => crate::cursor::position(window),
+        Command::WatchCursorLeave { on } => {
+            crate::cursor::set_watching(*on);
+            Ok(serde_json::json!({ "watching": on }))
+        }
+        Command::SetBadgeCount { count } => {
+            set_badge(window, *count);
+            Ok(serde_json::json!({ "count": count }))
+        }
+        Command::SetMediaMetadata {
+            title,
+            artist,
+            album,
+            artwork_url,
+            duration_ms,
+            position_ms,
+            playback,
+        } => {
+            let integrated = crate::media::set_now_playing(
+                window,
+                &crate::media::NowPlaying {
+                    title: title.clone(),
+                    artist: artist.clone(),
+                    album: album.clone(),
+                    artwork_url: artwork_url.clone(),
+                    duration_ms: *duration_ms,
+                    position_ms: *position_ms,
+                    playback: *playback,
+                },
+            )?;
+            Ok(serde_json::json!({ "integrated": integrated }))
+        }
+        Command::RequestUserAttention { critical } => {
+            // Flashes the taskbar button / bounces the dock icon until the window is focused.
+            let kind = if *critical {
+                UserAttentionType::Critical
+            } else {
+                UserAttentionType::Informational
+            };
+            window.request_user_attention(Some(kind));
+            Ok(serde_json::json!({ "requested": true }))
+        }
+        Command::ClearWebData {
+            cookies,
+            cache,
+            local_storage,
+        } => {
+            let deleted = if *cookies {
+                crate::webdata::clear_cookies(webview)?
+            } else {
+                0
+            };
+            if *local_storage {
+                webview
+                    .evaluate_script(crate::webdata::STORAGE_CLEAR_SCRIPT)
+                    .map_err(|e| format!("Could not clear storage: {}", e))?;
+            }
+            if *cache {
+                crate::webdata::clear_cache()?;
+            }
+            Ok(serde_json::json!({
+                "cookiesDeleted": deleted,
+                "cache": cache,
+                "localStorage": local_storage,
+            }))
+        }
+        Command::SetSpellcheck { enabled, languages } => {
+            settings::set_spellcheck(*enabled, languages.as_deref())?;
+            let languages = languages
+                .clone()
+                .unwrap_or_else(|| settings::Settings::load().spellcheck_languages);
+            crate::window::apply_spellcheck(webview, *enabled, &languages)?;
+            Ok(serde_json::json!({ "enabled": enabled, "languages": languages }))
+        }
+        Command::OpenPanel {
+            panel,
+            path,
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let bounds = PanelBounds {
+                x: *x,
+                y: *y,
+                width: *width,
+                height: *height,
+            };
+            children.panels.open(window, context, panel, path, bounds)?;
+            Ok(serde_json::json!({ "panel": panel, "path": path }))
+        }
+        Command::SetPanelBounds {
+            panel,
+            x,
+            y,
+            width,
+            height,
+        } => {
+            children.panels.set_bounds(
+                panel,
+                PanelBounds {
+                    x: *x,
+                    y: *y,
+                    width: *width,
+                    height: *height,
+                },
+            )?;
+            Ok(serde_json::json!({ "panel": panel }))
+        }
+        Command::ClosePanel { panel } => {
+            Ok(serde_json::json!({ "closed": children.panels.close(panel) }))
+        }
+        Command::ListPanels => Ok(serde_json::json!({ "panels": children.panels.names() })),
+        Command::ListWindows => Ok(serde_json::json!({ "windows": children.window_ids() })),
+        Command::EmitToWindow {
+            window_id,
+            event,
+            payload,
+        } => {
+            crate::events::validate_name(event)?;
+            let target = if window_id == MAIN_WINDOW {
+                webview
+            } else {
+                children
+                    .webview(window_id)
+                    .ok_or_else(|| format!("No window {}", window_id))?
+            };
+            crate::event_loop::deliver_host_event(target, event, payload);
+            Ok(serde_json::json!({ "windowId": window_id, "event": event }))
+        }
+        Command::ResolveModal { .. } => {
+            Err("ResolveModal must be sent from the modal window".to_string())
+        }
+        Command::UiReady => Err("UiReady must be sent from the main window's page".to_string()),
+        Command::ShowWindow | Command::HideWindow => {
+            Err("ShowWindow and HideWindow must be sent from a window".to_string())
+        }
+        _ => Err("Not a window command".to_string()),
+    }
+}
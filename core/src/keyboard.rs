@@ -0,0 +1,165 @@
+//! Active keyboard layout and `keyboard-layout-changed` host events.
+//!
+//! Linux reads GNOME's `org.gnome.desktop.input-sources` (`mru-sources`, most recent first) and
+//! falls back to `setxkbmap -query`; macOS reads `AppleCurrentKeyboardLayoutInputSourceID` from
+//! HIToolbox preferences; Windows reports the default input language (layouts switched per
+//! window are not visible to another process). The watcher starts on the first query so apps that
+//! never ask do not pay for polling.
+
+use std::process::Command;
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::events;
+
+/// Poll interval for layout change events.
+const POLL: Duration = Duration::from_secs(2);
+
+static WATCH: Once = Once::new();
+
+/// Active layout: platform identifier (e.g. `"us"`, `"de+nodeadkeys"`,
+/// `"com.apple.keylayout.German"`), display name, and language tag when known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyboardLayout {
+    pub id: String,
+    pub name: Option<String>,
+    pub language: Option<String>,
+}
+
+fn run(program: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    out.status
+        .success()
+        .then(|| String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// First xkb source of a gsettings `mru-sources`/`sources` value, e.g.
+/// `[('xkb', 'de+nodeadkeys'), ('ibus', 'anthy')]` → `de+nodeadkeys`.
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn parse_gsettings_sources(text: &str) -> Option<String> {
+    text.split('(').skip(1).find_map(|entry| {
+        let mut fields = entry.split('\'').skip(1).step_by(2);
+        let kind = fields.next()?;
+        let id = fields.next()?;
+        (kind == "xkb" && !id.is_empty()).then(|| id.to_string())
+    })
+}
+
+/// First layout (with its variant) from `setxkbmap -query` output, e.g. `layout: de,us` +
+/// `variant: nodeadkeys,` → `de+nodeadkeys`.
+#[cfg(target_os = "linux")]
+#[must_use]
+pub fn parse_setxkbmap(text: &str) -> Option<String> {
+    let field = |name: &str| {
+        text.lines()
+            .find_map(|l| {
+                l.strip_prefix(name)?
+                    .trim_start()
+                    .strip_prefix(':')
+                    .map(str::trim)
+            })
+            .and_then(|v| v.split(',').next())
+            .filter(|v| !v.is_empty())
+    };
+    let layout = field("layout")?;
+    Some(match field("variant") {
+        Some(variant) => format!("{}+{}", layout, variant),
+        None => layout.to_string(),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn read_layout() -> Option<KeyboardLayout> {
+    let id = ["mru-sources", "sources"]
+        .iter()
+        .find_map(|key| {
+            run(
+                "gsettings",
+                &["get", "org.gnome.desktop.input-sources", key],
+            )
+            .and_then(|t| parse_gsettings_sources(&t))
+        })
+        .or_else(|| run("setxkbmap", &["-query"]).and_then(|t| parse_setxkbmap(&t)))?;
+    Some(KeyboardLayout {
+        id,
+        name: None,
+        language: None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read_layout() -> Option<KeyboardLayout> {
+    let out = run(
+        "defaults",
+        &[
+            "read",
+            "com.apple.HIToolbox",
+            "AppleCurrentKeyboardLayoutInputSourceID",
+        ],
+    )?;
+    let id = out.trim().to_string();
+    let name = id.rsplit('.').next().map(String::from);
+    (!id.is_empty()).then_some(KeyboardLayout {
+        id,
+        name,
+        language: None,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn read_layout() -> Option<KeyboardLayout> {
+    let script = "Add-Type -AssemblyName System.Windows.Forms; \
+        $l = [System.Windows.Forms.InputLanguage]::DefaultInputLanguage; \
+        \"$($l.Handle.ToInt64())|$($l.LayoutName)|$($l.Culture.Name)\"";
+    let out = run(
+        "powershell",
+        &["-NoProfile", "-NonInteractive", "-Command", script],
+    )?;
+    let mut parts = out.trim().splitn(3, '|');
+    let handle: i64 = parts.next()?.parse().ok()?;
+    let name = parts.next().filter(|s| !s.is_empty()).map(String::from);
+    let language = parts.next().filter(|s| !s.is_empty()).map(String::from);
+    // Low word is the language id, high word the layout (KLID-style hex id).
+    Some(KeyboardLayout {
+        id: format!("{:08X}", handle & 0xFFFF_FFFF),
+        name,
+        language,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_layout() -> Option<KeyboardLayout> {
+    None
+}
+
+/// Current keyboard layout, if the platform reports it. Starts the change watcher on first call.
+#[must_use]
+pub fn current_layout() -> Option<KeyboardLayout> {
+    let layout = read_layout();
+    let initial = layout.clone();
+    WATCH.call_once(move || watch(initial));
+    layout
+}
+
+/// Emits `keyboard-layout-changed` with the new layout whenever the polled layout differs.
+fn watch(mut last: Option<KeyboardLayout>) {
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL);
+            let Some(layout) = read_layout() else {
+                continue;
+            };
+            if last.as_ref() != Some(&layout) {
+                if let Ok(payload) = serde_json::to_value(&layout) {
+                    events::emit("keyboard-layout-changed", payload);
+                }
+                last = Some(layout);
+            }
+        }
+    });
+}
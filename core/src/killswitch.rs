@@ -0,0 +1,155 @@
+//! Kill switch for risky runtime modules.
+//!
+//! A small manifest (`{ "disabledModules": ["updater", ...] }`) disables whole subsystems at
+//! runtime. Sources, merged: the local `disabledModules` config key, an optional remote
+//! manifest URL set at build time via `DESKTOP_RUNTIME_KILL_SWITCH_URL`, fetched once at startup
+//! on the IPC worker pool, and modules the launch mode leaves out (`services`, minimal mode). Changes take effect immediately and are pushed to the UI as a
+//! `modules-changed` host event.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+use crate::events;
+use crate::storage;
+
+/// Config key holding locally disabled modules (array of module names).
+pub const CONFIG_KEY: &str = "disabledModules";
+
+/// Remote manifest URL, if configured at build time.
+const REMOTE_MANIFEST_URL: Option<&str> = option_env!("DESKTOP_RUNTIME_KILL_SWITCH_URL");
+
+/// Runtime subsystems that can be switched off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeModule {
+    /// Update check, download, and install.
+    Updater,
+    /// Native file and folder dialogs.
+    Dialogs,
+    /// Opening external URLs in the default browser.
+    OpenUrl,
+    /// Starting other programs and writing outside the app's own data: open-with, relaunch,
+    /// archive extraction and creation, serial port access.
+    Shell,
+}
+
+impl RuntimeModule {
+    /// Manifest name of the module (matches the serde representation).
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Updater => "updater",
+            Self::Dialogs => "dialogs",
+            Self::OpenUrl => "open-url",
+            Self::Shell => "shell",
+        }
+    }
+}
+
+/// Manifest shape shared by the config key and the remote file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Manifest {
+    #[serde(default)]
+    disabled_modules: Vec<RuntimeModule>,
+}
+
+static LOCAL: RwLock<BTreeSet<RuntimeModule>> = RwLock::new(BTreeSet::new());
+static REMOTE: RwLock<BTreeSet<RuntimeModule>> = RwLock::new(BTreeSet::new());
+static MODE: RwLock<BTreeSet<RuntimeModule>> = RwLock::new(BTreeSet::new());
+
+/// Returns true if `module` is disabled by the local config, the remote manifest, or the launch mode.
+#[must_use]
+pub fn is_disabled(module: RuntimeModule) -> bool {
+    let read = |set: &RwLock<BTreeSet<RuntimeModule>>| {
+        set.read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&module)
+    };
+    read(&LOCAL) || read(&REMOTE) || read(&MODE)
+}
+
+/// Returns all currently disabled modules (sorted, deduplicated).
+#[must_use]
+pub fn disabled_modules() -> Vec<RuntimeModule> {
+    let local = LOCAL.read().unwrap_or_else(|e| e.into_inner());
+    let remote = REMOTE.read().unwrap_or_else(|e| e.into_inner());
+    let mode = MODE.read().unwrap_or_else(|e| e.into_inner());
+    let mut all: BTreeSet<RuntimeModule> = local.union(&remote).copied().collect();
+    all.extend(mode.iter().copied());
+    all.into_iter().collect()
+}
+
+/// Parses a manifest body. Unknown module names make the whole manifest invalid (`None`).
+fn parse_manifest(raw: &serde_json::Value) -> Option<BTreeSet<RuntimeModule>> {
+    let manifest: Manifest = serde_json::from_value(raw.clone()).ok()?;
+    Some(manifest.disabled_modules.into_iter().collect())
+}
+
+/// Replaces one source set and notifies the UI if the effective set changed.
+fn replace(source: &RwLock<BTreeSet<RuntimeModule>>, next: BTreeSet<RuntimeModule>) {
+    let before = disabled_modules();
+    *source.write().unwrap_or_else(|e| e.into_inner()) = next;
+    let after = disabled_modules();
+    if before != after {
+        log::warn!("Kill switch: disabled modules now {:?}", after);
+        events::emit("modules-changed", serde_json::json!({ "disabled": after }));
+    }
+}
+
+/// Disables `modules` for the rest of the process (launch mode, e.g. minimal mode).
+pub fn disable_for_mode(modules: &[RuntimeModule]) {
+    replace(&MODE, modules.iter().copied().collect());
+}
+
+/// Parses the local config value: a missing key disables nothing, an array of module names
+/// disables those. Anything else (unknown names included) is invalid (`None`).
+fn parse_local(raw: Option<serde_json::Value>) -> Option<BTreeSet<RuntimeModule>> {
+    match raw {
+        None | Some(serde_json::Value::Null) => Some(BTreeSet::new()),
+        Some(raw) => serde_json::from_value::<Vec<RuntimeModule>>(raw)
+            .ok()
+            .map(|m| m.into_iter().collect()),
+    }
+}
+
+/// Loads the local kill switch from config. Call at startup and after the config key is written.
+/// An invalid value keeps the previous local state, as an invalid remote manifest does.
+pub fn reload_local() {
+    match parse_local(storage::get_value(CONFIG_KEY)) {
+        Some(set) => replace(&LOCAL, set),
+        None => log::warn!(
+            "Kill switch: config key '{}' is invalid; ignoring",
+            CONFIG_KEY
+        ),
+    }
+}
+
+/// Fetches the remote manifest (if configured) and applies it. Blocking; run on a worker thread.
+/// Network or parse failures keep the previous remote state.
+pub fn refresh_remote() {
+    let Some(url) = REMOTE_MANIFEST_URL else {
+        return;
+    };
+    let body: serde_json::Value = match crate::net::agent()
+        .and_then(|agent| {
+            agent
+                .get(url)
+                .set("User-Agent", "Desktop-Runtime-Kill-Switch")
+                .call()
+                .map_err(|e| e.to_string())
+        })
+        .and_then(|r| r.into_json().map_err(|e| e.to_string()))
+    {
+        Ok(b) => b,
+        Err(e) => {
+            log::warn!("Kill switch manifest fetch failed: {}", e);
+            return;
+        }
+    };
+    match parse_manifest(&body) {
+        Some(set) => replace(&REMOTE, set),
+        None => log::warn!("Kill switch manifest is invalid; ignoring"),
+    }
+}
@@ -0,0 +1,56 @@
+//! Desktop runtime library: event loop, protocol, typed IPC, storage, and platform integration.
+//!
+//! The `desktop-runtime-core` binary wires these together; benches under `benches/` drive the hot
+//! paths (asset serving, IPC dispatch, response delivery) directly.
+
+pub mod apps;
+pub mod archive;
+pub mod audit;
+pub mod automation;
+pub mod bluetooth;
+pub mod capture;
+pub mod config;
+pub mod cursor;
+pub mod devreload;
+pub mod event_loop;
+pub mod events;
+pub mod fonts;
+pub mod grants;
+pub mod hex;
+pub mod i18n;
+pub mod idle;
+pub mod ipc;
+pub mod keyboard;
+pub mod killswitch;
+pub mod lifecycle;
+pub mod locale;
+pub mod media;
+pub mod memory;
+pub mod metrics;
+pub mod modal;
+pub mod net;
+pub mod panel;
+pub mod paths;
+pub mod permissions;
+pub mod popover;
+pub mod power;
+pub mod priority;
+pub mod profiler;
+pub mod protocol;
+pub mod renderer;
+pub mod scheduler;
+pub mod serial;
+pub mod services;
+pub mod session;
+pub mod settings;
+pub mod sound;
+pub mod speech;
+pub mod startup;
+pub mod storage;
+pub mod usb;
+pub mod watchdog;
+pub mod webdata;
+pub mod window;
+
+#[cfg(test)]
+mod protocol_tests;
@@ -0,0 +1,200 @@
+//! Quitting and relaunching the app from worker threads (`RestartApp`, update installs).
+//!
+//! A relaunch is handed to a detached helper (`/bin/sh` on Unix, PowerShell on Windows) that
+//! waits for this process to exit before starting the new one, so the two instances never run
+//! side by side. The event loop is asked to quit shortly after, giving the IPC response that
+//! triggered it time to reach the UI. The runtime holds no single-instance lock of its own, so
+//! waiting for the exit is the handoff: the new instance starts once this one has released the
+//! config lock and the WebView profile.
+
+use std::ffi::{OsStr, OsString};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// Most arguments accepted by `RestartApp { args }`.
+const MAX_RESTART_ARGS: usize = 64;
+
+/// Longest single argument accepted by `RestartApp { args }` (bytes).
+const MAX_RESTART_ARG_BYTES: usize = 4096;
+
+/// Time for the triggering IPC response to reach the UI before the loop exits.
+const QUIT_DELAY: Duration = Duration::from_millis(500);
+
+/// Asks the event loop to exit after `QUIT_DELAY`.
+pub fn quit_soon() {
+    std::thread::spawn(|| {
+        std::thread::sleep(QUIT_DELAY);
+        crate::events::request_quit();
+    });
+}
+
+/// Command that starts this app again: the AppImage (`$APPIMAGE`) when running from one, the
+/// `.app` bundle via `open` on macOS, otherwise the current executable. `args` replace the
+/// arguments; `None` keeps this instance's (the AppImage and bundle are started without).
+pub fn relaunch_command(args: Option<&[String]>) -> Result<Vec<OsString>, String> {
+    let explicit = || args.into_iter().flatten().map(OsString::from);
+    if cfg!(target_os = "linux")
+        && let Some(appimage) = std::env::var_os("APPIMAGE")
+    {
+        return Ok(std::iter::once(appimage).chain(explicit()).collect());
+    }
+    let exe =
+        std::env::current_exe().map_err(|e| format!("Could not find the executable: {}", e))?;
+    if cfg!(target_os = "macos")
+        && let Some(bundle) = crate::paths::app_bundle_path(&exe)
+    {
+        let mut command = vec!["/usr/bin/open".into(), bundle.into_os_string()];
+        if args.is_some_and(|a| !a.is_empty()) {
+            command.push("--args".into());
+            command.extend(explicit());
+        }
+        return Ok(command);
+    }
+    let rest: Vec<OsString> = match args {
+        Some(_) => explicit().collect(),
+        None => std::env::args_os().skip(1).collect(),
+    };
+    Ok(std::iter::once(exe.into_os_string()).chain(rest).collect())
+}
+
+/// Checks `RestartApp` arguments: at most `MAX_RESTART_ARGS`, each at most
+/// `MAX_RESTART_ARG_BYTES` and free of NUL bytes.
+pub fn validate_restart_args(args: &[String]) -> Result<(), String> {
+    if args.len() > MAX_RESTART_ARGS {
+        return Err(format!("At most {} arguments", MAX_RESTART_ARGS));
+    }
+    match args
+        .iter()
+        .find(|a| a.len() > MAX_RESTART_ARG_BYTES || a.contains('\0'))
+    {
+        Some(bad) => Err(format!("Invalid argument: {:.64}", bad)),
+        None => Ok(()),
+    }
+}
+
+/// Runs `command` once this process has exited. Does not quit; see `quit_soon`.
+#[cfg(unix)]
+pub fn run_after_exit(command: &[OsString]) -> Result<(), String> {
+    Command::new("/bin/sh")
+        .args([
+            "-c",
+            "while kill -0 \"$0\" 2>/dev/null; do sleep 0.2; done; exec \"$@\"",
+        ])
+        .arg(std::process::id().to_string())
+        .args(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+        .map_err(|e| format!("Could not schedule relaunch: {}", e))
+}
+
+/// PowerShell statements that wait for this process to exit, then start `command`.
+#[cfg(windows)]
+pub fn after_exit_script(command: &[OsString]) -> Result<String, String> {
+    let (program, args) = command.split_first().ok_or("Empty relaunch command")?;
+    let mut script = format!(
+        "Wait-Process -Id {} -ErrorAction SilentlyContinue; Start-Process -FilePath {}",
+        std::process::id(),
+        ps_quote(program)
+    );
+    if !args.is_empty() {
+        script.push_str(&format!(
+            " -ArgumentList {}",
+            ps_quote(&windows_command_line(args))
+        ));
+    }
+    Ok(script)
+}
+
+/// Runs `command` once this process has exited. Does not quit; see `quit_soon`.
+#[cfg(windows)]
+pub fn run_after_exit(command: &[OsString]) -> Result<(), String> {
+    run_powershell_detached(&after_exit_script(command)?)
+}
+
+/// Starts `script` in a hidden PowerShell that outlives this process.
+#[cfg(windows)]
+pub fn run_powershell_detached(script: &str) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+        .map_err(|e| format!("Could not start powershell: {}", e))
+}
+
+/// Single-quoted PowerShell string literal. PowerShell also closes such a literal on the
+/// typographic single quotes (U+2018–U+201B), so those are doubled like `'`.
+#[must_use]
+pub fn ps_quote(s: &OsStr) -> String {
+    let mut out = String::from("'");
+    for c in s.to_string_lossy().chars() {
+        if matches!(c, '\'' | '\u{2018}'..='\u{201B}') {
+            out.push(c);
+        }
+        out.push(c);
+    }
+    out.push('\'');
+    out
+}
+
+/// Arguments joined into one Windows command line that `CommandLineToArgvW` (and the MSVC
+/// runtime) splits back into `args`: arguments with whitespace or quotes are quoted, embedded
+/// quotes are escaped, and backslashes are doubled where they precede a quote.
+#[must_use]
+pub fn windows_command_line(args: &[OsString]) -> OsString {
+    let quoted: Vec<String> = args
+        .iter()
+        .map(|a| windows_quote_arg(&a.to_string_lossy()))
+        .collect();
+    quoted.join(" ").into()
+}
+
+fn windows_quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '\u{b}', '"']) {
+        return arg.to_string();
+    }
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                out.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                out.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                out.extend(std::iter::repeat_n('\\', backslashes));
+                out.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    out.extend(std::iter::repeat_n('\\', backslashes * 2));
+    out.push('"');
+    out
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn run_after_exit(_command: &[OsString]) -> Result<(), String> {
+    Err("Relaunch is not supported on this platform".to_string())
+}
+
+/// Relaunches the app, with `args` instead of the current arguments when given: schedules a new
+/// instance for after exit, then quits.
+pub fn restart(args: Option<&[String]>) -> Result<(), String> {
+    if let Some(args) = args {
+        validate_restart_args(args)?;
+    }
+    run_after_exit(&relaunch_command(args)?)?;
+    quit_soon();
+    Ok(())
+}
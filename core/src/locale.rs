@@ -0,0 +1,150 @@
+//! Locale detection and preference order.
+//!
+//! The `locale` config key (BCP 47 tag, e.g. `"fr-FR"`) overrides the OS preference list.
+//! Used by the protocol layer to pick a per-locale UI bundle and by `GetLocale`.
+//!
+//! First day of week and number separators are CLDR defaults for the region/language; per-user
+//! overrides made in OS settings are not read.
+
+use crate::settings::Settings;
+
+/// Config key for an explicit locale override.
+pub const CONFIG_KEY: &str = "locale";
+
+/// Returns preferred locale tags, most preferred first: config override, then OS preferences.
+#[must_use]
+pub fn preferred_locales() -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(tag) = Settings::load().locale {
+        out.push(tag);
+    }
+    out.extend(sys_locale::get_locales());
+    out
+}
+
+/// Expands tags into lookup candidates: each tag as-is (with `_` normalized to `-`), then its
+/// language subtag. Duplicates are dropped, order preserved (`["fr_FR", "de"]` → `fr-FR, fr, de`).
+#[must_use]
+pub fn candidate_tags(tags: &[String]) -> Vec<String> {
+    let mut out: Vec<String> = Vec::new();
+    for tag in tags {
+        let full = tag.split('.').next().unwrap_or(tag).replace('_', "-");
+        let lang = full.split('-').next().unwrap_or(&full).to_string();
+        for c in [full, lang] {
+            if !c.is_empty() && !out.iter().any(|o| o.eq_ignore_ascii_case(&c)) {
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Regions whose week starts on Sunday (CLDR `firstDay`).
+const SUNDAY_REGIONS: &[&str] = &[
+    "AG", "AS", "BR", "BS", "BT", "BW", "BZ", "CA", "CN", "CO", "DM", "DO", "ET", "GT", "GU", "HK",
+    "HN", "ID", "IL", "IN", "JM", "JP", "KE", "KH", "KR", "LA", "MH", "MM", "MO", "MT", "MX", "MZ",
+    "NI", "NP", "PA", "PE", "PH", "PK", "PR", "PT", "PY", "SA", "SG", "SV", "TH", "TT", "TW", "UM",
+    "US", "VE", "VI", "WS", "YE", "ZA", "ZW",
+];
+
+/// Regions whose week starts on Saturday (CLDR `firstDay`).
+const SATURDAY_REGIONS: &[&str] = &[
+    "AE", "AF", "BH", "DJ", "DZ", "EG", "IQ", "IR", "JO", "KW", "LY", "OM", "QA", "SD", "SY",
+];
+
+/// Languages using `,` as decimal separator, with their grouping separator.
+const COMMA_DECIMAL_LANGUAGES: &[(&str, &str)] = &[
+    ("bg", "\u{a0}"),
+    ("ca", "."),
+    ("cs", "\u{a0}"),
+    ("da", "."),
+    ("de", "."),
+    ("el", "."),
+    ("es", "."),
+    ("et", "\u{a0}"),
+    ("fi", "\u{a0}"),
+    ("fr", "\u{202f}"),
+    ("hr", "."),
+    ("hu", "\u{a0}"),
+    ("id", "."),
+    ("it", "."),
+    ("lt", "\u{a0}"),
+    ("lv", "\u{a0}"),
+    ("nb", "\u{a0}"),
+    ("nl", "."),
+    ("pl", "\u{a0}"),
+    ("pt", "."),
+    ("ro", "."),
+    ("ru", "\u{a0}"),
+    ("sk", "\u{a0}"),
+    ("sl", "."),
+    ("sr", "."),
+    ("sv", "\u{a0}"),
+    ("tr", "."),
+    ("uk", "\u{a0}"),
+    ("vi", "."),
+];
+
+/// Splits a tag into (language, region), e.g. `"en_US.UTF-8"` → `("en", Some("US"))`.
+fn split_tag(tag: &str) -> (String, Option<String>) {
+    let full = tag.split('.').next().unwrap_or(tag).replace('_', "-");
+    let mut parts = full.split('-');
+    let lang = parts.next().unwrap_or_default().to_ascii_lowercase();
+    // Region: 2 letters or 3 digits (skips script subtags like `Hant`).
+    let region = parts
+        .find(|p| {
+            (p.len() == 2 && p.chars().all(|c| c.is_ascii_alphabetic()))
+                || (p.len() == 3 && p.chars().all(|c| c.is_ascii_digit()))
+        })
+        .map(str::to_ascii_uppercase);
+    (lang, region)
+}
+
+/// First day of the week for a region (`"sunday"`, `"saturday"`, or `"monday"`).
+#[must_use]
+pub fn first_day_of_week(region: Option<&str>) -> &'static str {
+    match region {
+        Some(r) if SUNDAY_REGIONS.contains(&r) => "sunday",
+        Some(r) if SATURDAY_REGIONS.contains(&r) => "saturday",
+        _ => "monday",
+    }
+}
+
+/// Decimal and grouping separators for a language/region.
+#[must_use]
+pub fn number_separators(lang: &str, region: Option<&str>) -> (&'static str, &'static str) {
+    // Swiss German/Italian/French use `.` and `’`.
+    if region == Some("CH") && matches!(lang, "de" | "it" | "fr" | "rm") {
+        return (".", "\u{2019}");
+    }
+    COMMA_DECIMAL_LANGUAGES
+        .iter()
+        .find(|(l, _)| *l == lang)
+        .map_or((".", ","), |(_, group)| (",", *group))
+}
+
+/// Locale and regional settings for `GetLocale`. `uiLanguage` is the effective language (config
+/// override, else the OS UI language reported as `systemLanguage`).
+#[must_use]
+pub fn locale_info() -> serde_json::Value {
+    let system: Vec<String> = sys_locale::get_locales().collect();
+    let override_tag = Settings::load().locale;
+    let ui_language = override_tag
+        .clone()
+        .or_else(|| system.first().cloned())
+        .unwrap_or_else(|| "en-US".to_string());
+    let (lang, region) = split_tag(&ui_language);
+    // Prefer the OS region when the chosen tag has none (e.g. override "fr" on a fr-CA system).
+    let region = region.or_else(|| system.iter().find_map(|t| split_tag(t).1));
+    let (decimal, group) = number_separators(&lang, region.as_deref());
+    serde_json::json!({
+        "uiLanguage": ui_language.split('.').next().unwrap_or(&ui_language).replace('_', "-"),
+        "systemLanguage": sys_locale::get_locale(),
+        "language": lang,
+        "region": region,
+        "preferredLanguages": system,
+        "override": override_tag,
+        "firstDayOfWeek": first_day_of_week(region.as_deref()),
+        "numberFormat": { "decimalSeparator": decimal, "groupSeparator": group },
+    })
+}
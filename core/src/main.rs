@@ -1,36 +1,43 @@
 //! Desktop runtime: single event loop, one WebView, embedded UI, typed IPC.
 //!
-//! No Tokio spawn in the main loop; use `Runtime::new_current_thread()` only if async is needed.
+//! No Tokio in the main loop; async command handlers run on a current-thread runtime on their own
+//! thread (`ipc::AsyncRuntime`).
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod config;
-mod event_loop;
-mod ipc;
-mod paths;
-mod protocol;
-mod storage;
-mod window;
-
-#[cfg(test)]
-mod protocol_tests;
-
-use crate::config::{
-    ENV_DEVTOOLS, IPC_WORKER_POOL_SIZE, MAX_PENDING_IPC, SHOW_WINDOW_FALLBACK_SECS, UI,
-    WINDOW_HEIGHT, WINDOW_MIN_HEIGHT, WINDOW_MIN_WIDTH, WINDOW_WIDTH,
+use desktop_runtime_core::config::{
+    ENV_DEVTOOLS, ENV_SIGNED_IPC, IPC_WORKER_POOL_SIZE, PRODUCT_NAME, UI, WINDOW_HEIGHT,
+    WINDOW_MIN_HEIGHT, WINDOW_MIN_WIDTH, WINDOW_WIDTH,
+};
+use desktop_runtime_core::event_loop::{Timers, UserEvent, run_event_loop};
+use desktop_runtime_core::ipc::{
+    AsyncRuntime, DropPolicy, IpcEnvelope, IpcLimits, IpcReceiver, IpcResponse, IpcSender, IpcSlot,
+    Seen, SessionKey, SessionToken, WorkerPool, command_lane, concurrency_limit, handle_command,
+    ipc_channel, is_async_command, is_blocking_command, is_window_command, parse_envelope,
+    respond_json,
+};
+use desktop_runtime_core::paths::user_data_dir;
+use desktop_runtime_core::protocol::{
+    LOAD_ERROR_PATH, MEDIA_SCHEME, ServeResult, is_entry_document, is_trusted_origin,
+    media_response, select_bundle,
+};
+use desktop_runtime_core::services::Services;
+use desktop_runtime_core::watchdog::Watchdog;
+use desktop_runtime_core::window::{
+    AppHandler, IpcHandler, MAIN_WINDOW, PixelRect, StartMode, ViewContext, apply_spellcheck,
+    build_splash, fit_to_monitors, init_script, rescale_bounds, spellcheck_script, start_mode,
+    user_agent_suffix_script, window_icon,
+};
+use desktop_runtime_core::{
+    automation, devreload, events, ipc, killswitch, locale, metrics, permissions, protocol,
+    renderer, settings, startup, storage,
 };
-use crate::event_loop::{run_event_loop, UserEvent};
-use crate::ipc::{handle_command, is_blocking_command, parse_message, IpcResponse};
-use crate::paths::user_data_dir;
-use crate::protocol::{serve, ServeResult};
-use crate::window::{init_script, window_icon};
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tao::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
-use std::thread;
 use tao::event_loop::EventLoopBuilder;
-use wry::http::Response;
 use wry::WebViewBuilder;
 
 /// Exits the process with code 1 after logging. Use for unrecoverable startup failures.
@@ -39,159 +46,444 @@ fn exit_fatal(msg: &str) -> ! {
     std::process::exit(1);
 }
 
-/// Pushes one IPC response JSON to the queue and sends `IpcFlush` only when this is the first item
-/// (so the event loop is woken once per batch). Recovers from mutex poison so a panicking thread
-/// cannot leave the queue permanently locked and cause unbounded growth or deadlock.
-fn push_ipc_and_wake(proxy: &tao::event_loop::EventLoopProxy<UserEvent>, queue: &Mutex<Vec<String>>, json: String) {
-    let was_first = {
-        let mut q = queue.lock().unwrap_or_else(|e| {
-            log::error!("IPC queue mutex was poisoned, recovering");
-            e.into_inner()
-        });
-        q.push(json);
-        q.len() == 1
-    };
-    if was_first {
+/// Queues one IPC response in its reserved slot and sends `IpcFlush` only for the first response
+/// since the last drain (so the event loop is woken once per batch).
+fn push_ipc_and_wake(
+    proxy: &tao::event_loop::EventLoopProxy<UserEvent>,
+    queue: &IpcSender,
+    slot: IpcSlot,
+    window: Option<String>,
+    json: String,
+) {
+    if queue.send(slot, window, json) {
         let _ = proxy.send_event(UserEvent::IpcFlush);
     }
 }
 
+/// Answers `id` with a `busy` error instead of running it. Bypasses the queue capacity check:
+/// each rejected request yields one small response, so the UI promise settles immediately.
+fn reject_busy(
+    proxy: &tao::event_loop::EventLoopProxy<UserEvent>,
+    queue: &IpcSender,
+    window: Option<String>,
+    id: String,
+) {
+    log::warn!("IPC backpressure: rejecting request as busy (id={})", id);
+    metrics::record_busy();
+    ipc::abandon_request(&id);
+    if let Ok(json) = serde_json::to_string(&IpcResponse::busy(id)) {
+        push_ipc_and_wake(proxy, queue, queue.reserve(), window, json);
+    }
+}
+
+/// Backpressure check for a request about to run: its response slot, or `None` when it must not
+/// run. With `capacity` responses pending, `limits.drop_policy` decides: reject it as busy,
+/// ignore it, or make room by dropping the oldest queued response.
+fn admit(
+    limits: IpcLimits,
+    proxy: &tao::event_loop::EventLoopProxy<UserEvent>,
+    queue: &IpcSender,
+    received: &IpcReceiver,
+    envelope: &IpcEnvelope,
+) -> Option<IpcSlot> {
+    if let Some(slot) = queue.try_reserve() {
+        return Some(slot);
+    }
+    match limits.drop_policy {
+        DropPolicy::DropOldest if received.drop_oldest() => return Some(queue.reserve()),
+        DropPolicy::DropNew => {
+            log::warn!("IPC backpressure: ignoring request (id={})", envelope.id);
+            metrics::record_dropped_request();
+            ipc::abandon_request(&envelope.id);
+        }
+        DropPolicy::Reject | DropPolicy::DropOldest => {
+            reject_busy(
+                proxy,
+                queue,
+                envelope.window_id.clone(),
+                envelope.id.clone(),
+            );
+        }
+    }
+    None
+}
+
 fn main() {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn"))
-        .init();
+    startup::init();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let start_mode = start_mode(&args);
 
     let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
     let proxy = event_loop.create_proxy();
-    let pending_ipc = Arc::new(AtomicUsize::new(0));
-    let ipc_queue: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-    let ipc_pool = rayon::ThreadPoolBuilder::new()
-        .num_threads(IPC_WORKER_POOL_SIZE)
-        .build()
+    let settings = settings::Settings::load();
+    let (ipc_sender, ipc_queue) = ipc_channel(settings.ipc_limits.capacity);
+    let ipc_queue = Rc::new(ipc_queue);
+    let devtools = std::env::var(ENV_DEVTOOLS).as_deref() == Ok("1");
+    if devtools {
+        ipc::enable_trace(ipc_sender.stats());
+    }
+    let ipc_pool = WorkerPool::new(IPC_WORKER_POOL_SIZE)
         .unwrap_or_else(|e| exit_fatal(&format!("IPC worker pool: {}", e)));
+    metrics::init(ipc_sender.stats(), ipc_pool.clone());
+    let async_runtime =
+        AsyncRuntime::start().unwrap_or_else(|e| exit_fatal(&format!("Async IPC runtime: {}", e)));
 
+    events::init(proxy.clone());
+    killswitch::reload_local();
+    let services = Services::for_settings(&settings);
+    if devreload::enabled() {
+        devreload::watch(proxy.clone());
+    }
+    automation::start(proxy.clone());
+    services.start(&ipc_pool);
+    // Popover mode needs the tray to open the window, and the window starts hidden.
+    let popover = settings.tray_popover && services.tray;
+    let start_mode = if popover {
+        StartMode::Hidden
+    } else {
+        start_mode
+    };
+
+    let window_phase = startup::phase("window");
     let window = {
         let mut b = tao::window::WindowBuilder::new()
-            .with_title("Desktop Runtime")
+            .with_title(PRODUCT_NAME)
             .with_inner_size(LogicalSize::new(WINDOW_WIDTH, WINDOW_HEIGHT))
             .with_min_inner_size(LogicalSize::new(WINDOW_MIN_WIDTH, WINDOW_MIN_HEIGHT))
-            .with_visible(false);
+            .with_visible(false)
+            .with_always_on_top(settings.always_on_top)
+            .with_visible_on_all_workspaces(settings.visible_on_all_workspaces);
         if let Some(icon) = window_icon() {
             b = b.with_window_icon(Some(icon));
         }
+        if popover {
+            b = b.with_decorations(false).with_always_on_top(true);
+            #[cfg(target_os = "windows")]
+            {
+                use tao::platform::windows::WindowBuilderExtWindows;
+                b = b.with_skip_taskbar(true);
+            }
+            #[cfg(target_os = "linux")]
+            {
+                use tao::platform::unix::WindowBuilderExtUnix;
+                b = b.with_skip_taskbar(true);
+            }
+        }
         if let Some(bounds) = storage::load_window_bounds() {
+            let monitors: Vec<_> = event_loop.available_monitors().collect();
+            let rects: Vec<PixelRect> = monitors.iter().map(PixelRect::from).collect();
+            // Fit, convert the size to the scale of the display it lands on, then fit that size.
+            let bounds = fit_to_monitors(bounds, &rects);
+            let scale = monitors
+                .iter()
+                .find(|m| {
+                    PixelRect::from(*m).contains(bounds.x + (bounds.width / 2) as i32, bounds.y)
+                })
+                .map_or(1.0, |m| m.scale_factor());
+            let bounds = fit_to_monitors(rescale_bounds(bounds, scale), &rects);
             b = b
                 .with_position(PhysicalPosition::new(bounds.x, bounds.y))
-                .with_inner_size(PhysicalSize::new(bounds.width, bounds.height));
+                .with_inner_size(PhysicalSize::new(bounds.width, bounds.height))
+                .with_maximized(bounds.maximized);
+            if bounds.fullscreen {
+                b = b.with_fullscreen(Some(tao::window::Fullscreen::Borderless(None)));
+            }
         }
         b.build(&event_loop).unwrap_or_else(|e| {
             exit_fatal(&format!("Failed to create window: {}", e));
         })
     };
+    drop(window_phase);
+
+    let signing_key =
+        (std::env::var(ENV_SIGNED_IPC).as_deref() == Ok("1")).then(SessionKey::generate);
+    let handler_key = signing_key.clone();
+    let session_token = SessionToken::generate();
+    let handler_token = session_token.clone();
+
+    if start_mode == StartMode::Minimized {
+        window.set_minimized(true);
+    }
+
+    let splash = if start_mode == StartMode::Normal && settings.show_splash {
+        build_splash(&event_loop)
+    } else {
+        None
+    };
 
+    let report_parse_errors = devtools || cfg!(debug_assertions);
     let ipc_proxy = proxy.clone();
-    let pending_ipc_handler = Arc::clone(&pending_ipc);
-    let ipc_queue_handler = Arc::clone(&ipc_queue);
-    let ipc_handler = move |req: wry::http::Request<String>| {
-        let body = req.body();
-        let Some(envelope) = parse_message(body) else { return };
-
-        if is_blocking_command(&envelope.command) {
-            if pending_ipc_handler.load(Ordering::Relaxed) >= MAX_PENDING_IPC {
-                log::warn!("IPC backpressure: dropping blocking request (id={})", envelope.id);
+    let ipc_limits = settings.ipc_limits;
+    let ipc_sender_handler = ipc_sender.clone();
+    let ipc_queue_handler = Rc::clone(&ipc_queue);
+    // Shared by every WebView with the bridge; `window_id` is the WebView's window, and responses
+    // go back to it.
+    let ipc_handler: IpcHandler =
+        Rc::new(move |window_id: &str, req: wry::http::Request<String>| {
+            let window = (window_id != MAIN_WINDOW).then(|| window_id.to_string());
+            let origin = req.uri().to_string();
+            if !is_trusted_origin(&origin) {
+                log::warn!("IPC: rejecting message from untrusted origin {}", origin);
+                return;
+            }
+            let body = req.body();
+            if !handler_token.verify(body) {
+                log::warn!("IPC: rejecting message without a valid session token");
                 return;
             }
-            pending_ipc_handler.fetch_add(1, Ordering::Relaxed);
-            let worker_proxy = ipc_proxy.clone();
-            let worker_pending = Arc::clone(&pending_ipc_handler);
-            let worker_queue = Arc::clone(&ipc_queue_handler);
-            ipc_pool.spawn(move || {
-                let resp = match handle_command(&envelope.command) {
-                    Ok(data) => IpcResponse::ok(envelope.id, data),
-                    Err(e) => IpcResponse::err(envelope.id, e),
-                };
-                if let Ok(json) = serde_json::to_string(&resp) {
-                    push_ipc_and_wake(&worker_proxy, &worker_queue, json);
-                } else {
-                    worker_pending.fetch_sub(1, Ordering::Relaxed);
+            let verified;
+            let body = match &handler_key {
+                Some(key) => {
+                    let Some(inner) = key.verify(body) else {
+                        log::warn!("Signed IPC: rejecting unsigned or tampered message");
+                        return;
+                    };
+                    verified = inner;
+                    &verified
                 }
-            });
-            return;
-        }
+                None => body,
+            };
+            let envelope = match parse_envelope(body) {
+                Ok(envelope) => envelope,
+                Err(e) => {
+                    log::debug!("IPC: unparseable message ({}): {}", e.code, e.message);
+                    // Dev aid: answer with a structured error instead of letting the promise time out.
+                    if report_parse_errors
+                        && let Some(resp) = e.to_response()
+                        && let Ok(json) = serde_json::to_string(&resp)
+                    {
+                        match ipc_sender_handler.try_reserve() {
+                            Some(slot) => push_ipc_and_wake(
+                                &ipc_proxy,
+                                &ipc_sender_handler,
+                                slot,
+                                window,
+                                json,
+                            ),
+                            None => metrics::record_dropped(),
+                        }
+                    }
+                    return;
+                }
+            };
+            let mut envelope = envelope;
+            envelope.window_id = window;
 
-        let resp = match handle_command(&envelope.command) {
-            Ok(data) => IpcResponse::ok(envelope.id, data),
-            Err(e) => IpcResponse::err(envelope.id, e),
-        };
-        if let Ok(json) = serde_json::to_string(&resp) {
-            if pending_ipc_handler.load(Ordering::Relaxed) >= MAX_PENDING_IPC {
-                log::warn!("IPC backpressure: dropping response (id={})", resp.id);
+            match ipc::check_duplicate(&envelope.id) {
+                Seen::New => {}
+                Seen::InFlight => {
+                    log::debug!(
+                        "IPC: coalescing retry of in-flight request (id={})",
+                        envelope.id
+                    );
+                    return;
+                }
+                Seen::Completed(cached) => {
+                    let Some(slot) = admit(
+                        ipc_limits,
+                        &ipc_proxy,
+                        &ipc_sender_handler,
+                        &ipc_queue_handler,
+                        &envelope,
+                    ) else {
+                        return;
+                    };
+                    let json = cached.or_else(|| {
+                        serde_json::to_string(&IpcResponse::duplicate(envelope.id)).ok()
+                    });
+                    if let Some(json) = json {
+                        push_ipc_and_wake(
+                            &ipc_proxy,
+                            &ipc_sender_handler,
+                            slot,
+                            envelope.window_id,
+                            json,
+                        );
+                    }
+                    return;
+                }
+            }
+
+            let Some(slot) = admit(
+                ipc_limits,
+                &ipc_proxy,
+                &ipc_sender_handler,
+                &ipc_queue_handler,
+                &envelope,
+            ) else {
+                return;
+            };
+
+            if is_window_command(&envelope.command) {
+                let _ = ipc_proxy.send_event(UserEvent::WindowCommand(envelope, slot));
+                return;
+            }
+
+            // A command that produces no response drops its slot, releasing it.
+            if is_async_command(&envelope.command) {
+                let task_proxy = ipc_proxy.clone();
+                let task_queue = ipc_sender_handler.clone();
+                let task_window = envelope.window_id.clone();
+                async_runtime.spawn(envelope, move |json| {
+                    if let Some(json) = json {
+                        push_ipc_and_wake(&task_proxy, &task_queue, slot, task_window, json);
+                    }
+                });
+                return;
+            }
+
+            if is_blocking_command(&envelope.command) {
+                let worker_proxy = ipc_proxy.clone();
+                let worker_queue = ipc_sender_handler.clone();
+                let lane = command_lane(&envelope.command);
+                let limit = concurrency_limit(&envelope.command);
+                ipc_pool.spawn(lane, limit, move || {
+                    if let Some(json) =
+                        respond_json(envelope.id, || handle_command(&envelope.command))
+                    {
+                        push_ipc_and_wake(
+                            &worker_proxy,
+                            &worker_queue,
+                            slot,
+                            envelope.window_id,
+                            json,
+                        );
+                    }
+                });
                 return;
             }
-            pending_ipc_handler.fetch_add(1, Ordering::Relaxed);
-            push_ipc_and_wake(&ipc_proxy, &ipc_queue_handler, json);
-        }
-    };
 
-    let protocol_handler = move |_: wry::WebViewId<'_>, request: wry::http::Request<Vec<u8>>| {
-        let path = request.uri().path();
-        let (status, body, mime_type) = match serve(&UI, path) {
-            ServeResult::Found { body, mime_type } => (200, body, mime_type),
-            ServeResult::NotFound => (
+            if let Some(json) = respond_json(envelope.id, || handle_command(&envelope.command)) {
+                push_ipc_and_wake(
+                    &ipc_proxy,
+                    &ipc_sender_handler,
+                    slot,
+                    envelope.window_id,
+                    json,
+                );
+            }
+        });
+
+    let ui_bundle = select_bundle(&UI, &locale::candidate_tags(&locale::preferred_locales()));
+    if let Some(bundle) = ui_bundle {
+        log::info!("Serving UI bundle for locale '{}'", bundle);
+    }
+    let dev_dist = devreload::enabled().then(devreload::dist_dir);
+    // Shared with later WebViews (panels, modals), which serve the same bundle.
+    let serve_app: AppHandler = Arc::new(move |path: &str| {
+        if path == LOAD_ERROR_PATH {
+            return protocol::load_error_response(&UI, ui_bundle);
+        }
+        let Some(root) = dev_dist.as_deref() else {
+            return protocol::app_response(&UI, ui_bundle, path);
+        };
+        match protocol::serve_dir(root, path) {
+            ServeResult::Found { body, mime_type } => {
+                protocol::response(200, body, mime_type, protocol::CSP)
+            }
+            _ => protocol::response(
                 404,
                 std::borrow::Cow::Borrowed(b"Not Found".as_slice()),
                 "text/plain",
+                protocol::CSP,
             ),
-        };
-        Response::builder()
-            .status(status)
-            .header("Content-Type", mime_type)
-            .header("Content-Security-Policy", protocol::CSP)
-            .header("X-Content-Type-Options", "nosniff")
-            .body(body)
-            .unwrap_or_else(|e| {
-                log::error!("Protocol response build failed: {}", e);
-        Response::builder()
-            .status(500)
-            .body(std::borrow::Cow::Borrowed(b"Internal Server Error".as_slice()))
-            .unwrap()
-            })
+        }
+    });
+    let protocol_handler = {
+        let serve_app = Arc::clone(&serve_app);
+        let p = proxy.clone();
+        move |_: wry::WebViewId<'_>, request: wry::http::Request<Vec<u8>>| {
+            startup::first_protocol_request();
+            let path = request.uri().path();
+            let response = serve_app(path);
+            if is_entry_document(path) && !response.status().is_success() {
+                let _ = p.send_event(UserEvent::LoadFailed(format!(
+                    "{} returned {}",
+                    path,
+                    response.status()
+                )));
+            }
+            response
+        }
+    };
+
+    let media_handler = move |_: wry::WebViewId<'_>, request: wry::http::Request<Vec<u8>>| {
+        let range = request
+            .headers()
+            .get(wry::http::header::RANGE)
+            .and_then(|v| v.to_str().ok());
+        media_response(request.uri().path(), range)
     };
 
-    let navigation_allow = move |url: String| url.starts_with("app://") || url.contains("app.localhost");
+    let navigation_allow = move |url: String| is_trusted_origin(&url);
 
-    let show_window_proxy = proxy.clone();
-    let shown = Arc::new(AtomicUsize::new(0));
+    // The window is shown on `UiReady`; after `uiReadyTimeoutMs` without one it is shown anyway,
+    // or, when the page never finished loading, with the error page instead.
+    let page_loaded = Rc::new(Cell::new(false));
     let on_page_load = {
-        let p = show_window_proxy.clone();
-        let s = Arc::clone(&shown);
-        move |_event: wry::PageLoadEvent, _url: String| {
-            if s.fetch_add(1, Ordering::Relaxed) == 0 {
-                let _ = p.send_event(UserEvent::ShowWindow);
+        let loaded = Rc::clone(&page_loaded);
+        move |event: wry::PageLoadEvent, _url: String| {
+            if matches!(event, wry::PageLoadEvent::Finished) {
+                startup::first_page_load();
+                loaded.set(true);
             }
         }
     };
+    let mut timers = Timers::default();
     {
         let p = proxy.clone();
-        let s = Arc::clone(&shown);
-        thread::spawn(move || {
-            thread::sleep(Duration::from_secs(SHOW_WINDOW_FALLBACK_SECS));
-            if s.fetch_add(1, Ordering::Relaxed) == 0 {
-                let _ = p.send_event(UserEvent::ShowWindow);
-            }
+        let timeout = Duration::from_millis(settings.ui_ready_timeout_ms);
+        timers.add(Instant::now() + timeout, move || {
+            let event = if page_loaded.get() {
+                UserEvent::UiReadyTimeout
+            } else {
+                UserEvent::LoadFailed(format!("no page load within {} ms", timeout.as_millis()))
+            };
+            let _ = p.send_event(event);
         });
     }
 
+    let webview_phase = startup::phase("webview");
     let mut web_context = wry::WebContext::new(Some(user_data_dir()));
-    let devtools = std::env::var(ENV_DEVTOOLS).as_deref() == Ok("1");
 
     let builder = WebViewBuilder::new_with_web_context(&mut web_context)
         .with_custom_protocol("app".to_string(), protocol_handler)
+        .with_custom_protocol(MEDIA_SCHEME.to_string(), media_handler)
         .with_url("app://localhost/index.html")
-        .with_ipc_handler(ipc_handler)
-        .with_initialization_script(init_script())
+        .with_ipc_handler({
+            let ipc_handler = Rc::clone(&ipc_handler);
+            move |req| ipc_handler(MAIN_WINDOW, req)
+        })
+        .with_initialization_script(init_script(
+            session_token.as_str(),
+            signing_key.as_ref().map(SessionKey::to_hex).as_deref(),
+            devtools,
+            MAIN_WINDOW,
+        ))
         .with_navigation_handler(navigation_allow)
         .with_on_page_load_handler(on_page_load)
-        .with_devtools(devtools);
+        .with_devtools(devtools)
+        .with_autoplay(settings.autoplay)
+        .with_incognito(settings.incognito);
+    let builder = renderer::watch(builder, proxy.clone());
+    let builder = match settings.spellcheck {
+        Some(enabled) => builder.with_initialization_script(spellcheck_script(enabled)),
+        None => builder,
+    };
+    let builder = match settings.background_throttling {
+        Some(policy) => builder.with_background_throttling(policy.into()),
+        None => builder,
+    };
+    let builder = match (&settings.user_agent, &settings.user_agent_suffix) {
+        (Some(ua), Some(suffix)) => builder.with_user_agent(format!("{} {}", ua, suffix)),
+        (Some(ua), None) => builder.with_user_agent(ua.as_str()),
+        (None, Some(suffix)) => {
+            builder.with_initialization_script(user_agent_suffix_script(suffix))
+        }
+        (None, None) => builder,
+    };
 
     #[cfg(any(target_os = "windows", target_os = "macos"))]
     let webview = builder.build(&window).unwrap_or_else(|e| {
@@ -202,11 +494,54 @@ fn main() {
     let webview = {
         use tao::platform::unix::WindowExtUnix;
         use wry::WebViewBuilderExtUnix;
-        let vbox = window.default_vbox().unwrap_or_else(|| exit_fatal("Failed to get GTK vbox"));
+        let vbox = window
+            .default_vbox()
+            .unwrap_or_else(|| exit_fatal("Failed to get GTK vbox"));
         builder.build_gtk(vbox).unwrap_or_else(|e| {
             exit_fatal(&format!("Failed to build webview: {}", e));
         })
     };
+    drop(webview_phase);
+
+    permissions::attach(&webview, proxy.clone());
+    renderer::attach(&webview, proxy.clone());
+
+    if let Some(enabled) = settings.spellcheck
+        && let Err(e) = apply_spellcheck(&webview, enabled, &settings.spellcheck_languages)
+    {
+        log::warn!("{}", e);
+    }
 
-    run_event_loop(event_loop, webview, window, web_context, proxy, pending_ipc, ipc_queue);
+    if (settings.zoom - 1.0).abs() > f64::EPSILON
+        && let Err(e) = webview.zoom(settings.zoom)
+    {
+        log::warn!("Could not apply zoom {}: {}", settings.zoom, e);
+    }
+
+    let bridge_script = Box::new(move |window_id: &str| {
+        init_script(
+            session_token.as_str(),
+            signing_key.as_ref().map(SessionKey::to_hex).as_deref(),
+            devtools,
+            window_id,
+        )
+    });
+    let views = ViewContext::new(web_context, serve_app, bridge_script, ipc_handler);
+    let watchdog = Watchdog::new(settings.watchdog, proxy.clone());
+    run_event_loop(
+        event_loop,
+        webview,
+        window,
+        splash,
+        views,
+        proxy,
+        ipc_sender,
+        ipc_queue,
+        settings.ipc_limits,
+        timers,
+        services.tray,
+        popover,
+        start_mode != StartMode::Hidden,
+        watchdog,
+    );
 }
@@ -0,0 +1,177 @@
+//! System media controls: now-playing metadata from `SetMediaMetadata`, and media key / sound menu
+//! actions back to the UI as `media-control` host events (`{ action, offsetMs?, positionMs? }`).
+//!
+//! Built on `souvlaki`: an MPRIS player (`org.mpris.MediaPlayer2.<slug>`) on Linux, the System
+//! Media Transport Controls on Windows, and `MPNowPlayingInfoCenter` on macOS. The controls are
+//! created on the event loop thread (SMTC needs the window handle) on the first
+//! `SetMediaMetadata`, so apps that never play media do not appear in the sound menu.
+
+use std::cell::RefCell;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use souvlaki::{
+    MediaControlEvent, MediaControls, MediaMetadata, MediaPlayback, MediaPosition, PlatformConfig,
+    SeekDirection,
+};
+use tao::window::Window;
+
+use crate::config::PRODUCT_NAME;
+use crate::events;
+
+/// Seek step for media keys that do not say how far to seek.
+const SEEK_STEP_MS: i64 = 10_000;
+
+/// Playback state shown by the OS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackStatus {
+    #[default]
+    Playing,
+    Paused,
+    Stopped,
+}
+
+/// Now-playing metadata reported by the UI.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NowPlaying {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub artwork_url: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub position_ms: Option<u64>,
+    pub playback: PlaybackStatus,
+}
+
+thread_local! {
+    /// The OS controls, owned by the event loop thread once registered.
+    static CONTROLS: RefCell<Option<MediaControls>> = const { RefCell::new(None) };
+}
+
+/// Forwards a control action to the UI.
+fn emit_action(action: &str, extra: serde_json::Value) {
+    let mut payload = serde_json::json!({ "action": action });
+    if let (Some(map), serde_json::Value::Object(extra)) = (payload.as_object_mut(), extra) {
+        map.extend(extra);
+    }
+    events::emit("media-control", payload);
+}
+
+/// Well-known MPRIS name suffix: the app slug with characters invalid in bus names replaced.
+fn bus_suffix() -> String {
+    let slug: String = crate::paths::app_slug()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if slug.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("app{}", slug)
+    } else {
+        slug
+    }
+}
+
+/// Maps an OS control event to a `media-control` action and its extra fields. `None` for events
+/// the UI does not handle (volume, open URI, quit).
+#[must_use]
+pub fn action_for(event: MediaControlEvent) -> Option<(&'static str, serde_json::Value)> {
+    let none = serde_json::json!({});
+    let ms = |d: Duration| i64::try_from(d.as_millis()).unwrap_or(i64::MAX);
+    let signed = |direction: SeekDirection, ms: i64| match direction {
+        SeekDirection::Forward => ms,
+        SeekDirection::Backward => -ms,
+    };
+    Some(match event {
+        MediaControlEvent::Play => ("play", none),
+        MediaControlEvent::Pause => ("pause", none),
+        MediaControlEvent::Toggle => ("playPause", none),
+        MediaControlEvent::Next => ("next", none),
+        MediaControlEvent::Previous => ("previous", none),
+        MediaControlEvent::Stop => ("stop", none),
+        MediaControlEvent::Seek(direction) => (
+            "seek",
+            serde_json::json!({ "offsetMs": signed(direction, SEEK_STEP_MS) }),
+        ),
+        MediaControlEvent::SeekBy(direction, by) => (
+            "seek",
+            serde_json::json!({ "offsetMs": signed(direction, ms(by)) }),
+        ),
+        MediaControlEvent::SetPosition(MediaPosition(at)) => {
+            ("setPosition", serde_json::json!({ "positionMs": ms(at) }))
+        }
+        MediaControlEvent::Raise => ("raise", none),
+        MediaControlEvent::SetVolume(_)
+        | MediaControlEvent::OpenUri(_)
+        | MediaControlEvent::Quit => return None,
+    })
+}
+
+fn start(window: &Window) -> Result<MediaControls, String> {
+    #[cfg(target_os = "windows")]
+    let hwnd = {
+        use tao::platform::windows::WindowExtWindows;
+        Some(window.hwnd() as *mut std::ffi::c_void)
+    };
+    #[cfg(not(target_os = "windows"))]
+    let hwnd = {
+        let _ = window;
+        None
+    };
+    let dbus_name = bus_suffix();
+    let mut controls = MediaControls::new(PlatformConfig {
+        display_name: PRODUCT_NAME,
+        dbus_name: &dbus_name,
+        hwnd,
+    })
+    .map_err(|e| format!("Media controls unavailable: {:?}", e))?;
+    controls
+        .attach(|event| {
+            if let Some((action, extra)) = action_for(event) {
+                emit_action(action, extra);
+            }
+        })
+        .map_err(|e| format!("Could not register media controls: {:?}", e))?;
+    Ok(controls)
+}
+
+/// Pushes `now` to the OS controls.
+fn publish(controls: &mut MediaControls, now: &NowPlaying) -> Result<(), String> {
+    let progress = now
+        .position_ms
+        .map(|ms| MediaPosition(Duration::from_millis(ms)));
+    controls
+        .set_metadata(MediaMetadata {
+            title: now.title.as_deref(),
+            artist: now.artist.as_deref(),
+            album: now.album.as_deref(),
+            cover_url: now.artwork_url.as_deref(),
+            duration: now.duration_ms.map(Duration::from_millis),
+        })
+        .and_then(|()| {
+            controls.set_playback(match now.playback {
+                PlaybackStatus::Playing => MediaPlayback::Playing { progress },
+                PlaybackStatus::Paused => MediaPlayback::Paused { progress },
+                PlaybackStatus::Stopped => MediaPlayback::Stopped,
+            })
+        })
+        .map_err(|e| format!("Could not update media controls: {:?}", e))
+}
+
+/// Publishes `now` to the OS, registering the controls on first use. Runs on the
+/// event loop thread. Returns whether an OS integration is active.
+pub fn set_now_playing(window: &Window, now: &NowPlaying) -> Result<bool, String> {
+    CONTROLS.with_borrow_mut(|controls| {
+        let controls = match controls {
+            Some(controls) => controls,
+            slot => slot.insert(start(window)?),
+        };
+        publish(controls, now)?;
+        Ok(true)
+    })
+}
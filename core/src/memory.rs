@@ -0,0 +1,139 @@
+//! Memory pressure handling for long-running (tray-resident) instances.
+//!
+//! With `memoryLimitMb` set, a watcher thread sums the resident memory of this process and its
+//! child processes (the WebView's content processes) every `POLL`. On crossing the limit it drops
+//! the host's caches (IPC duplicate-response cache, IPC trace), emits `memory-pressure`
+//! (`{ rssBytes, limitBytes }`) so the UI can release its own, and with `memoryTrimWebView` asks
+//! the event loop to clear the WebView's in-memory caches (WebKitGTK only; no-op elsewhere).
+//! It fires again only after usage drops below `REARM_PERCENT` of the limit. Both keys are
+//! re-read every poll; `0` turns it off.
+
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::events;
+use crate::ipc;
+use crate::settings::Settings;
+
+/// Config key: resident memory (MiB, this process and its children) that counts as pressure;
+/// `0` disables the watcher's actions.
+pub const MEMORY_LIMIT_KEY: &str = "memoryLimitMb";
+
+/// Config key: also clear the WebView's caches under pressure.
+pub const MEMORY_TRIM_WEBVIEW_KEY: &str = "memoryTrimWebView";
+
+/// Poll interval.
+const POLL: Duration = Duration::from_secs(30);
+
+/// Usage must fall below this share of the limit before pressure is reported again.
+const REARM_PERCENT: u64 = 90;
+
+/// Resident bytes of `root` and all its descendants, from `(pid, parent, bytes)` rows.
+#[must_use]
+pub fn tree_memory(root: u32, processes: &[(u32, Option<u32>, u64)]) -> u64 {
+    let mut children: BTreeMap<u32, Vec<(u32, u64)>> = BTreeMap::new();
+    let mut total = 0;
+    for &(pid, parent, bytes) in processes {
+        if pid == root {
+            total += bytes;
+        } else if let Some(parent) = parent {
+            children.entry(parent).or_default().push((pid, bytes));
+        }
+    }
+    let mut stack = vec![root];
+    while let Some(pid) = stack.pop() {
+        for &(child, bytes) in children.get(&pid).into_iter().flatten() {
+            total += bytes;
+            stack.push(child);
+        }
+    }
+    total
+}
+
+/// Resident bytes of this process and its children.
+fn current_usage(sys: &mut System) -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    let rows: Vec<(u32, Option<u32>, u64)> = sys
+        .processes()
+        .iter()
+        .map(|(pid, p)| {
+            (
+                pid.as_u32(),
+                p.parent().map(|parent| parent.as_u32()),
+                p.memory(),
+            )
+        })
+        .collect();
+    Some(tree_memory(pid.as_u32(), &rows))
+}
+
+/// Drops host-side caches that are rebuilt on demand.
+pub fn trim_caches() {
+    ipc::trim_duplicate_cache();
+    ipc::clear_trace();
+}
+
+/// Starts the watcher thread.
+pub fn watch() {
+    thread::spawn(|| {
+        let mut sys = System::new();
+        let mut under_pressure = false;
+        loop {
+            thread::sleep(POLL);
+            let settings = Settings::load();
+            let limit = settings.memory_limit_mb.saturating_mul(1024 * 1024);
+            if limit == 0 {
+                under_pressure = false;
+                continue;
+            }
+            let Some(usage) = current_usage(&mut sys) else {
+                continue;
+            };
+            if !under_pressure && usage >= limit {
+                under_pressure = true;
+                log::warn!(
+                    "Memory pressure: {} MiB resident, limit {} MiB",
+                    usage / (1024 * 1024),
+                    settings.memory_limit_mb
+                );
+                trim_caches();
+                if settings.memory_trim_webview {
+                    events::request_memory_trim();
+                }
+                events::emit(
+                    "memory-pressure",
+                    serde_json::json!({ "rssBytes": usage, "limitBytes": limit }),
+                );
+            } else if under_pressure && usage < limit / 100 * REARM_PERCENT {
+                under_pressure = false;
+            }
+        }
+    });
+}
+
+#[cfg(target_os = "linux")]
+fn trim_platform(webview: &wry::WebView) {
+    use webkit2gtk::{WebContextExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    if let Some(context) = webview.webview().context() {
+        context.clear_cache();
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn trim_platform(_webview: &wry::WebView) {}
+
+/// Clears the WebView's resource caches (WebKitGTK; no-op elsewhere, since WKWebView has no
+/// public trim API).
+pub fn trim_webview(webview: &wry::WebView) {
+    trim_platform(webview);
+}
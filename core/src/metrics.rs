@@ -0,0 +1,137 @@
+//! Runtime metrics for `GetRuntimeMetrics` (diagnostics screens, performance reports).
+//!
+//! Counters are updated from the IPC path and the event loop; process RSS and CPU time come from
+//! `sysinfo` at snapshot time.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::ipc::{IpcStats, WorkerPool};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// IPC state owned by `main`, registered once at startup.
+struct Sources {
+    ipc: IpcStats,
+    workers: WorkerPool,
+}
+
+/// Event-loop wakeups counted in one-minute windows.
+struct WakeupWindow {
+    start: Option<Instant>,
+    current: u64,
+    last_minute: Option<u64>,
+}
+
+static SOURCES: OnceLock<Sources> = OnceLock::new();
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static DROPPED_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static BUSY: AtomicU64 = AtomicU64::new(0);
+static WAKEUPS: Mutex<WakeupWindow> = Mutex::new(WakeupWindow {
+    start: None,
+    current: 0,
+    last_minute: None,
+});
+
+/// Registers the IPC response queue counters and worker pool. Later calls are ignored.
+pub fn init(ipc: IpcStats, workers: WorkerPool) {
+    let _ = SOURCES.set(Sources { ipc, workers });
+}
+
+/// Counts one IPC response dropped by backpressure.
+pub fn record_dropped() {
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Total dropped IPC responses since startup.
+#[must_use]
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Counts one IPC request ignored by backpressure (`drop-new` policy).
+pub fn record_dropped_request() {
+    DROPPED_REQUESTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Total IPC requests ignored by backpressure since startup.
+#[must_use]
+pub fn dropped_requests() -> u64 {
+    DROPPED_REQUESTS.load(Ordering::Relaxed)
+}
+
+/// Counts one IPC request rejected with a `busy` response by backpressure.
+pub fn record_busy() {
+    BUSY.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Total IPC requests rejected as busy since startup.
+#[must_use]
+pub fn busy_rejections() -> u64 {
+    BUSY.load(Ordering::Relaxed)
+}
+
+/// Counts one event-loop wakeup (`NewEvents`).
+pub fn record_wakeup() {
+    let now = Instant::now();
+    let mut w = WAKEUPS.lock().unwrap_or_else(|e| e.into_inner());
+    let start = *w.start.get_or_insert(now);
+    let elapsed = now.duration_since(start);
+    if elapsed >= MINUTE {
+        // A gap longer than two windows means the previous minute had no wakeups.
+        w.last_minute = Some(if elapsed >= 2 * MINUTE { 0 } else { w.current });
+        w.current = 0;
+        w.start = Some(now);
+    }
+    w.current += 1;
+}
+
+/// Wakeups in the last complete minute, or extrapolated from the first (partial) minute.
+fn wakeups_per_minute() -> u64 {
+    let w = WAKEUPS.lock().unwrap_or_else(|e| e.into_inner());
+    match (w.last_minute, w.start) {
+        (Some(n), _) => n,
+        (None, Some(start)) => {
+            let secs = start.elapsed().as_secs_f64().max(1.0);
+            (w.current as f64 * 60.0 / secs) as u64
+        }
+        (None, None) => 0,
+    }
+}
+
+/// Current metrics as JSON.
+#[must_use]
+pub fn snapshot() -> serde_json::Value {
+    let (rss, cpu_ms) = sysinfo::get_current_pid()
+        .ok()
+        .and_then(|pid| {
+            let mut sys = System::new();
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[pid]),
+                false,
+                ProcessRefreshKind::nothing().with_memory().with_cpu(),
+            );
+            sys.process(pid)
+                .map(|p| (p.memory(), p.accumulated_cpu_time()))
+        })
+        .unzip();
+    let (pending, queue_depth) = SOURCES
+        .get()
+        .map_or((0, 0), |s| (s.ipc.pending(), s.ipc.queued()));
+    let (queued_interactive, queued_background) =
+        SOURCES.get().map_or((0, 0), |s| s.workers.queued());
+    serde_json::json!({
+        "rssBytes": rss,
+        "cpuTimeMs": cpu_ms,
+        "ipcQueueDepth": queue_depth,
+        "pendingIpc": pending,
+        "queuedWork": { "interactive": queued_interactive, "background": queued_background },
+        "droppedResponses": dropped(),
+        "droppedRequests": dropped_requests(),
+        "busyRejections": busy_rejections(),
+        "eventLoopWakeupsPerMinute": wakeups_per_minute(),
+    })
+}
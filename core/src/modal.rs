@@ -0,0 +1,226 @@
+//! Modal child windows (`OpenModal`): a dialog-style window owned by the main window that blocks
+//! interaction with it until the modal's UI posts a result or the user closes it.
+//!
+//! The modal loads a UI path (`settings.html`, served from the `app://` bundle) or a trusted-origin
+//! URL, with the IPC bridge under window id `modal`. Its page calls `window.modal.resolve(value)`
+//! (the `ResolveModal` command) once, which closes the modal and completes the `OpenModal` request
+//! with `{ result: value, dismissed: false }`. Closing the window instead gives
+//! `{ result: null, dismissed: true }`. One modal is open at a time.
+//!
+//! Blocking the parent: Windows disables the owner window (`set_enable`); Linux marks the modal
+//! GTK window modal and transient for the parent; macOS attaches it as a child window and ignores
+//! clicks on the parent. On every platform focus moves back to the modal if the parent gets it.
+
+use tao::dpi::{LogicalSize, PhysicalPosition};
+use tao::event_loop::EventLoopWindowTarget;
+use tao::window::{Window, WindowBuilder, WindowId};
+use wry::WebView;
+
+use crate::config::PRODUCT_NAME;
+use crate::event_loop::UserEvent;
+use crate::protocol::is_trusted_origin;
+use crate::window::{MODAL_WINDOW, ViewContext};
+
+/// Defines `window.modal.resolve(value)` in the modal page.
+const MODAL_SCRIPT: &str = r#"
+window.modal = Object.freeze({
+    resolve: function (value) {
+        return window.native.invoke({ name: 'ResolveModal', result: value === undefined ? null : value });
+    }
+});
+"#;
+
+/// Completes the `OpenModal` request on the event loop thread.
+pub type ModalDone = Box<dyn FnOnce(Result<serde_json::Value, String>)>;
+
+/// URL the modal loads: a trusted-origin URL as is, anything else as an `app://` UI path.
+pub fn modal_url(url: &str) -> Result<String, String> {
+    if is_trusted_origin(url) {
+        Ok(url.to_string())
+    } else {
+        crate::panel::panel_url(url)
+    }
+}
+
+/// Response for a finished modal: the posted value, or `null` with `dismissed` when closed.
+#[must_use]
+pub fn modal_response(result: Option<serde_json::Value>) -> serde_json::Value {
+    serde_json::json!({
+        "dismissed": result.is_none(),
+        "result": result.unwrap_or(serde_json::Value::Null),
+    })
+}
+
+struct Modal {
+    // Declared before `window` so the webview drops first.
+    webview: WebView,
+    window: Window,
+    done: ModalDone,
+}
+
+/// The open modal, if any. Owned by the event loop.
+#[derive(Default)]
+pub struct Modals {
+    open: Option<Modal>,
+}
+
+impl Modals {
+    /// Opens a modal over `parent`; `done` gets the response when it closes, or an error now.
+    #[allow(clippy::too_many_arguments)]
+    pub fn open(
+        &mut self,
+        target: &EventLoopWindowTarget<UserEvent>,
+        parent: &Window,
+        context: &mut ViewContext,
+        url: &str,
+        width: f64,
+        height: f64,
+        done: ModalDone,
+    ) {
+        if self.open.is_some() {
+            return done(Err("A modal is already open".to_string()));
+        }
+        match build(target, parent, context, url, width, height) {
+            Ok((window, webview)) => {
+                set_parent_blocked(parent, true);
+                window.set_focus();
+                self.open = Some(Modal {
+                    webview,
+                    window,
+                    done,
+                });
+            }
+            Err(e) => done(Err(e)),
+        }
+    }
+
+    /// True when `id` is the open modal's window.
+    #[must_use]
+    pub fn is_modal(&self, id: WindowId) -> bool {
+        self.open.as_ref().is_some_and(|m| m.window.id() == id)
+    }
+
+    /// WebView of the open modal.
+    #[must_use]
+    pub fn webview(&self) -> Option<&WebView> {
+        self.open.as_ref().map(|m| &m.webview)
+    }
+
+    /// Moves focus back to the open modal (the parent was focused).
+    pub fn refocus(&self) {
+        if let Some(modal) = &self.open {
+            modal.window.set_focus();
+        }
+    }
+
+    /// Closes the open modal, unblocks `parent`, and completes its request with `result` (`None`
+    /// when dismissed). False when no modal is open.
+    pub fn finish(&mut self, parent: &Window, result: Option<serde_json::Value>) -> bool {
+        let Some(Modal {
+            webview,
+            window,
+            done,
+        }) = self.open.take()
+        else {
+            return false;
+        };
+        // Re-enable the parent before the modal goes away so the OS activates it, not another app.
+        set_parent_blocked(parent, false);
+        drop(webview);
+        drop(window);
+        parent.set_focus();
+        done(Ok(modal_response(result)));
+        true
+    }
+}
+
+fn build(
+    target: &EventLoopWindowTarget<UserEvent>,
+    parent: &Window,
+    context: &mut ViewContext,
+    url: &str,
+    width: f64,
+    height: f64,
+) -> Result<(Window, WebView), String> {
+    if !(width.is_finite() && height.is_finite() && width > 0.0 && height > 0.0) {
+        return Err("Modal width and height must be positive".to_string());
+    }
+    let url = modal_url(url)?;
+
+    let size = LogicalSize::new(width, height);
+    let mut builder = WindowBuilder::new()
+        .with_title(PRODUCT_NAME)
+        .with_inner_size(size)
+        .with_minimizable(false)
+        .with_maximizable(false);
+    if let Ok(origin) = parent.outer_position() {
+        // Centered over the parent.
+        let outer = parent.outer_size();
+        let inner = size.to_physical::<i32>(parent.scale_factor());
+        builder = builder.with_position(PhysicalPosition::new(
+            origin.x + (outer.width as i32 - inner.width) / 2,
+            origin.y + (outer.height as i32 - inner.height) / 2,
+        ));
+    }
+    if let Some(icon) = crate::window::window_icon() {
+        builder = builder.with_window_icon(Some(icon));
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use tao::platform::windows::{WindowBuilderExtWindows, WindowExtWindows};
+        builder = builder.with_owner_window(parent.hwnd());
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use tao::platform::macos::{WindowBuilderExtMacOS, WindowExtMacOS};
+        builder = builder.with_parent_window(parent.ns_window());
+    }
+    #[cfg(target_os = "linux")]
+    {
+        use tao::platform::unix::{WindowBuilderExtUnix, WindowExtUnix};
+        builder = builder.with_transient_for(parent.gtk_window());
+    }
+    let window = builder
+        .build(target)
+        .map_err(|e| format!("Could not create modal window: {}", e))?;
+
+    let webview_builder = context
+        .builder(MODAL_WINDOW)
+        .with_url(&url)
+        .with_initialization_script(MODAL_SCRIPT)
+        .with_navigation_handler(|url| is_trusted_origin(&url));
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    let webview = webview_builder.build(&window);
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let webview = {
+        use tao::platform::unix::WindowExtUnix;
+        use webkit2gtk::glib::prelude::ObjectExt;
+        use wry::WebViewBuilderExtUnix;
+        window.gtk_window().set_property("modal", true);
+        let vbox = window
+            .default_vbox()
+            .ok_or("Could not get the modal's GTK container")?;
+        webview_builder.build_gtk(vbox)
+    };
+
+    let webview = webview.map_err(|e| format!("Could not create modal webview: {}", e))?;
+    Ok((window, webview))
+}
+
+/// Blocks or unblocks input to the modal's parent where the platform has no owned-modal concept.
+fn set_parent_blocked(parent: &Window, blocked: bool) {
+    #[cfg(target_os = "windows")]
+    {
+        use tao::platform::windows::WindowExtWindows;
+        parent.set_enable(!blocked);
+    }
+    #[cfg(target_os = "macos")]
+    if let Err(e) = parent.set_ignore_cursor_events(blocked) {
+        log::warn!("Could not block the parent window: {}", e);
+    }
+    // GTK's modal flag already blocks the parent.
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let _ = (parent, blocked);
+}
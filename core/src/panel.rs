@@ -0,0 +1,175 @@
+//! Panels: extra child webviews inside the main window (split view, docs or preview panes).
+//!
+//! A panel loads a page from the same `app://` UI bundle and sits at logical bounds inside the
+//! window, above the main WebView. Layout belongs to the UI: it leaves room for the panel and
+//! calls `SetPanelBounds` when its own layout changes (panels do not follow window resizes).
+//! Each panel gets the IPC bridge with its name as window id (responses to its requests go back
+//! to it), and navigation is limited to trusted origins like the main WebView.
+//!
+//! `Panels` is owned by the event loop, which runs `OpenPanel` / `SetPanelBounds` /
+//! `ClosePanel` as window commands. Child webviews use wry's `build_as_child`, which on Linux
+//! needs X11; under Wayland `OpenPanel` reports an error instead.
+
+use std::collections::BTreeMap;
+
+use tao::window::Window;
+use wry::dpi::{LogicalPosition, LogicalSize};
+use wry::{Rect, WebView};
+
+use crate::config::MAX_PANELS;
+use crate::protocol::is_trusted_origin;
+use crate::window::{MAIN_WINDOW, MODAL_WINDOW, ViewContext};
+
+/// Panel placement in logical pixels, relative to the window's content area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanelBounds {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl PanelBounds {
+    /// Rejects non-finite values and empty sizes.
+    pub fn validate(self) -> Result<Self, String> {
+        let Self {
+            x,
+            y,
+            width,
+            height,
+        } = self;
+        if ![x, y, width, height].iter().all(|v| v.is_finite()) {
+            return Err("Panel bounds must be finite numbers".to_string());
+        }
+        if width <= 0.0 || height <= 0.0 {
+            return Err("Panel width and height must be positive".to_string());
+        }
+        Ok(self)
+    }
+
+    fn rect(self) -> Rect {
+        Rect {
+            position: LogicalPosition::new(self.x, self.y).into(),
+            size: LogicalSize::new(self.width, self.height).into(),
+        }
+    }
+}
+
+/// Checks a panel name (also its window id): 1–64 ASCII letters, digits, `-` or `_`, and not
+/// another window's id.
+pub fn validate_name(name: &str) -> Result<&str, String> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && name != MAIN_WINDOW
+        && name != MODAL_WINDOW;
+    if valid {
+        Ok(name)
+    } else {
+        Err(format!("Invalid panel name: {}", name))
+    }
+}
+
+/// `app://` URL for a UI path such as `docs/index.html` or `/preview.html?file=a`. Rejects other
+/// schemes, protocol-relative and traversing paths.
+pub fn panel_url(path: &str) -> Result<String, String> {
+    let path = path.trim();
+    let route = path.split(['?', '#']).next().unwrap_or_default();
+    if path.starts_with("//") || route.contains(':') || route.contains('\\') || route.contains("..")
+    {
+        return Err(format!("Invalid panel path: {}", path));
+    }
+    Ok(format!("app://localhost/{}", path.trim_start_matches('/')))
+}
+
+/// Open panels by name.
+#[derive(Default)]
+pub struct Panels {
+    views: BTreeMap<String, WebView>,
+}
+
+impl Panels {
+    /// Opens panel `name` at `bounds` showing `path`; an open panel with that name navigates and
+    /// moves instead.
+    pub fn open(
+        &mut self,
+        window: &Window,
+        context: &mut ViewContext,
+        name: &str,
+        path: &str,
+        bounds: PanelBounds,
+    ) -> Result<(), String> {
+        let name = validate_name(name)?;
+        let url = panel_url(path)?;
+        let bounds = bounds.validate()?;
+        if let Some(view) = self.views.get(name) {
+            view.load_url(&url)
+                .map_err(|e| format!("Could not load panel: {}", e))?;
+            return view
+                .set_bounds(bounds.rect())
+                .map_err(|e| format!("Could not move panel: {}", e));
+        }
+        if self.views.len() >= MAX_PANELS {
+            return Err(format!("At most {} panels can be open", MAX_PANELS));
+        }
+        check_child_support(window)?;
+        let view = context
+            .builder(name)
+            .with_url(&url)
+            .with_bounds(bounds.rect())
+            .with_navigation_handler(|url| is_trusted_origin(&url))
+            .build_as_child(window)
+            .map_err(|e| format!("Could not create panel: {}", e))?;
+        self.views.insert(name.to_string(), view);
+        Ok(())
+    }
+
+    /// Moves and resizes an open panel.
+    pub fn set_bounds(&self, name: &str, bounds: PanelBounds) -> Result<(), String> {
+        let view = self
+            .views
+            .get(name)
+            .ok_or_else(|| format!("No panel named {}", name))?;
+        view.set_bounds(bounds.validate()?.rect())
+            .map_err(|e| format!("Could not move panel: {}", e))
+    }
+
+    /// Closes panel `name`. False when it was not open.
+    pub fn close(&mut self, name: &str) -> bool {
+        self.views.remove(name).is_some()
+    }
+
+    /// WebView of panel `name`.
+    #[must_use]
+    pub fn webview(&self, name: &str) -> Option<&WebView> {
+        self.views.get(name)
+    }
+
+    /// WebViews of all open panels.
+    pub fn webviews(&self) -> impl Iterator<Item = &WebView> {
+        self.views.values()
+    }
+
+    /// Names of the open panels.
+    #[must_use]
+    pub fn names(&self) -> Vec<String> {
+        self.views.keys().cloned().collect()
+    }
+}
+
+/// Child webviews on Linux are X11 windows; wry panics on other handles, so check first.
+#[cfg(target_os = "linux")]
+fn check_child_support(window: &Window) -> Result<(), String> {
+    use wry::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+    match window.window_handle().map(|h| h.as_raw()) {
+        Ok(RawWindowHandle::Xlib(_)) => Ok(()),
+        _ => Err("Panels need X11 on Linux; they are not available under Wayland".to_string()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn check_child_support(_window: &Window) -> Result<(), String> {
+    Ok(())
+}
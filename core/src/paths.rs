@@ -4,23 +4,52 @@
 //! runtime does not depend on platform-specific env vars or paths.
 //! User data dir is computed once at first use to avoid repeated env and I/O at startup.
 
-use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::PRODUCT_NAME;
 
 static USER_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
 
+/// Manifest of temp files created by `temp_file` (path → creation time, ms since epoch).
+const TEMP_MANIFEST: &str = "temp-files.json";
+
+/// Serializes manifest read-modify-write across threads.
+static TEMP_MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Lowercase, dash-separated product name for Unix-style names (`Desktop Runtime` → `desktop-runtime`).
+#[must_use]
+pub fn app_slug() -> String {
+    let mut slug = String::new();
+    for c in PRODUCT_NAME.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "app".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
 fn compute_user_data_dir() -> PathBuf {
     #[cfg(target_os = "windows")]
-    let preferred = std::env::var("LOCALAPPDATA").ok().map(|local| {
-        PathBuf::from(local).join("Desktop Runtime").join("WebView2")
-    });
+    let preferred = std::env::var("LOCALAPPDATA")
+        .ok()
+        .map(|local| PathBuf::from(local).join(PRODUCT_NAME).join("WebView2"));
 
     #[cfg(target_os = "macos")]
     let preferred = std::env::var("HOME").ok().map(|home| {
         PathBuf::from(home)
             .join("Library")
             .join("Application Support")
-            .join("Desktop Runtime")
+            .join(PRODUCT_NAME)
     });
 
     #[cfg(target_os = "linux")]
@@ -32,12 +61,12 @@ fn compute_user_data_dir() -> PathBuf {
                 .ok()
                 .map(|h| PathBuf::from(h).join(".local").join("share"))
         })
-        .map(|p| p.join("desktop-runtime"));
+        .map(|p| p.join(app_slug()));
 
     preferred
         .and_then(|path| std::fs::create_dir_all(&path).ok().map(|()| path))
         .unwrap_or_else(|| {
-            let fallback = std::env::temp_dir().join("Desktop-Runtime");
+            let fallback = std::env::temp_dir().join(PRODUCT_NAME.replace(' ', "-"));
             if std::fs::create_dir_all(&fallback).is_err() {
                 log::warn!("Could not create user data dir; using temp_dir as-is");
             }
@@ -50,9 +79,19 @@ fn compute_user_data_dir() -> PathBuf {
 /// Prefers platform user dirs; falls back to temp so we never use the install path.
 #[must_use]
 pub fn user_data_dir() -> PathBuf {
-    USER_DATA_DIR
-        .get_or_init(compute_user_data_dir)
-        .clone()
+    USER_DATA_DIR.get_or_init(compute_user_data_dir).clone()
+}
+
+/// Returns the support bundle directory (`<user data>/support`), creating it if needed.
+/// Diagnostics meant to be attached to bug reports (profiles, logs) are written here.
+#[must_use]
+#[cfg_attr(not(all(feature = "profiling", unix)), allow(dead_code))]
+pub fn support_dir() -> PathBuf {
+    let dir = user_data_dir().join("support");
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::warn!("Could not create support dir {}: {}", dir.display(), e);
+    }
+    dir
 }
 
 /// Returns the app config directory (parent of user_data_dir on Windows, same on macOS/Linux).
@@ -60,7 +99,91 @@ pub fn user_data_dir() -> PathBuf {
 #[allow(dead_code)]
 pub fn app_config_dir() -> PathBuf {
     let base = user_data_dir();
-    base.parent()
-        .map(PathBuf::from)
-        .unwrap_or_else(|| base)
+    base.parent().map(PathBuf::from).unwrap_or_else(|| base)
+}
+
+fn temp_manifest_path() -> PathBuf {
+    user_data_dir().join(TEMP_MANIFEST)
+}
+
+fn load_temp_manifest() -> BTreeMap<PathBuf, u64> {
+    std::fs::read_to_string(temp_manifest_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_temp_manifest(manifest: &BTreeMap<PathBuf, u64>) {
+    match serde_json::to_string_pretty(manifest) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(temp_manifest_path(), json) {
+                log::warn!("Could not write temp file manifest: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Could not serialize temp file manifest: {}", e),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| u64::try_from(d.as_millis()).unwrap_or(u64::MAX))
+        .unwrap_or(0)
+}
+
+/// The `.app` bundle containing `exe` (`/Applications/Foo.app/Contents/MacOS/foo` →
+/// `/Applications/Foo.app`), or `None` when the executable does not run from a bundle.
+#[must_use]
+pub fn app_bundle_path(exe: &Path) -> Option<PathBuf> {
+    exe.ancestors()
+        .skip(1)
+        .find(|p| p.extension().is_some_and(|e| e == "app"))
+        .filter(|bundle| exe.starts_with(bundle.join("Contents")))
+        .map(Path::to_path_buf)
+}
+
+/// Creates an empty, uniquely named file `<prefix>-<uuid>.<ext>` in `<temp>/<app slug>/`
+/// and records it in the temp manifest so `cleanup_temp_files` can delete it later.
+pub fn temp_file(prefix: &str, ext: &str) -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(app_slug());
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}-{}.{}", prefix, uuid::Uuid::new_v4(), ext));
+    std::fs::File::create(&path)?;
+    let _guard = TEMP_MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = load_temp_manifest();
+    manifest.insert(path.clone(), now_ms());
+    save_temp_manifest(&manifest);
+    Ok(path)
+}
+
+/// Deletes tracked temp files older than `max_age` and drops entries whose file is gone.
+/// Returns the number of files deleted.
+pub fn cleanup_temp_files(max_age: Duration) -> usize {
+    let _guard = TEMP_MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifest = load_temp_manifest();
+    let cutoff = now_ms().saturating_sub(u64::try_from(max_age.as_millis()).unwrap_or(u64::MAX));
+    let before = manifest.len();
+    let mut deleted = 0;
+    manifest.retain(|path, created| {
+        if !path.exists() {
+            return false;
+        }
+        if *created > cutoff {
+            return true;
+        }
+        match std::fs::remove_file(path) {
+            Ok(()) => {
+                deleted += 1;
+                false
+            }
+            Err(e) => {
+                log::warn!("Could not delete temp file {}: {}", path.display(), e);
+                true
+            }
+        }
+    });
+    if manifest.len() != before {
+        save_temp_manifest(&manifest);
+    }
+    deleted
 }
@@ -0,0 +1,213 @@
+//! Camera and microphone permission for `getUserMedia`.
+//!
+//! On Linux the WebKitGTK `permission-request` signal is handled here: a stored decision for the
+//! requesting origin answers immediately, otherwise a native consent dialog runs on its own thread
+//! and the answer comes back to the event loop (`UserEvent::MediaPermission`), which resolves the
+//! held request. Decisions are persisted per origin and device under `mediaPermissions` in
+//! `config.json`, a host-only key: the UI can read and reset them (`DeleteConfig`) but not grant
+//! itself access. wry exposes no permission hook for
+//! WebView2 or WKWebView, so those keep their built-in prompts and nothing is stored there.
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n;
+use crate::storage;
+
+/// Config key: `{ "<origin>": { "camera": bool, "microphone": bool } }`.
+pub const MEDIA_PERMISSIONS_KEY: &str = "mediaPermissions";
+
+/// Capture device kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Device {
+    Camera,
+    Microphone,
+}
+
+impl Device {
+    fn key(self) -> &'static str {
+        match self {
+            Self::Camera => "camera",
+            Self::Microphone => "microphone",
+        }
+    }
+}
+
+/// What to do with a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    Deny,
+    /// No stored decision for at least one device.
+    Ask,
+}
+
+/// Origin (`scheme://host[:port]`) of `url`.
+#[must_use]
+pub fn origin_of(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+            format!("{}://{}", scheme, host)
+        }
+        None => url.to_string(),
+    }
+}
+
+fn stored(origin: &str, device: Device) -> Option<bool> {
+    storage::get_value(MEDIA_PERMISSIONS_KEY)?
+        .get(origin)?
+        .get(device.key())?
+        .as_bool()
+}
+
+/// Stored decision for `devices`: any denial denies, all granted allows.
+#[must_use]
+pub fn decide(origin: &str, devices: &[Device]) -> Decision {
+    let answers: Vec<Option<bool>> = devices.iter().map(|d| stored(origin, *d)).collect();
+    if answers.contains(&Some(false)) {
+        Decision::Deny
+    } else if !answers.is_empty() && answers.iter().all(|a| *a == Some(true)) {
+        Decision::Allow
+    } else {
+        Decision::Ask
+    }
+}
+
+/// Persists `allow` for each device of `origin`. Runs under the config lock, so answers to
+/// overlapping prompts are all kept.
+pub fn remember(origin: &str, devices: &[Device], allow: bool) {
+    storage::update_value(MEDIA_PERMISSIONS_KEY, |current| {
+        let mut all = current
+            .filter(serde_json::Value::is_object)
+            .unwrap_or_else(|| serde_json::json!({}));
+        let entry = &mut all[origin];
+        if !entry.is_object() {
+            *entry = serde_json::json!({});
+        }
+        for device in devices {
+            entry[device.key()] = serde_json::json!(allow);
+        }
+        all
+    });
+}
+
+/// `GetMediaPermissions` response: `{ permissions: { "<origin>": { camera?, microphone? } } }`.
+#[must_use]
+pub fn list() -> serde_json::Value {
+    let all = storage::get_value(MEDIA_PERMISSIONS_KEY)
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    serde_json::json!({ "permissions": all })
+}
+
+/// Shows the consent dialog (blocking) and stores the answer.
+pub fn ask(origin: &str, devices: &[Device]) -> bool {
+    let key = match devices {
+        [Device::Camera] => "dialog.mediaPermission.camera",
+        [Device::Microphone] => "dialog.mediaPermission.microphone",
+        _ => "dialog.mediaPermission.cameraAndMicrophone",
+    };
+    let answer = rfd::MessageDialog::new()
+        .set_title(i18n::t("dialog.mediaPermission.title"))
+        .set_description(i18n::t(key))
+        .set_level(rfd::MessageLevel::Warning)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show();
+    let allow = answer == rfd::MessageDialogResult::Yes;
+    remember(origin, devices, allow);
+    allow
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+
+    use webkit2gtk::glib::object::Cast;
+    use webkit2gtk::{PermissionRequest, PermissionRequestExt, UserMediaPermissionRequest};
+    use webkit2gtk::{UserMediaPermissionRequestExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    use super::{Decision, Device, ask, decide, origin_of};
+    use crate::event_loop::UserEvent;
+
+    thread_local! {
+        /// Requests waiting for the consent dialog, by id. Only touched on the GTK thread.
+        static HELD: RefCell<(u64, BTreeMap<u64, PermissionRequest>)> = const { RefCell::new((0, BTreeMap::new())) };
+    }
+
+    pub fn attach(webview: &wry::WebView, proxy: tao::event_loop::EventLoopProxy<UserEvent>) {
+        webview
+            .webview()
+            .connect_permission_request(move |view, request| {
+                let Some(media) = request.downcast_ref::<UserMediaPermissionRequest>() else {
+                    return false;
+                };
+                let mut devices = Vec::new();
+                if media.is_for_video_device() {
+                    devices.push(Device::Camera);
+                }
+                if media.is_for_audio_device() {
+                    devices.push(Device::Microphone);
+                }
+                let origin = origin_of(view.uri().as_deref().unwrap_or(""));
+                match decide(&origin, &devices) {
+                    Decision::Allow => request.allow(),
+                    Decision::Deny => request.deny(),
+                    Decision::Ask => {
+                        let id = HELD.with(|held| {
+                            let mut held = held.borrow_mut();
+                            held.0 += 1;
+                            let id = held.0;
+                            held.1.insert(id, request.clone());
+                            id
+                        });
+                        let proxy = proxy.clone();
+                        let spawned = std::thread::Builder::new()
+                            .name("media-permission".to_string())
+                            .spawn(move || {
+                                let allow = ask(&origin, &devices);
+                                let _ = proxy
+                                    .send_event(UserEvent::MediaPermission { request: id, allow });
+                            });
+                        if spawned.is_err() {
+                            resolve(id, false);
+                        }
+                    }
+                }
+                true
+            });
+    }
+
+    pub fn resolve(id: u64, allow: bool) {
+        let request = HELD.with(|held| held.borrow_mut().1.remove(&id));
+        match request {
+            Some(r) if allow => r.allow(),
+            Some(r) => r.deny(),
+            None => {}
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use crate::event_loop::UserEvent;
+
+    pub fn attach(_webview: &wry::WebView, _proxy: tao::event_loop::EventLoopProxy<UserEvent>) {}
+
+    pub fn resolve(_id: u64, _allow: bool) {}
+}
+
+/// Installs the permission handler on `webview` (Linux; no-op elsewhere).
+pub fn attach(
+    webview: &wry::WebView,
+    proxy: tao::event_loop::EventLoopProxy<crate::event_loop::UserEvent>,
+) {
+    platform::attach(webview, proxy);
+}
+
+/// Allows or denies held request `id` once the consent dialog was answered.
+pub fn resolve(id: u64, allow: bool) {
+    platform::resolve(id, allow);
+}
@@ -0,0 +1,114 @@
+//! Tray popover mode (`"trayPopover": true` in `config.json`): the main window becomes a small
+//! frameless panel that opens next to the tray icon and hides when it loses focus, the usual
+//! shape of a menu-bar utility.
+//!
+//! The window starts hidden, without decorations or a taskbar entry, and stays above others. A
+//! left click on the tray icon toggles it, placed against the icon: above it when the icon is in
+//! the lower half of its display (a bottom taskbar), below it otherwise (the macOS menu bar).
+//! Linux tray icons report no clicks, so there the tray menu's "Show" item opens it at the
+//! cursor. Closing it hides it. The mode needs the tray, so minimal mode turns it off. Read once
+//! at startup.
+
+use std::time::{Duration, Instant};
+
+use tao::dpi::PhysicalPosition;
+use tao::window::Window;
+
+use crate::window::PixelRect;
+
+/// Config key: run the main window as a tray popover.
+pub const TRAY_POPOVER_KEY: &str = "trayPopover";
+
+/// Space between the tray icon and the popover (physical pixels).
+const GAP: i32 = 4;
+
+/// A tray click this soon after the popover hid on focus loss is the click that took the focus,
+/// so it closes the popover instead of reopening it.
+const REOPEN_GUARD: Duration = Duration::from_millis(300);
+
+/// Top-left corner for a popover of `size` next to `anchor` (the tray icon's bounds) on
+/// `monitor`: centered on the icon, above it in the lower half of the display and below it
+/// otherwise, kept on the display.
+#[must_use]
+pub fn popover_position(anchor: PixelRect, size: (u32, u32), monitor: PixelRect) -> (i32, i32) {
+    let (width, height) = (size.0 as i32, size.1 as i32);
+    let center_x = anchor.x + anchor.width as i32 / 2;
+    let below_middle = anchor.y + anchor.height as i32 / 2 > monitor.y + monitor.height as i32 / 2;
+    let y = if below_middle {
+        anchor.y - height - GAP
+    } else {
+        anchor.y + anchor.height as i32 + GAP
+    };
+    let right = (monitor.x + monitor.width as i32 - width).max(monitor.x);
+    let bottom = (monitor.y + monitor.height as i32 - height).max(monitor.y);
+    (
+        (center_x - width / 2).clamp(monitor.x, right),
+        y.clamp(monitor.y, bottom),
+    )
+}
+
+/// Popover state, owned by the event loop.
+#[derive(Default)]
+pub struct Popover {
+    hidden_at: Option<Instant>,
+}
+
+impl Popover {
+    /// Tray click: hides the popover if shown, otherwise shows it at `anchor`.
+    pub fn toggle(&mut self, window: &Window, anchor: PixelRect) {
+        let just_hidden = self.hidden_at.is_some_and(|at| at.elapsed() < REOPEN_GUARD);
+        if window.is_visible() {
+            self.hide(window);
+        } else if !just_hidden {
+            self.show(window, anchor);
+        }
+    }
+
+    /// Shows and focuses the popover next to `anchor`.
+    pub fn show(&mut self, window: &Window, anchor: PixelRect) {
+        let center = (
+            anchor.x + anchor.width as i32 / 2,
+            anchor.y + anchor.height as i32 / 2,
+        );
+        let monitor = window
+            .available_monitors()
+            .map(|m| PixelRect::from(&m))
+            .find(|m| m.contains(center.0, center.1))
+            .or_else(|| window.primary_monitor().map(|m| PixelRect::from(&m)));
+        if let Some(monitor) = monitor {
+            let size = window.outer_size();
+            let (x, y) = popover_position(anchor, (size.width, size.height), monitor);
+            window.set_outer_position(PhysicalPosition::new(x, y));
+        }
+        self.hidden_at = None;
+        window.set_visible(true);
+        window.set_focus();
+    }
+
+    /// Shows the popover at the cursor (no tray click position on this platform).
+    pub fn show_at_cursor(&mut self, window: &Window) {
+        let anchor = window.cursor_position().map_or(
+            PixelRect {
+                x: 0,
+                y: 0,
+                width: 1,
+                height: 1,
+            },
+            |pos| PixelRect {
+                x: pos.x as i32,
+                y: pos.y as i32,
+                width: 1,
+                height: 1,
+            },
+        );
+        self.show(window, anchor);
+    }
+
+    /// Hides the popover (focus lost, closed, or toggled).
+    pub fn hide(&mut self, window: &Window) {
+        if window.is_visible() {
+            window.set_visible(false);
+            self.hidden_at = Some(Instant::now());
+        }
+    }
+}
@@ -0,0 +1,218 @@
+//! Battery/power status and power host events.
+//!
+//! `status` reads the battery from sysfs (Linux), `pmset` (macOS), or `Win32_Battery` via
+//! PowerShell (Windows). `watch` starts a background thread that emits:
+//! - `power-suspend` / `power-resume`: from logind's `PrepareForSleep` signal on Linux. Elsewhere
+//!   (or without logind) only `power-resume` is emitted, detected as a wall-clock gap.
+//! - `low-battery` `{ percentage }`: once per discharge when on battery and at or below the
+//!   `lowBatteryPercent` config key (default 15).
+
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+
+use crate::events;
+use crate::settings::Settings;
+
+/// Config key: battery percentage at or below which `low-battery` is emitted.
+pub const LOW_BATTERY_CONFIG_KEY: &str = "lowBatteryPercent";
+
+/// Watcher tick; a wall-clock jump beyond `tick + RESUME_GAP` is treated as a resume.
+const TICK: Duration = Duration::from_secs(10);
+const RESUME_GAP: Duration = Duration::from_secs(30);
+
+/// Battery is polled every this many ticks.
+const BATTERY_POLL_TICKS: u32 = 6;
+
+/// Power source and battery state. Fields are `None` when unknown (e.g. desktop without battery).
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub on_battery: Option<bool>,
+    pub percentage: Option<u8>,
+    pub charging: Option<bool>,
+}
+
+/// Status from a sysfs `power_supply` class directory (`/sys/class/power_supply` on Linux).
+#[must_use]
+pub fn parse_power_supply(root: &std::path::Path) -> PowerStatus {
+    let mut status = PowerStatus::default();
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return status;
+    };
+    let read = |dir: &std::path::Path, name: &str| {
+        std::fs::read_to_string(dir.join(name))
+            .map(|s| s.trim().to_string())
+            .ok()
+    };
+    let mut dirs: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+    dirs.sort();
+    for dir in dirs {
+        match read(&dir, "type").as_deref() {
+            Some("Mains") => {
+                let online = read(&dir, "online").as_deref() == Some("1");
+                status.on_battery = Some(!online && status.on_battery != Some(false));
+            }
+            Some("Battery") if status.percentage.is_none() => {
+                status.percentage = read(&dir, "capacity").and_then(|c| c.parse().ok());
+                status.charging = read(&dir, "status").map(|s| s == "Charging");
+                if status.on_battery.is_none() {
+                    status.on_battery = read(&dir, "status").map(|s| s == "Discharging");
+                }
+            }
+            _ => {}
+        }
+    }
+    status
+}
+
+/// Status from `pmset -g batt` output (macOS).
+#[must_use]
+pub fn parse_pmset(text: &str) -> PowerStatus {
+    let percentage = text
+        .split_whitespace()
+        .find_map(|w| w.strip_suffix("%;")?.parse().ok());
+    PowerStatus {
+        on_battery: Some(text.contains("'Battery Power'")),
+        percentage,
+        charging: percentage.map(|_| text.contains("; charging;")),
+    }
+}
+
+/// Status from the `Win32_Battery` JSON the Windows query prints (empty when there is no battery).
+#[must_use]
+pub fn parse_win32_battery(stdout: &[u8]) -> PowerStatus {
+    let Ok(v) = serde_json::from_slice::<serde_json::Value>(stdout) else {
+        // No battery: desktop on mains power.
+        return PowerStatus {
+            on_battery: Some(false),
+            ..PowerStatus::default()
+        };
+    };
+    // BatteryStatus: 1 = discharging, 2 = on AC, 6-9 = charging.
+    let code = v["BatteryStatus"].as_u64();
+    PowerStatus {
+        on_battery: code.map(|c| c == 1),
+        percentage: v["EstimatedChargeRemaining"]
+            .as_u64()
+            .map(|p| p.min(100) as u8),
+        charging: code.map(|c| (6..=9).contains(&c)),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read_status() -> PowerStatus {
+    parse_power_supply(std::path::Path::new("/sys/class/power_supply"))
+}
+
+#[cfg(target_os = "macos")]
+fn read_status() -> PowerStatus {
+    let Ok(out) = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+    else {
+        return PowerStatus::default();
+    };
+    parse_pmset(&String::from_utf8_lossy(&out.stdout))
+}
+
+#[cfg(target_os = "windows")]
+fn read_status() -> PowerStatus {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let script = "Get-CimInstance Win32_Battery | Select-Object -First 1 \
+                  EstimatedChargeRemaining,BatteryStatus | ConvertTo-Json -Compress";
+    let Ok(out) = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+    else {
+        return PowerStatus::default();
+    };
+    parse_win32_battery(&out.stdout)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_status() -> PowerStatus {
+    PowerStatus::default()
+}
+
+/// Current power status. May spawn a helper process (macOS, Windows); call off the UI thread.
+#[must_use]
+pub fn status() -> PowerStatus {
+    read_status()
+}
+
+/// Subscribes to logind's `PrepareForSleep`. Returns false if the system bus is unavailable.
+#[cfg(target_os = "linux")]
+fn watch_logind() -> bool {
+    use dbus::blocking::Connection;
+    use dbus::message::MatchRule;
+
+    let Ok(conn) = Connection::new_system() else {
+        return false;
+    };
+    let rule = MatchRule::new_signal("org.freedesktop.login1.Manager", "PrepareForSleep");
+    let subscribed = conn.add_match(rule, |(sleeping,): (bool,), _, _| {
+        let name = if sleeping {
+            "power-suspend"
+        } else {
+            "power-resume"
+        };
+        events::emit(name, serde_json::json!({}));
+        true
+    });
+    if subscribed.is_err() {
+        return false;
+    }
+    thread::spawn(move || {
+        while conn.process(Duration::from_secs(3600)).is_ok() {}
+        log::warn!("logind connection closed; power-suspend events stopped");
+    });
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+fn watch_logind() -> bool {
+    false
+}
+
+/// Starts power event delivery (see module docs).
+pub fn watch() {
+    let detect_resume = !watch_logind();
+    thread::spawn(move || {
+        let mut last_wall = SystemTime::now();
+        let mut warned = false;
+        let mut ticks = 0u32;
+        loop {
+            thread::sleep(TICK);
+            let now = SystemTime::now();
+            if detect_resume
+                && let Ok(gap) = now.duration_since(last_wall)
+                && gap > TICK + RESUME_GAP
+            {
+                events::emit(
+                    "power-resume",
+                    serde_json::json!({ "sleptSecs": (gap - TICK).as_secs() }),
+                );
+            }
+            last_wall = now;
+
+            ticks = ticks.wrapping_add(1);
+            if !ticks.is_multiple_of(BATTERY_POLL_TICKS) {
+                continue;
+            }
+            let s = status();
+            let threshold = Settings::load().low_battery_percent;
+            let low = s.on_battery == Some(true) && s.percentage.is_some_and(|p| p <= threshold);
+            if low && !warned {
+                events::emit(
+                    "low-battery",
+                    serde_json::json!({ "percentage": s.percentage }),
+                );
+            }
+            warned = low;
+        }
+    });
+}
@@ -0,0 +1,118 @@
+//! Efficiency mode: lower process priority while the app works in the background.
+//!
+//! `SetEfficiencyMode { on }` switches it by hand; with `"backgroundEfficiency": true` in config
+//! the event loop turns it on when the main window is hidden (to the tray, or a closed popover)
+//! and off when it is shown again. macOS applies the background task policy (`taskpolicy -b`:
+//! background QoS clamp on CPU, I/O, and timers). Windows sets the `Idle` priority class rather
+//! than EcoQoS. Linux is unsupported: an unprivileged process can raise its nice value but never lower it
+//! back. The switch spawns a short-lived helper process, so callers run it off the event loop.
+
+#[cfg(any(target_os = "macos", windows))]
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Config key: turn efficiency mode on while the main window is hidden.
+pub const BACKGROUND_EFFICIENCY_KEY: &str = "backgroundEfficiency";
+
+/// Whether efficiency mode is on.
+static ON: AtomicBool = AtomicBool::new(false);
+
+/// Serializes switches so helper processes apply them in order.
+static SWITCH: Mutex<()> = Mutex::new(());
+
+#[cfg(target_os = "macos")]
+fn apply(on: bool) -> Result<(), String> {
+    let status = Command::new("/usr/sbin/taskpolicy")
+        .args([
+            if on { "-b" } else { "-B" },
+            "-p",
+            &std::process::id().to_string(),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Could not run taskpolicy: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("taskpolicy failed: {}", status))
+    }
+}
+
+#[cfg(windows)]
+fn apply(on: bool) -> Result<(), String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let script = format!(
+        "(Get-Process -Id {}).PriorityClass = '{}'",
+        std::process::id(),
+        if on { "Idle" } else { "Normal" }
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|e| format!("Could not start powershell: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Setting the priority class failed: {}", status))
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn apply(_on: bool) -> Result<(), String> {
+    Err("Efficiency mode is not supported on this platform".to_string())
+}
+
+/// Whether efficiency mode is on.
+#[must_use]
+pub fn is_on() -> bool {
+    ON.load(Ordering::Relaxed)
+}
+
+/// Turns efficiency mode on or off. Blocks while the helper process runs.
+pub fn set(on: bool) -> Result<serde_json::Value, String> {
+    let _guard = SWITCH.lock().unwrap_or_else(|e| e.into_inner());
+    if is_on() != on {
+        apply(on)?;
+        ON.store(on, Ordering::Relaxed);
+    }
+    Ok(serde_json::json!({ "on": on }))
+}
+
+/// Tracks the main window's visibility for `backgroundEfficiency`.
+#[derive(Debug)]
+pub struct VisibilityFollower {
+    visible: bool,
+}
+
+impl VisibilityFollower {
+    #[must_use]
+    pub fn new(visible: bool) -> Self {
+        Self { visible }
+    }
+
+    /// Records the window's current visibility. On a change, and only if `enabled()` (read
+    /// then, not on every call), returns the efficiency state to switch to: on while hidden.
+    pub fn observe(&mut self, visible: bool, enabled: impl FnOnce() -> bool) -> Option<bool> {
+        if visible == std::mem::replace(&mut self.visible, visible) || !enabled() {
+            return None;
+        }
+        Some(!visible)
+    }
+}
+
+/// Turns efficiency mode on or off on its own thread, for callers on the event loop.
+pub fn set_in_background(on: bool) {
+    std::thread::spawn(move || {
+        if let Err(e) = set(on) {
+            log::warn!("Efficiency mode: {}", e);
+        }
+    });
+}
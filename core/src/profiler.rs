@@ -0,0 +1,89 @@
+//! On-demand host CPU profiling for bug reports.
+//!
+//! `StartRuntimeProfile { seconds }` samples all host threads with pprof and writes a flamegraph
+//! SVG into the support dir. Requires the `profiling` cargo feature (Unix only); otherwise the
+//! command returns an error. WebView traces are not collected: wry exposes no CDP access.
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Upper bound on a single profile run.
+pub const MAX_PROFILE_SECS: u64 = 60;
+
+/// Set while a profile is running; only one at a time.
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Profiles for `seconds` (clamped to `1..=MAX_PROFILE_SECS`) and returns the flamegraph path.
+/// Blocking; run on a worker thread.
+pub fn capture(seconds: u64) -> Result<PathBuf, String> {
+    let _running = Running::acquire()?;
+    let sampler = start()?;
+    std::thread::sleep(Duration::from_secs(seconds.clamp(1, MAX_PROFILE_SECS)));
+    finish(sampler)
+}
+
+/// Same as `capture`, but waits on a tokio timer so no thread is held while sampling.
+pub async fn capture_async(seconds: u64) -> Result<PathBuf, String> {
+    let _running = Running::acquire()?;
+    let sampler = start()?;
+    tokio::time::sleep(Duration::from_secs(seconds.clamp(1, MAX_PROFILE_SECS))).await;
+    finish(sampler)
+}
+
+/// Holds `RUNNING` for one profile; released on drop, including on early return.
+struct Running;
+
+impl Running {
+    fn acquire() -> Result<Self, String> {
+        if RUNNING.swap(true, Ordering::AcqRel) {
+            return Err("A profile is already running".to_string());
+        }
+        Ok(Self)
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        RUNNING.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(all(feature = "profiling", unix))]
+type Sampler = pprof::ProfilerGuard<'static>;
+
+/// Uninhabited without the `profiling` feature: `start` always fails.
+#[cfg(not(all(feature = "profiling", unix)))]
+enum Sampler {}
+
+#[cfg(all(feature = "profiling", unix))]
+fn start() -> Result<Sampler, String> {
+    pprof::ProfilerGuardBuilder::default()
+        .frequency(100)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(all(feature = "profiling", unix))]
+fn finish(guard: Sampler) -> Result<PathBuf, String> {
+    let report = guard.report().build().map_err(|e| e.to_string())?;
+    let stamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let path = crate::paths::support_dir().join(format!("profile-{}.svg", stamp));
+    let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+    report.flamegraph(file).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+#[cfg(not(all(feature = "profiling", unix)))]
+fn start() -> Result<Sampler, String> {
+    Err("Profiling not available (build with the `profiling` feature on Unix)".to_string())
+}
+
+#[cfg(not(all(feature = "profiling", unix)))]
+fn finish(sampler: Sampler) -> Result<PathBuf, String> {
+    match sampler {}
+}
@@ -2,9 +2,21 @@
 //!
 //! Path traversal (`..`) is rejected. Only files from the compile-time embedded directory
 //! are served. MIME types are derived from extension only.
+//!
+//! `media://` serves local files the user granted through native dialogs (see `grants`), so the UI
+//! can show images and video without relaxing the CSP. Requests are canonicalized and checked
+//! against live read grants; anything else is 403, whether or not the file exists. Files are read
+//! per request in ranges of at most `MEDIA_CHUNK_BYTES`, so `<video>` can seek and large files
+//! are never loaded whole.
+//!
+//! Per-locale bundles (`ui/dist/<locale>/index.html`) are supported: `select_bundle` picks one at
+//! startup and `serve_localized` prefers it, falling back to the root bundle per file.
 
 use include_dir::Dir;
 use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use crate::grants::{self, Access};
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -26,15 +38,97 @@ pub enum ServeResult<'a> {
 // Constants
 // ---------------------------------------------------------------------------
 
-/// Content-Security-Policy for all app:// responses.
-pub const CSP: &str = "default-src 'self'; script-src 'self'; connect-src 'none';";
+/// Content-Security-Policy for all app:// responses. Images and media may also load from media://
+/// (`http(s)://media.localhost` on WebView2).
+pub const CSP: &str = "default-src 'self'; script-src 'self'; connect-src 'none'; img-src 'self' media: http://media.localhost https://media.localhost; media-src 'self' media: http://media.localhost https://media.localhost;";
+
+/// Scheme of the user-granted local file protocol (`media://localhost/<absolute path>`).
+pub const MEDIA_SCHEME: &str = "media";
+
+/// Content-Security-Policy for media:// responses: inert content only (e.g. no script in SVG).
+pub const MEDIA_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'; sandbox";
+
+/// Largest media:// response body. Longer files are served in ranges of this size (206), which
+/// media elements request in turn.
+const MEDIA_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
 
 /// X-Content-Type-Options for all responses.
 const X_CONTENT_TYPE_OPTIONS: &str = "nosniff";
 
+/// Origins the embedded UI is served from. `app://localhost` on macOS/Linux; WebView2 maps custom
+/// protocols to `http(s)://app.localhost`.
+const TRUSTED_ORIGINS: [&str; 3] = [
+    "app://localhost",
+    "http://app.localhost",
+    "https://app.localhost",
+];
+
+/// The embedded UI's origin on this platform, for URLs loaded at runtime (`load_url` does not map
+/// custom protocols the way the builder's `with_url` does on Windows).
+#[cfg(target_os = "windows")]
+pub const APP_ORIGIN: &str = "http://app.localhost";
+#[cfg(not(target_os = "windows"))]
+pub const APP_ORIGIN: &str = "app://localhost";
+
+/// Request path of the page shown when the main document fails to load (see `load_error_response`).
+pub const LOAD_ERROR_PATH: &str = "/__load-error";
+
 /// Default document when path is "/" or empty.
 pub(crate) const INDEX_PATH: &str = "index.html";
 
+/// Embedded page served with status 404 for missing paths, when the UI ships one.
+pub(crate) const NOT_FOUND_PAGE: &str = "404.html";
+
+/// Embedded page served with status 500 when a response cannot be built, when the UI ships one.
+pub(crate) const ERROR_PAGE: &str = "error.html";
+
+/// One Cache-Control rule: `pattern` is an exact path, or a directory prefix when it ends in `/`.
+struct CacheRule {
+    pattern: &'static str,
+    value: &'static str,
+}
+
+/// Cache-Control for app:// files (first match wins). Vite emits content-hashed names under
+/// `assets/`, so they never change; the entry document must always revalidate.
+const CACHE_RULES: &[CacheRule] = &[
+    CacheRule {
+        pattern: "assets/",
+        value: "public, max-age=31536000, immutable",
+    },
+    CacheRule {
+        pattern: INDEX_PATH,
+        value: "no-cache",
+    },
+];
+
+/// Cache-Control for files no rule matches, and for media:// responses.
+const DEFAULT_CACHE_CONTROL: &str = "no-cache";
+
+/// Cache-Control for error responses.
+const NO_STORE: &str = "no-store";
+
+/// Built-in 500 page used when the UI has no `error.html`.
+const FALLBACK_ERROR_HTML: &str = "<!doctype html><html><head><meta charset=\"utf-8\"><title>Error</title>\
+<style>body{font-family:system-ui,sans-serif;display:flex;align-items:center;justify-content:center;\
+height:100vh;margin:0;color:#444;background:#f6f6f6}h1{font-weight:500;font-size:1.25rem}</style>\
+</head><body><h1>Something went wrong. Please reload.</h1></body></html>";
+
+/// CSP for the built-in error page (inline styles only, no script).
+const FALLBACK_ERROR_CSP: &str = "default-src 'none'; style-src 'unsafe-inline'";
+
+// ---------------------------------------------------------------------------
+// Origin
+// ---------------------------------------------------------------------------
+
+/// True if `url` belongs to the embedded UI origin (exact origin, followed by `/`, `?`, `#`, or end).
+#[must_use]
+pub fn is_trusted_origin(url: &str) -> bool {
+    TRUSTED_ORIGINS.iter().any(|origin| {
+        url.strip_prefix(origin)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', '?', '#']))
+    })
+}
+
 // ---------------------------------------------------------------------------
 // MIME type
 // ---------------------------------------------------------------------------
@@ -58,11 +152,49 @@ pub(crate) fn mime_from_path(path: &str) -> &'static str {
         "image/png"
     } else if path.ends_with(".woff2") {
         "font/woff2"
+    } else if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        "image/jpeg"
+    } else if path.ends_with(".gif") {
+        "image/gif"
+    } else if path.ends_with(".webp") {
+        "image/webp"
+    } else if path.ends_with(".mp4") {
+        "video/mp4"
+    } else if path.ends_with(".webm") {
+        "video/webm"
+    } else if path.ends_with(".mp3") {
+        "audio/mpeg"
+    } else if path.ends_with(".ogg") {
+        "audio/ogg"
+    } else if path.ends_with(".wav") {
+        "audio/wav"
     } else {
         "application/octet-stream"
     }
 }
 
+// ---------------------------------------------------------------------------
+// Caching
+// ---------------------------------------------------------------------------
+
+/// Returns the Cache-Control value for a request path, per `CACHE_RULES`.
+#[must_use]
+pub(crate) fn cache_control(uri_path: &str) -> &'static str {
+    let Some(path) = normalize_path(uri_path) else {
+        return NO_STORE;
+    };
+    CACHE_RULES
+        .iter()
+        .find(|r| {
+            if r.pattern.ends_with('/') {
+                path.starts_with(r.pattern)
+            } else {
+                path == r.pattern
+            }
+        })
+        .map_or(DEFAULT_CACHE_CONTROL, |r| r.value)
+}
+
 // ---------------------------------------------------------------------------
 // Serve
 // ---------------------------------------------------------------------------
@@ -103,6 +235,295 @@ pub fn serve(ui: &'static Dir, uri_path: &str) -> ServeResult<'static> {
     }
 }
 
+/// Serves one request from a directory on disk (dev reload). Same path rules as `serve`.
+#[must_use]
+pub fn serve_dir(root: &Path, uri_path: &str) -> ServeResult<'static> {
+    let Some(path) = normalize_path(uri_path) else {
+        return ServeResult::NotFound;
+    };
+    match std::fs::read(root.join(path)) {
+        Ok(bytes) => ServeResult::Found {
+            body: Cow::Owned(bytes),
+            mime_type: mime_from_path(path),
+        },
+        Err(_) => ServeResult::NotFound,
+    }
+}
+
+/// Picks the per-locale bundle directory for the first matching candidate tag (case-insensitive).
+///
+/// A bundle is a top-level directory of `ui` containing `index.html`. Returns its name, or `None`
+/// when no candidate matches (serve from the root bundle).
+#[must_use]
+pub fn select_bundle(ui: &'static Dir, candidates: &[String]) -> Option<&'static str> {
+    let bundles: Vec<&'static str> = ui
+        .dirs()
+        .filter(|d| d.get_file(d.path().join(INDEX_PATH)).is_some())
+        .filter_map(|d| d.path().to_str())
+        .collect();
+    candidates
+        .iter()
+        .find_map(|c| bundles.iter().find(|b| b.eq_ignore_ascii_case(c)).copied())
+}
+
+/// Serves one request, preferring `bundle` (from `select_bundle`) and falling back to the root.
+#[must_use]
+pub fn serve_localized(
+    ui: &'static Dir,
+    bundle: Option<&str>,
+    uri_path: &str,
+) -> ServeResult<'static> {
+    if let Some(bundle) = bundle
+        && let Some(path) = normalize_path(uri_path)
+        && let Some(file) = ui.get_file(format!("{}/{}", bundle, path))
+    {
+        return ServeResult::Found {
+            body: Cow::Borrowed(file.contents()),
+            mime_type: mime_from_path(path),
+        };
+    }
+    serve(ui, uri_path)
+}
+
+// ---------------------------------------------------------------------------
+// Media (user-granted local files)
+// ---------------------------------------------------------------------------
+
+/// Decodes `%XX` escapes. Returns `None` for malformed escapes or non-UTF-8 results.
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+/// Maps a media:// request path to a filesystem path (`/home/u/a.png`; `/C:/u/a.png` on Windows).
+#[must_use]
+pub(crate) fn media_path(uri_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(uri_path)?;
+    if decoded.contains('\0') {
+        return None;
+    }
+    #[cfg(windows)]
+    let decoded = decoded.trim_start_matches('/').to_string();
+    let path = PathBuf::from(decoded);
+    path.is_absolute().then_some(path)
+}
+
+/// First range of a `Range: bytes=...` header as (start, inclusive end) within a `total`-byte
+/// file. `None` when the header is not a usable byte range (serve from the start); `Some(None)`
+/// when the range lies past the end of the file (416).
+#[must_use]
+pub(crate) fn parse_range(header: &str, total: u64) -> Option<Option<(u64, u64)>> {
+    let spec = header.trim().strip_prefix("bytes=")?.split(',').next()?;
+    let (first, last) = spec.trim().split_once('-')?;
+    if first.is_empty() {
+        let suffix: u64 = last.parse().ok()?;
+        return Some((suffix > 0 && total > 0).then(|| (total.saturating_sub(suffix), total - 1)));
+    }
+    let start: u64 = first.parse().ok()?;
+    let end: u64 = if last.is_empty() {
+        u64::MAX
+    } else {
+        last.parse().ok()?
+    };
+    if end < start {
+        return None;
+    }
+    Some((start < total).then(|| (start, end.min(total - 1))))
+}
+
+/// Reads `len` bytes of `path` from `start`.
+fn read_range(path: &Path, start: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    let mut body = Vec::with_capacity(usize::try_from(len).unwrap_or(0));
+    file.take(len).read_to_end(&mut body)?;
+    Ok(body)
+}
+
+/// Serves one media:// request with its `Range` header. The file must be covered by a live read
+/// grant; missing and ungranted paths both get 403, so pages cannot probe the filesystem.
+#[must_use]
+pub fn media_response(uri_path: &str, range: Option<&str>) -> http::Response<Cow<'static, [u8]>> {
+    use http::header::{ACCEPT_RANGES, CONTENT_RANGE, HeaderValue};
+    let text = |status: u16, body: &'static [u8]| {
+        response(status, Cow::Borrowed(body), "text/plain", MEDIA_CSP)
+    };
+    let Some(path) = media_path(uri_path) else {
+        return text(404, b"Not Found");
+    };
+    let Ok(resolved) = grants::check(&path, Access::Read) else {
+        return text(403, b"Forbidden");
+    };
+    let total = match std::fs::metadata(&resolved) {
+        Ok(meta) if meta.is_file() => meta.len(),
+        _ => return text(404, b"Not Found"),
+    };
+    let (status, start, end) = match range.and_then(|h| parse_range(h, total)) {
+        Some(Some((start, end))) => (206, start, end),
+        Some(None) => {
+            let mut resp = text(416, b"Range Not Satisfiable");
+            if let Ok(value) = HeaderValue::from_str(&format!("bytes */{}", total)) {
+                resp.headers_mut().insert(CONTENT_RANGE, value);
+            }
+            return resp;
+        }
+        None if total <= MEDIA_CHUNK_BYTES => (200, 0, total.saturating_sub(1)),
+        None => (206, 0, total - 1),
+    };
+    let end = end.min(start + MEDIA_CHUNK_BYTES - 1);
+    let len = if total == 0 { 0 } else { end - start + 1 };
+    let body = match read_range(&resolved, start, len) {
+        Ok(body) => body,
+        Err(e) => {
+            log::warn!("media:// read failed for {}: {}", resolved.display(), e);
+            return text(404, b"Not Found");
+        }
+    };
+    let mime_type = mime_from_path(&resolved.to_string_lossy().to_ascii_lowercase());
+    let mut resp = match build(status, Cow::Owned(body), mime_type, MEDIA_CSP, NO_STORE) {
+        Ok(resp) => resp,
+        Err(e) => {
+            log::error!("Protocol response build failed: {}", e);
+            return error_response(None);
+        }
+    };
+    let headers = resp.headers_mut();
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    if status == 206
+        && let Ok(value) = HeaderValue::from_str(&format!("bytes {}-{}/{}", start, end, total))
+    {
+        headers.insert(CONTENT_RANGE, value);
+    }
+    resp
+}
+
+/// Builds a response from static header values. Names are pre-parsed constants and values are
+/// borrowed (`HeaderValue::from_static`), so no header bytes are copied per request; only an
+/// invalid status can fail.
+fn build(
+    status: u16,
+    body: Cow<'static, [u8]>,
+    mime_type: &'static str,
+    csp: &'static str,
+    cache_control: &'static str,
+) -> Result<http::Response<Cow<'static, [u8]>>, http::Error> {
+    use http::header::{
+        CACHE_CONTROL, CONTENT_SECURITY_POLICY, CONTENT_TYPE, HeaderValue,
+        X_CONTENT_TYPE_OPTIONS as NOSNIFF,
+    };
+    let mut resp = http::Response::new(body);
+    *resp.status_mut() = http::StatusCode::from_u16(status)?;
+    let headers = resp.headers_mut();
+    headers.reserve(4);
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(mime_type));
+    headers.insert(CONTENT_SECURITY_POLICY, HeaderValue::from_static(csp));
+    headers.insert(CACHE_CONTROL, HeaderValue::from_static(cache_control));
+    headers.insert(NOSNIFF, HeaderValue::from_static(X_CONTENT_TYPE_OPTIONS));
+    Ok(resp)
+}
+
+/// 500 response: the UI's `error.html` if present, else the built-in page.
+fn error_response(ui: Option<(&'static Dir, Option<&str>)>) -> http::Response<Cow<'static, [u8]>> {
+    let embedded = ui.and_then(
+        |(ui, bundle)| match serve_localized(ui, bundle, ERROR_PAGE) {
+            ServeResult::Found { body, mime_type } => {
+                build(500, body, mime_type, CSP, NO_STORE).ok()
+            }
+            _ => None,
+        },
+    );
+    embedded
+        .or_else(|| {
+            build(
+                500,
+                Cow::Borrowed(FALLBACK_ERROR_HTML.as_bytes()),
+                "text/html",
+                FALLBACK_ERROR_CSP,
+                NO_STORE,
+            )
+            .ok()
+        })
+        .unwrap_or_else(|| {
+            let mut resp = http::Response::new(Cow::Borrowed(b"Internal Server Error".as_slice()));
+            *resp.status_mut() = http::StatusCode::INTERNAL_SERVER_ERROR;
+            resp
+        })
+}
+
+/// Builds a protocol response with the security headers and `no-cache` (`no-store` for errors). Falls back to a 500 page if building fails.
+#[must_use]
+pub fn response(
+    status: u16,
+    body: Cow<'static, [u8]>,
+    mime_type: &'static str,
+    csp: &'static str,
+) -> http::Response<Cow<'static, [u8]>> {
+    let cache = if status == 200 {
+        DEFAULT_CACHE_CONTROL
+    } else {
+        NO_STORE
+    };
+    build(status, body, mime_type, csp, cache).unwrap_or_else(|e| {
+        log::error!("Protocol response build failed: {}", e);
+        error_response(None)
+    })
+}
+
+/// Serves one app:// request. Missing paths get the UI's `404.html` (status 404) when present,
+/// else plain text; response-build failures get `error.html` or a built-in 500 page.
+#[must_use]
+pub fn app_response(
+    ui: &'static Dir,
+    bundle: Option<&str>,
+    uri_path: &str,
+) -> http::Response<Cow<'static, [u8]>> {
+    let (status, body, mime_type) = match serve_localized(ui, bundle, uri_path) {
+        ServeResult::Found { body, mime_type } => (200, body, mime_type),
+        ServeResult::NotFound => match serve_localized(ui, bundle, NOT_FOUND_PAGE) {
+            ServeResult::Found { body, mime_type } => (404, body, mime_type),
+            _ => (404, Cow::Borrowed(b"Not Found".as_slice()), "text/plain"),
+        },
+    };
+    let cache = if status == 200 {
+        cache_control(uri_path)
+    } else {
+        NO_STORE
+    };
+    build(status, body, mime_type, CSP, cache).unwrap_or_else(|e| {
+        log::error!("Protocol response build failed: {}", e);
+        error_response(Some((ui, bundle)))
+    })
+}
+
+/// True for a request of the entry document (`/`, `/index.html`).
+#[must_use]
+pub fn is_entry_document(uri_path: &str) -> bool {
+    let path = uri_path.trim_start_matches('/');
+    path.is_empty() || path == INDEX_PATH
+}
+
+/// Served at `LOAD_ERROR_PATH`: the UI's `error.html` if present, else the built-in error page.
+#[must_use]
+pub fn load_error_response(
+    ui: &'static Dir,
+    bundle: Option<&str>,
+) -> http::Response<Cow<'static, [u8]>> {
+    error_response(Some((ui, bundle)))
+}
+
 /// Builds an HTTP 200 response with CSP and Content-Type. Used by the protocol handler.
 #[allow(dead_code)]
 pub fn response_200(
@@ -2,7 +2,12 @@
 
 #[cfg(test)]
 mod tests {
-    use crate::protocol::{mime_from_path, normalize_path, serve, ServeResult, INDEX_PATH};
+    use crate::locale::{candidate_tags, first_day_of_week, number_separators};
+    use crate::protocol::{
+        INDEX_PATH, ServeResult, app_response, cache_control, is_entry_document, is_trusted_origin,
+        load_error_response, media_path, media_response, mime_from_path, normalize_path,
+        parse_range, select_bundle, serve, serve_localized,
+    };
     use include_dir::include_dir;
 
     static TEST_UI: include_dir::Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../ui/dist");
@@ -53,4 +58,139 @@ mod tests {
         assert_eq!(mime_from_path("e.woff2"), "font/woff2");
         assert_eq!(mime_from_path("f.unknown"), "application/octet-stream");
     }
+
+    #[test]
+    fn select_bundle_none_without_locale_dirs() {
+        let candidates = vec!["fr-FR".to_string(), "fr".to_string()];
+        assert_eq!(select_bundle(&TEST_UI, &candidates), None);
+        assert_eq!(select_bundle(&TEST_UI, &[]), None);
+    }
+
+    #[test]
+    fn serve_localized_falls_back_to_root() {
+        let r = serve_localized(&TEST_UI, Some("xx"), "/../etc/passwd");
+        assert!(matches!(r, ServeResult::NotFound));
+        let r = serve_localized(&TEST_UI, Some("xx"), "/nonexistent.foo");
+        assert!(matches!(r, ServeResult::NotFound));
+    }
+
+    #[test]
+    fn candidate_tags_expand_language_and_dedupe() {
+        let tags = vec![
+            "fr_FR.UTF-8".to_string(),
+            "fr".to_string(),
+            "de-AT".to_string(),
+        ];
+        assert_eq!(candidate_tags(&tags), vec!["fr-FR", "fr", "de-AT", "de"]);
+    }
+
+    #[test]
+    fn trusted_origin_exact_match_only() {
+        assert!(is_trusted_origin("app://localhost/index.html"));
+        assert!(is_trusted_origin("app://localhost"));
+        assert!(is_trusted_origin("http://app.localhost/"));
+        assert!(is_trusted_origin("https://app.localhost/index.html#x"));
+        assert!(!is_trusted_origin("app://localhost.evil.com/"));
+        assert!(!is_trusted_origin("https://evil.com/?app.localhost"));
+        assert!(!is_trusted_origin("https://app.localhost.evil.com/"));
+        assert!(!is_trusted_origin("file:///index.html"));
+    }
+
+    #[test]
+    fn media_requires_grant() {
+        let dir = std::env::temp_dir().join(format!("media-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("a b.png");
+        std::fs::write(&file, b"png").unwrap();
+        let uri = format!(
+            "/{}",
+            file.display()
+                .to_string()
+                .trim_start_matches('/')
+                .replace(' ', "%20")
+        );
+        assert_eq!(media_path(&uri).as_deref(), Some(file.as_path()));
+        assert_eq!(media_response(&uri, None).status(), 403);
+        // A missing file answers the same as an ungranted one.
+        let missing = uri.replace("a%20b.png", "missing.png");
+        assert_eq!(media_response(&missing, None).status(), 403);
+        crate::grants::grant(&dir, crate::grants::Access::Read, None);
+        let resp = media_response(&uri, None);
+        assert_eq!(resp.status(), 200);
+        assert_eq!(&**resp.body(), b"png");
+        assert_eq!(resp.headers()["content-type"], "image/png");
+        assert_eq!(resp.headers()["accept-ranges"], "bytes");
+        let resp = media_response(&uri, Some("bytes=1-"));
+        assert_eq!(resp.status(), 206);
+        assert_eq!(&**resp.body(), b"ng");
+        assert_eq!(resp.headers()["content-range"], "bytes 1-2/3");
+        assert_eq!(media_response(&uri, Some("bytes=9-")).status(), 416);
+        crate::grants::revoke(&dir);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn media_ranges_parse() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some(Some((0, 99))));
+        assert_eq!(parse_range("bytes=900-", 1000), Some(Some((900, 999))));
+        assert_eq!(parse_range("bytes=-100", 1000), Some(Some((900, 999))));
+        assert_eq!(parse_range("bytes=500-5000", 1000), Some(Some((500, 999))));
+        assert_eq!(parse_range("bytes=0-1, 5-6", 1000), Some(Some((0, 1))));
+        assert_eq!(parse_range("bytes=1000-", 1000), Some(None));
+        assert_eq!(parse_range("bytes=5-1", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+    }
+
+    #[test]
+    fn media_path_rejects_relative_and_bad_escapes() {
+        assert!(media_path("relative/a.png").is_none());
+        assert!(media_path("/tmp/%zz.png").is_none());
+    }
+
+    #[test]
+    fn app_response_missing_path_is_404() {
+        let resp = app_response(&TEST_UI, None, "/no/such/page");
+        assert_eq!(resp.status(), 404);
+        let resp = app_response(&TEST_UI, None, "/../escape");
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[test]
+    fn load_error_page_for_entry_document() {
+        assert!(is_entry_document("/"));
+        assert!(is_entry_document("/index.html"));
+        assert!(!is_entry_document("/assets/index.html"));
+        assert!(!is_entry_document("/404.html"));
+        // No error.html in the bundle: the built-in page.
+        let resp = load_error_response(&TEST_UI, None);
+        assert_eq!(resp.status(), 500);
+        assert!(
+            std::str::from_utf8(resp.body())
+                .unwrap()
+                .contains("Something went wrong")
+        );
+    }
+
+    #[test]
+    fn cache_control_rules() {
+        assert_eq!(
+            cache_control("/assets/index-abc123.js"),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(cache_control("/"), "no-cache");
+        assert_eq!(cache_control("/index.html"), "no-cache");
+        assert_eq!(cache_control("/favicon.ico"), "no-cache");
+        assert_eq!(cache_control("/x/assets/a.js"), "no-cache");
+    }
+
+    #[test]
+    fn regional_defaults() {
+        assert_eq!(first_day_of_week(Some("US")), "sunday");
+        assert_eq!(first_day_of_week(Some("DE")), "monday");
+        assert_eq!(first_day_of_week(Some("EG")), "saturday");
+        assert_eq!(first_day_of_week(None), "monday");
+        assert_eq!(number_separators("en", Some("US")), (".", ","));
+        assert_eq!(number_separators("de", Some("DE")), (",", "."));
+        assert_eq!(number_separators("de", Some("CH")), (".", "\u{2019}"));
+    }
 }
@@ -0,0 +1,124 @@
+//! Recovery from a crashed WebView content process.
+//!
+//! When the main WebView's web content process dies (WebKitGTK `web-process-terminated`, WKWebView
+//! `webViewWebContentProcessDidTerminate`), the window would stay blank. The platform hook sends
+//! `UserEvent::RendererGone` and the loop reloads `index.html` in the same WebView, which starts
+//! a fresh content process, then emits `renderer-restarted` (`{ reason, restarts }`) to the new
+//! page once it sends `UiReady`. A page that keeps crashing gets the load error page instead of
+//! being reloaded forever: at most `MAX_RENDERER_RESTARTS` within `RENDERER_RESTART_WINDOW_SECS`.
+//!
+//! Windows has no hook, so there a crash is only noticed by the `watchdog`, as a page that stopped
+//! answering pings.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::config::{MAX_RENDERER_RESTARTS, RENDERER_RESTART_WINDOW_SECS};
+use crate::event_loop::UserEvent;
+
+/// Recent restarts, to stop reloading a page that crashes on load.
+#[derive(Debug, Default)]
+pub struct RestartBudget {
+    recent: VecDeque<Instant>,
+}
+
+impl RestartBudget {
+    /// Records a crash at `now`. True when the page may be reloaded; false once
+    /// `MAX_RENDERER_RESTARTS` reloads already happened within the window.
+    pub fn try_restart(&mut self, now: Instant) -> bool {
+        let window = Duration::from_secs(RENDERER_RESTART_WINDOW_SECS);
+        while self
+            .recent
+            .front()
+            .is_some_and(|&at| now.duration_since(at) >= window)
+        {
+            self.recent.pop_front();
+        }
+        if self.recent.len() >= MAX_RENDERER_RESTARTS {
+            return false;
+        }
+        self.recent.push_back(now);
+        true
+    }
+
+    /// Restarts within the current window.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.recent.len()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use webkit2gtk::{WebProcessTerminationReason, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    use crate::event_loop::UserEvent;
+
+    pub fn attach(webview: &wry::WebView, proxy: tao::event_loop::EventLoopProxy<UserEvent>) {
+        webview
+            .webview()
+            .connect_web_process_terminated(move |_, reason| {
+                let reason = match reason {
+                    WebProcessTerminationReason::Crashed => "crashed",
+                    WebProcessTerminationReason::ExceededMemoryLimit => "memory-limit",
+                    // Terminated on purpose; nothing to recover.
+                    WebProcessTerminationReason::TerminatedByApi => return,
+                    _ => "unknown",
+                };
+                let _ = proxy.send_event(UserEvent::RendererGone(reason.to_string()));
+            });
+    }
+
+    pub fn watch(
+        builder: wry::WebViewBuilder<'_>,
+        _proxy: tao::event_loop::EventLoopProxy<UserEvent>,
+    ) -> wry::WebViewBuilder<'_> {
+        builder
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use wry::WebViewBuilderExtDarwin;
+
+    use crate::event_loop::UserEvent;
+
+    pub fn attach(_webview: &wry::WebView, _proxy: tao::event_loop::EventLoopProxy<UserEvent>) {}
+
+    pub fn watch(
+        builder: wry::WebViewBuilder<'_>,
+        proxy: tao::event_loop::EventLoopProxy<UserEvent>,
+    ) -> wry::WebViewBuilder<'_> {
+        builder.with_on_web_content_process_terminate_handler(move || {
+            let _ = proxy.send_event(UserEvent::RendererGone("terminated".to_string()));
+        })
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+mod platform {
+    use crate::event_loop::UserEvent;
+
+    pub fn attach(_webview: &wry::WebView, _proxy: tao::event_loop::EventLoopProxy<UserEvent>) {}
+
+    pub fn watch(
+        builder: wry::WebViewBuilder<'_>,
+        _proxy: tao::event_loop::EventLoopProxy<UserEvent>,
+    ) -> wry::WebViewBuilder<'_> {
+        builder
+    }
+}
+
+/// Hooks crash detection into the main WebView's builder (macOS; unchanged elsewhere).
+pub fn watch(
+    builder: wry::WebViewBuilder<'_>,
+    proxy: tao::event_loop::EventLoopProxy<UserEvent>,
+) -> wry::WebViewBuilder<'_> {
+    platform::watch(builder, proxy)
+}
+
+/// Hooks crash detection into the built main WebView (Linux; no-op elsewhere).
+pub fn attach(webview: &wry::WebView, proxy: tao::event_loop::EventLoopProxy<UserEvent>) {
+    platform::attach(webview, proxy);
+}
@@ -0,0 +1,247 @@
+//! Host-side scheduled tasks (`ScheduleTask` / `CancelTask`).
+//!
+//! Tasks live in the host, so they keep firing across WebView reloads. The event loop calls
+//! `fire_due` on every iteration and `WaitUntil`s the returned deadline; each firing is reported as
+//! a `task-fired` host event. Fires missed while the loop was not running (e.g. system suspend) are
+//! coalesced into one. Cron expressions use five fields (minute hour day-of-month month
+//! day-of-week) evaluated in UTC.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::config::{SCHEDULED_TASK_LIMIT, SCHEDULED_TASK_MIN_INTERVAL_MS};
+use crate::events;
+
+/// Parsed five-field cron expression; each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day-of-month and day-of-week both restricted: either may match (cron semantics).
+    day_or_weekday: bool,
+}
+
+/// Parses one cron field (`*`, `*/n`, `a`, `a-b`, `a-b/n`, comma lists) into a bitmask.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (
+                r,
+                s.parse::<u32>()
+                    .map_err(|_| format!("Invalid step in '{}'", part))?,
+            ),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(format!("Invalid step in '{}'", part));
+        }
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let a = a
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid value in '{}'", part))?;
+            let b = b
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid value in '{}'", part))?;
+            (a, b)
+        } else {
+            let v = range
+                .parse::<u32>()
+                .map_err(|_| format!("Invalid value in '{}'", part))?;
+            (v, if step > 1 { max } else { v })
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(format!("'{}' out of range {}-{}", part, min, max));
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+impl Cron {
+    /// Parses `"m h dom mon dow"`. Day-of-week accepts 0-7 (0 and 7 are Sunday).
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(
+                "Cron expression needs 5 fields: minute hour day month weekday".to_string(),
+            );
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            day_or_weekday: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    fn day_matches(&self, day: u32, weekday: u32) -> bool {
+        let dom = self.days & (1 << day) != 0;
+        let dow = self.weekdays & (1 << weekday) != 0;
+        if self.day_or_weekday {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+
+    /// First matching minute strictly after `after_secs` (Unix seconds, UTC), within ~4 years.
+    #[must_use]
+    pub fn next_after(&self, after_secs: u64) -> Option<u64> {
+        let mut t = (after_secs / 60 + 1) * 60;
+        let limit = t + 4 * 366 * 86_400;
+        while t < limit {
+            let days = t / 86_400;
+            let (_, month, day) = civil_from_days(days);
+            let weekday = ((days + 4) % 7) as u32;
+            if self.months & (1 << month) == 0 || !self.day_matches(day, weekday) {
+                t = (days + 1) * 86_400;
+                continue;
+            }
+            let hour = (t % 86_400) / 3600;
+            if self.hours & (1 << hour) == 0 {
+                t = (t / 3600 + 1) * 3600;
+                continue;
+            }
+            if self.minutes & (1 << ((t % 3600) / 60)) != 0 {
+                return Some(t);
+            }
+            t += 60;
+        }
+        None
+    }
+}
+
+/// (year, month 1-12, day 1-31) for days since 1970-01-01.
+pub(crate) fn civil_from_days(days: u64) -> (i64, u32, u32) {
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// When a task fires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    Interval(Duration),
+    Cron(Cron),
+}
+
+impl Schedule {
+    /// Builds a schedule from exactly one of `interval_ms` or `cron`.
+    pub fn from_args(interval_ms: Option<u64>, cron: Option<&str>) -> Result<Self, String> {
+        match (interval_ms, cron) {
+            (Some(ms), None) if ms >= SCHEDULED_TASK_MIN_INTERVAL_MS => {
+                Ok(Self::Interval(Duration::from_millis(ms)))
+            }
+            (Some(_), None) => Err(format!(
+                "interval_ms must be at least {}",
+                SCHEDULED_TASK_MIN_INTERVAL_MS
+            )),
+            (None, Some(expr)) => Cron::parse(expr).map(Self::Cron),
+            _ => Err("Specify exactly one of interval_ms or cron".to_string()),
+        }
+    }
+
+    /// Next fire time after now, as a monotonic deadline.
+    fn next(&self, now: Instant) -> Option<Instant> {
+        match self {
+            Self::Interval(every) => Some(now + *every),
+            Self::Cron(cron) => {
+                let wall = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+                let at = cron.next_after(wall.as_secs())?;
+                Some(now + Duration::from_secs(at).saturating_sub(wall))
+            }
+        }
+    }
+}
+
+struct Task {
+    schedule: Schedule,
+    next: Instant,
+}
+
+static TASKS: Mutex<BTreeMap<String, Task>> = Mutex::new(BTreeMap::new());
+
+fn unix_ms(at: Instant, now: Instant) -> u64 {
+    let wall = SystemTime::now() + at.saturating_duration_since(now);
+    wall.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Adds or replaces task `id`. Returns the first fire time (Unix ms).
+pub fn schedule(id: &str, schedule: Schedule) -> Result<u64, String> {
+    if id.is_empty() {
+        return Err("Task id must not be empty".to_string());
+    }
+    let now = Instant::now();
+    let next = schedule.next(now).ok_or("Cron expression never matches")?;
+    let mut tasks = TASKS.lock().unwrap_or_else(|e| e.into_inner());
+    if !tasks.contains_key(id) && tasks.len() >= SCHEDULED_TASK_LIMIT {
+        return Err(format!("At most {} scheduled tasks", SCHEDULED_TASK_LIMIT));
+    }
+    tasks.insert(id.to_string(), Task { schedule, next });
+    Ok(unix_ms(next, now))
+}
+
+/// Removes task `id`. Returns whether it existed.
+pub fn cancel(id: &str) -> bool {
+    TASKS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(id)
+        .is_some()
+}
+
+/// Fires due tasks (one `task-fired` event each) and reschedules them. Returns the next deadline
+/// so the event loop can `WaitUntil` it.
+pub fn fire_due() -> Option<Instant> {
+    let now = Instant::now();
+    let (fired, next) = {
+        let mut tasks = TASKS.lock().unwrap_or_else(|e| e.into_inner());
+        let mut fired = Vec::new();
+        tasks.retain(|id, task| {
+            if task.next > now {
+                return true;
+            }
+            fired.push(id.clone());
+            match task.schedule.next(now) {
+                Some(next) => {
+                    task.next = next;
+                    true
+                }
+                None => false,
+            }
+        });
+        (fired, tasks.values().map(|t| t.next).min())
+    };
+    let fired_at = unix_ms(now, now);
+    for id in fired {
+        events::emit(
+            "task-fired",
+            serde_json::json!({ "id": id, "firedAt": fired_at }),
+        );
+    }
+    next
+}
@@ -0,0 +1,311 @@
+//! Serial ports: `ListSerialPorts`, `SerialOpen` / `SerialWrite` / `SerialClose`.
+//!
+//! Built on the `serialport` crate (without its libudev feature; Linux ports are enumerated from
+//! sysfs). Only enumerated ports can be opened, so the controlling terminal (`/dev/tty`), virtual
+//! consoles, and other character devices are out of reach. Ports are opened non-blocking, so a
+//! modem line without carrier detect does not hang the caller. Each open port has a reader thread
+//! that emits `serial-data` `{ portId, data }` (base64) and, when the device goes away or errors,
+//! `serial-closed` `{ portId, error }`. Reads time out every 100 ms so `SerialClose` stops the
+//! thread promptly; writes give up after `SERIAL_WRITE_TIMEOUT_MS` (flow control holding the line).
+//! Port I/O runs outside the lock on the port table, so one stuck port does not block the others.
+
+use std::collections::BTreeMap;
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serialport::{SerialPort, SerialPortType};
+
+use crate::config::{SERIAL_MAX_OPEN_PORTS, SERIAL_WRITE_TIMEOUT_MS};
+use crate::events;
+
+/// Read timeout, after which the reader checks for `SerialClose`.
+const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Parity bit setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+/// Line settings for `SerialOpen`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineSettings {
+    pub baud_rate: u32,
+    /// 5–8.
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2.
+    pub stop_bits: u8,
+    /// RTS/CTS hardware flow control.
+    pub flow_control: bool,
+}
+
+impl LineSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.baud_rate == 0 {
+            return Err("baud_rate must be positive".to_string());
+        }
+        if !(5..=8).contains(&self.data_bits) {
+            return Err("data_bits must be 5 to 8".to_string());
+        }
+        if !matches!(self.stop_bits, 1 | 2) {
+            return Err("stop_bits must be 1 or 2".to_string());
+        }
+        Ok(())
+    }
+
+    fn builder(&self, path: &str) -> serialport::SerialPortBuilder {
+        serialport::new(path, self.baud_rate)
+            .data_bits(match self.data_bits {
+                5 => serialport::DataBits::Five,
+                6 => serialport::DataBits::Six,
+                7 => serialport::DataBits::Seven,
+                _ => serialport::DataBits::Eight,
+            })
+            .parity(match self.parity {
+                Parity::None => serialport::Parity::None,
+                Parity::Odd => serialport::Parity::Odd,
+                Parity::Even => serialport::Parity::Even,
+            })
+            .stop_bits(if self.stop_bits == 2 {
+                serialport::StopBits::Two
+            } else {
+                serialport::StopBits::One
+            })
+            .flow_control(if self.flow_control {
+                serialport::FlowControl::Hardware
+            } else {
+                serialport::FlowControl::None
+            })
+            .timeout(Duration::from_millis(SERIAL_WRITE_TIMEOUT_MS))
+    }
+}
+
+/// One enumerated port. USB fields are filled when the port sits on a USB device.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortInfo {
+    pub path: String,
+    /// `"usb"`, `"bluetooth"`, or `"other"`.
+    pub kind: &'static str,
+    pub vendor_id: Option<String>,
+    pub product_id: Option<String>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+/// Lexical check for device paths `SerialOpen` may accept: `COM<n>` on Windows; `/dev/tty*`,
+/// `/dev/cu.*`, `/dev/serial/...`, `/dev/rfcomm*` elsewhere, except the controlling terminal
+/// (`/dev/tty`) and virtual consoles (`/dev/tty<n>`). Rejects traversal. The port must also be
+/// enumerated by `list`.
+#[must_use]
+pub fn is_serial_path(path: &str) -> bool {
+    if cfg!(windows) {
+        let digits = path.strip_prefix("COM").unwrap_or("");
+        return !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+    }
+    let Some(rest) = path.strip_prefix("/dev/") else {
+        return false;
+    };
+    if let Some(suffix) = rest.strip_prefix("tty")
+        && suffix.bytes().all(|b| b.is_ascii_digit())
+    {
+        return false;
+    }
+    !rest
+        .split('/')
+        .any(|part| part.is_empty() || part == "." || part == "..")
+        && ["tty", "cu.", "serial/", "rfcomm"]
+            .iter()
+            .any(|p| rest.starts_with(p))
+}
+
+/// Legacy 8250 UARTs that Linux registers whether or not hardware is present.
+#[cfg(target_os = "linux")]
+fn is_placeholder_uart(path: &str) -> bool {
+    let Some(name) = path.strip_prefix("/dev/") else {
+        return false;
+    };
+    std::fs::read_link(Path::new("/sys/class/tty").join(name).join("device/driver"))
+        .ok()
+        .and_then(|d| d.file_name().map(|n| n == "serial8250"))
+        .unwrap_or(false)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_placeholder_uart(_path: &str) -> bool {
+    false
+}
+
+/// Serial ports present on the system.
+pub fn list() -> Result<Vec<PortInfo>, String> {
+    // `serialport` panics without sysfs (some containers).
+    if cfg!(target_os = "linux") && !Path::new("/sys/class/tty").is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut ports: Vec<PortInfo> = serialport::available_ports()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|p| is_serial_path(&p.port_name) && !is_placeholder_uart(&p.port_name))
+        .map(|p| {
+            let mut info = PortInfo {
+                path: p.port_name,
+                kind: "other",
+                ..PortInfo::default()
+            };
+            match p.port_type {
+                SerialPortType::UsbPort(usb) => {
+                    info.kind = "usb";
+                    info.vendor_id = Some(format!("{:04x}", usb.vid));
+                    info.product_id = Some(format!("{:04x}", usb.pid));
+                    info.serial_number = usb.serial_number;
+                    info.manufacturer = usb.manufacturer;
+                    info.product = usb.product;
+                }
+                SerialPortType::BluetoothPort => info.kind = "bluetooth",
+                SerialPortType::PciPort | SerialPortType::Unknown => {}
+            }
+            info
+        })
+        .collect();
+    ports.sort_by(|a, b| a.path.cmp(&b.path));
+    ports.dedup_by(|a, b| a.path == b.path);
+    Ok(ports)
+}
+
+/// Enumerated port path for `path`, following `/dev/serial/by-id` style links.
+fn resolve(path: &str) -> Result<String, String> {
+    if !is_serial_path(path) {
+        return Err(format!("Not a serial device path: {}", path));
+    }
+    let listed = list()?;
+    let canonical = std::fs::canonicalize(path).ok();
+    listed
+        .into_iter()
+        .map(|p| p.path)
+        .find(|p| p == path || canonical.as_deref().is_some_and(|c| Path::new(p) == c))
+        .ok_or_else(|| format!("Not an available serial port: {}", path))
+}
+
+struct Port {
+    writer: Mutex<Box<dyn SerialPort>>,
+    stop: AtomicBool,
+}
+
+/// Open ports by id. Held only to look up, add, or remove ports, never during I/O.
+static PORTS: Mutex<BTreeMap<String, Arc<Port>>> = Mutex::new(BTreeMap::new());
+
+fn ports() -> std::sync::MutexGuard<'static, BTreeMap<String, Arc<Port>>> {
+    PORTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn read_loop(port_id: String, path: String, mut reader: Box<dyn SerialPort>, port: Arc<Port>) {
+    let mut buf = vec![0u8; 4096];
+    let error = loop {
+        if port.stop.load(Ordering::Relaxed) {
+            return;
+        }
+        match reader.read(&mut buf) {
+            Ok(0) if !cfg!(windows) && !Path::new(&path).exists() => {
+                break Some("Device removed".to_string());
+            }
+            Ok(0) => {}
+            Ok(n) => events::emit(
+                "serial-data",
+                serde_json::json!({
+                    "portId": port_id,
+                    "data": base64::engine::general_purpose::STANDARD.encode(&buf[..n]),
+                }),
+            ),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    ErrorKind::TimedOut | ErrorKind::Interrupted | ErrorKind::WouldBlock
+                ) => {}
+            Err(e) => break Some(e.to_string()),
+        }
+    };
+    ports().remove(&port_id);
+    events::emit(
+        "serial-closed",
+        serde_json::json!({ "portId": port_id, "error": error }),
+    );
+}
+
+/// Opens and configures `path`. Returns the port id used by the other commands and events.
+pub fn open(path: &str, line: LineSettings) -> Result<String, String> {
+    line.validate()?;
+    let path = resolve(path)?;
+    if ports().len() >= SERIAL_MAX_OPEN_PORTS {
+        return Err(format!(
+            "At most {} serial ports can be open",
+            SERIAL_MAX_OPEN_PORTS
+        ));
+    }
+    let writer = line
+        .builder(&path)
+        .open()
+        .map_err(|e| format!("Could not open {}: {}", path, e))?;
+    let mut reader = writer.try_clone().map_err(|e| e.to_string())?;
+    reader
+        .set_timeout(READ_TIMEOUT)
+        .map_err(|e| e.to_string())?;
+    let port = Arc::new(Port {
+        writer: Mutex::new(writer),
+        stop: AtomicBool::new(false),
+    });
+    let port_id = uuid::Uuid::new_v4().to_string();
+    {
+        let mut ports = ports();
+        if ports.len() >= SERIAL_MAX_OPEN_PORTS {
+            return Err(format!(
+                "At most {} serial ports can be open",
+                SERIAL_MAX_OPEN_PORTS
+            ));
+        }
+        ports.insert(port_id.clone(), Arc::clone(&port));
+    }
+    let (id, p, handle) = (port_id.clone(), path.clone(), Arc::clone(&port));
+    let spawned = thread::Builder::new()
+        .name("serial-read".to_string())
+        .spawn(move || read_loop(id, p, reader, handle));
+    if let Err(e) = spawned {
+        ports().remove(&port_id);
+        return Err(e.to_string());
+    }
+    Ok(port_id)
+}
+
+/// Writes `data` to an open port. Returns the number of bytes written.
+pub fn write(port_id: &str, data: &[u8]) -> Result<usize, String> {
+    let port = ports().get(port_id).cloned().ok_or("Unknown port")?;
+    let mut writer = port.writer.lock().unwrap_or_else(|e| e.into_inner());
+    writer.write_all(data).map_err(|e| match e.kind() {
+        ErrorKind::TimedOut => "Write timed out (flow control holding the line?)".to_string(),
+        _ => e.to_string(),
+    })?;
+    Ok(data.len())
+}
+
+/// Closes a port. Returns whether it was open. A write in progress finishes or times out first;
+/// the device is released once it has.
+pub fn close(port_id: &str) -> bool {
+    match ports().remove(port_id) {
+        Some(port) => {
+            port.stop.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,99 @@
+//! Background services started at launch, and the `minimalMode` deployment flag that turns them
+//! off.
+//!
+//! With `"minimalMode": true` in `config.json` the app has no presence beyond its window: no tray
+//! icon, no polling threads (config watcher, power, idle, memory, session snapshots), no startup network
+//! (remote kill switch manifest) or temp cleanup, and the updater module is disabled. Features
+//! that start on first use (USB hotplug, keyboard layout, ...) still do so when the UI asks. The
+//! flag is read once at startup.
+
+use std::time::Duration;
+
+use crate::config::TEMP_FILE_MAX_AGE_SECS;
+use crate::ipc::{Lane, WorkerPool};
+use crate::killswitch::{self, RuntimeModule};
+use crate::settings::Settings;
+use crate::{idle, memory, paths, power, session, storage};
+
+/// Which launch-time services run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Services {
+    pub tray: bool,
+    /// Polls `config.json` for external edits.
+    pub config_watch: bool,
+    pub power_events: bool,
+    pub idle_events: bool,
+    /// Watches resident memory against `memoryLimitMb`.
+    pub memory_watch: bool,
+    pub session_snapshots: bool,
+    /// Fetches the remote kill switch manifest.
+    pub remote_kill_switch: bool,
+    pub temp_cleanup: bool,
+    pub updater: bool,
+}
+
+impl Services {
+    /// Everything on.
+    pub const FULL: Self = Self {
+        tray: true,
+        config_watch: true,
+        power_events: true,
+        idle_events: true,
+        memory_watch: true,
+        session_snapshots: true,
+        remote_kill_switch: true,
+        temp_cleanup: true,
+        updater: true,
+    };
+
+    /// Everything off.
+    pub const MINIMAL: Self = Self {
+        tray: false,
+        config_watch: false,
+        power_events: false,
+        idle_events: false,
+        memory_watch: false,
+        session_snapshots: false,
+        remote_kill_switch: false,
+        temp_cleanup: false,
+        updater: false,
+    };
+
+    /// Services for the configured mode.
+    #[must_use]
+    pub fn for_settings(settings: &Settings) -> Self {
+        if settings.minimal_mode {
+            Self::MINIMAL
+        } else {
+            Self::FULL
+        }
+    }
+
+    /// Starts the enabled services. Background jobs go to `pool`.
+    pub fn start(&self, pool: &WorkerPool) {
+        if !self.updater {
+            killswitch::disable_for_mode(&[RuntimeModule::Updater]);
+        }
+        if self.config_watch {
+            storage::watch();
+        }
+        if self.power_events {
+            power::watch();
+        }
+        if self.idle_events {
+            idle::watch();
+        }
+        if self.memory_watch {
+            memory::watch();
+        }
+        session::start(self.session_snapshots);
+        if self.remote_kill_switch {
+            pool.spawn(Lane::Background, None, killswitch::refresh_remote);
+        }
+        if self.temp_cleanup {
+            pool.spawn(Lane::Background, None, || {
+                paths::cleanup_temp_files(Duration::from_secs(TEMP_FILE_MAX_AGE_SECS));
+            });
+        }
+    }
+}
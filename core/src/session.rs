@@ -0,0 +1,123 @@
+//! App-defined session state and unclean-shutdown detection.
+//!
+//! `SaveSession { state }` stores an opaque JSON blob (open documents, scroll positions) in
+//! `session.json`, written via a temp file and rename so a crash mid-write keeps the previous
+//! snapshot. A `session.running` marker is created at startup and removed when the event loop
+//! exits; finding it at the next launch means the previous run crashed, reported as `crashed` by
+//! `GetSession` so the UI can offer "Restore previous session".
+//!
+//! Every `sessionSnapshotSecs` (default 60, `0` disables) a `session-snapshot` host event asks the
+//! UI to save its current state.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::events;
+use crate::paths::user_data_dir;
+use crate::settings::{self, Settings};
+
+/// Config key: seconds between `session-snapshot` events. `0` disables periodic snapshots.
+pub const SNAPSHOT_CONFIG_KEY: &str = "sessionSnapshotSecs";
+
+/// Largest accepted session blob (serialized).
+const MAX_SESSION_BYTES: usize = 4 * 1024 * 1024;
+
+const SESSION_FILENAME: &str = "session.json";
+const RUNNING_MARKER: &str = "session.running";
+
+/// Whether the previous run left its running marker behind.
+static PREVIOUS_CRASHED: AtomicBool = AtomicBool::new(false);
+
+fn session_path() -> PathBuf {
+    user_data_dir().join(SESSION_FILENAME)
+}
+
+fn marker_path() -> PathBuf {
+    user_data_dir().join(RUNNING_MARKER)
+}
+
+fn now_ms() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0)
+}
+
+/// Records whether the last run exited cleanly, writes this run's marker, and, with `snapshots`,
+/// starts the snapshot timer.
+pub fn start(snapshots: bool) {
+    let marker = marker_path();
+    if marker.exists() {
+        log::warn!("Previous session did not exit cleanly");
+        PREVIOUS_CRASHED.store(true, Ordering::Relaxed);
+    }
+    if let Err(e) = fs::write(&marker, std::process::id().to_string()) {
+        log::warn!("Could not write session marker: {}", e);
+    }
+    if !snapshots {
+        return;
+    }
+    thread::spawn(|| {
+        loop {
+            let secs = Settings::load().session_snapshot_secs;
+            if secs == 0 {
+                // Re-check occasionally so enabling snapshots applies without a restart.
+                thread::sleep(Duration::from_secs(
+                    settings::DEFAULTS.session_snapshot_secs,
+                ));
+                continue;
+            }
+            thread::sleep(Duration::from_secs(secs));
+            events::emit("session-snapshot", serde_json::json!({}));
+        }
+    });
+}
+
+/// Removes the running marker. Called once when the event loop exits.
+pub fn mark_clean_exit() {
+    let _ = fs::remove_file(marker_path());
+}
+
+/// Persists the session blob, replacing the previous snapshot atomically.
+pub fn save(state: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let saved_at = now_ms();
+    let json = serde_json::to_string(&serde_json::json!({ "savedAt": saved_at, "state": state }))
+        .map_err(|e| e.to_string())?;
+    if json.len() > MAX_SESSION_BYTES {
+        return Err(format!("Session state exceeds {} bytes", MAX_SESSION_BYTES));
+    }
+    let path = session_path();
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp, &path).map_err(|e| e.to_string())?;
+    Ok(serde_json::json!({ "savedAt": saved_at }))
+}
+
+/// Last saved session: `{ state, savedAt, crashed }`. `state` and `savedAt` are `null` when
+/// nothing was saved; `crashed` is true when the previous run did not exit cleanly.
+#[must_use]
+pub fn load() -> serde_json::Value {
+    let saved: serde_json::Value = fs::read_to_string(session_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    serde_json::json!({
+        "state": saved["state"],
+        "savedAt": saved["savedAt"],
+        "crashed": PREVIOUS_CRASHED.load(Ordering::Relaxed),
+    })
+}
+
+/// Deletes the saved session (e.g. after the user declines to restore it).
+pub fn clear() -> Result<serde_json::Value, String> {
+    match fs::remove_file(session_path()) {
+        Ok(()) => Ok(serde_json::json!({ "cleared": true })),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Ok(serde_json::json!({ "cleared": false }))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
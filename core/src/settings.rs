@@ -0,0 +1,377 @@
+//! Typed view of `config.json` with compile-time defaults.
+//!
+//! `Settings::load` reads the config once and returns every well-known setting with its type
+//! and default applied; setters validate and write a single key. Config keys stay defined next
+//! to the feature that owns them. Missing or mistyped values fall back to `DEFAULTS`.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::MAX_IPC_QUEUE_CAPACITY;
+use crate::idle::THRESHOLD_CONFIG_KEY;
+use crate::ipc::{
+    ALL_WORKSPACES_KEY, ALWAYS_ON_TOP_KEY, IPC_BATCH_SIZE_KEY, IPC_DROP_POLICY_KEY,
+    IPC_QUEUE_CAPACITY_KEY, IpcLimits,
+};
+use crate::memory::{MEMORY_LIMIT_KEY, MEMORY_TRIM_WEBVIEW_KEY};
+use crate::popover::TRAY_POPOVER_KEY;
+use crate::power::LOW_BATTERY_CONFIG_KEY;
+use crate::priority::BACKGROUND_EFFICIENCY_KEY;
+use crate::session::SNAPSHOT_CONFIG_KEY;
+use crate::storage::{self, AppConfig};
+use crate::watchdog::{
+    WATCHDOG_ACTION_KEY, WATCHDOG_INTERVAL_KEY, WATCHDOG_MAX_MISSED_KEY, WatchdogConfig,
+};
+use crate::window::{
+    AUTOPLAY_KEY, BACKGROUND_THROTTLING_KEY, INCOGNITO_KEY, SPELLCHECK_KEY,
+    SPELLCHECK_LANGUAGES_KEY, SPLASH_KEY, UI_READY_TIMEOUT_KEY, USER_AGENT_KEY,
+    USER_AGENT_SUFFIX_KEY, default_user_agent_suffix, validate_spellcheck_languages,
+};
+
+/// Config key: hide to the tray instead of quitting when the window is closed.
+pub const CLOSE_TO_TRAY_KEY: &str = "closeToTray";
+
+/// Config key: WebView zoom factor applied at startup.
+pub const ZOOM_KEY: &str = "zoom";
+
+/// Config key: release channel for update checks.
+pub const UPDATE_CHANNEL_KEY: &str = "updateChannel";
+
+/// Config key: launch without tray, background threads, or updater (see `services`).
+pub const MINIMAL_MODE_KEY: &str = "minimalMode";
+
+/// Config key: also offer prereleases on the stable channel.
+pub const ALLOW_PRERELEASE_KEY: &str = "allowPrerelease";
+
+/// Accepted zoom range for `set_zoom`.
+const ZOOM_RANGE: std::ops::RangeInclusive<f64> = 0.25..=5.0;
+
+/// Release channel for update checks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+/// WebView timer/rendering policy while the window is hidden or minimized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundThrottling {
+    /// Keep running at full speed (e.g. for audio or long-running timers).
+    Disabled,
+    /// Suspend the page entirely.
+    Suspend,
+    /// Throttle timers and rendering.
+    Throttle,
+}
+
+impl From<BackgroundThrottling> for wry::BackgroundThrottlingPolicy {
+    fn from(b: BackgroundThrottling) -> Self {
+        match b {
+            BackgroundThrottling::Disabled => Self::Disabled,
+            BackgroundThrottling::Suspend => Self::Suspend,
+            BackgroundThrottling::Throttle => Self::Throttle,
+        }
+    }
+}
+
+/// Well-known settings with defaults applied.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Settings {
+    pub theme: Option<String>,
+    pub close_to_tray: bool,
+    pub zoom: f64,
+    pub update_channel: UpdateChannel,
+    /// Offer prereleases; always on for the beta channel.
+    pub allow_prerelease: bool,
+    /// Locale override (BCP 47); `None` follows the OS.
+    pub locale: Option<String>,
+    pub always_on_top: bool,
+    pub visible_on_all_workspaces: bool,
+    pub show_splash: bool,
+    /// `0` disables idle events.
+    pub idle_threshold_secs: u64,
+    pub low_battery_percent: u8,
+    /// `0` disables memory pressure handling.
+    pub memory_limit_mb: u64,
+    pub memory_trim_webview: bool,
+    /// `0` disables periodic session snapshots.
+    pub session_snapshot_secs: u64,
+    /// Full user agent override; `None` keeps the platform default.
+    pub user_agent: Option<String>,
+    /// Appended to the user agent; `None` appends nothing.
+    pub user_agent_suffix: Option<String>,
+    pub autoplay: bool,
+    /// `None` keeps the platform default.
+    pub background_throttling: Option<BackgroundThrottling>,
+    /// In-memory WebView profile; nothing persists across launches.
+    pub incognito: bool,
+    /// No tray, background threads, or updater; read at startup.
+    pub minimal_mode: bool,
+    /// Main window as a tray popover (see `popover`); read at startup.
+    pub tray_popover: bool,
+    /// Efficiency mode while the main window is hidden (see `priority`).
+    pub background_efficiency: bool,
+    /// IPC backpressure and delivery limits; read at startup.
+    pub ipc_limits: IpcLimits,
+    /// Wait for `UiReady` before showing the main window anyway; read at startup.
+    pub ui_ready_timeout_ms: u64,
+    /// Unresponsive-page watchdog (see `watchdog`); read at startup.
+    pub watchdog: WatchdogConfig,
+    /// `None` keeps the platform default.
+    pub spellcheck: Option<bool>,
+    /// Empty follows the OS input languages.
+    pub spellcheck_languages: Vec<String>,
+}
+
+/// Values used when a key is missing or has the wrong type.
+pub const DEFAULTS: Settings = Settings {
+    theme: None,
+    close_to_tray: false,
+    zoom: 1.0,
+    update_channel: UpdateChannel::Stable,
+    allow_prerelease: false,
+    locale: None,
+    always_on_top: false,
+    visible_on_all_workspaces: false,
+    show_splash: false,
+    idle_threshold_secs: 0,
+    low_battery_percent: 15,
+    memory_limit_mb: 0,
+    memory_trim_webview: false,
+    session_snapshot_secs: 60,
+    user_agent: None,
+    user_agent_suffix: None,
+    autoplay: true,
+    background_throttling: None,
+    incognito: false,
+    minimal_mode: false,
+    tray_popover: false,
+    background_efficiency: false,
+    ipc_limits: IpcLimits::DEFAULT,
+    ui_ready_timeout_ms: 5000,
+    watchdog: WatchdogConfig::DEFAULT,
+    spellcheck: None,
+    spellcheck_languages: Vec::new(),
+};
+
+impl Settings {
+    /// Whether update checks should consider prereleases.
+    #[must_use]
+    pub fn allows_prerelease(&self) -> bool {
+        self.allow_prerelease || self.update_channel == UpdateChannel::Beta
+    }
+
+    /// Reads all settings from `config.json`.
+    #[must_use]
+    pub fn load() -> Self {
+        Self::from_config(&storage::load_config())
+    }
+
+    /// Typed settings from an already loaded config.
+    #[must_use]
+    pub fn from_config(config: &AppConfig) -> Self {
+        let get = |key: &str| config.data.get(key);
+        let bool_or =
+            |key: &str, default: bool| get(key).and_then(|v| v.as_bool()).unwrap_or(default);
+        let u64_or = |key: &str, default: u64| get(key).and_then(|v| v.as_u64()).unwrap_or(default);
+        Self {
+            theme: config.theme.clone(),
+            close_to_tray: bool_or(CLOSE_TO_TRAY_KEY, DEFAULTS.close_to_tray),
+            zoom: get(ZOOM_KEY)
+                .and_then(|v| v.as_f64())
+                .filter(|z| ZOOM_RANGE.contains(z))
+                .unwrap_or(DEFAULTS.zoom),
+            update_channel: get(UPDATE_CHANNEL_KEY)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or(DEFAULTS.update_channel),
+            allow_prerelease: bool_or(ALLOW_PRERELEASE_KEY, DEFAULTS.allow_prerelease),
+            locale: get(crate::locale::CONFIG_KEY)
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+            always_on_top: bool_or(ALWAYS_ON_TOP_KEY, DEFAULTS.always_on_top),
+            visible_on_all_workspaces: bool_or(
+                ALL_WORKSPACES_KEY,
+                DEFAULTS.visible_on_all_workspaces,
+            ),
+            show_splash: bool_or(SPLASH_KEY, DEFAULTS.show_splash),
+            idle_threshold_secs: u64_or(THRESHOLD_CONFIG_KEY, DEFAULTS.idle_threshold_secs),
+            low_battery_percent: get(LOW_BATTERY_CONFIG_KEY)
+                .and_then(|v| v.as_u64())
+                .map_or(DEFAULTS.low_battery_percent, |v| v.min(100) as u8),
+            memory_limit_mb: u64_or(MEMORY_LIMIT_KEY, DEFAULTS.memory_limit_mb),
+            memory_trim_webview: bool_or(MEMORY_TRIM_WEBVIEW_KEY, DEFAULTS.memory_trim_webview),
+            session_snapshot_secs: u64_or(SNAPSHOT_CONFIG_KEY, DEFAULTS.session_snapshot_secs),
+            user_agent: get(USER_AGENT_KEY)
+                .and_then(|v| v.as_str())
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+            user_agent_suffix: match get(USER_AGENT_SUFFIX_KEY) {
+                Some(serde_json::Value::Bool(true)) => Some(default_user_agent_suffix()),
+                Some(serde_json::Value::String(s)) if !s.is_empty() => Some(s.clone()),
+                _ => DEFAULTS.user_agent_suffix,
+            },
+            autoplay: bool_or(AUTOPLAY_KEY, DEFAULTS.autoplay),
+            background_throttling: get(BACKGROUND_THROTTLING_KEY)
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .or(DEFAULTS.background_throttling),
+            incognito: bool_or(INCOGNITO_KEY, DEFAULTS.incognito),
+            minimal_mode: bool_or(MINIMAL_MODE_KEY, DEFAULTS.minimal_mode),
+            tray_popover: bool_or(TRAY_POPOVER_KEY, DEFAULTS.tray_popover),
+            background_efficiency: bool_or(
+                BACKGROUND_EFFICIENCY_KEY,
+                DEFAULTS.background_efficiency,
+            ),
+            ipc_limits: IpcLimits {
+                capacity: get(IPC_QUEUE_CAPACITY_KEY)
+                    .and_then(|v| v.as_u64())
+                    .map_or(DEFAULTS.ipc_limits.capacity, |v| {
+                        (v as usize).clamp(1, MAX_IPC_QUEUE_CAPACITY)
+                    }),
+                drop_policy: get(IPC_DROP_POLICY_KEY)
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or(DEFAULTS.ipc_limits.drop_policy),
+                batch_size: u64_or(IPC_BATCH_SIZE_KEY, DEFAULTS.ipc_limits.batch_size as u64)
+                    as usize,
+            },
+            ui_ready_timeout_ms: u64_or(UI_READY_TIMEOUT_KEY, DEFAULTS.ui_ready_timeout_ms),
+            watchdog: WatchdogConfig {
+                interval_secs: u64_or(WATCHDOG_INTERVAL_KEY, DEFAULTS.watchdog.interval_secs),
+                max_missed: u64_or(
+                    WATCHDOG_MAX_MISSED_KEY,
+                    u64::from(DEFAULTS.watchdog.max_missed),
+                )
+                .clamp(1, u64::from(u32::MAX)) as u32,
+                action: get(WATCHDOG_ACTION_KEY)
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .unwrap_or(DEFAULTS.watchdog.action),
+            },
+            spellcheck: get(SPELLCHECK_KEY).and_then(|v| v.as_bool()),
+            spellcheck_languages: get(SPELLCHECK_LANGUAGES_KEY)
+                .and_then(|v| serde_json::from_value::<Vec<String>>(v.clone()).ok())
+                .filter(|l| validate_spellcheck_languages(l).is_ok())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Partial update for `UpdateSettings`; omitted fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct SettingsPatch {
+    #[serde(default)]
+    pub theme: Option<String>,
+    #[serde(default)]
+    pub close_to_tray: Option<bool>,
+    #[serde(default)]
+    pub zoom: Option<f64>,
+    #[serde(default)]
+    pub update_channel: Option<UpdateChannel>,
+    #[serde(default)]
+    pub allow_prerelease: Option<bool>,
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Sets the UI theme name.
+pub fn set_theme(theme: &str) {
+    storage::set_theme(theme.to_string());
+}
+
+/// Sets whether closing the window hides it to the tray.
+pub fn set_close_to_tray(on: bool) {
+    storage::set_value(CLOSE_TO_TRAY_KEY.to_string(), serde_json::json!(on));
+}
+
+/// Sets whether the window stays above others.
+pub fn set_always_on_top(on: bool) {
+    storage::set_value(ALWAYS_ON_TOP_KEY.to_string(), serde_json::json!(on));
+}
+
+/// Sets whether the window shows on every workspace.
+pub fn set_visible_on_all_workspaces(on: bool) {
+    storage::set_value(ALL_WORKSPACES_KEY.to_string(), serde_json::json!(on));
+}
+
+/// Sets spell checking and, when given, its languages.
+pub fn set_spellcheck(enabled: bool, languages: Option<&[String]>) -> Result<(), String> {
+    if let Some(languages) = languages {
+        validate_spellcheck_languages(languages)?;
+        storage::set_value(
+            SPELLCHECK_LANGUAGES_KEY.to_string(),
+            serde_json::json!(languages),
+        );
+    }
+    storage::set_value(SPELLCHECK_KEY.to_string(), serde_json::json!(enabled));
+    Ok(())
+}
+
+/// Sets the startup zoom factor (0.25–5.0).
+pub fn set_zoom(zoom: f64) -> Result<(), String> {
+    if !ZOOM_RANGE.contains(&zoom) {
+        return Err(format!(
+            "Zoom must be between {} and {}",
+            ZOOM_RANGE.start(),
+            ZOOM_RANGE.end()
+        ));
+    }
+    storage::set_value(ZOOM_KEY.to_string(), serde_json::json!(zoom));
+    Ok(())
+}
+
+/// Sets the update channel.
+pub fn set_update_channel(channel: UpdateChannel) {
+    storage::set_value(UPDATE_CHANNEL_KEY.to_string(), serde_json::json!(channel));
+}
+
+/// Sets whether update checks offer prereleases on the stable channel.
+pub fn set_allow_prerelease(on: bool) {
+    storage::set_value(ALLOW_PRERELEASE_KEY.to_string(), serde_json::json!(on));
+}
+
+/// Sets the locale override; an empty tag clears it (follow the OS).
+pub fn set_locale(tag: &str) -> Result<(), String> {
+    if tag.is_empty() {
+        return storage::delete_value(crate::locale::CONFIG_KEY).map(|_| ());
+    }
+    storage::set_value(
+        crate::locale::CONFIG_KEY.to_string(),
+        serde_json::json!(tag),
+    );
+    Ok(())
+}
+
+/// Applies a patch after validating every field, then returns the resulting settings.
+pub fn apply(patch: &SettingsPatch) -> Result<Settings, String> {
+    if let Some(zoom) = patch.zoom
+        && !ZOOM_RANGE.contains(&zoom)
+    {
+        return Err(format!(
+            "Zoom must be between {} and {}",
+            ZOOM_RANGE.start(),
+            ZOOM_RANGE.end()
+        ));
+    }
+    if let Some(theme) = &patch.theme {
+        set_theme(theme);
+    }
+    if let Some(on) = patch.close_to_tray {
+        set_close_to_tray(on);
+    }
+    if let Some(zoom) = patch.zoom {
+        set_zoom(zoom)?;
+    }
+    if let Some(channel) = patch.update_channel {
+        set_update_channel(channel);
+    }
+    if let Some(on) = patch.allow_prerelease {
+        set_allow_prerelease(on);
+    }
+    if let Some(tag) = &patch.locale {
+        set_locale(tag)?;
+    }
+    Ok(Settings::load())
+}
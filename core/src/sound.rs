@@ -0,0 +1,117 @@
+//! Short audio cues for `PlaySystemSound` and `PlayAudioFile`, played by the host so they are not
+//! subject to the WebView's autoplay policy.
+//!
+//! Sounds are played by the platform's command-line player without waiting: `canberra-gtk-play`
+//! (sound theme event ids) falling back to `paplay` with the freedesktop theme files on Linux, and
+//! `afplay` with the built-in alert sounds on macOS. Windows is not supported yet. Finished players
+//! are reaped on the next call; at most `MAX_PLAYING` run at once.
+
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Concurrent players; further requests fail until one finishes.
+const MAX_PLAYING: usize = 4;
+
+/// Kind of cue for `PlaySystemSound`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SystemSound {
+    Notification,
+    Success,
+    Warning,
+    Error,
+}
+
+impl SystemSound {
+    /// freedesktop sound theme event id.
+    #[must_use]
+    pub fn theme_id(self) -> &'static str {
+        match self {
+            Self::Notification => "message-new-instant",
+            Self::Success => "complete",
+            Self::Warning => "dialog-warning",
+            Self::Error => "dialog-error",
+        }
+    }
+
+    /// macOS alert sound in `/System/Library/Sounds`.
+    #[must_use]
+    pub fn macos_sound(self) -> &'static str {
+        match self {
+            Self::Notification => "Glass",
+            Self::Success => "Hero",
+            Self::Warning => "Sosumi",
+            Self::Error => "Basso",
+        }
+    }
+}
+
+static PLAYING: Mutex<Vec<Child>> = Mutex::new(Vec::new());
+
+/// Starts `program args..` without waiting, after reaping finished players.
+fn spawn(program: &str, args: &[&str]) -> Result<(), String> {
+    let mut playing = PLAYING.lock().unwrap_or_else(|e| e.into_inner());
+    playing.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+    if playing.len() >= MAX_PLAYING {
+        return Err(format!("At most {} sounds can play at once", MAX_PLAYING));
+    }
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Could not start {}: {}", program, e))?;
+    playing.push(child);
+    Ok(())
+}
+
+/// Plays a system cue.
+pub fn play_system(kind: SystemSound) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        let id = format!("--id={}", kind.theme_id());
+        spawn("canberra-gtk-play", &[&id]).or_else(|_| {
+            let file = format!(
+                "/usr/share/sounds/freedesktop/stereo/{}.oga",
+                kind.theme_id()
+            );
+            spawn("paplay", &[&file])
+        })
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let file = format!("/System/Library/Sounds/{}.aiff", kind.macos_sound());
+        spawn("/usr/bin/afplay", &[&file])
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (kind, spawn);
+        Err("System sounds are not supported on this platform".to_string())
+    }
+}
+
+/// Plays an audio file (formats depend on the player: WAV/OGG/FLAC via `paplay`, also MP3/AAC via
+/// `afplay`). The caller checks that `path` is granted.
+pub fn play_file(path: &Path) -> Result<(), String> {
+    if !path.is_file() {
+        return Err(format!("Not a file: {}", path.display()));
+    }
+    let path = path.to_string_lossy();
+    #[cfg(target_os = "linux")]
+    {
+        spawn("paplay", &[&path])
+    }
+    #[cfg(target_os = "macos")]
+    {
+        spawn("/usr/bin/afplay", &[&path])
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = path;
+        Err("Audio playback is not supported on this platform".to_string())
+    }
+}
@@ -0,0 +1,134 @@
+//! Text-to-speech for `Speak`, `StopSpeaking`, and `ListVoices`.
+//!
+//! Uses the `tts` crate: speech-dispatcher on Linux (its own client connection, so `StopSpeaking`
+//! cancels this app's messages only), AVFoundation on macOS, WinRT on Windows. One engine is
+//! opened on first use and shared. A new `Speak` interrupts the current utterance.
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tts::Tts;
+
+use crate::config::SPEECH_MAX_TEXT_CHARS;
+
+/// Accepted `rate` range; 1.0 is the voice's normal speed.
+const RATE_RANGE: std::ops::RangeInclusive<f32> = 0.25..=4.0;
+
+/// The shared engine, opened on first use.
+static ENGINE: Mutex<Option<Tts>> = Mutex::new(None);
+
+/// One installed voice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Voice {
+    pub name: String,
+    /// BCP 47 tag as reported by the engine (e.g. `en-US`, `de`); may be empty.
+    pub language: String,
+}
+
+fn validate(text: &str, rate: Option<f32>) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Text must not be empty".to_string());
+    }
+    if text.chars().count() > SPEECH_MAX_TEXT_CHARS {
+        return Err(format!(
+            "Text is longer than {} characters",
+            SPEECH_MAX_TEXT_CHARS
+        ));
+    }
+    if let Some(rate) = rate
+        && !RATE_RANGE.contains(&rate)
+    {
+        return Err(format!(
+            "Rate must be between {} and {}",
+            RATE_RANGE.start(),
+            RATE_RANGE.end()
+        ));
+    }
+    Ok(())
+}
+
+/// Engine rate for a speed multiplier, on the engine's `min..=normal..=max` scale. Each doubling
+/// covers half the distance from normal to the limit, so 0.25 and 4.0 reach `min` and `max`.
+#[must_use]
+pub fn engine_rate(rate: f32, min: f32, normal: f32, max: f32) -> f32 {
+    let steps = (rate.log2() / 2.0).clamp(-1.0, 1.0);
+    if steps >= 0.0 {
+        normal + (max - normal) * steps
+    } else {
+        normal + (normal - min) * steps
+    }
+}
+
+/// Runs `f` on the shared engine, opening it if needed.
+fn with_engine<T>(f: impl FnOnce(&mut Tts) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = ENGINE.lock().unwrap_or_else(|e| e.into_inner());
+    let engine = match &mut *guard {
+        Some(engine) => engine,
+        slot => {
+            slot.insert(Tts::default().map_err(|e| format!("Text-to-speech unavailable: {}", e))?)
+        }
+    };
+    f(engine)
+}
+
+fn failed(e: tts::Error) -> String {
+    format!("Text-to-speech failed: {}", e)
+}
+
+/// Speaks `text`, interrupting anything this app is already saying.
+pub fn speak(text: &str, voice: Option<&str>, rate: Option<f32>) -> Result<(), String> {
+    validate(text, rate)?;
+    with_engine(|engine| {
+        if let Some(name) = voice {
+            let found = engine
+                .voices()
+                .map_err(failed)?
+                .into_iter()
+                .find(|v| v.name() == name || v.id() == name)
+                .ok_or_else(|| format!("Unknown voice: {}", name))?;
+            engine.set_voice(&found).map_err(failed)?;
+        }
+        if engine.supported_features().rate {
+            let (min, normal, max) = (engine.min_rate(), engine.normal_rate(), engine.max_rate());
+            engine
+                .set_rate(engine_rate(rate.unwrap_or(1.0), min, normal, max))
+                .map_err(failed)?;
+        }
+        engine.speak(text, true).map(|_| ()).map_err(failed)
+    })
+}
+
+/// Stops the current utterance. Returns whether anything was speaking (best effort where the
+/// engine cannot tell).
+pub fn stop() -> Result<bool, String> {
+    if ENGINE.lock().unwrap_or_else(|e| e.into_inner()).is_none() {
+        return Ok(false);
+    }
+    with_engine(|engine| {
+        let features = engine.supported_features();
+        if !features.stop {
+            return Ok(false);
+        }
+        let speaking = !features.is_speaking || engine.is_speaking().map_err(failed)?;
+        if speaking {
+            engine.stop().map_err(failed)?;
+        }
+        Ok(speaking)
+    })
+}
+
+/// Installed voices.
+pub fn voices() -> Result<Vec<Voice>, String> {
+    with_engine(|engine| {
+        Ok(engine
+            .voices()
+            .map_err(failed)?
+            .into_iter()
+            .map(|v| Voice {
+                name: v.name(),
+                language: v.language().to_string(),
+            })
+            .collect())
+    })
+}
@@ -0,0 +1,113 @@
+//! Startup timings for `GetStartupTimings` and the startup summary log line.
+//!
+//! `main` wraps window creation and WebView build in `phase` guards (each also a `tracing` span);
+//! the protocol handler and page-load handler record the first request and first completed load.
+//! Times are relative to `init`, called first thing in `main`.
+
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static START: OnceLock<Instant> = OnceLock::new();
+static PHASES: Mutex<Vec<(&'static str, Duration, Duration)>> = Mutex::new(Vec::new());
+static FIRST_PROTOCOL_REQUEST: OnceLock<Duration> = OnceLock::new();
+static FIRST_PAGE_LOAD: OnceLock<Duration> = OnceLock::new();
+
+/// Marks process start. Later calls are ignored.
+pub fn init() {
+    let _ = START.get_or_init(Instant::now);
+}
+
+fn elapsed() -> Duration {
+    START.get_or_init(Instant::now).elapsed()
+}
+
+/// A running startup phase; its duration is recorded when dropped.
+pub struct Phase {
+    name: &'static str,
+    start: Duration,
+    _span: tracing::span::EnteredSpan,
+}
+
+/// Starts timing `name` inside a `startup` span.
+#[must_use]
+pub fn phase(name: &'static str) -> Phase {
+    Phase {
+        name,
+        start: elapsed(),
+        _span: tracing::info_span!("startup", phase = name).entered(),
+    }
+}
+
+impl Drop for Phase {
+    fn drop(&mut self) {
+        let duration = elapsed().saturating_sub(self.start);
+        PHASES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((self.name, self.start, duration));
+    }
+}
+
+/// Records the first app:// request. Lock-free after the first call (runs per request).
+pub fn first_protocol_request() {
+    if FIRST_PROTOCOL_REQUEST.get().is_none() {
+        let _ = FIRST_PROTOCOL_REQUEST.set(elapsed());
+    }
+}
+
+/// Records the first completed page load and logs the startup summary once.
+pub fn first_page_load() {
+    if FIRST_PAGE_LOAD.get().is_some() || FIRST_PAGE_LOAD.set(elapsed()).is_err() {
+        return;
+    }
+    tracing::info!(target: "startup", "first page load");
+    log::info!("{}", summary());
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+/// One-line summary, e.g. `Startup: window 12.0 ms, webview 85.3 ms; first request at 97.1 ms,
+/// first page load at 240.8 ms`.
+#[must_use]
+pub fn summary() -> String {
+    let phases = PHASES.lock().unwrap_or_else(|e| e.into_inner());
+    let parts: Vec<String> = phases
+        .iter()
+        .map(|(name, _, duration)| format!("{} {:.1} ms", name, millis(*duration)))
+        .collect();
+    let at = |mark: &OnceLock<Duration>| {
+        mark.get()
+            .map_or_else(|| "-".to_string(), |d| format!("{:.1} ms", millis(*d)))
+    };
+    format!(
+        "Startup: {}; first request at {}, first page load at {}",
+        parts.join(", "),
+        at(&FIRST_PROTOCOL_REQUEST),
+        at(&FIRST_PAGE_LOAD)
+    )
+}
+
+/// Timings for `GetStartupTimings`: phases with start offset and duration, and the first request
+/// and page load offsets (`null` until they happen). All values in milliseconds.
+#[must_use]
+pub fn timings() -> serde_json::Value {
+    let phases: Vec<serde_json::Value> = PHASES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(name, start, duration)| {
+            serde_json::json!({
+                "name": name,
+                "startMs": millis(*start),
+                "durationMs": millis(*duration),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "phases": phases,
+        "firstProtocolRequestMs": FIRST_PROTOCOL_REQUEST.get().map(|d| millis(*d)),
+        "firstPageLoadMs": FIRST_PAGE_LOAD.get().map(|d| millis(*d)),
+    })
+}
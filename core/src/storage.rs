@@ -2,22 +2,83 @@
 //!
 //! Loads and saves config.json; used for window state, theme preference, and
 //! generic key-value storage (ReadConfig/WriteConfig).
+//!
+//! Writes go through `update_config`: an exclusive advisory lock on `config.json.lock` is held
+//! while the file is re-read, the single change applied, and the result written to a temp file
+//! and renamed over `config.json`. Concurrent writers (other windows, other instances sharing the
+//! data dir) therefore merge instead of clobbering each other's keys, and readers never see a
+//! half-written file.
+//!
+//! Every change to a top-level key (from IPC, the host, or another process editing the file)
+//! emits a `config-changed { key }` host event; `watch` polls the file for outside edits.
+//!
+//! Host-only keys (`HOST_ONLY_KEYS`) bound the UI's own capabilities; the UI cannot write,
+//! import, delete, or clear them. They are set in config.json by hand or by the host. Consent
+//! keys among them may be deleted, which withdraws the consent.
+//!
+//! Large binary values go to a content-addressed blob store (`blobs/<aa>/<sha256>`) instead of
+//! config.json; see `put_blob`.
+//!
+//! Values written with `set_secure` live under `secure` in the same file, encrypted with
+//! AES-256-GCM. The key is generated once and held in the OS credential store (Keychain,
+//! Windows Credential Manager / DPAPI, Secret Service) under the app's bundle id; without a
+//! credential store secure writes fail rather than falling back to plaintext.
 
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
 
+use crate::config::{
+    BUNDLE_ID, CONFIG_NAMESPACE_QUOTA_BYTES, CONFIG_NAMESPACE_QUOTAS, CONFIG_WATCH_POLL_MS,
+};
+use crate::events;
 use crate::paths::user_data_dir;
 
 const CONFIG_FILENAME: &str = "config.json";
 
-/// Window bounds for persistence (physical position and size).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Blob store directory under the user data dir.
+const BLOBS_DIR: &str = "blobs";
+
+/// Largest blob accepted by `put_blob`.
+pub const MAX_BLOB_BYTES: usize = 32 * 1024 * 1024;
+
+/// Credential store account holding the base64 config encryption key.
+const SECURE_KEY_ACCOUNT: &str = "config-encryption-key";
+
+/// AES-GCM nonce length in bytes (prefixed to each stored ciphertext).
+const NONCE_LEN: usize = 12;
+
+/// Last known top-level config values, for `config-changed` diffs. `None` until first observed.
+static KNOWN: Mutex<Option<BTreeMap<String, serde_json::Value>>> = Mutex::new(None);
+
+/// Cached encryption key; loaded (or created) from the credential store on first secure access.
+static SECURE_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+/// Window bounds for persistence (physical position and size). For a maximized or fullscreen
+/// window the bounds are its restored (normal) ones, so un-maximizing after a restart returns to
+/// them instead of a screen-sized window. `scale_factor` is the display scale the size was saved
+/// at, so it can be converted when the window reopens on a display with another scale.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct WindowBounds {
     pub x: i32,
     pub y: i32,
     pub width: u32,
     pub height: u32,
+    #[serde(default)]
+    pub maximized: bool,
+    #[serde(default)]
+    pub fullscreen: bool,
+    #[serde(
+        default,
+        rename = "scaleFactor",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub scale_factor: Option<f64>,
 }
 
 /// Root config structure. Extensible via generic key-value map.
@@ -27,6 +88,9 @@ pub struct AppConfig {
     pub window: Option<WindowBounds>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub theme: Option<String>,
+    /// Encrypted values: key → base64(nonce ‖ AES-256-GCM ciphertext). Not returned by ReadConfig.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secure: BTreeMap<String, String>,
     #[serde(flatten)]
     pub data: HashMap<String, serde_json::Value>,
 }
@@ -35,6 +99,35 @@ fn config_path() -> std::path::PathBuf {
     user_data_dir().join(CONFIG_FILENAME)
 }
 
+/// Serializes writers within this process (the file lock covers other processes).
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Runs `f` on the current on-disk config under the config lock and persists the result if it
+/// changed. Nothing is written when `f` returns an error.
+fn update_config<R>(f: impl FnOnce(&mut AppConfig) -> Result<R, String>) -> Result<R, String> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = config_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let lock_file = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("json.lock"))
+        .map_err(|e| format!("Config lock: {}", e))?;
+    lock_file
+        .lock()
+        .map_err(|e| format!("Config lock: {}", e))?;
+    let mut config = load_config();
+    let before = serde_json::to_value(&config).ok();
+    let result = f(&mut config)?;
+    if serde_json::to_value(&config).ok() != before {
+        save_config(&config);
+    }
+    Ok(result)
+}
+
 /// Loads config from user data dir. Returns default on missing or parse error.
 #[must_use]
 pub fn load_config() -> AppConfig {
@@ -45,22 +138,87 @@ pub fn load_config() -> AppConfig {
     serde_json::from_str(&content).unwrap_or_default()
 }
 
-/// Saves config to user data dir. Logs and ignores errors.
-pub fn save_config(config: &AppConfig) {
+/// Writes config to user data dir via temp file + rename. Logs and ignores errors. Callers hold
+/// the config lock (see `update_config`).
+fn save_config(config: &AppConfig) {
     let path = config_path();
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-    if let Ok(json) = serde_json::to_string_pretty(config) {
-        let _ = fs::write(&path, json);
-    } else {
+    let Ok(json) = serde_json::to_string_pretty(config) else {
         log::warn!("Failed to serialize config");
+        return;
+    };
+    let tmp = path.with_extension("json.tmp");
+    if let Err(e) = fs::write(&tmp, json).and_then(|()| fs::rename(&tmp, &path)) {
+        log::warn!("Failed to write config: {}", e);
+        return;
+    }
+    notify_changes(config);
+}
+
+/// Top-level keys and values of `config`; each secure entry counts as its own key.
+fn flatten(config: &AppConfig) -> BTreeMap<String, serde_json::Value> {
+    let mut out = BTreeMap::new();
+    if let Ok(serde_json::Value::Object(obj)) = serde_json::to_value(config) {
+        for (k, v) in obj {
+            if k == "secure" {
+                continue;
+            }
+            out.insert(k, v);
+        }
     }
+    for (k, v) in &config.secure {
+        out.insert(k.clone(), serde_json::Value::String(v.clone()));
+    }
+    out
+}
+
+/// Keys whose values differ between `old` and `new` (added, changed, or removed).
+#[must_use]
+pub fn changed_keys(
+    old: &BTreeMap<String, serde_json::Value>,
+    new: &BTreeMap<String, serde_json::Value>,
+) -> Vec<String> {
+    let mut keys: Vec<String> = new
+        .iter()
+        .filter(|(k, v)| old.get(*k) != Some(*v))
+        .map(|(k, _)| k.clone())
+        .collect();
+    keys.extend(old.keys().filter(|k| !new.contains_key(*k)).cloned());
+    keys.sort();
+    keys
+}
+
+/// Records `config` as the known state and emits `config-changed` for each key that differs.
+fn notify_changes(config: &AppConfig) {
+    let current = flatten(config);
+    let mut known = KNOWN.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(previous) = known.as_ref() {
+        for key in changed_keys(previous, &current) {
+            events::emit("config-changed", serde_json::json!({ "key": key }));
+        }
+    }
+    *known = Some(current);
+}
+
+/// Starts a thread that re-reads `config.json` when its modification time changes, so edits by
+/// other instances (or by hand) produce `config-changed` events too.
+pub fn watch() {
+    notify_changes(&load_config());
+    std::thread::spawn(|| {
+        let modified = || fs::metadata(config_path()).and_then(|m| m.modified()).ok();
+        let mut last: Option<SystemTime> = modified();
+        loop {
+            std::thread::sleep(Duration::from_millis(CONFIG_WATCH_POLL_MS));
+            let now = modified();
+            if now != last {
+                last = now;
+                notify_changes(&load_config());
+            }
+        }
+    });
 }
 
 /// Reads a single key from config.
 #[must_use]
-#[allow(dead_code)]
 pub fn get_value(key: &str) -> Option<serde_json::Value> {
     let mut config = load_config();
     config.data.remove(key)
@@ -68,21 +226,291 @@ pub fn get_value(key: &str) -> Option<serde_json::Value> {
 
 /// Writes a single key-value pair into config and persists.
 pub fn set_value(key: String, value: serde_json::Value) {
-    let mut config = load_config();
-    config.data.insert(key, value);
-    save_config(&config);
+    let result = update_config(|config| {
+        config.data.insert(key, value);
+        Ok(())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to set config value: {}", e);
+    }
+}
+
+/// Replaces a single key with `f(current value)` under the config lock, so concurrent updates
+/// of the same key keep each other's changes.
+pub fn update_value(key: &str, f: impl FnOnce(Option<serde_json::Value>) -> serde_json::Value) {
+    let result = update_config(|config| {
+        let next = f(config.data.remove(key));
+        config.data.insert(key.to_string(), next);
+        Ok(())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to update config value: {}", e);
+    }
+}
+
+/// Sets the theme field and persists.
+pub fn set_theme(theme: String) {
+    let result = update_config(|config| {
+        config.theme = Some(theme);
+        Ok(())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to set theme: {}", e);
+    }
+}
+
+/// Config keys the UI may not write, import, delete, or clear.
+const HOST_ONLY_KEYS: &[&str] = &[
+    crate::grants::TTL_CONFIG_KEY,
+    crate::audit::REDACT_CONFIG_KEY,
+    crate::net::PROXY_CONFIG_KEY,
+    crate::net::CA_BUNDLE_CONFIG_KEY,
+    crate::net::PINS_CONFIG_KEY,
+    crate::permissions::MEDIA_PERMISSIONS_KEY,
+    crate::usb::USB_ACCESS_KEY,
+];
+
+/// Host-only keys that record the user's consent. The UI may delete them, which only withdraws
+/// the consent.
+const CONSENT_KEYS: &[&str] = &[
+    crate::permissions::MEDIA_PERMISSIONS_KEY,
+    crate::usb::USB_ACCESS_KEY,
+];
+
+/// Whether `key` is reserved for the host (see `HOST_ONLY_KEYS`).
+#[must_use]
+pub fn is_host_only(key: &str) -> bool {
+    HOST_ONLY_KEYS.contains(&key)
+}
+
+/// Fails for host-only keys; UI writes call this first.
+pub fn check_ui_writable(key: &str) -> Result<(), String> {
+    if is_host_only(key) {
+        return Err(format!("Config key '{}' is reserved for the host", key));
+    }
+    Ok(())
+}
+
+/// Fails for host-only keys other than consent keys; UI deletes call this first.
+pub fn check_ui_deletable(key: &str) -> Result<(), String> {
+    if CONSENT_KEYS.contains(&key) {
+        return Ok(());
+    }
+    check_ui_writable(key)
+}
+
+/// Namespace reported for keys without a `.`.
+const DEFAULT_NAMESPACE: &str = "default";
+
+/// Namespace of a generic key: the part before the first `.`, else `default`.
+#[must_use]
+pub fn namespace_of(key: &str) -> &str {
+    match key.split_once('.') {
+        Some((ns, _)) if !ns.is_empty() => ns,
+        _ => DEFAULT_NAMESPACE,
+    }
+}
+
+/// Size limit for `namespace` in bytes.
+#[must_use]
+pub fn namespace_quota(namespace: &str) -> usize {
+    CONFIG_NAMESPACE_QUOTAS
+        .iter()
+        .find(|(ns, _)| *ns == namespace)
+        .map_or(CONFIG_NAMESPACE_QUOTA_BYTES, |(_, q)| *q)
+}
+
+/// Stored size of one entry: key length plus compact JSON length of the value.
+fn entry_size(key: &str, value: &serde_json::Value) -> usize {
+    key.len() + serde_json::to_string(value).map_or(0, |s| s.len())
+}
+
+/// Bytes and key count per namespace of the generic key-value data.
+fn namespace_usage(config: &AppConfig) -> BTreeMap<String, (usize, usize)> {
+    let mut usage: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (k, v) in &config.data {
+        let entry = usage.entry(namespace_of(k).to_string()).or_default();
+        entry.0 += entry_size(k, v);
+        entry.1 += 1;
+    }
+    usage
+}
+
+/// Like `set_value`, but rejects the write if it would push the key's namespace over its quota.
+/// Used for UI writes, so host-only keys are refused; host-owned keys go through `set_value`.
+pub fn set_value_within_quota(key: String, value: serde_json::Value) -> Result<(), String> {
+    check_ui_writable(&key)?;
+    update_config(|config| {
+        let namespace = namespace_of(&key).to_string();
+        let (used, _) = namespace_usage(config)
+            .remove(&namespace)
+            .unwrap_or_default();
+        let current = config.data.get(&key).map_or(0, |v| entry_size(&key, v));
+        let after = used - current + entry_size(&key, &value);
+        let quota = namespace_quota(&namespace);
+        if after > quota {
+            return Err(format!(
+                "Storage quota exceeded for namespace '{}' ({} of {} bytes)",
+                namespace, after, quota
+            ));
+        }
+        config.data.insert(key, value);
+        Ok(())
+    })
+}
+
+/// `GetStorageUsage` response: per-namespace `{ namespace, bytes, keys, quota }` and the size
+/// of `config.json` on disk.
+#[must_use]
+pub fn storage_usage() -> serde_json::Value {
+    let config = load_config();
+    let namespaces: Vec<serde_json::Value> = namespace_usage(&config)
+        .into_iter()
+        .map(|(ns, (bytes, keys))| {
+            serde_json::json!({ "namespace": ns, "bytes": bytes, "keys": keys, "quota": namespace_quota(&ns) })
+        })
+        .collect();
+    let file_bytes = fs::metadata(config_path()).map_or(0, |m| m.len());
+    serde_json::json!({ "namespaces": namespaces, "fileBytes": file_bytes })
+}
+
+/// `format` marker of settings bundles written by `export_settings`.
+const SETTINGS_FORMAT: &str = "desktop-runtime-settings";
+
+/// Current settings bundle version; `import_settings` accepts this version only.
+const SETTINGS_VERSION: u64 = 1;
+
+/// Largest settings bundle `import_settings` will read.
+const MAX_SETTINGS_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Writes theme and generic keys to `path` as a settings bundle. Secure values and window
+/// bounds are machine-specific and never exported, nor are host-only keys, which an import
+/// would refuse. Returns the number of keys written.
+pub fn export_settings(path: &std::path::Path) -> Result<usize, String> {
+    let config = load_config();
+    let mut settings: BTreeMap<String, serde_json::Value> = config
+        .data
+        .into_iter()
+        .filter(|(k, _)| !is_host_only(k))
+        .collect();
+    if let Some(theme) = config.theme {
+        settings.insert("theme".to_string(), serde_json::Value::String(theme));
+    }
+    let count = settings.len();
+    let bundle = serde_json::json!({
+        "format": SETTINGS_FORMAT,
+        "version": SETTINGS_VERSION,
+        "exportedAt": std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis()),
+        "settings": settings,
+    });
+    let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| e.to_string())?;
+    Ok(count)
+}
+
+/// Reads a settings bundle from `path` and merges it into config. The bundle is validated as a
+/// whole (format, version, no `window`/`secure` or host-only keys, namespace quotas) before anything is
+/// written. Returns the imported key names.
+pub fn import_settings(path: &std::path::Path) -> Result<Vec<String>, String> {
+    let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_SETTINGS_BYTES {
+        return Err(format!(
+            "Settings file exceeds {} bytes",
+            MAX_SETTINGS_BYTES
+        ));
+    }
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let bundle: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| format!("Invalid settings file: {}", e))?;
+    if bundle["format"] != SETTINGS_FORMAT {
+        return Err("Not a settings export".to_string());
+    }
+    if bundle["version"].as_u64() != Some(SETTINGS_VERSION) {
+        return Err(format!(
+            "Unsupported settings version: {}",
+            bundle["version"]
+        ));
+    }
+    let settings = bundle["settings"]
+        .as_object()
+        .ok_or("Settings file has no settings object")?;
+    if let Some(key) = settings
+        .keys()
+        .find(|k| *k == "window" || *k == "secure" || is_host_only(k))
+    {
+        return Err(format!("Settings file may not contain '{}'", key));
+    }
+    let theme = match settings.get("theme") {
+        None => None,
+        Some(serde_json::Value::String(t)) => Some(t.clone()),
+        Some(_) => return Err("Setting 'theme' must be a string".to_string()),
+    };
+    update_config(|config| {
+        for (k, v) in settings {
+            if k != "theme" {
+                config.data.insert(k.clone(), v.clone());
+            }
+        }
+        if let Some((ns, (bytes, _))) = namespace_usage(config)
+            .into_iter()
+            .find(|(ns, (bytes, _))| *bytes > namespace_quota(ns))
+        {
+            return Err(format!(
+                "Storage quota exceeded for namespace '{}' ({} of {} bytes)",
+                ns,
+                bytes,
+                namespace_quota(&ns)
+            ));
+        }
+        if theme.is_some() {
+            config.theme = theme;
+        }
+        Ok(settings.keys().cloned().collect())
+    })
+}
+
+/// Removes a key (generic or secure; `window` and `theme` reset those fields) and persists.
+/// Returns whether anything was removed.
+pub fn delete_value(key: &str) -> Result<bool, String> {
+    update_config(|config| {
+        Ok(match key {
+            "window" => config.window.take().is_some(),
+            "theme" => config.theme.take().is_some(),
+            _ => {
+                let plain = config.data.remove(key).is_some();
+                let secure = config.secure.remove(key).is_some();
+                plain || secure
+            }
+        })
+    })
+}
+
+/// Removes generic and secure keys starting with `prefix` (all of them when `None`) and
+/// persists. Window bounds, theme, and host-only keys are kept. Returns the removed key names.
+pub fn clear_values(prefix: Option<&str>) -> Result<Vec<String>, String> {
+    update_config(|config| {
+        let matches = |k: &String| prefix.is_none_or(|p| k.starts_with(p)) && !is_host_only(k);
+        let mut removed: Vec<String> = config.data.keys().filter(|k| matches(k)).cloned().collect();
+        removed.extend(config.secure.keys().filter(|k| matches(k)).cloned());
+        config.data.retain(|k, _| !matches(k));
+        config.secure.retain(|k, _| !matches(k));
+        removed.sort();
+        removed.dedup();
+        Ok(removed)
+    })
 }
 
 /// Saves window bounds and persists.
-pub fn save_window_bounds(x: i32, y: i32, width: u32, height: u32) {
-    let mut config = load_config();
-    config.window = Some(WindowBounds {
-        x,
-        y,
-        width,
-        height,
+pub fn save_window_bounds(bounds: WindowBounds) {
+    let result = update_config(|config| {
+        config.window = Some(bounds);
+        Ok(())
     });
-    save_config(&config);
+    if let Err(e) = result {
+        log::warn!("Failed to save window bounds: {}", e);
+    }
 }
 
 /// Returns saved window bounds if any.
@@ -103,7 +531,10 @@ pub fn get_full_config() -> serde_json::Value {
                 "x": w.x,
                 "y": w.y,
                 "width": w.width,
-                "height": w.height
+                "height": w.height,
+                "maximized": w.maximized,
+                "fullscreen": w.fullscreen,
+                "scaleFactor": w.scale_factor
             }),
         );
     }
@@ -115,3 +546,152 @@ pub fn get_full_config() -> serde_json::Value {
     }
     serde_json::Value::Object(obj)
 }
+
+/// Returns the config encryption key, creating and storing one in the credential store if absent.
+fn secure_key() -> Result<[u8; 32], String> {
+    let mut cached = SECURE_KEY.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(key) = *cached {
+        return Ok(key);
+    }
+    let entry = keyring::Entry::new(BUNDLE_ID, SECURE_KEY_ACCOUNT).map_err(|e| e.to_string())?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let key: [u8; 32] = match entry.get_password() {
+        Ok(stored) => b64
+            .decode(stored)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or("Stored config encryption key is invalid")?,
+        Err(keyring::Error::NoEntry) => {
+            let key: [u8; 32] = Aes256Gcm::generate_key(OsRng).into();
+            entry
+                .set_password(&b64.encode(key))
+                .map_err(|e| e.to_string())?;
+            key
+        }
+        Err(e) => return Err(format!("Credential store unavailable: {}", e)),
+    };
+    *cached = Some(key);
+    Ok(key)
+}
+
+/// Encrypts `value` (as JSON) under `key`; the config key is bound as associated data so a
+/// ciphertext cannot be moved to another key.
+pub(crate) fn seal_value(
+    secret: &[u8; 32],
+    key: &str,
+    value: &serde_json::Value,
+) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(secret));
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(value).map_err(|e| e.to_string())?;
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: &plaintext,
+                aad: key.as_bytes(),
+            },
+        )
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(base64::engine::general_purpose::STANDARD.encode(out))
+}
+
+/// Reverses `seal_value`; fails if the value was altered or sealed under another key name.
+pub(crate) fn open_value(
+    secret: &[u8; 32],
+    key: &str,
+    sealed: &str,
+) -> Result<serde_json::Value, String> {
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(sealed)
+        .map_err(|e| e.to_string())?;
+    if bytes.len() < NONCE_LEN {
+        return Err("Secure value is truncated".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(secret));
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: key.as_bytes(),
+            },
+        )
+        .map_err(|_| "Secure value could not be decrypted".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| e.to_string())
+}
+
+/// Encrypts and persists a value under `key` in the secure section of config.
+pub fn set_secure(key: &str, value: &serde_json::Value) -> Result<(), String> {
+    let sealed = seal_value(&secure_key()?, key, value)?;
+    update_config(|config| {
+        config.secure.insert(key.to_string(), sealed);
+        Ok(())
+    })
+}
+
+/// Reads and decrypts a secure value. `Ok(None)` when the key is not set.
+pub fn get_secure(key: &str) -> Result<Option<serde_json::Value>, String> {
+    let config = load_config();
+    let Some(sealed) = config.secure.get(key) else {
+        return Ok(None);
+    };
+    open_value(&secure_key()?, key, sealed).map(Some)
+}
+
+/// Path of blob `id` (64 lowercase hex chars), or an error for malformed ids.
+fn blob_path(id: &str) -> Result<std::path::PathBuf, String> {
+    if id.len() != 64
+        || !id
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        return Err("Invalid blob id".to_string());
+    }
+    Ok(user_data_dir().join(BLOBS_DIR).join(&id[..2]).join(id))
+}
+
+/// Stores `bytes` and returns their id (SHA-256, hex). Identical content is stored once.
+pub fn put_blob(bytes: &[u8]) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    if bytes.len() > MAX_BLOB_BYTES {
+        return Err(format!("Blob exceeds {} bytes", MAX_BLOB_BYTES));
+    }
+    let id = format!("{:x}", Sha256::digest(bytes));
+    let path = blob_path(&id)?;
+    if path.exists() {
+        return Ok(id);
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let tmp = path.with_extension(format!("tmp-{}", uuid::Uuid::new_v4()));
+    fs::write(&tmp, bytes)
+        .and_then(|()| fs::rename(&tmp, &path))
+        .map_err(|e| {
+            let _ = fs::remove_file(&tmp);
+            e.to_string()
+        })?;
+    Ok(id)
+}
+
+/// Reads blob `id`. `Ok(None)` when it does not exist.
+pub fn get_blob(id: &str) -> Result<Option<Vec<u8>>, String> {
+    match fs::read(blob_path(id)?) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Deletes blob `id`. Returns whether it existed.
+pub fn delete_blob(id: &str) -> Result<bool, String> {
+    match fs::remove_file(blob_path(id)?) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.to_string()),
+    }
+}
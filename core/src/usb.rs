@@ -0,0 +1,270 @@
+//! USB device enumeration (`ListUsbDevices`) and `usb-device-attached` / `usb-device-detached`
+//! host events.
+//!
+//! Device lists identify the user's hardware, so access is gated: the first `ListUsbDevices` shows
+//! a native consent dialog and the answer is stored under `usbAccess` in `config.json`, a
+//! host-only key (`false` denies every later call; `DeleteConfig` asks again). Linux reads sysfs, macOS
+//! `system_profiler`, Windows the PnP device list. The hotplug watcher starts after the first
+//! allowed listing and polls, so apps that never ask do not pay for it.
+
+use std::collections::BTreeMap;
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::events;
+use crate::i18n;
+use crate::storage;
+
+/// Config key: `true` once the user allowed device access, `false` if they declined. Host-only:
+/// the UI can withdraw consent but not grant it.
+pub const USB_ACCESS_KEY: &str = "usbAccess";
+
+/// Poll interval for attach/detach events.
+const POLL: Duration = Duration::from_secs(2);
+
+static WATCH: Once = Once::new();
+
+/// One attached USB device.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsbDevice {
+    /// Identifies the device while it stays plugged in: sysfs name (Linux), location id (macOS),
+    /// PnP instance id (Windows).
+    pub location: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+    /// Exposes a HID interface (keyboards, game controllers, many vendor tools).
+    pub hid: bool,
+}
+
+/// Leading hex number of `text`, with or without `0x` (e.g. `"0x05ac  (Apple Inc.)"` → `0x05ac`).
+#[must_use]
+pub fn parse_hex_id(text: &str) -> Option<u16> {
+    let text = text.trim();
+    let text = text.strip_prefix("0x").unwrap_or(text);
+    let end = text
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(text.len());
+    u16::from_str_radix(&text[..end], 16).ok()
+}
+
+#[cfg(target_os = "linux")]
+fn read_devices() -> Result<Vec<UsbDevice>, String> {
+    use std::path::Path;
+
+    let root = Path::new("/sys/bus/usb/devices");
+    let names: Vec<String> = std::fs::read_dir(root)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    let read = |dir: &Path, file: &str| {
+        std::fs::read_to_string(dir.join(file))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let mut devices = Vec::new();
+    // Interfaces are named `<device>:<config>.<n>`; root hubs `usbN`.
+    for name in names
+        .iter()
+        .filter(|n| !n.contains(':') && !n.starts_with("usb"))
+    {
+        let dir = root.join(name);
+        let (Some(vendor_id), Some(product_id)) = (
+            read(&dir, "idVendor").and_then(|v| parse_hex_id(&v)),
+            read(&dir, "idProduct").and_then(|v| parse_hex_id(&v)),
+        ) else {
+            continue;
+        };
+        let prefix = format!("{}:", name);
+        let hid = names
+            .iter()
+            .filter(|n| n.starts_with(&prefix))
+            .any(|n| read(&root.join(n), "bInterfaceClass").as_deref() == Some("03"));
+        devices.push(UsbDevice {
+            location: name.clone(),
+            vendor_id,
+            product_id,
+            manufacturer: read(&dir, "manufacturer"),
+            product: read(&dir, "product"),
+            serial_number: read(&dir, "serial"),
+            hid,
+        });
+    }
+    Ok(devices)
+}
+
+#[cfg(target_os = "macos")]
+fn read_devices() -> Result<Vec<UsbDevice>, String> {
+    fn walk(items: &serde_json::Value, out: &mut Vec<UsbDevice>) {
+        for item in items.as_array().into_iter().flatten() {
+            let text = |key: &str| item[key].as_str().map(String::from);
+            if let (Some(vendor_id), Some(product_id), Some(location)) = (
+                item["vendor_id"].as_str().and_then(parse_hex_id),
+                item["product_id"].as_str().and_then(parse_hex_id),
+                item["location_id"]
+                    .as_str()
+                    .and_then(|l| l.split_whitespace().next()),
+            ) {
+                out.push(UsbDevice {
+                    location: location.to_string(),
+                    vendor_id,
+                    product_id,
+                    manufacturer: text("manufacturer"),
+                    product: text("_name"),
+                    serial_number: text("serial_num"),
+                    hid: false,
+                });
+            }
+            walk(&item["_items"], out);
+        }
+    }
+    let out = std::process::Command::new("system_profiler")
+        .args(["SPUSBDataType", "-json"])
+        .output()
+        .map_err(|e| format!("Could not run system_profiler: {}", e))?;
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).map_err(|e| e.to_string())?;
+    let mut devices = Vec::new();
+    walk(&v["SPUSBDataType"], &mut devices);
+    Ok(devices)
+}
+
+/// `USB\VID_046D&PID_C52B\...` → (vendor, product).
+#[cfg(target_os = "windows")]
+fn pnp_ids(device_id: &str) -> Option<(u16, u16)> {
+    let upper = device_id.to_ascii_uppercase();
+    let vid = upper.split("VID_").nth(1).and_then(parse_hex_id)?;
+    let pid = upper.split("PID_").nth(1).and_then(parse_hex_id)?;
+    Some((vid, pid))
+}
+
+#[cfg(target_os = "windows")]
+fn read_devices() -> Result<Vec<UsbDevice>, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let script = "@(Get-CimInstance Win32_PnPEntity | Where-Object { $_.DeviceID -like 'USB\\VID_*' \
+                  -or $_.DeviceID -like 'HID\\VID_*' } | Select-Object DeviceID,Name,Manufacturer) \
+                  | ConvertTo-Json -Compress";
+    let out = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", script])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .map_err(|e| format!("Could not run powershell: {}", e))?;
+    let v: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap_or(serde_json::json!([]));
+    let entries: Vec<&serde_json::Value> = match &v {
+        serde_json::Value::Array(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    let hid_ids: Vec<(u16, u16)> = entries
+        .iter()
+        .filter_map(|e| e["DeviceID"].as_str())
+        .filter(|id| id.starts_with("HID\\"))
+        .filter_map(pnp_ids)
+        .collect();
+    Ok(entries
+        .iter()
+        .filter_map(|e| {
+            let device_id = e["DeviceID"].as_str()?;
+            // Per-interface entries of composite devices carry `&MI_nn`.
+            if !device_id.starts_with("USB\\") || device_id.contains("&MI_") {
+                return None;
+            }
+            let (vendor_id, product_id) = pnp_ids(device_id)?;
+            let serial = device_id.rsplit('\\').next().filter(|s| !s.contains('&'));
+            Some(UsbDevice {
+                location: device_id.to_string(),
+                vendor_id,
+                product_id,
+                manufacturer: e["Manufacturer"].as_str().map(String::from),
+                product: e["Name"].as_str().map(String::from),
+                serial_number: serial.map(String::from),
+                hid: hid_ids.contains(&(vendor_id, product_id)),
+            })
+        })
+        .collect())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_devices() -> Result<Vec<UsbDevice>, String> {
+    Err("USB enumeration is not supported on this platform".to_string())
+}
+
+/// Stored consent, asking with a native dialog (blocking) the first time.
+fn check_access() -> Result<(), String> {
+    let allowed = match storage::get_value(USB_ACCESS_KEY).and_then(|v| v.as_bool()) {
+        Some(allowed) => allowed,
+        None => {
+            let answer = rfd::MessageDialog::new()
+                .set_title(i18n::t("dialog.usbAccess.title"))
+                .set_description(i18n::t("dialog.usbAccess.message"))
+                .set_level(rfd::MessageLevel::Warning)
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show();
+            let allowed = answer == rfd::MessageDialogResult::Yes;
+            storage::set_value(USB_ACCESS_KEY.to_string(), serde_json::json!(allowed));
+            allowed
+        }
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err("USB device access was denied".to_string())
+    }
+}
+
+/// Attached USB devices, sorted by location. Asks for consent on first use; starts the hotplug
+/// watcher once allowed. Blocking (dialog, helper process); call off the UI thread.
+pub fn list() -> Result<Vec<UsbDevice>, String> {
+    check_access()?;
+    let mut devices = read_devices()?;
+    devices.sort_by(|a, b| a.location.cmp(&b.location));
+    let initial = devices.clone();
+    WATCH.call_once(move || watch(initial));
+    Ok(devices)
+}
+
+/// Emits attach/detach events for devices that appeared or vanished since the last poll, while
+/// access stays allowed.
+fn watch(initial: Vec<UsbDevice>) {
+    let by_location = |devices: Vec<UsbDevice>| -> BTreeMap<String, UsbDevice> {
+        devices
+            .into_iter()
+            .map(|d| (d.location.clone(), d))
+            .collect()
+    };
+    let mut last = by_location(initial);
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL);
+            if storage::get_value(USB_ACCESS_KEY).and_then(|v| v.as_bool()) != Some(true) {
+                continue;
+            }
+            let Ok(devices) = read_devices() else {
+                continue;
+            };
+            let current = by_location(devices);
+            for (location, device) in &current {
+                if !last.contains_key(location)
+                    && let Ok(payload) = serde_json::to_value(device)
+                {
+                    events::emit("usb-device-attached", payload);
+                }
+            }
+            for (location, device) in &last {
+                if !current.contains_key(location)
+                    && let Ok(payload) = serde_json::to_value(device)
+                {
+                    events::emit("usb-device-detached", payload);
+                }
+            }
+            last = current;
+        }
+    });
+}
@@ -0,0 +1,197 @@
+//! Unresponsive-page watchdog for the main WebView.
+//!
+//! Every `watchdogIntervalSecs` the event loop pings the page by evaluating a trivial script
+//! with a completion callback; a page stuck in a long task or an endless loop never runs it.
+//! After `watchdogMaxMissed` pings in a row go unanswered the host emits `renderer-unresponsive`
+//! (`{ missedPings }`; panels and the modal see it at once) and applies `watchdogAction` once:
+//! `"notify"` stops there, `"ask"` shows a native "Page unresponsive" dialog offering a reload,
+//! `"reload"` reloads at once. `renderer-responsive` follows when a ping is answered again. Pings
+//! pause while the window is hidden or minimized, where platforms may suspend the page. Read at
+//! startup; `watchdogIntervalSecs: 0` turns it off.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tao::event_loop::EventLoopProxy;
+
+use crate::event_loop::UserEvent;
+use crate::events;
+use crate::i18n;
+
+/// Config key: seconds between pings; `0` disables the watchdog.
+pub const WATCHDOG_INTERVAL_KEY: &str = "watchdogIntervalSecs";
+
+/// Config key: consecutive unanswered pings before the page counts as unresponsive.
+pub const WATCHDOG_MAX_MISSED_KEY: &str = "watchdogMaxMissed";
+
+/// Config key: `"notify"`, `"ask"`, or `"reload"` (see `WatchdogAction`).
+pub const WATCHDOG_ACTION_KEY: &str = "watchdogAction";
+
+/// What the host does once the page is unresponsive, besides emitting `renderer-unresponsive`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatchdogAction {
+    /// Nothing more.
+    Notify,
+    /// Ask the user whether to reload or keep waiting.
+    Ask,
+    /// Reload the page.
+    Reload,
+}
+
+/// Watchdog settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchdogConfig {
+    /// `0`: off.
+    pub interval_secs: u64,
+    pub max_missed: u32,
+    pub action: WatchdogAction,
+}
+
+impl WatchdogConfig {
+    pub const DEFAULT: Self = Self {
+        interval_secs: 10,
+        max_missed: 3,
+        action: WatchdogAction::Notify,
+    };
+}
+
+/// Change in the page's state after a ping round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Unresponsive,
+    Responsive,
+}
+
+/// Ping bookkeeping, separate from the WebView so it can be tested.
+#[derive(Debug, Default)]
+pub struct PingState {
+    /// Pings sent since the last answer.
+    missed: Arc<AtomicU32>,
+    unresponsive: bool,
+}
+
+impl PingState {
+    /// Counts a new ping as outstanding and returns the callback that answers it.
+    pub fn send(&self) -> impl Fn() + Send + 'static {
+        self.missed.fetch_add(1, Ordering::Relaxed);
+        let missed = Arc::clone(&self.missed);
+        move || missed.store(0, Ordering::Relaxed)
+    }
+
+    /// Judges the pings so far; reports only changes.
+    pub fn check(&mut self, max_missed: u32) -> Option<Verdict> {
+        let missed = self.missed.load(Ordering::Relaxed);
+        if !self.unresponsive && missed >= max_missed.max(1) {
+            self.unresponsive = true;
+            return Some(Verdict::Unresponsive);
+        }
+        if self.unresponsive && missed == 0 {
+            self.unresponsive = false;
+            return Some(Verdict::Responsive);
+        }
+        None
+    }
+
+    /// Pings sent since the last answer.
+    #[must_use]
+    pub fn missed(&self) -> u32 {
+        self.missed.load(Ordering::Relaxed)
+    }
+
+    /// Forgets outstanding pings (page reloaded, or window hidden).
+    pub fn reset(&mut self) {
+        self.missed = Arc::new(AtomicU32::new(0));
+        self.unresponsive = false;
+    }
+}
+
+/// Watchdog state, owned by the event loop.
+pub struct Watchdog {
+    config: WatchdogConfig,
+    proxy: EventLoopProxy<UserEvent>,
+    pings: PingState,
+    next: Instant,
+}
+
+impl Watchdog {
+    /// `None` when `config` turns the watchdog off.
+    #[must_use]
+    pub fn new(config: WatchdogConfig, proxy: EventLoopProxy<UserEvent>) -> Option<Self> {
+        (config.interval_secs > 0).then(|| Self {
+            config,
+            proxy,
+            pings: PingState::default(),
+            next: Instant::now() + Duration::from_secs(config.interval_secs),
+        })
+    }
+
+    /// Judges the last pings and sends the next one if due. Returns the next ping time, for the
+    /// event loop's wake-up deadline.
+    pub fn poll(&mut self, window: &tao::window::Window, webview: &wry::WebView) -> Instant {
+        let now = Instant::now();
+        if now < self.next {
+            return self.next;
+        }
+        self.next = now + Duration::from_secs(self.config.interval_secs);
+        if !window.is_visible() || window.is_minimized() {
+            self.pings.reset();
+            return self.next;
+        }
+        match self.pings.check(self.config.max_missed) {
+            Some(Verdict::Unresponsive) => self.unresponsive(),
+            Some(Verdict::Responsive) => {
+                log::info!("Page is responding again");
+                events::emit("renderer-responsive", serde_json::json!({}));
+            }
+            None => {}
+        }
+        let answer = self.pings.send();
+        if let Err(e) = webview.evaluate_script_with_callback("0", move |_| answer()) {
+            log::warn!("Watchdog ping failed: {}", e);
+        }
+        self.next
+    }
+
+    /// Forgets outstanding pings after the page was replaced.
+    pub fn reset(&mut self) {
+        self.pings.reset();
+    }
+
+    fn unresponsive(&self) {
+        let missed = self.pings.missed();
+        log::warn!("Page did not answer {} pings; unresponsive", missed);
+        events::emit(
+            "renderer-unresponsive",
+            serde_json::json!({ "missedPings": missed }),
+        );
+        match self.config.action {
+            WatchdogAction::Notify => {}
+            WatchdogAction::Reload => {
+                let _ = self.proxy.send_event(UserEvent::Reload);
+            }
+            WatchdogAction::Ask => {
+                let proxy = self.proxy.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("watchdog-dialog".to_string())
+                    .spawn(move || {
+                        let answer = rfd::MessageDialog::new()
+                            .set_title(i18n::t("dialog.unresponsive.title"))
+                            .set_description(i18n::t("dialog.unresponsive.message"))
+                            .set_level(rfd::MessageLevel::Warning)
+                            .set_buttons(rfd::MessageButtons::YesNo)
+                            .show();
+                        if answer == rfd::MessageDialogResult::Yes {
+                            let _ = proxy.send_event(UserEvent::Reload);
+                        }
+                    });
+                if let Err(e) = spawned {
+                    log::warn!("Could not show the unresponsive page dialog: {}", e);
+                }
+            }
+        }
+    }
+}
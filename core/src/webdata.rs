@@ -0,0 +1,69 @@
+//! Selective WebView data wipe for `ClearWebData` (logout flows, support troubleshooting).
+//!
+//! Cookies go through wry's cookie API and page storage is cleared by a script in the app origin
+//! (the only origin the WebView loads), so both work while the page is running. The HTTP cache has
+//! no per-kind API, so its directories under `user_data_dir()` are removed: WebView2 keeps them in
+//! `EBWebView/Default`, WebKitGTK in the data dir itself. WKWebView (macOS) keeps its cache in the
+//! system data store, so `cache` is a no-op there.
+
+use std::fs;
+use std::io;
+
+use crate::paths::user_data_dir;
+
+/// Cache directories relative to `user_data_dir()`; missing ones are skipped.
+const CACHE_DIRS: &[&str] = &[
+    "EBWebView/Default/Cache",
+    "EBWebView/Default/Code Cache",
+    "EBWebView/Default/GPUCache",
+    "WebKitCache",
+    "CacheStorage",
+];
+
+/// Clears `localStorage`, `sessionStorage`, IndexedDB databases, and Cache Storage for the page.
+pub const STORAGE_CLEAR_SCRIPT: &str = r#"
+    (function() {
+        try { localStorage.clear(); } catch (e) {}
+        try { sessionStorage.clear(); } catch (e) {}
+        if (window.indexedDB && typeof indexedDB.databases === 'function') {
+            indexedDB.databases().then(function(dbs) {
+                dbs.forEach(function(db) { if (db.name) indexedDB.deleteDatabase(db.name); });
+            }).catch(function() {});
+        }
+        if (window.caches) {
+            caches.keys().then(function(keys) { keys.forEach(function(k) { caches.delete(k); }); }).catch(function() {});
+        }
+    })();
+"#;
+
+/// Deletes every cookie in the WebView profile. Returns how many were deleted.
+pub fn clear_cookies(webview: &wry::WebView) -> Result<usize, String> {
+    let cookies = webview
+        .cookies()
+        .map_err(|e| format!("Could not list cookies: {}", e))?;
+    for cookie in &cookies {
+        webview
+            .delete_cookie(cookie)
+            .map_err(|e| format!("Could not delete cookie {}: {}", cookie.name(), e))?;
+    }
+    Ok(cookies.len())
+}
+
+/// Removes the WebView cache directories. Files the engine holds open (WebView2 on Windows) may
+/// fail; the first such error is returned after trying every directory.
+pub fn clear_cache() -> Result<(), String> {
+    let root = user_data_dir();
+    let mut first_err = None;
+    for dir in CACHE_DIRS {
+        let path = root.join(dir);
+        match fs::remove_dir_all(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                log::warn!("Could not remove {}: {}", path.display(), e);
+                first_err.get_or_insert_with(|| format!("Could not clear cache {}: {}", dir, e));
+            }
+        }
+    }
+    first_err.map_or(Ok(()), Err)
+}
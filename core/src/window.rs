@@ -4,9 +4,206 @@
 //! event handling stay focused on orchestration.
 //! The app icon PNG is decoded once and reused for both window and tray.
 
-use std::sync::OnceLock;
+use crate::config::{PRODUCT_NAME, WINDOW_MIN_VISIBLE_PX};
+use crate::storage::WindowBounds;
+use std::borrow::Cow;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
+use tao::dpi::{LogicalSize, PhysicalPosition};
+use tao::event_loop::EventLoopWindowTarget;
 use tao::window::Icon;
 
+/// Config key: show a splash window while the main UI loads.
+pub const SPLASH_KEY: &str = "showSplash";
+
+/// Config key: replaces the WebView user agent entirely.
+pub const USER_AGENT_KEY: &str = "userAgent";
+
+/// Config key: text appended to the user agent; `true` appends `<ProductName>/<version>`.
+pub const USER_AGENT_SUFFIX_KEY: &str = "userAgentSuffix";
+
+/// Config key: whether media may autoplay without a user gesture.
+pub const AUTOPLAY_KEY: &str = "autoplay";
+
+/// Config key: `"disabled"`, `"suspend"`, or `"throttle"` while the window is hidden.
+pub const BACKGROUND_THROTTLING_KEY: &str = "backgroundThrottling";
+
+/// Config key: keep cookies, cache, and storage in memory only (nothing under `user_data_dir()`).
+pub const INCOGNITO_KEY: &str = "incognito";
+
+/// Config key: enable or disable spell checking in editable fields; unset keeps the platform default.
+pub const SPELLCHECK_KEY: &str = "spellcheck";
+
+/// Config key: spell-check languages (e.g. `["en-US", "de"]`); empty follows the OS.
+pub const SPELLCHECK_LANGUAGES_KEY: &str = "spellcheckLanguages";
+
+/// Most languages accepted by `validate_spellcheck_languages`.
+const MAX_SPELLCHECK_LANGUAGES: usize = 8;
+
+/// Longest title accepted by `SetWindowTitle`.
+const MAX_TITLE_CHARS: usize = 256;
+
+/// Config key: `"normal"`, `"hidden"`, or `"minimized"` (see `StartMode`).
+pub const START_MODE_KEY: &str = "startMode";
+
+/// Config key: milliseconds to wait for the UI's `UiReady` before showing the main window anyway.
+pub const UI_READY_TIMEOUT_KEY: &str = "uiReadyTimeoutMs";
+
+/// How the main window appears at launch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartMode {
+    /// Shown when the UI sends `UiReady` (or `uiReadyTimeoutMs` passes).
+    Normal,
+    /// Never shown automatically; the tray "Show" item (or IPC) reveals it.
+    Hidden,
+    /// Shown minimized when the UI sends `UiReady` (or `uiReadyTimeoutMs` passes).
+    Minimized,
+}
+
+/// Resolves the start mode: CLI flags (`--hidden`, `--minimized`) win over the config key.
+#[must_use]
+pub fn start_mode(args: &[String]) -> StartMode {
+    if args.iter().any(|a| a == crate::config::ARG_HIDDEN) {
+        return StartMode::Hidden;
+    }
+    if args.iter().any(|a| a == crate::config::ARG_MINIMIZED) {
+        return StartMode::Minimized;
+    }
+    match crate::storage::get_value(START_MODE_KEY)
+        .as_ref()
+        .and_then(|v| v.as_str())
+    {
+        Some("hidden") => StartMode::Hidden,
+        Some("minimized") => StartMode::Minimized,
+        _ => StartMode::Normal,
+    }
+}
+
+/// Splash window size (logical).
+const SPLASH_WIDTH: f64 = 360.0;
+const SPLASH_HEIGHT: f64 = 200.0;
+
+/// Splash markup: static, no scripts, no external resources. `{name}` is the escaped product name.
+const SPLASH_HTML: &str = r#"<!doctype html><html><head><meta charset="utf-8"><style>
+html,body{margin:0;height:100%;font-family:system-ui,sans-serif;background:#20232a;color:#61dafb}
+body{display:flex;flex-direction:column;align-items:center;justify-content:center;gap:16px}
+.s{width:28px;height:28px;border:3px solid #3a3f4b;border-top-color:#61dafb;border-radius:50%;animation:r 1s linear infinite}
+@keyframes r{to{transform:rotate(360deg)}}
+</style></head><body><div>{name}</div><div class="s"></div></body></html>"#;
+
+/// Startup splash: undecorated window with a static HTML page. Dropped when the main window shows.
+/// Field order matters: the webview must drop before its window.
+pub struct Splash {
+    _webview: wry::WebView,
+    _window: tao::window::Window,
+}
+
+/// Serves an `app://` request path from the UI bundle (shared by the main WebView's protocol
+/// handler and every later WebView).
+pub type AppHandler = Arc<dyn Fn(&str) -> http::Response<Cow<'static, [u8]>> + Send + Sync>;
+
+/// Window id of the main window. Panels use their name; the modal is `MODAL_WINDOW`.
+pub const MAIN_WINDOW: &str = "main";
+
+/// Window id of the open modal (`OpenModal`).
+pub const MODAL_WINDOW: &str = "modal";
+
+/// Shared IPC message handler; gets the id of the window whose WebView posted the message.
+pub type IpcHandler = Rc<dyn Fn(&str, wry::http::Request<String>)>;
+
+/// Web context, `app://` handler, and IPC bridge for WebViews created after the main one
+/// (panels, modals). Owned by the event loop; the WebViews built from it must drop first.
+pub struct ViewContext {
+    web_context: wry::WebContext,
+    // On Linux the `app` scheme is registered on the shared web context by the main WebView, so
+    // there this only serves `asset`.
+    app: AppHandler,
+    /// Bridge init script for a window id (see `init_script`).
+    bridge_script: Box<dyn Fn(&str) -> String>,
+    ipc: IpcHandler,
+}
+
+impl ViewContext {
+    #[must_use]
+    pub fn new(
+        web_context: wry::WebContext,
+        app: AppHandler,
+        bridge_script: Box<dyn Fn(&str) -> String>,
+        ipc: IpcHandler,
+    ) -> Self {
+        Self {
+            web_context,
+            app,
+            bridge_script,
+            ipc,
+        }
+    }
+
+    /// WebView builder for window `window_id` on the shared web context: serves `app://` from the
+    /// UI bundle and installs the IPC bridge, with responses routed back to `window_id`.
+    pub fn builder(&mut self, window_id: &str) -> wry::WebViewBuilder<'_> {
+        let ipc = Rc::clone(&self.ipc);
+        let id = window_id.to_string();
+        let builder = wry::WebViewBuilder::new_with_web_context(&mut self.web_context)
+            .with_initialization_script((self.bridge_script)(window_id))
+            .with_ipc_handler(move |request| ipc(&id, request));
+        #[cfg(not(target_os = "linux"))]
+        let builder = {
+            let app = Arc::clone(&self.app);
+            builder.with_custom_protocol("app".to_string(), move |_, request| {
+                app(request.uri().path())
+            })
+        };
+        builder
+    }
+
+    /// Bytes of a file from the UI bundle (what `app://localhost/<path>` serves), e.g.
+    /// `icons/document.png`. Never reads outside the bundle.
+    pub fn asset(&self, path: &str) -> Result<Vec<u8>, String> {
+        let url = crate::panel::panel_url(path)?;
+        let response = (self.app)(url.trim_start_matches("app://localhost"));
+        if response.status() != 200 {
+            return Err(format!("No UI asset at {}", path));
+        }
+        Ok(response.into_body().into_owned())
+    }
+}
+
+/// Windows besides the main one, addressed by window id: panels (by name) and the modal.
+#[derive(Default)]
+pub struct Children {
+    pub panels: crate::panel::Panels,
+    pub modals: crate::modal::Modals,
+}
+
+impl Children {
+    /// WebView of child window `id`.
+    #[must_use]
+    pub fn webview(&self, id: &str) -> Option<&wry::WebView> {
+        if id == MODAL_WINDOW {
+            self.modals.webview()
+        } else {
+            self.panels.webview(id)
+        }
+    }
+
+    /// WebViews of all child windows.
+    pub fn webviews(&self) -> impl Iterator<Item = &wry::WebView> {
+        self.panels.webviews().chain(self.modals.webview())
+    }
+
+    /// Ids of all windows, the main window first.
+    #[must_use]
+    pub fn window_ids(&self) -> Vec<String> {
+        let mut ids = vec![MAIN_WINDOW.to_string()];
+        ids.extend(self.panels.names());
+        if self.modals.webview().is_some() {
+            ids.push(MODAL_WINDOW.to_string());
+        }
+        ids
+    }
+}
+
 /// Cached decoded icon (RGBA pixels, width, height). Decoded once at first use.
 fn decoded_icon() -> Option<&'static (Vec<u8>, u32, u32)> {
     static CACHED: OnceLock<Option<(Vec<u8>, u32, u32)>> = OnceLock::new();
@@ -26,6 +223,39 @@ pub fn window_icon() -> Option<Icon> {
     Icon::from_rgba(rgba.clone(), *width, *height).ok()
 }
 
+/// Windows taskbar overlay icon: the embedded app icon scaled to 16×16 (shared decode).
+#[cfg(target_os = "windows")]
+#[must_use]
+pub fn overlay_icon() -> Option<Icon> {
+    let (rgba, width, height) = decoded_icon()?;
+    let img = image::RgbaImage::from_raw(*width, *height, rgba.clone())?;
+    let small = image::imageops::resize(&img, 16, 16, image::imageops::FilterType::Triangle);
+    Icon::from_rgba(small.into_raw(), 16, 16).ok()
+}
+
+/// Decodes a PNG into a window icon.
+pub fn icon_from_bytes(bytes: &[u8]) -> Result<Icon, String> {
+    let img =
+        image::load_from_memory(bytes).map_err(|e| format!("Could not decode icon: {}", e))?;
+    let rgba = img.to_rgba8();
+    let (width, height) = rgba.dimensions();
+    Icon::from_rgba(rgba.into_raw(), width, height).map_err(|e| format!("Invalid icon: {}", e))
+}
+
+/// Checks a window title: at most `MAX_TITLE_CHARS` characters, no control characters.
+pub fn validate_title(title: &str) -> Result<&str, String> {
+    if title.chars().count() > MAX_TITLE_CHARS {
+        return Err(format!(
+            "Window title is longer than {} characters",
+            MAX_TITLE_CHARS
+        ));
+    }
+    if title.chars().any(char::is_control) {
+        return Err("Window title must not contain control characters".to_string());
+    }
+    Ok(title)
+}
+
 /// Loads the tray icon from the embedded asset (same decode as window icon).
 #[must_use]
 pub fn tray_icon() -> Option<tray_icon::Icon> {
@@ -33,24 +263,432 @@ pub fn tray_icon() -> Option<tray_icon::Icon> {
     tray_icon::Icon::from_rgba(rgba.clone(), *width, *height).ok()
 }
 
-/// Returns the init script: disables context menu, exposes `window.native` and IPC resolve helpers.
+/// Per-session key the host passes to `__resolveIpc` / `__resolveIpcBatch` / `__onHostEvent`; calls without it are ignored,
+/// so page scripts cannot forge resolutions or events. Generated once at first use.
 #[must_use]
-pub fn init_script() -> &'static str {
-    r#"
-        document.addEventListener('contextmenu', function(e) { e.preventDefault(); });
-        window.native = {
-            send: function(msg) {
-                if (window.ipc && typeof window.ipc.postMessage === 'function') {
-                    window.ipc.postMessage(msg);
-                }
-            }
-        };
-        window.__ipcResolve = window.__ipcResolve || {};
-        window.__resolveIpc = function(id, json) {
-            if (window.__ipcResolve[id]) {
-                window.__ipcResolve[id](json);
-                delete window.__ipcResolve[id];
+pub fn delivery_key() -> &'static str {
+    static KEY: OnceLock<String> = OnceLock::new();
+    KEY.get_or_init(|| uuid::Uuid::new_v4().simple().to_string())
+}
+
+/// Returns the init script: disables context menu and installs the IPC bridge.
+///
+/// All bridge state (session `token`, pending requests, host event listeners, optional HMAC
+/// signing key) lives in a closure. `window.native` (`send`, `invoke`, `on`, and `windowId`, the
+/// id other windows target with `EmitToWindow`), `__resolveIpc`,
+/// `__resolveIpcBatch`, and `__onHostEvent` are frozen, non-configurable globals; all but `native`
+/// require `delivery_key()`.
+/// `window.ipc.postMessage` is captured at init so later page scripts cannot sniff messages.
+/// The legacy `window.__ipcResolve` map is consulted only for ids not issued by `native.invoke`
+/// (UI builds predating it). With `devtools`, `window.__devIpc` (`trace`, `show`, `replay`) is
+/// added for inspecting the host-side IPC trace.
+#[must_use]
+pub fn init_script(
+    token: &str,
+    signing_key_hex: Option<&str>,
+    devtools: bool,
+    window_id: &str,
+) -> String {
+    format!(
+        r#"
+        document.addEventListener('contextmenu', function(e) {{ e.preventDefault(); }});
+        (function() {{
+            var token = "{token}";
+            var deliveryKey = "{delivery_key}";
+            var signingKeyHex = "{signing_key}";
+            var timeoutMs = {timeout_ms};
+            var pending = {{}};
+            var listeners = {{}};
+            var post = null;
+            function capturePost() {{
+                if (!post && window.ipc && typeof window.ipc.postMessage === 'function') {{
+                    post = window.ipc.postMessage.bind(window.ipc);
+                }}
+            }}
+            capturePost();
+            function rawPost(obj) {{
+                obj.token = token;
+                capturePost();
+                if (post) post(JSON.stringify(obj));
+            }}
+            var sign = null;
+            if (signingKeyHex && window.crypto && window.crypto.subtle) {{
+                var bytes = new Uint8Array(signingKeyHex.length / 2);
+                for (var i = 0; i < bytes.length; i++) bytes[i] = parseInt(signingKeyHex.substr(i * 2, 2), 16);
+                var keyPromise = window.crypto.subtle.importKey('raw', bytes, {{ name: 'HMAC', hash: 'SHA-256' }}, false, ['sign']);
+                sign = function(msg) {{
+                    return keyPromise
+                        .then(function(k) {{ return window.crypto.subtle.sign('HMAC', k, new TextEncoder().encode(msg)); }})
+                        .then(function(sig) {{
+                            return Array.prototype.map.call(new Uint8Array(sig), function(b) {{
+                                return ('0' + b.toString(16)).slice(-2);
+                            }}).join('');
+                        }});
+                }};
+            }}
+            var signingRequired = signingKeyHex.length > 0;
+            signingKeyHex = null;
+            function send(msg) {{
+                var obj;
+                try {{ obj = JSON.parse(msg); }} catch (e) {{ return; }}
+                if (!signingRequired) {{ rawPost(obj); return; }}
+                if (!sign) return;
+                sign(msg).then(function(hex) {{ rawPost({{ signed: msg, sig: hex }}); }}).catch(function() {{}});
+            }}
+            function newId() {{
+                if (window.crypto && typeof window.crypto.randomUUID === 'function') return window.crypto.randomUUID();
+                return Date.now().toString(16) + '-' + Math.random().toString(16).slice(2);
+            }}
+            function invoke(message, timeout) {{
+                return new Promise(function(resolve, reject) {{
+                    var obj = Object.assign({{}}, message);
+                    if (!obj.id) obj.id = newId();
+                    var id = obj.id;
+                    var timer = setTimeout(function() {{
+                        if (pending[id]) {{
+                            delete pending[id];
+                            reject(new Error('IPC timeout'));
+                        }}
+                    }}, timeout || timeoutMs);
+                    pending[id] = function(result) {{
+                        clearTimeout(timer);
+                        if (result && result.err) {{
+                            var error = new Error(result.err);
+                            if (result.code) error.code = result.code;
+                            reject(error);
+                        }} else resolve(result);
+                    }};
+                    send(JSON.stringify(obj));
+                }});
+            }}
+            function on(name, callback) {{
+                var list = listeners[name] = listeners[name] || [];
+                list.push(callback);
+                return function() {{
+                    var i = list.indexOf(callback);
+                    if (i !== -1) list.splice(i, 1);
+                }};
+            }}
+            function define(name, value) {{
+                Object.defineProperty(window, name, {{ value: value, writable: false, configurable: false }});
+            }}
+            define('native', Object.freeze({{ send: send, invoke: invoke, on: on, windowId: "{window_id}" }}));
+            function resolve(id, result) {{
+                var cb = pending[id];
+                if (cb) {{
+                    delete pending[id];
+                    cb(result);
+                    return;
+                }}
+                var legacy = window.__ipcResolve && window.__ipcResolve[id];
+                if (typeof legacy === 'function') legacy(result);
+            }}
+            define('__resolveIpc', function(key, id, result) {{
+                if (key !== deliveryKey) return;
+                resolve(id, result);
+            }});
+            define('__resolveIpcBatch', function(key, batch) {{
+                if (key !== deliveryKey) return;
+                for (var i = 0; i < batch.length; i++) {{
+                    try {{ resolve(batch[i].id, batch[i]); }} catch (e) {{}}
+                }}
+            }});
+            define('__onHostEvent', function(key, name, data) {{
+                if (key !== deliveryKey) return;
+                (listeners[name] || []).slice().forEach(function(cb) {{
+                    try {{ cb(data); }} catch (e) {{}}
+                }});
+            }});
+            if ({devtools}) {{
+                var trace = function() {{ return invoke({{ name: 'GetIpcTrace' }}).then(function(r) {{ return r.ok; }}); }};
+                define('__devIpc', Object.freeze({{
+                    trace: trace,
+                    show: function() {{
+                        return trace().then(function(t) {{
+                            console.log('IPC queue depth: ' + t.queueDepth);
+                            console.table(t.entries.map(function(e) {{
+                                return {{ command: e.command.name, ok: e.ok, ms: e.durationMs.toFixed(1), error: e.error }};
+                            }}));
+                            return t;
+                        }});
+                    }},
+                    replay: function(index) {{
+                        return trace().then(function(t) {{
+                            var e = t.entries[index < 0 ? t.entries.length + index : index];
+                            if (!e) throw new Error('No trace entry ' + index);
+                            return invoke(e.command);
+                        }});
+                    }}
+                }}));
+            }}
+        }})();
+    "#,
+        token = token,
+        delivery_key = delivery_key(),
+        signing_key = signing_key_hex.unwrap_or(""),
+        timeout_ms = crate::ipc::IPC_TIMEOUT_MS,
+        devtools = devtools,
+        window_id = window_id,
+    )
+}
+
+/// Default `userAgentSuffix` value for `true`: `<ProductName>/<version>` with spaces removed.
+#[must_use]
+pub fn default_user_agent_suffix() -> String {
+    format!(
+        "{}/{}",
+        PRODUCT_NAME.replace(' ', ""),
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// Init script appending `suffix` to `navigator.userAgent`.
+///
+/// wry can only replace the user agent, and the platform default is not known before the WebView
+/// exists, so without a `userAgent` override the suffix is visible to page scripts only (the app
+/// loads nothing but app:// content, so no server ever sees the header).
+#[must_use]
+pub fn user_agent_suffix_script(suffix: &str) -> String {
+    let json = serde_json::Value::from(format!(" {}", suffix)).to_string();
+    let literal = crate::event_loop::json_literal(&json);
+    format!(
+        r#"
+        (function() {{
+            var ua = navigator.userAgent + {literal};
+            Object.defineProperty(Navigator.prototype, 'userAgent', {{ get: function() {{ return ua; }}, configurable: false }});
+        }})();
+    "#
+    )
+}
+
+/// Rejects more than 8 languages or tags with characters other than ASCII letters, digits, `-`, `_`.
+pub fn validate_spellcheck_languages(languages: &[String]) -> Result<(), String> {
+    if languages.len() > MAX_SPELLCHECK_LANGUAGES {
+        return Err(format!(
+            "At most {} spell-check languages",
+            MAX_SPELLCHECK_LANGUAGES
+        ));
+    }
+    match languages.iter().find(|l| {
+        l.is_empty()
+            || l.len() > 35
+            || !l
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }) {
+        Some(bad) => Err(format!("Invalid language tag '{}'", bad)),
+        None => Ok(()),
+    }
+}
+
+/// Script setting the inherited `spellcheck` attribute on `<html>` now and after each DOM load.
+#[must_use]
+pub fn spellcheck_script(enabled: bool) -> String {
+    format!(
+        r#"
+        (function() {{
+            function apply() {{ if (document.documentElement) document.documentElement.spellcheck = {enabled}; }}
+            apply();
+            document.addEventListener('DOMContentLoaded', apply);
+        }})();
+    "#
+    )
+}
+
+/// Turns spell checking on or off for the loaded page and, on Linux, the WebKitGTK context (which
+/// is off by default and the only engine with a language API). Elsewhere `languages` is ignored and
+/// the OS input languages apply.
+pub fn apply_spellcheck(
+    webview: &wry::WebView,
+    enabled: bool,
+    languages: &[String],
+) -> Result<(), String> {
+    #[cfg(target_os = "linux")]
+    {
+        use webkit2gtk::{WebContextExt, WebViewExt};
+        use wry::WebViewExtUnix;
+        if let Some(context) = webview.webview().context() {
+            context.set_spell_checking_enabled(enabled);
+            if !languages.is_empty() {
+                let tags: Vec<String> = languages.iter().map(|l| l.replace('-', "_")).collect();
+                let refs: Vec<&str> = tags.iter().map(String::as_str).collect();
+                context.set_spell_checking_languages(&refs);
             }
+        }
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = languages;
+    webview
+        .evaluate_script(&spellcheck_script(enabled))
+        .map_err(|e| format!("Could not apply spell check: {}", e))
+}
+
+/// Rectangle in physical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PixelRect {
+    /// True when the physical point is inside the rectangle.
+    #[must_use]
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        let (x, y) = (i64::from(x), i64::from(y));
+        x >= i64::from(self.x)
+            && y >= i64::from(self.y)
+            && x < i64::from(self.x) + i64::from(self.width)
+            && y < i64::from(self.y) + i64::from(self.height)
+    }
+}
+
+impl From<&tao::monitor::MonitorHandle> for PixelRect {
+    fn from(monitor: &tao::monitor::MonitorHandle) -> Self {
+        let (pos, size) = (monitor.position(), monitor.size());
+        Self {
+            x: pos.x,
+            y: pos.y,
+            width: size.width,
+            height: size.height,
+        }
+    }
+}
+
+/// Fits saved window bounds to the connected displays. Bounds whose title bar is still reachable
+/// on a display are kept, shrunk into that display if its resolution dropped; otherwise (display
+/// unplugged, layout changed) the window is centered on the nearest display, shrunk to fit. With
+/// no displays reported the bounds are kept as saved.
+#[must_use]
+pub fn fit_to_monitors(bounds: WindowBounds, monitors: &[PixelRect]) -> WindowBounds {
+    let visible = WINDOW_MIN_VISIBLE_PX as i64;
+    let (x, y) = (i64::from(bounds.x), i64::from(bounds.y));
+    let reachable = |m: &&PixelRect| {
+        let (mx, my) = (i64::from(m.x), i64::from(m.y));
+        let overlap = (x + i64::from(bounds.width)).min(mx + i64::from(m.width)) - x.max(mx);
+        overlap >= visible && y >= my && y <= my + i64::from(m.height) - visible
+    };
+    if let Some(m) = monitors.iter().find(reachable) {
+        if bounds.width <= m.width && bounds.height <= m.height {
+            return bounds;
+        }
+        let (width, height) = (bounds.width.min(m.width), bounds.height.min(m.height));
+        return WindowBounds {
+            x: bounds.x.clamp(m.x, m.x + (m.width - width) as i32),
+            y: bounds.y.clamp(m.y, m.y + (m.height - height) as i32),
+            width,
+            height,
+            ..bounds
         };
-    "#
+    }
+    // Nearest by distance from the window's center to each display.
+    let (cx, cy) = (
+        x + i64::from(bounds.width / 2),
+        y + i64::from(bounds.height / 2),
+    );
+    let distance = |m: &&PixelRect| {
+        let (mx, my) = (i64::from(m.x), i64::from(m.y));
+        let dx = cx.clamp(mx, mx + i64::from(m.width)) - cx;
+        let dy = cy.clamp(my, my + i64::from(m.height)) - cy;
+        dx * dx + dy * dy
+    };
+    let Some(m) = monitors.iter().min_by_key(distance) else {
+        return bounds;
+    };
+    let (width, height) = (bounds.width.min(m.width), bounds.height.min(m.height));
+    WindowBounds {
+        x: m.x + ((m.width - width) / 2) as i32,
+        y: m.y + ((m.height - height) / 2) as i32,
+        width,
+        height,
+        ..bounds
+    }
+}
+
+/// Converts saved bounds to a display with `scale_factor`: the size keeps its logical size
+/// when it was saved at another scale. The position stays physical (display coordinates).
+#[must_use]
+pub fn rescale_bounds(bounds: WindowBounds, scale_factor: f64) -> WindowBounds {
+    let ratio = match bounds.scale_factor {
+        Some(saved) if saved > 0.0 && scale_factor > 0.0 => scale_factor / saved,
+        _ => 1.0,
+    };
+    let scale = |v: u32| ((f64::from(v) * ratio).round() as u32).max(1);
+    WindowBounds {
+        width: scale(bounds.width),
+        height: scale(bounds.height),
+        scale_factor: Some(scale_factor),
+        ..bounds
+    }
+}
+
+/// Window opacity as an 8-bit alpha. `value` must be within 0.0–1.0.
+pub fn opacity_alpha(value: f64) -> Result<u8, String> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("Opacity must be between 0 and 1: {}", value));
+    }
+    Ok((value * 255.0).round() as u8)
+}
+
+/// Sets the whole window's opacity (0.0 transparent – 1.0 opaque). Linux uses the GTK widget
+/// opacity (needs a compositor); Windows and macOS report an error.
+pub fn set_opacity(window: &tao::window::Window, value: f64) -> Result<(), String> {
+    opacity_alpha(value)?;
+    #[cfg(target_os = "linux")]
+    {
+        use tao::platform::unix::WindowExtUnix;
+        use webkit2gtk::glib::prelude::ObjectExt;
+        window.gtk_window().set_property("opacity", value);
+        Ok(())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = window;
+        Err("Window opacity is not supported on this platform".to_string())
+    }
+}
+
+/// Builds the splash window centered on the primary monitor. Returns `None` on any failure
+/// (startup continues without a splash).
+pub fn build_splash<T: 'static>(target: &EventLoopWindowTarget<T>) -> Option<Splash> {
+    let mut b = tao::window::WindowBuilder::new()
+        .with_title(PRODUCT_NAME)
+        .with_inner_size(LogicalSize::new(SPLASH_WIDTH, SPLASH_HEIGHT))
+        .with_decorations(false)
+        .with_resizable(false)
+        .with_always_on_top(true);
+    if let Some(monitor) = target.primary_monitor() {
+        let scale = monitor.scale_factor();
+        let (mx, my) = (monitor.position().x, monitor.position().y);
+        let (mw, mh) = (monitor.size().width as f64, monitor.size().height as f64);
+        b = b.with_position(PhysicalPosition::new(
+            mx + ((mw - SPLASH_WIDTH * scale) / 2.0) as i32,
+            my + ((mh - SPLASH_HEIGHT * scale) / 2.0) as i32,
+        ));
+    }
+    if let Some(icon) = window_icon() {
+        b = b.with_window_icon(Some(icon));
+    }
+    let window = b.build(target).ok()?;
+    let name = PRODUCT_NAME
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;");
+    let builder = wry::WebViewBuilder::new().with_html(SPLASH_HTML.replace("{name}", &name));
+
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    let webview = builder.build(&window).ok()?;
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let webview = {
+        use tao::platform::unix::WindowExtUnix;
+        use wry::WebViewBuilderExtUnix;
+        builder.build_gtk(window.default_vbox()?).ok()?
+    };
+
+    Some(Splash {
+        _webview: webview,
+        _window: window,
+    })
 }
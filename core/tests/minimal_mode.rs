@@ -0,0 +1,37 @@
+//! Minimal mode starts no background threads and makes no network requests. Kept in its own
+//! test binary so the thread count is not disturbed by other tests running in parallel.
+
+use desktop_runtime_core::ipc::{Command, WorkerPool, handle_command};
+use desktop_runtime_core::killswitch::{self, RuntimeModule};
+use desktop_runtime_core::services::Services;
+use desktop_runtime_core::settings::Settings;
+
+#[cfg(target_os = "linux")]
+fn thread_count() -> usize {
+    std::fs::read_dir("/proc/self/task")
+        .map(Iterator::count)
+        .unwrap_or(0)
+}
+
+#[test]
+fn minimal_mode_starts_nothing_in_the_background() {
+    let mut config = desktop_runtime_core::storage::AppConfig::default();
+    config
+        .data
+        .insert("minimalMode".into(), serde_json::json!(true));
+    let services = Services::for_settings(&Settings::from_config(&config));
+    assert_eq!(services, Services::MINIMAL);
+
+    let pool = WorkerPool::new(1).unwrap();
+    #[cfg(target_os = "linux")]
+    let before = thread_count();
+    services.start(&pool);
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    #[cfg(target_os = "linux")]
+    assert_eq!(thread_count(), before);
+
+    assert!(killswitch::is_disabled(RuntimeModule::Updater));
+    let err = handle_command(&Command::CheckForUpdates).unwrap_err();
+    assert!(err.contains("disabled"), "{}", err);
+    desktop_runtime_core::session::mark_clean_exit();
+}
@@ -23,6 +23,8 @@ if command -v apt-get >/dev/null 2>&1; then
     libsoup2.4-dev \
     libayatana-appindicator3-dev \
     libxdo-dev \
+    libspeechd-dev \
+    libclang-dev \
     patchelf
 elif command -v dnf >/dev/null 2>&1; then
   sudo dnf install -y \
@@ -36,6 +38,8 @@ elif command -v dnf >/dev/null 2>&1; then
     libsoup-devel \
     libappindicator-gtk3-devel \
     libxdo-devel \
+    speech-dispatcher-devel \
+    clang-devel \
     patchelf
 elif command -v pacman >/dev/null 2>&1; then
   sudo pacman -S --noconfirm --needed \
@@ -50,6 +54,8 @@ elif command -v pacman >/dev/null 2>&1; then
     libsoup \
     libayatana-appindicator3 \
     libxdo \
+    speech-dispatcher \
+    clang \
     patchelf
 else
   echo "Unsupported package manager. This script supports: apt-get (Debian/Ubuntu), dnf (Fedora/RHEL), pacman (Arch)." >&2
@@ -1,5 +1,6 @@
 import React, { useState, useEffect } from 'react';
 import { useNativeCommand } from './useNativeCommand.js';
+import { uiReady } from './bridge.js';
 
 export default function App() {
   const sendCommand = useNativeCommand();
@@ -13,6 +14,10 @@ export default function App() {
   const [systemInfo, setSystemInfo] = useState(null);
   const [selectedPath, setSelectedPath] = useState(null);
 
+  useEffect(() => {
+    uiReady().catch(() => {});
+  }, []);
+
   useEffect(() => {
     let cancelled = false;
     sendCommand({ name: 'GetVersion' })
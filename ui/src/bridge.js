@@ -1,48 +1,46 @@
 /**
- * IPC via window.native.send. One promise per request; 30s timeout; lifecycle cleaned on timeout/resolve.
+ * IPC via window.native.invoke (installed by the host init script). One promise per request;
+ * 30s timeout; pending requests and resolution live in the bridge closure, not on window.
  */
 const IPC_TIMEOUT_MS = 30000;
 
-function uuid() {
-  return 'xxxxxxxx-xxxx-4xxx-yxxx-xxxxxxxxxxxx'.replace(/[xy]/g, function (c) {
-    const r = (Math.random() * 16) | 0;
-    const v = c === 'x' ? r : (r & 0x3) | 0x8;
-    return v.toString(16);
-  });
-}
-
 export function send(message) {
-  return new Promise((resolve, reject) => {
-    if (!window.native || typeof window.native.send !== 'function') {
-      reject(new Error('Native bridge not available'));
-      return;
-    }
-    let obj;
-    try {
-      obj = typeof message === 'string' ? JSON.parse(message) : message;
-    } catch (e) {
-      reject(e);
-      return;
-    }
-    const id = obj.id || uuid();
-    if (!obj.id) obj.id = id;
-    const msg = typeof message === 'string' ? message : JSON.stringify(obj);
+  if (!window.native || typeof window.native.invoke !== 'function') {
+    return Promise.reject(new Error('Native bridge not available'));
+  }
+  let obj;
+  try {
+    obj = typeof message === 'string' ? JSON.parse(message) : message;
+  } catch (e) {
+    return Promise.reject(e);
+  }
+  return window.native.invoke(obj, IPC_TIMEOUT_MS);
+}
 
-    const timer = setTimeout(() => {
-      if (window.__ipcResolve && window.__ipcResolve[id]) {
-        delete window.__ipcResolve[id];
-        reject(new Error('IPC timeout'));
-      }
-    }, IPC_TIMEOUT_MS);
+/**
+ * Subscribes to a host event pushed by the runtime (e.g. 'modules-changed'). Returns an unsubscribe function.
+ */
+export function onHostEvent(name, callback) {
+  if (!window.native || typeof window.native.on !== 'function') {
+    return () => {};
+  }
+  return window.native.on(name, callback);
+}
 
-    if (!window.__ipcResolve) window.__ipcResolve = {};
-    window.__ipcResolve[id] = (result) => {
-      clearTimeout(timer);
-      delete window.__ipcResolve[id];
-      if (result && result.err) reject(new Error(result.err));
-      else resolve(result);
-    };
+/**
+ * Tells the runtime the page listens for host events. Events emitted before this (startup deep
+ * links, opened files, tray clicks) are held and replayed in order. Call once, after subscribing.
+ */
+export function uiReady() {
+  return send({ name: 'UiReady' });
+}
 
-    window.native.send(msg);
-  });
+/**
+ * URL for a user-granted local file served by the media:// protocol (e.g. for <img src>).
+ */
+export function mediaUrl(path) {
+  const normalized = path.replace(/\\/g, '/');
+  const encoded = normalized.split('/').map(encodeURIComponent).join('/');
+  const base = navigator.userAgent.includes('Windows') ? 'http://media.localhost' : 'media://localhost';
+  return base + (encoded.startsWith('/') ? '' : '/') + encoded;
 }